@@ -3,7 +3,7 @@ type User = {
 };
 
 fn main(): string {
-    let u: User = { id: #U32(17u32) };
+    let mut u: User = { id: #U32(17u32) };
 
     if 1 > 2 {
         u.id = #UUID("hello");
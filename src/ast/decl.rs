@@ -9,6 +9,9 @@ use super::{expr::Expr, type_annotation::TypeAnnotation};
 pub struct Param {
     pub identifier: IdentifierNode,
     pub constraint: TypeAnnotation,
+    /// A function parameter declared with `mut`, other parameters cannot be assigned
+    /// to. Always false for fields and parameters of function types.
+    pub is_mutable: bool,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -16,6 +19,7 @@ pub struct FnDecl {
     pub id: DeclarationId,
     pub documentation: Option<DocAnnotation>,
     pub identifier: IdentifierNode,
+    pub generic_params: Vec<IdentifierNode>,
     pub params: Vec<Param>,
     pub return_type: TypeAnnotation,
     pub body: BlockContents,
@@ -27,14 +31,47 @@ pub struct TypeAliasDecl {
     pub id: DeclarationId,
     pub documentation: Option<DocAnnotation>,
     pub identifier: IdentifierNode,
+    pub generic_params: Vec<IdentifierNode>,
     pub value: TypeAnnotation,
     pub is_exported: bool,
 }
 
+/// A value computed at compile time, e.g. `export const LIMIT = 64;`
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConstDecl {
+    pub id: DeclarationId,
+    pub documentation: Option<DocAnnotation>,
+    pub identifier: IdentifierNode,
+    pub constraint: Option<TypeAnnotation>,
+    pub value: Expr,
+    pub is_exported: bool,
+}
+
+/// Functions attached to a type alias, e.g. `impl Point { fn len(self): f64 { .. } }`.
+/// Those taking `self` are methods called as `point.len()`, the others are reached
+/// with `Point::name`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImplDecl {
+    pub target: IdentifierNode,
+    pub methods: Vec<FnDecl>,
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum Declaration {
     TypeAlias(TypeAliasDecl),
     Fn(FnDecl),
+    Impl(ImplDecl),
+    Const(ConstDecl),
+}
+
+impl Declaration {
+    pub fn generic_params(&self) -> &[IdentifierNode] {
+        match self {
+            Declaration::TypeAlias(decl) => &decl.generic_params,
+            Declaration::Fn(decl) => &decl.generic_params,
+            Declaration::Impl(_) | Declaration::Const(_) => &[],
+        }
+    }
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -42,6 +79,8 @@ pub struct VarDecl {
     pub id: DeclarationId,
     pub documentation: Option<DocAnnotation>,
     pub identifier: IdentifierNode,
+    /// Declared with `let mut`, other variables cannot be assigned to
+    pub is_mutable: bool,
     pub constraint: Option<TypeAnnotation>,
     pub value: Expr,
 }
@@ -34,6 +34,9 @@ pub enum ExprKind {
     Neg {
         right: Box<Expr>,
     },
+    BitNot {
+        right: Box<Expr>,
+    },
     Add {
         left: Box<Expr>,
         right: Box<Expr>,
@@ -86,6 +89,31 @@ pub enum ExprKind {
         left: Box<Expr>,
         right: Box<Expr>,
     },
+    BitAnd {
+        left: Box<Expr>,
+        right: Box<Expr>,
+    },
+    BitOr {
+        left: Box<Expr>,
+        right: Box<Expr>,
+    },
+    BitXor {
+        left: Box<Expr>,
+        right: Box<Expr>,
+    },
+    ShiftLeft {
+        left: Box<Expr>,
+        right: Box<Expr>,
+    },
+    ShiftRight {
+        left: Box<Expr>,
+        right: Box<Expr>,
+    },
+    /// A half-open range of integers, e.g. `0..n`
+    Range {
+        start: Box<Expr>,
+        end: Box<Expr>,
+    },
     Struct(Vec<(IdentifierNode, Expr)>),
     Access {
         left: Box<Expr>,
@@ -95,6 +123,9 @@ pub enum ExprKind {
         left: Box<Expr>,
         field: IdentifierNode,
     },
+    /// A type on the left of a static access which isn't a plain name, e.g.
+    /// `List<i32>` in `List<i32>::with_capacity(8usize)`
+    Type(TypeAnnotation),
     Index {
         left: Box<Expr>,
         index: Box<Expr>,
@@ -129,6 +160,11 @@ pub enum ExprKind {
         else_branch: Option<BlockContents>,
     },
     List(Vec<Expr>),
+    /// `[value; size]`, a fixed size array with every item set to `value`
+    Array {
+        value: Box<Expr>,
+        size: Box<Expr>,
+    },
     CodeBlock(BlockContents),
 }
 
@@ -1,15 +1,56 @@
-use crate::ast::{IdentifierNode, Span, StringNode};
+use crate::{
+    ast::{IdentifierNode, Span, StringNode},
+    parse::DocAnnotation,
+};
 
 use super::{
-    decl::{TypeAliasDecl, VarDecl},
-    expr::{BlockContents, Expr},
+    decl::{ConstDecl, ImplDecl, TypeAliasDecl, VarDecl},
+    expr::{BlockContents, Expr, ExprKind},
 };
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CompoundOperator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Modulo,
+}
+
+impl CompoundOperator {
+    pub fn to_string(&self) -> String {
+        String::from(match self {
+            CompoundOperator::Add => "+=",
+            CompoundOperator::Subtract => "-=",
+            CompoundOperator::Multiply => "*=",
+            CompoundOperator::Divide => "/=",
+            CompoundOperator::Modulo => "%=",
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IncrementOperator {
+    Increment,
+    Decrement,
+}
+
+impl IncrementOperator {
+    pub fn to_string(&self) -> String {
+        String::from(match self {
+            IncrementOperator::Increment => "++",
+            IncrementOperator::Decrement => "--",
+        })
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum StmtKind {
     Expression(Expr),
     TypeAliasDecl(TypeAliasDecl),
+    ImplDecl(ImplDecl),
     VarDecl(VarDecl),
+    ConstDecl(ConstDecl),
     Break,
     Continue,
     Return {
@@ -19,14 +60,32 @@ pub enum StmtKind {
         target: Expr,
         value: Expr,
     },
+    /// e.g. `x += 1;`, the target is evaluated once
+    CompoundAssignment {
+        target: Expr,
+        operator: CompoundOperator,
+        value: Expr,
+    },
+    /// e.g. `x++;`, adds one of the target's own type
+    Increment {
+        target: Expr,
+        operator: IncrementOperator,
+    },
     From {
         path: StringNode,
         identifiers: Vec<(IdentifierNode, Option<IdentifierNode>)>, // optional alias
+        /// Name the whole module is imported under, its exports are reached with `::`
+        namespace: Option<IdentifierNode>,
     },
     While {
         condition: Box<Expr>,
         body: BlockContents,
     },
+    For {
+        item: IdentifierNode,
+        iterable: Box<Expr>,
+        body: BlockContents,
+    },
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -34,3 +93,19 @@ pub struct Stmt {
     pub kind: StmtKind,
     pub span: Span,
 }
+
+impl Stmt {
+    /// The documentation of a declaration, which precedes the statement's span
+    pub fn documentation(&self) -> Option<&DocAnnotation> {
+        match &self.kind {
+            StmtKind::TypeAliasDecl(decl) => decl.documentation.as_ref(),
+            StmtKind::VarDecl(decl) => decl.documentation.as_ref(),
+            StmtKind::ConstDecl(decl) => decl.documentation.as_ref(),
+            StmtKind::Expression(Expr {
+                kind: ExprKind::Fn(decl),
+                ..
+            }) => decl.documentation.as_ref(),
+            _ => None,
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use crate::ast::{IdentifierNode, Span};
+use crate::ast::{expr::Expr, IdentifierNode, Span};
 
 use super::decl::Param;
 
@@ -17,6 +17,8 @@ pub enum TypeAnnotationKind {
     U16,
     U32,
     U64,
+    USize,
+    ISize,
     I8,
     I16,
     I32,
@@ -25,10 +27,22 @@ pub enum TypeAnnotationKind {
     F64,
     String,
     Identifier(IdentifierNode),
+    /// A generic type alias applied to type arguments, e.g. `Option<i32>`
+    GenericInstance {
+        identifier: IdentifierNode,
+        args: Vec<TypeAnnotation>,
+    },
     Struct(Vec<Param>),
     Tag(TagAnnotation),
     Union(Vec<TagAnnotation>),
     List(Box<TypeAnnotation>),
+    /// `[T]`, a view over items stored in a list or string
+    Slice(Box<TypeAnnotation>),
+    /// `[T; N]`, the size is a constant expression
+    Array {
+        item_type: Box<TypeAnnotation>,
+        size: Box<Expr>,
+    },
     FnType {
         params: Vec<Param>,
         return_type: Box<TypeAnnotation>,
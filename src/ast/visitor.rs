@@ -1,7 +1,7 @@
 use crate::ast::{
-    decl::{FnDecl, TypeAliasDecl, VarDecl},
+    decl::{ConstDecl, FnDecl, ImplDecl, TypeAliasDecl, VarDecl},
     expr::{BlockContents, Expr, ExprKind, MatchArm, MatchPattern},
-    stmt::{Stmt, StmtKind},
+    stmt::{CompoundOperator, IncrementOperator, Stmt, StmtKind},
     type_annotation::{TagAnnotation, TypeAnnotation, TypeAnnotationKind},
     IdentifierNode, StringNode,
 };
@@ -48,10 +48,25 @@ pub trait ASTVisitor<'ast>: Sized {
         self.visit_type_alias_decl(decl);
     }
 
+    fn visit_impl_decl_stmt(&mut self, decl: &'ast ImplDecl) {
+        self.visit_ident_type(decl.target);
+        for method in &decl.methods {
+            self.visit_fn_decl(method);
+        }
+    }
+
     fn visit_var_decl_stmt(&mut self, decl: &'ast VarDecl) {
         self.visit_var_decl(decl);
     }
 
+    fn visit_const_decl_stmt(&mut self, decl: &'ast ConstDecl) {
+        self.visit_ident_decl(decl.identifier);
+        if let Some(constraint) = &decl.constraint {
+            self.visit_type(constraint);
+        }
+        self.visit_expr(&decl.value);
+    }
+
     fn visit_break_stmt(&mut self) {}
     fn visit_continue_stmt(&mut self) {}
 
@@ -64,11 +79,29 @@ pub trait ASTVisitor<'ast>: Sized {
         self.visit_expr(target);
     }
 
+    fn visit_compound_assignment_stmt(
+        &mut self,
+        target: &'ast Expr,
+        _operator: CompoundOperator,
+        value: &'ast Expr,
+    ) {
+        self.visit_expr(value);
+        self.visit_expr(target);
+    }
+
+    fn visit_increment_stmt(&mut self, target: &'ast Expr, _operator: IncrementOperator) {
+        self.visit_expr(target);
+    }
+
     fn visit_from_stmt(
         &mut self,
         _path: &'ast StringNode,
         _ids: &'ast [(IdentifierNode, Option<IdentifierNode>)],
+        namespace: Option<IdentifierNode>,
     ) {
+        if let Some(namespace) = namespace {
+            self.visit_ident_decl(namespace);
+        }
     }
 
     fn visit_while_stmt(&mut self, condition: &'ast Expr, body: &'ast BlockContents) {
@@ -76,12 +109,26 @@ pub trait ASTVisitor<'ast>: Sized {
         self.visit_block(body);
     }
 
+    fn visit_for_stmt(
+        &mut self,
+        item: IdentifierNode,
+        iterable: &'ast Expr,
+        body: &'ast BlockContents,
+    ) {
+        self.visit_expr(iterable);
+        self.visit_ident_decl(item);
+        self.visit_block(body);
+    }
+
     fn visit_not_expr(&mut self, right: &'ast Expr) {
         self.visit_expr(right);
     }
     fn visit_neg_expr(&mut self, right: &'ast Expr) {
         self.visit_expr(right);
     }
+    fn visit_bit_not_expr(&mut self, right: &'ast Expr) {
+        self.visit_expr(right);
+    }
 
     fn visit_add_expr(&mut self, left: &'ast Expr, right: &'ast Expr) {
         self.visit_binary_defaults(left, right);
@@ -122,6 +169,24 @@ pub trait ASTVisitor<'ast>: Sized {
     fn visit_or_expr(&mut self, left: &'ast Expr, right: &'ast Expr) {
         self.visit_binary_defaults(left, right);
     }
+    fn visit_bit_and_expr(&mut self, left: &'ast Expr, right: &'ast Expr) {
+        self.visit_binary_defaults(left, right);
+    }
+    fn visit_bit_or_expr(&mut self, left: &'ast Expr, right: &'ast Expr) {
+        self.visit_binary_defaults(left, right);
+    }
+    fn visit_bit_xor_expr(&mut self, left: &'ast Expr, right: &'ast Expr) {
+        self.visit_binary_defaults(left, right);
+    }
+    fn visit_shl_expr(&mut self, left: &'ast Expr, right: &'ast Expr) {
+        self.visit_binary_defaults(left, right);
+    }
+    fn visit_shr_expr(&mut self, left: &'ast Expr, right: &'ast Expr) {
+        self.visit_binary_defaults(left, right);
+    }
+    fn visit_range_expr(&mut self, start: &'ast Expr, end: &'ast Expr) {
+        self.visit_binary_defaults(start, end);
+    }
 
     fn visit_struct_init_expr(&mut self, fields: &'ast [(IdentifierNode, Expr)]) {
         for (id, expr) in fields {
@@ -188,6 +253,11 @@ pub trait ASTVisitor<'ast>: Sized {
         }
     }
 
+    fn visit_array_literal_expr(&mut self, value: &'ast Expr, size: &'ast Expr) {
+        self.visit_expr(value);
+        self.visit_expr(size);
+    }
+
     fn visit_codeblock_expr(&mut self, block: &'ast BlockContents) {
         self.visit_block(block);
     }
@@ -211,9 +281,15 @@ pub trait ASTVisitor<'ast>: Sized {
 
     fn visit_fn_decl(&mut self, decl: &'ast FnDecl) {
         self.visit_ident_decl(decl.identifier);
+        for param in &decl.generic_params {
+            self.visit_ident_decl(*param);
+        }
         for param in &decl.params {
             self.visit_ident_decl(param.identifier);
-            self.visit_type(&param.constraint);
+            // The type of a bare `self` is implied by its impl block
+            if param.constraint.span != param.identifier.span {
+                self.visit_type(&param.constraint);
+            }
         }
         self.visit_type(&decl.return_type);
         self.visit_block(&decl.body);
@@ -229,6 +305,9 @@ pub trait ASTVisitor<'ast>: Sized {
 
     fn visit_type_alias_decl(&mut self, decl: &'ast TypeAliasDecl) {
         self.visit_ident_decl(decl.identifier);
+        for param in &decl.generic_params {
+            self.visit_ident_decl(*param);
+        }
         self.visit_type(&decl.value);
     }
 
@@ -265,13 +344,32 @@ pub fn walk_stmt<'ast, V: ASTVisitor<'ast>>(v: &mut V, stmt: &'ast Stmt) {
     match &stmt.kind {
         StmtKind::Expression(e) => v.visit_expr_stmt(e),
         StmtKind::TypeAliasDecl(d) => v.visit_type_alias_decl_stmt(d),
+        StmtKind::ImplDecl(d) => v.visit_impl_decl_stmt(d),
         StmtKind::VarDecl(d) => v.visit_var_decl_stmt(d),
+        StmtKind::ConstDecl(d) => v.visit_const_decl_stmt(d),
         StmtKind::Break => v.visit_break_stmt(),
         StmtKind::Continue => v.visit_continue_stmt(),
         StmtKind::Return { value } => v.visit_return_stmt(value),
         StmtKind::Assignment { target, value } => v.visit_assignment_stmt(target, value),
-        StmtKind::From { path, identifiers } => v.visit_from_stmt(path, identifiers),
+        StmtKind::CompoundAssignment {
+            target,
+            operator,
+            value,
+        } => v.visit_compound_assignment_stmt(target, *operator, value),
+        StmtKind::Increment { target, operator } => {
+            v.visit_increment_stmt(target, *operator)
+        }
+        StmtKind::From {
+            path,
+            identifiers,
+            namespace,
+        } => v.visit_from_stmt(path, identifiers, *namespace),
         StmtKind::While { condition, body } => v.visit_while_stmt(condition, body),
+        StmtKind::For {
+            item,
+            iterable,
+            body,
+        } => v.visit_for_stmt(*item, iterable, body),
     }
 }
 
@@ -280,6 +378,7 @@ pub fn walk_expr<'ast, V: ASTVisitor<'ast>>(v: &mut V, expr: &'ast Expr) {
         ExprKind::Identifier(id) => v.visit_identifier_expr(*id),
         ExprKind::Not { right } => v.visit_not_expr(right),
         ExprKind::Neg { right } => v.visit_neg_expr(right),
+        ExprKind::BitNot { right } => v.visit_bit_not_expr(right),
         ExprKind::Add { left, right } => v.visit_add_expr(left, right),
         ExprKind::Subtract { left, right } => v.visit_sub_expr(left, right),
         ExprKind::Multiply { left, right } => v.visit_mul_expr(left, right),
@@ -293,11 +392,18 @@ pub fn walk_expr<'ast, V: ASTVisitor<'ast>>(v: &mut V, expr: &'ast Expr) {
         ExprKind::NotEqual { left, right } => v.visit_neq_expr(left, right),
         ExprKind::And { left, right } => v.visit_and_expr(left, right),
         ExprKind::Or { left, right } => v.visit_or_expr(left, right),
+        ExprKind::BitAnd { left, right } => v.visit_bit_and_expr(left, right),
+        ExprKind::BitOr { left, right } => v.visit_bit_or_expr(left, right),
+        ExprKind::BitXor { left, right } => v.visit_bit_xor_expr(left, right),
+        ExprKind::ShiftLeft { left, right } => v.visit_shl_expr(left, right),
+        ExprKind::ShiftRight { left, right } => v.visit_shr_expr(left, right),
+        ExprKind::Range { start, end } => v.visit_range_expr(start, end),
         ExprKind::Struct(fields) => v.visit_struct_init_expr(fields),
         ExprKind::Access { left, field } => v.visit_access_expr(left, *field),
         ExprKind::StaticAccess { left, field } => {
             v.visit_static_access_expr(left, *field)
         }
+        ExprKind::Type(ty) => v.visit_type(ty),
         ExprKind::TypeCast { left, target } => v.visit_type_cast_expr(left, target),
         ExprKind::Tag { name, value } => v.visit_tag_expr(*name, value.as_deref()),
         ExprKind::FnCall { left, args } => v.visit_fn_call_expr(left, args),
@@ -311,6 +417,7 @@ pub fn walk_expr<'ast, V: ASTVisitor<'ast>>(v: &mut V, expr: &'ast Expr) {
             else_branch,
         } => v.visit_if_expr(branches, else_branch.as_ref()),
         ExprKind::List(items) => v.visit_list_literal_expr(items),
+        ExprKind::Array { value, size } => v.visit_array_literal_expr(value, size),
         ExprKind::CodeBlock(block) => v.visit_codeblock_expr(block),
         ExprKind::Index { left, index } => v.visit_index_expr(left, index),
         ExprKind::IsVariant { left, variants } => v.visit_is_variant_expr(left, variants),
@@ -329,6 +436,12 @@ pub fn walk_block<'ast, V: ASTVisitor<'ast>>(v: &mut V, block: &'ast BlockConten
 pub fn walk_type<'ast, V: ASTVisitor<'ast>>(v: &mut V, ty: &'ast TypeAnnotation) {
     match &ty.kind {
         TypeAnnotationKind::Identifier(id) => v.visit_ident_type(*id),
+        TypeAnnotationKind::GenericInstance { identifier, args } => {
+            v.visit_ident_type(*identifier);
+            for arg in args {
+                v.visit_type(arg);
+            }
+        }
         TypeAnnotationKind::Struct(fields) => {
             for f in fields {
                 v.visit_ident_label(f.identifier);
@@ -349,7 +462,13 @@ pub fn walk_type<'ast, V: ASTVisitor<'ast>>(v: &mut V, ty: &'ast TypeAnnotation)
                 }
             }
         }
-        TypeAnnotationKind::List(inner) => v.visit_type(inner),
+        TypeAnnotationKind::List(inner) | TypeAnnotationKind::Slice(inner) => {
+            v.visit_type(inner)
+        }
+        TypeAnnotationKind::Array { item_type, size } => {
+            v.visit_type(item_type);
+            v.visit_expr(size);
+        }
         TypeAnnotationKind::FnType {
             params,
             return_type,
@@ -366,6 +485,8 @@ pub fn walk_type<'ast, V: ASTVisitor<'ast>>(v: &mut V, ty: &'ast TypeAnnotation)
         TypeAnnotationKind::U16 => {}
         TypeAnnotationKind::U32 => {}
         TypeAnnotationKind::U64 => {}
+        TypeAnnotationKind::USize => {}
+        TypeAnnotationKind::ISize => {}
         TypeAnnotationKind::I8 => {}
         TypeAnnotationKind::I16 => {}
         TypeAnnotationKind::I32 => {}
@@ -0,0 +1,440 @@
+use std::{fmt, io::IsTerminal, path::PathBuf};
+
+use crate::{
+    codegen::CodegenTarget,
+    compile::{EmitKind, ErrorFormat},
+};
+
+pub const USAGE: &str = "\
+Usage: willow <command> [options] <file>
+       willow repl [options]
+
+Commands:
+    check               Type-check the program without generating code
+    build               Compile the program into an executable or C source file
+    run                 Execute the program with the HIR interpreter
+    emit <kind>         Print the program as `hir`, `c` or `asm`
+    fmt                 Rewrite the module in the canonical layout
+    repl                Evaluate statements and expressions interactively
+
+Options:
+    -o, --output <path>         Write the output to <path>
+    --target <x86_64|c>         Backend used by `build` (default: x86_64)
+    --error-format <human|short|json>
+                                How errors are reported (default: human)
+    --color <auto|always|never> Whether errors use colors (default: auto)
+    --check                     With `fmt`, fail instead of rewriting unformatted files
+    --emit <hir|c|asm>          Print the program instead, the same as `emit <kind>`
+    -h, --help                  Print this message
+    -V, --version               Print the version of the compiler";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    Check,
+    Build,
+    Run,
+    Emit(EmitKind),
+    Fmt,
+    Repl,
+    Help,
+    Version,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// Use colors only when errors are written to a terminal
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    pub fn should_color(&self) -> bool {
+        match self {
+            ColorChoice::Auto => std::io::stderr().is_terminal(),
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CliOptions {
+    pub command: Command,
+    /// The entry module, always present unless the command is Repl, Help or Version
+    pub input: Option<PathBuf>,
+    pub output: Option<PathBuf>,
+    pub target: CodegenTarget,
+    pub error_format: ErrorFormat,
+    pub color: ColorChoice,
+    /// Only report whether `fmt` would change the input
+    pub check: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CliError {
+    MissingCommand,
+    UnknownCommand(String),
+    UnknownOption(String),
+    MissingValue(String),
+    InvalidValue { option: String, value: String },
+    MissingEmitKind,
+    MissingInput,
+    UnexpectedArgument(String),
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::MissingCommand => write!(f, "no command given"),
+            CliError::UnknownCommand(command) => {
+                write!(f, "unknown command \"{}\"", command)
+            }
+            CliError::UnknownOption(option) => write!(f, "unknown option \"{}\"", option),
+            CliError::MissingValue(option) => {
+                write!(f, "option \"{}\" expects a value", option)
+            }
+            CliError::InvalidValue { option, value } => {
+                write!(f, "invalid value \"{}\" for option \"{}\"", value, option)
+            }
+            CliError::MissingEmitKind => {
+                write!(f, "`emit` expects one of \"hir\", \"c\" or \"asm\"")
+            }
+            CliError::MissingInput => write!(f, "no input file given"),
+            CliError::UnexpectedArgument(argument) => {
+                write!(f, "unexpected argument \"{}\"", argument)
+            }
+        }
+    }
+}
+
+/// Parses the arguments following the program name. Options may be given as
+/// `--name value` or `--name=value` and in any position after the command.
+pub fn parse_args<I>(args: I) -> Result<CliOptions, CliError>
+where
+    I: IntoIterator<Item = String>,
+{
+    let mut args = args.into_iter().peekable();
+
+    let command = match args.peek().map(String::as_str) {
+        // `willow --emit=hir main.wl` predates the commands
+        Some(arg) if arg == "--emit" || arg.starts_with("--emit=") => {
+            Command::Emit(EmitKind::Hir)
+        }
+        _ => parse_command(args.next().as_deref())?,
+    };
+
+    let mut options = CliOptions {
+        command,
+        input: None,
+        output: None,
+        target: CodegenTarget::default(),
+        error_format: ErrorFormat::default(),
+        color: ColorChoice::default(),
+        check: false,
+    };
+
+    if matches!(command, Command::Help | Command::Version) {
+        return Ok(options);
+    }
+
+    let mut emit_kind = None;
+
+    while let Some(arg) = args.next() {
+        if !arg.starts_with('-') || arg == "-" {
+            if matches!(command, Command::Emit(_)) && emit_kind.is_none() {
+                emit_kind = Some(parse_emit_kind(&arg)?);
+            } else if options.input.is_none() && command != Command::Repl {
+                options.input = Some(PathBuf::from(arg));
+            } else {
+                return Err(CliError::UnexpectedArgument(arg));
+            }
+            continue;
+        }
+
+        let (name, inline_value) = match arg.split_once('=') {
+            Some((name, value)) => (name.to_string(), Some(value.to_string())),
+            None => (arg.clone(), None),
+        };
+
+        if name == "-h" || name == "--help" {
+            options.command = Command::Help;
+            return Ok(options);
+        }
+
+        if name == "--check" && command == Command::Fmt && inline_value.is_none() {
+            options.check = true;
+            continue;
+        }
+
+        // `--emit` replaces a command which compiles the program
+        let is_known = match name.as_str() {
+            "-o" | "--output" | "--target" | "--error-format" | "--color" => true,
+            "--emit" => matches!(
+                command,
+                Command::Check | Command::Build | Command::Run | Command::Emit(_)
+            ),
+            _ => false,
+        };
+        if !is_known {
+            return Err(CliError::UnknownOption(arg));
+        }
+
+        let value = match inline_value.or_else(|| args.next()) {
+            Some(value) => value,
+            None => return Err(CliError::MissingValue(name)),
+        };
+
+        let invalid = || CliError::InvalidValue {
+            option: name.clone(),
+            value: value.clone(),
+        };
+
+        match name.as_str() {
+            "-o" | "--output" => options.output = Some(PathBuf::from(&value)),
+            "--emit" => emit_kind = Some(parse_emit_kind(&value).map_err(|_| invalid())?),
+            "--target" => {
+                options.target = match value.as_str() {
+                    "x86_64" => CodegenTarget::X86_64,
+                    "c" => CodegenTarget::C,
+                    _ => return Err(invalid()),
+                }
+            }
+            "--error-format" => {
+                options.error_format = match value.as_str() {
+                    "human" => ErrorFormat::Human,
+                    "short" => ErrorFormat::Short,
+                    "json" => ErrorFormat::Json,
+                    _ => return Err(invalid()),
+                }
+            }
+            "--color" => {
+                options.color = match value.as_str() {
+                    "auto" => ColorChoice::Auto,
+                    "always" => ColorChoice::Always,
+                    "never" => ColorChoice::Never,
+                    _ => return Err(invalid()),
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    if let Some(kind) = emit_kind {
+        options.command = Command::Emit(kind);
+    } else if let Command::Emit(_) = command {
+        return Err(CliError::MissingEmitKind);
+    }
+
+    if options.input.is_none() && command != Command::Repl {
+        return Err(CliError::MissingInput);
+    }
+
+    Ok(options)
+}
+
+/// The command named by the first argument
+fn parse_command(command: Option<&str>) -> Result<Command, CliError> {
+    let command = match command {
+        None => return Err(CliError::MissingCommand),
+        Some("check") => Command::Check,
+        Some("build") => Command::Build,
+        Some("run") => Command::Run,
+        // The kind is filled in by the first positional argument
+        Some("emit") => Command::Emit(EmitKind::Hir),
+        Some("fmt") => Command::Fmt,
+        Some("repl") => Command::Repl,
+        Some("-h" | "--help" | "help") => Command::Help,
+        Some("-V" | "--version") => Command::Version,
+        Some(other) => return Err(CliError::UnknownCommand(other.to_string())),
+    };
+
+    Ok(command)
+}
+
+fn parse_emit_kind(kind: &str) -> Result<EmitKind, CliError> {
+    match kind {
+        "hir" => Ok(EmitKind::Hir),
+        "c" => Ok(EmitKind::C),
+        "asm" => Ok(EmitKind::Assembly),
+        _ => Err(CliError::InvalidValue {
+            option: "emit".to_string(),
+            value: kind.to_string(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::{
+        cli::{parse_args, CliError, CliOptions, ColorChoice, Command},
+        codegen::CodegenTarget,
+        compile::{EmitKind, ErrorFormat},
+    };
+    use pretty_assertions::assert_eq;
+
+    fn parse(args: &str) -> Result<CliOptions, CliError> {
+        parse_args(args.split_whitespace().map(String::from))
+    }
+
+    #[test]
+    fn test_parse_build_options() {
+        assert_eq!(
+            parse(
+                "build main.wl -o out/app --target=c --error-format short --color=never"
+            ),
+            Ok(CliOptions {
+                command: Command::Build,
+                input: Some(PathBuf::from("main.wl")),
+                output: Some(PathBuf::from("out/app")),
+                target: CodegenTarget::C,
+                error_format: ErrorFormat::Short,
+                color: ColorChoice::Never,
+                check: false,
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_commands() {
+        let options = parse("check main.wl").unwrap();
+        assert_eq!(options.command, Command::Check);
+        assert_eq!(options.input, Some(PathBuf::from("main.wl")));
+
+        let options = parse("run main.wl").unwrap();
+        assert_eq!(options.command, Command::Run);
+        assert_eq!(options.input, Some(PathBuf::from("main.wl")));
+
+        let options = parse("build main.wl").unwrap();
+        assert_eq!(options.command, Command::Build);
+        assert_eq!(options.target, CodegenTarget::X86_64);
+        assert_eq!(options.output, None);
+
+        for args in ["-h", "--help", "help", "check main.wl --help"] {
+            assert_eq!(parse(args).unwrap().command, Command::Help);
+        }
+        for args in ["-V", "--version"] {
+            assert_eq!(parse(args).unwrap().command, Command::Version);
+        }
+    }
+
+    #[test]
+    fn test_parse_long_options() {
+        let options = parse(
+            "build main.wl --output app --target x86_64 --error-format=json --color always",
+        )
+        .unwrap();
+        assert_eq!(options.output, Some(PathBuf::from("app")));
+        assert_eq!(options.target, CodegenTarget::X86_64);
+        assert_eq!(options.error_format, ErrorFormat::Json);
+        assert_eq!(options.color, ColorChoice::Always);
+
+        let options = parse("check main.wl --error-format human --color=auto").unwrap();
+        assert_eq!(options.error_format, ErrorFormat::Human);
+        assert_eq!(options.color, ColorChoice::Auto);
+    }
+
+    #[test]
+    fn test_parse_emit_flag() {
+        let options = parse("--emit=hir main.wl").unwrap();
+        assert_eq!(options.command, Command::Emit(EmitKind::Hir));
+        assert_eq!(options.input, Some(PathBuf::from("main.wl")));
+
+        let options = parse("--emit c main.wl").unwrap();
+        assert_eq!(options.command, Command::Emit(EmitKind::C));
+
+        let options = parse("run main.wl --emit=asm").unwrap();
+        assert_eq!(options.command, Command::Emit(EmitKind::Assembly));
+        assert_eq!(options.input, Some(PathBuf::from("main.wl")));
+
+        assert_eq!(
+            parse("fmt main.wl --emit=hir"),
+            Err(CliError::UnknownOption("--emit=hir".to_string()))
+        );
+        assert_eq!(
+            parse("--emit=llvm main.wl"),
+            Err(CliError::InvalidValue {
+                option: "--emit".to_string(),
+                value: "llvm".to_string()
+            })
+        );
+        assert_eq!(
+            parse("--emit"),
+            Err(CliError::MissingValue("--emit".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse_emit() {
+        let options = parse("emit asm main.wl").unwrap();
+        assert_eq!(options.command, Command::Emit(EmitKind::Assembly));
+        assert_eq!(options.input, Some(PathBuf::from("main.wl")));
+
+        assert_eq!(parse("emit"), Err(CliError::MissingEmitKind));
+        assert_eq!(
+            parse("emit llvm main.wl"),
+            Err(CliError::InvalidValue {
+                option: "emit".to_string(),
+                value: "llvm".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_fmt() {
+        let options = parse("fmt --check main.wl").unwrap();
+        assert_eq!(options.command, Command::Fmt);
+        assert!(options.check);
+        assert_eq!(options.input, Some(PathBuf::from("main.wl")));
+
+        assert_eq!(
+            parse("check main.wl --check"),
+            Err(CliError::UnknownOption("--check".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse_repl() {
+        let options = parse("repl --color never").unwrap();
+        assert_eq!(options.command, Command::Repl);
+        assert_eq!(options.color, ColorChoice::Never);
+        assert_eq!(options.input, None);
+
+        assert_eq!(
+            parse("repl main.wl"),
+            Err(CliError::UnexpectedArgument("main.wl".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(parse(""), Err(CliError::MissingCommand));
+        assert_eq!(
+            parse("main.wl"),
+            Err(CliError::UnknownCommand("main.wl".to_string()))
+        );
+        assert_eq!(parse("check"), Err(CliError::MissingInput));
+        assert_eq!(
+            parse("run main.wl other.wl"),
+            Err(CliError::UnexpectedArgument("other.wl".to_string()))
+        );
+        assert_eq!(
+            parse("build main.wl -o"),
+            Err(CliError::MissingValue("-o".to_string()))
+        );
+        assert_eq!(
+            parse("check main.wl --color=sometimes"),
+            Err(CliError::InvalidValue {
+                option: "--color".to_string(),
+                value: "sometimes".to_string()
+            })
+        );
+        assert_eq!(
+            parse("check main.wl --verbose"),
+            Err(CliError::UnknownOption("--verbose".to_string()))
+        );
+    }
+}
@@ -0,0 +1,675 @@
+use std::fmt::Write;
+
+use crate::{
+    codegen::{
+        c::{constant_name, CEmitter, STRING_EQ_FN},
+        function_symbol, instruction_destination, CodegenError,
+    },
+    hir::{
+        cfg::{
+            BasicBlock, BasicBlockId, BinaryOperationKind, ControlFlowGraph, Instruction,
+            Terminator, UnaryOperationKind, Value, ValueId,
+        },
+        types::{
+            checked_declaration::{CheckedDeclaration, CheckedFnDecl},
+            checked_type::{StructKind, Type},
+        },
+        utils::{
+            layout::get_layout_of,
+            numeric::{
+                get_integer_bit_width, get_numeric_type_rank, is_float, is_integer,
+                is_signed,
+            },
+        },
+    },
+    tokenize::NumberKind,
+};
+
+macro_rules! emit {
+    ($self:ident, $($arg:tt)*) => {{
+        $self.out.push_str("    ");
+        let _ = writeln!($self.out, $($arg)*);
+    }};
+}
+
+fn is_string_pointer(ty: &Type) -> bool {
+    matches!(
+        ty,
+        Type::Pointer { constraint, .. }
+            if matches!(**constraint, Type::Struct(StructKind::String))
+    )
+}
+
+fn is_scalar(ty: &Type) -> bool {
+    is_integer(ty) || is_float(ty) || matches!(ty, Type::Bool | Type::Pointer { .. })
+}
+
+fn float_literal(value: f64, is_f32: bool) -> String {
+    let literal = if value.is_nan() {
+        "(0.0 / 0.0)".to_string()
+    } else if value.is_infinite() {
+        if value > 0.0 {
+            "(1.0 / 0.0)".to_string()
+        } else {
+            "(-1.0 / 0.0)".to_string()
+        }
+    } else {
+        format!("{:?}", value)
+    };
+
+    if is_f32 {
+        format!("((float){})", literal)
+    } else {
+        literal
+    }
+}
+
+/// Lowers a single function body. SSA values become C locals named after their
+/// ValueId, basic blocks become labels and block parameters are assigned right
+/// before the `goto` that enters the block.
+pub struct FunctionEmitter<'a, 'b> {
+    module: &'a mut CEmitter<'b>,
+    decl: &'a CheckedFnDecl,
+    cfg: &'a ControlFlowGraph,
+    out: String,
+}
+
+impl<'a, 'b> FunctionEmitter<'a, 'b> {
+    pub fn new(module: &'a mut CEmitter<'b>, decl: &'a CheckedFnDecl) -> Self {
+        let cfg = decl.body.as_ref().unwrap_or_else(|| {
+            panic!(
+                "INTERNAL COMPILER ERROR: Function {} has no body to lower",
+                decl.id.0
+            )
+        });
+
+        Self {
+            module,
+            decl,
+            cfg,
+            out: String::new(),
+        }
+    }
+
+    pub fn emit(mut self) -> Result<String, CodegenError> {
+        let signature = self.module.function_signature(self.decl);
+        let _ = writeln!(self.out, "{} {{", signature);
+
+        self.emit_locals();
+        if let Some(environment) = self.decl.environment {
+            let c_type = self.module.c_type(&self.type_of(environment));
+            emit!(self, "{} v{} = env;", c_type, environment.0);
+        }
+
+        for block in self.sorted_blocks() {
+            let _ = writeln!(self.out, "{}:;", self.block_label(block.id));
+
+            for instruction in &block.instructions {
+                self.emit_instruction(instruction)?;
+            }
+
+            match &block.terminator {
+                Some(terminator) => self.emit_terminator(terminator),
+                None => emit!(self, "abort();"),
+            }
+        }
+
+        let _ = writeln!(self.out, "}}");
+        Ok(self.out)
+    }
+
+    fn sorted_blocks(&self) -> Vec<&'a BasicBlock> {
+        let mut blocks: Vec<&BasicBlock> = self.cfg.blocks.values().collect();
+        let entry = self.cfg.entry_block;
+        blocks.sort_by_key(|b| (b.id != entry, b.id.0));
+        blocks
+    }
+
+    fn emit_locals(&mut self) {
+        for block in self.sorted_blocks() {
+            if block.id != self.cfg.entry_block {
+                for param in &block.params {
+                    self.declare_local(*param);
+                }
+            }
+
+            for instruction in &block.instructions {
+                if let Some(destination) = instruction_destination(instruction) {
+                    self.declare_local(destination);
+                }
+
+                if let Instruction::StackAlloc { destination, count } = instruction {
+                    let pointee = self.pointee_of(*destination);
+                    let c_type = self.module.c_type(&pointee);
+                    emit!(self, "{} s{}[{}];", c_type, destination.0, (*count).max(1));
+                }
+            }
+        }
+    }
+
+    fn declare_local(&mut self, id: ValueId) {
+        let ty = self.type_of(id);
+        if self.size_of(&ty) == 0 {
+            return;
+        }
+        let c_type = self.module.c_type(&ty);
+        emit!(self, "{} v{};", c_type, id.0);
+    }
+
+    fn type_of(&self, id: ValueId) -> Type {
+        self.module.program.get_value_id_type(&id)
+    }
+
+    fn value_type(&self, value: &Value) -> Type {
+        self.module.program.get_value_type(value)
+    }
+
+    fn size_of(&self, ty: &Type) -> usize {
+        get_layout_of(ty, self.module.program).size
+    }
+
+    fn pointee_of(&self, ptr: ValueId) -> Type {
+        match self.type_of(ptr) {
+            Type::Pointer { constraint, .. } => *constraint,
+            other => panic!(
+                "INTERNAL COMPILER ERROR: Expected value {} to be a pointer, found {:?}",
+                ptr.0, other
+            ),
+        }
+    }
+
+    fn block_label(&self, id: BasicBlockId) -> String {
+        format!("bb{}", id.0)
+    }
+
+    fn value_expr(&mut self, value: &Value) -> String {
+        match value {
+            Value::VoidLiteral => "0".to_string(),
+            Value::BoolLiteral(b) => (if *b { "1" } else { "0" }).to_string(),
+            Value::NumberLiteral(kind) => match kind {
+                NumberKind::I64(n) if *n == i64::MIN => "INT64_MIN".to_string(),
+                NumberKind::ISize(n) if *n == isize::MIN => "INTPTR_MIN".to_string(),
+                NumberKind::I64(n) => format!("((int64_t){})", n),
+                NumberKind::I32(n) => format!("((int32_t){})", n),
+                NumberKind::I16(n) => format!("((int16_t){})", n),
+                NumberKind::I8(n) => format!("((int8_t){})", n),
+                NumberKind::ISize(n) => format!("((intptr_t){})", n),
+                NumberKind::U64(n) => format!("UINT64_C({})", n),
+                NumberKind::U32(n) => format!("((uint32_t){}u)", n),
+                NumberKind::U16(n) => format!("((uint16_t){}u)", n),
+                NumberKind::U8(n) => format!("((uint8_t){}u)", n),
+                NumberKind::USize(n) => format!("((uintptr_t)UINT64_C({}))", n),
+                NumberKind::F32(n) => float_literal(*n as f64, true),
+                NumberKind::F64(n) => float_literal(*n, false),
+            },
+            Value::Function(id) => {
+                let fn_type = self.value_type(value);
+                format!(
+                    "(({}){{ {}, NULL }})",
+                    self.module.c_type(&fn_type),
+                    function_symbol(self.module.program, *id)
+                )
+            }
+            Value::Use(id) => format!("v{}", id.0),
+        }
+    }
+
+    /// The value converted to `target`, or None when `target` is zero-sized
+    fn converted(&mut self, value: &Value, target: &Type) -> Option<String> {
+        let source = self.value_type(value);
+        let expr = self.value_expr(value);
+        self.module.convert(&expr, &source, target)
+    }
+
+    /// Extends an integer to 64 bits according to its own signedness
+    fn widened(&mut self, value: &Value) -> String {
+        let ty = self.value_type(value);
+        let expr = self.value_expr(value);
+        if is_signed(&ty) {
+            format!("(uint64_t)(int64_t){}", expr)
+        } else {
+            format!("(uint64_t){}", expr)
+        }
+    }
+
+    fn emit_instruction(
+        &mut self,
+        instruction: &Instruction,
+    ) -> Result<(), CodegenError> {
+        match instruction {
+            Instruction::StackAlloc { destination, .. } => {
+                emit!(self, "v{} = s{};", destination.0, destination.0);
+            }
+            Instruction::HeapAlloc { destination, count } => {
+                let pointee = self.pointee_of(*destination);
+                let size = self.size_of(&pointee);
+                let c_type = self.module.c_type(&self.type_of(*destination));
+                let count = self.value_expr(count);
+                emit!(
+                    self,
+                    "v{} = ({})malloc({} * (size_t){});",
+                    destination.0,
+                    c_type,
+                    size,
+                    count
+                );
+            }
+            Instruction::HeapFree { ptr } => emit!(self, "free(v{});", ptr.0),
+            Instruction::Store { ptr, value } => {
+                let target = self.pointee_of(*ptr);
+                if let Some(expr) = self.converted(value, &target) {
+                    emit!(self, "*v{} = {};", ptr.0, expr);
+                }
+            }
+            Instruction::Load { destination, ptr } => {
+                let source = self.pointee_of(*ptr);
+                let target = self.type_of(*destination);
+                let from = format!("(*v{})", ptr.0);
+                if let Some(expr) = self.module.convert(&from, &source, &target) {
+                    emit!(self, "v{} = {};", destination.0, expr);
+                }
+            }
+            Instruction::LoadConstant {
+                destination,
+                constant_id,
+            } => {
+                emit!(
+                    self,
+                    "v{} = (uint8_t*){};",
+                    destination.0,
+                    constant_name(constant_id.0)
+                );
+            }
+            Instruction::UnaryOp {
+                op_kind,
+                destination,
+                operand,
+            } => {
+                let ty = self.value_type(operand);
+                let c_type = self.module.c_type(&ty);
+                let expr = self.value_expr(operand);
+                match op_kind {
+                    UnaryOperationKind::Not => {
+                        emit!(self, "v{} = !{};", destination.0, expr)
+                    }
+                    UnaryOperationKind::Neg if is_float(&ty) => {
+                        emit!(self, "v{} = -{};", destination.0, expr)
+                    }
+                    // Negating through uint64_t wraps instead of overflowing
+                    UnaryOperationKind::Neg => emit!(
+                        self,
+                        "v{} = ({})(0 - (uint64_t){});",
+                        destination.0,
+                        c_type,
+                        expr
+                    ),
+                    UnaryOperationKind::BitNot => {
+                        emit!(self, "v{} = ({})~{};", destination.0, c_type, expr)
+                    }
+                }
+            }
+            Instruction::BinaryOp {
+                op_kind,
+                destination,
+                left,
+                right,
+            } => self.emit_binary_op(op_kind, *destination, left, right),
+            Instruction::TypeCast {
+                destination,
+                operand,
+                target_type,
+            } => {
+                if let Some(expr) = self.converted(operand, target_type) {
+                    emit!(self, "v{} = {};", destination.0, expr);
+                }
+            }
+            Instruction::FunctionCall {
+                destination,
+                function_rvalue,
+                args,
+            } => self.emit_function_call(*destination, function_rvalue, args),
+            Instruction::GetFieldPtr {
+                destination,
+                base_ptr,
+                field_index,
+            } => {
+                let field = match self.pointee_of(*base_ptr) {
+                    Type::Struct(kind) => self.module.field_name(&kind, *field_index),
+                    other => panic!(
+                        "INTERNAL COMPILER ERROR: GetFieldPtr on a pointer to non-struct \
+                         type {:?}",
+                        other
+                    ),
+                };
+                let c_type = self.module.c_type(&self.type_of(*destination));
+                emit!(
+                    self,
+                    "v{} = ({})&v{}->{};",
+                    destination.0,
+                    c_type,
+                    base_ptr.0,
+                    field
+                );
+            }
+            Instruction::GetElementPtr {
+                destination,
+                base_ptr,
+                index,
+            } => {
+                let index = self.value_expr(index);
+                emit!(self, "v{} = v{} + {};", destination.0, base_ptr.0, index);
+            }
+            Instruction::MakeClosure {
+                destination,
+                function,
+                environment,
+            } => {
+                let c_type = self.module.c_type(&self.type_of(*destination));
+                emit!(
+                    self,
+                    "v{} = ({}){{ {}, v{} }};",
+                    destination.0,
+                    c_type,
+                    function_symbol(self.module.program, *function),
+                    environment.0
+                );
+            }
+            Instruction::Nop => {}
+            Instruction::FileOpen { .. } => {
+                return Err(CodegenError::UnsupportedInstruction("FileOpen"))
+            }
+            Instruction::FileWrite { .. } => {
+                return Err(CodegenError::UnsupportedInstruction("FileWrite"))
+            }
+            Instruction::FileRead { .. } => {
+                return Err(CodegenError::UnsupportedInstruction("FileRead"))
+            }
+            Instruction::FileClose { .. } => {
+                return Err(CodegenError::UnsupportedInstruction("FileClose"))
+            }
+            Instruction::SocketConnect { .. } => {
+                return Err(CodegenError::UnsupportedInstruction("SocketConnect"))
+            }
+            Instruction::SocketSend { .. } => {
+                return Err(CodegenError::UnsupportedInstruction("SocketSend"))
+            }
+            Instruction::SocketClose { .. } => {
+                return Err(CodegenError::UnsupportedInstruction("SocketClose"))
+            }
+        };
+
+        Ok(())
+    }
+
+    fn emit_binary_op(
+        &mut self,
+        op_kind: &BinaryOperationKind,
+        destination: ValueId,
+        left: &Value,
+        right: &Value,
+    ) {
+        let left_type = self.value_type(left);
+        let right_type = self.value_type(right);
+        let common_type =
+            if get_numeric_type_rank(&left_type) >= get_numeric_type_rank(&right_type) {
+                left_type.clone()
+            } else {
+                right_type.clone()
+            };
+
+        if matches!(
+            op_kind,
+            BinaryOperationKind::ShiftLeft | BinaryOperationKind::ShiftRight
+        ) {
+            self.emit_shift(op_kind, destination, left, right);
+            return;
+        }
+
+        if matches!(
+            op_kind,
+            BinaryOperationKind::Equal | BinaryOperationKind::NotEqual
+        ) {
+            let equals = self.equality_expr(left, right);
+            if matches!(op_kind, BinaryOperationKind::Equal) {
+                emit!(self, "v{} = {};", destination.0, equals);
+            } else {
+                emit!(self, "v{} = !{};", destination.0, equals);
+            }
+            return;
+        }
+
+        let common = self.module.c_type(&common_type);
+        let destination_type = self.module.c_type(&self.type_of(destination));
+        let l = format!("({}){}", common, self.value_expr(left));
+        let r = format!("({}){}", common, self.value_expr(right));
+
+        let operator = match op_kind {
+            BinaryOperationKind::Add => "+",
+            BinaryOperationKind::Subtract => "-",
+            BinaryOperationKind::Multiply => "*",
+            BinaryOperationKind::Divide => "/",
+            BinaryOperationKind::Modulo => "%",
+            BinaryOperationKind::LessThan => "<",
+            BinaryOperationKind::LessThanOrEqual => "<=",
+            BinaryOperationKind::GreaterThan => ">",
+            BinaryOperationKind::GreaterThanOrEqual => ">=",
+            BinaryOperationKind::BitAnd => "&",
+            BinaryOperationKind::BitOr => "|",
+            BinaryOperationKind::BitXor => "^",
+            BinaryOperationKind::Equal
+            | BinaryOperationKind::NotEqual
+            | BinaryOperationKind::ShiftLeft
+            | BinaryOperationKind::ShiftRight => unreachable!(),
+        };
+
+        match op_kind {
+            BinaryOperationKind::Modulo if is_float(&common_type) => {
+                let function = if common_type == Type::F32 {
+                    "fmodf"
+                } else {
+                    "fmod"
+                };
+                emit!(self, "v{} = {}({}, {});", destination.0, function, l, r);
+            }
+            // Integer arithmetic goes through uint64_t so overflow wraps like the
+            // native backend instead of being undefined behavior
+            BinaryOperationKind::Add
+            | BinaryOperationKind::Subtract
+            | BinaryOperationKind::Multiply
+                if !is_float(&common_type) =>
+            {
+                emit!(
+                    self,
+                    "v{} = ({})((uint64_t){} {} (uint64_t){});",
+                    destination.0,
+                    destination_type,
+                    l,
+                    operator,
+                    r
+                );
+            }
+            _ => emit!(self, "v{} = {} {} {};", destination.0, l, operator, r),
+        }
+    }
+
+    /// The amount wraps around the width of the left side, which also keeps the shift
+    /// defined in C. Left shifts go through uint64_t, right shifts through int64_t for
+    /// signed integers so they are arithmetic.
+    fn emit_shift(
+        &mut self,
+        op_kind: &BinaryOperationKind,
+        destination: ValueId,
+        left: &Value,
+        right: &Value,
+    ) {
+        let left_type = self.value_type(left);
+        let c_type = self.module.c_type(&left_type);
+        let l = format!("({}){}", c_type, self.value_expr(left));
+        let amount = format!(
+            "((uint64_t){} & {})",
+            self.value_expr(right),
+            get_integer_bit_width(&left_type) - 1
+        );
+
+        let (wide_type, operator) = match op_kind {
+            BinaryOperationKind::ShiftLeft => ("uint64_t", "<<"),
+            _ if is_signed(&left_type) => ("int64_t", ">>"),
+            _ => ("uint64_t", ">>"),
+        };
+
+        emit!(
+            self,
+            "v{} = ({})(({}){} {} {});",
+            destination.0,
+            c_type,
+            wide_type,
+            l,
+            operator,
+            amount
+        );
+    }
+
+    fn equality_expr(&mut self, left: &Value, right: &Value) -> String {
+        let left_type = self.value_type(left);
+        let right_type = self.value_type(right);
+
+        if is_string_pointer(&left_type) && is_string_pointer(&right_type) {
+            let l = self.value_expr(left);
+            let r = self.value_expr(right);
+            format!("{}({}, {})", STRING_EQ_FN, l, r)
+        } else if is_integer(&left_type) && is_integer(&right_type) {
+            let l = self.widened(left);
+            let r = self.widened(right);
+            format!("({} == {})", l, r)
+        } else if is_scalar(&left_type) {
+            let l = self.value_expr(left);
+            let r = self.value_expr(right);
+            format!("({} == {})", l, r)
+        } else {
+            let helper = self.module.equality_helper(&left_type, &right_type);
+            let l = self.value_expr(left);
+            let r = self.value_expr(right);
+            format!("{}(&{}, &{})", helper, l, r)
+        }
+    }
+
+    fn emit_function_call(
+        &mut self,
+        destination: Option<ValueId>,
+        function: &Value,
+        args: &[Value],
+    ) {
+        let param_types: Vec<Type> = match function {
+            Value::Function(id) => match self.module.program.get_declaration(*id) {
+                CheckedDeclaration::Function(f) => {
+                    f.params.iter().map(|p| p.ty.clone()).collect()
+                }
+                _ => panic!(
+                    "INTERNAL COMPILER ERROR: Value::Function(DeclarationId) contained \
+                     non-function declaration id"
+                ),
+            },
+            other => match self.value_type(other) {
+                Type::Fn(fn_type) => {
+                    fn_type.params.iter().map(|p| p.ty.clone()).collect()
+                }
+                ty => panic!(
+                    "INTERNAL COMPILER ERROR: Cannot call value of type {:?}",
+                    ty
+                ),
+            },
+        };
+
+        let mut args: Vec<String> = args
+            .iter()
+            .zip(&param_types)
+            .filter_map(|(arg, param_type)| self.converted(arg, param_type))
+            .collect();
+
+        // Calls through a function value pass its environment along
+        let callee = match function {
+            Value::Function(id) => {
+                args.push("NULL".to_string());
+                function_symbol(self.module.program, *id)
+            }
+            other => {
+                let callee = self.value_expr(other);
+                args.push(format!("{}.env", callee));
+                format!("{}.fn", callee)
+            }
+        };
+
+        let call = format!("{}({})", callee, args.join(", "));
+        match destination {
+            Some(destination) if self.size_of(&self.type_of(destination)) > 0 => {
+                emit!(self, "v{} = {};", destination.0, call)
+            }
+            _ => emit!(self, "{};", call),
+        }
+    }
+
+    fn emit_terminator(&mut self, terminator: &Terminator) {
+        match terminator {
+            Terminator::Jump { target, args } => {
+                emit!(self, "{{");
+                self.emit_jump(*target, args, 2);
+                emit!(self, "}}");
+            }
+            Terminator::CondJump {
+                condition,
+                true_target,
+                true_args,
+                false_target,
+                false_args,
+            } => {
+                let condition = self.value_expr(condition);
+                emit!(self, "if ({}) {{", condition);
+                self.emit_jump(*true_target, true_args, 2);
+                emit!(self, "}} else {{");
+                self.emit_jump(*false_target, false_args, 2);
+                emit!(self, "}}");
+            }
+            Terminator::Return { value } => {
+                let return_type = self.decl.return_type.clone();
+                match value.as_ref().and_then(|v| self.converted(v, &return_type)) {
+                    Some(expr) => emit!(self, "return {};", expr),
+                    None => emit!(self, "return;"),
+                }
+            }
+            Terminator::Unreachable => emit!(self, "abort();"),
+        }
+    }
+
+    /// Block arguments are staged in temporaries first so a block may receive its own
+    /// parameters in a different order. The caller opens the enclosing C scope.
+    fn emit_jump(&mut self, target: BasicBlockId, args: &[Value], depth: usize) {
+        let params = self.cfg.blocks[&target].params.clone();
+        if params.len() != args.len() {
+            panic!(
+                "INTERNAL COMPILER ERROR: Block {} expects {} arguments, received {}",
+                target.0,
+                params.len(),
+                args.len()
+            );
+        }
+
+        let indent = "    ".repeat(depth);
+        let mut assignments = Vec::new();
+
+        for (index, (param, arg)) in params.iter().zip(args).enumerate() {
+            let param_type = self.type_of(*param);
+            if let Some(expr) = self.converted(arg, &param_type) {
+                let c_type = self.module.c_type(&param_type);
+                let _ =
+                    writeln!(self.out, "{}{} arg{} = {};", indent, c_type, index, expr);
+                assignments.push((*param, index));
+            }
+        }
+
+        for (param, index) in assignments {
+            let _ = writeln!(self.out, "{}v{} = arg{};", indent, param.0, index);
+        }
+
+        let _ = writeln!(self.out, "{}goto {};", indent, self.block_label(target));
+    }
+}
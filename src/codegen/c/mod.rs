@@ -0,0 +1,751 @@
+use std::{collections::HashMap, fmt::Write};
+
+use crate::{
+    ast::DeclarationId,
+    codegen::{
+        c::function::FunctionEmitter, function_symbol, tagged_payload, CodegenError,
+    },
+    hir::{
+        types::{
+            checked_declaration::{CheckedDeclaration, CheckedFnDecl},
+            checked_type::{StructKind, Type},
+        },
+        utils::{
+            layout::{get_field_offset, get_layout_of},
+            numeric::{is_float, is_integer},
+        },
+        ProgramBuilder,
+    },
+};
+
+mod function;
+
+const STRING_EQ_FN: &str = "willow_string_eq";
+
+const C_KEYWORDS: &[&str] = &[
+    "auto", "break", "case", "char", "const", "continue", "default", "do", "double",
+    "else", "enum", "extern", "float", "for", "goto", "if", "inline", "int", "long",
+    "register", "restrict", "return", "short", "signed", "sizeof", "static", "struct",
+    "switch", "typedef", "union", "unsigned", "void", "volatile", "while", "bool",
+];
+
+/// Translates the whole program into a single C11 translation unit. Structs keep the
+/// field order produced by `pack_struct`, so the C compiler computes the same layout
+/// as `get_layout_of`.
+pub fn generate_c_source(
+    program: &ProgramBuilder,
+    entry: DeclarationId,
+) -> Result<String, CodegenError> {
+    let entry_fn = match program.get_declaration(entry) {
+        CheckedDeclaration::Function(f) => f,
+        _ => return Err(CodegenError::MissingEntryPoint),
+    };
+    if !entry_fn.params.is_empty() {
+        return Err(CodegenError::InvalidEntryPoint);
+    }
+
+    let mut emitter = CEmitter::new(program);
+
+    let mut functions: Vec<&CheckedFnDecl> = program
+        .declarations
+        .values()
+        .filter_map(|decl| match decl {
+            CheckedDeclaration::Function(f) if f.body.is_some() => Some(f),
+            _ => None,
+        })
+        .collect();
+    functions.sort_by_key(|f| f.id.0);
+
+    let mut prototypes = String::new();
+    let mut bodies = String::new();
+    for f in functions {
+        let _ = writeln!(prototypes, "{};", emitter.function_signature(f));
+        bodies.push_str(&FunctionEmitter::new(&mut emitter, f).emit()?);
+        bodies.push('\n');
+    }
+
+    let main_fn = emitter.entry_point(entry_fn);
+
+    let mut out = String::new();
+    out.push_str("#include <math.h>\n");
+    out.push_str("#include <stdint.h>\n");
+    out.push_str("#include <stdlib.h>\n");
+    out.push_str("#include <string.h>\n\n");
+    out.push_str(&emitter.forward_declarations);
+    out.push('\n');
+    out.push_str(&emitter.type_definitions);
+    out.push_str(&emitter.constant_data());
+    out.push('\n');
+    out.push_str(&emitter.runtime());
+    out.push('\n');
+    out.push_str(&prototypes);
+    out.push('\n');
+    out.push_str(&emitter.helpers);
+    out.push_str(&bodies);
+    out.push_str(&main_fn);
+
+    Ok(out)
+}
+
+/// Program-wide state: the C names given to Willow types and the helper functions
+/// generated on demand while lowering function bodies.
+pub struct CEmitter<'a> {
+    program: &'a ProgramBuilder,
+    type_names: HashMap<Type, String>,
+    forward_declarations: String,
+    type_definitions: String,
+    helpers: String,
+    conversions: HashMap<(Type, Type), String>,
+    equalities: HashMap<(Type, Type), String>,
+    name_counter: usize,
+}
+
+impl<'a> CEmitter<'a> {
+    fn new(program: &'a ProgramBuilder) -> Self {
+        Self {
+            program,
+            type_names: HashMap::new(),
+            forward_declarations: String::new(),
+            type_definitions: String::new(),
+            helpers: String::new(),
+            conversions: HashMap::new(),
+            equalities: HashMap::new(),
+            name_counter: 0,
+        }
+    }
+
+    fn next_name(&mut self, prefix: &str) -> String {
+        self.name_counter += 1;
+        format!("willow_{}_{}", prefix, self.name_counter)
+    }
+
+    fn size_of(&self, ty: &Type) -> usize {
+        get_layout_of(ty, self.program).size
+    }
+
+    pub fn c_type(&mut self, ty: &Type) -> String {
+        match ty {
+            Type::Void => "void".to_string(),
+            Type::Bool | Type::U8 => "uint8_t".to_string(),
+            Type::U16 => "uint16_t".to_string(),
+            Type::U32 => "uint32_t".to_string(),
+            Type::U64 => "uint64_t".to_string(),
+            Type::USize => "uintptr_t".to_string(),
+            Type::I8 => "int8_t".to_string(),
+            Type::I16 => "int16_t".to_string(),
+            Type::I32 => "int32_t".to_string(),
+            Type::I64 => "int64_t".to_string(),
+            Type::ISize => "intptr_t".to_string(),
+            Type::F32 => "float".to_string(),
+            Type::F64 => "double".to_string(),
+            Type::Pointer { constraint, .. } => format!("{}*", self.c_type(constraint)),
+            Type::Struct(kind) => self.struct_type(ty, kind),
+            Type::Fn(fn_type) => {
+                if let Some(name) = self.type_names.get(ty) {
+                    return name.clone();
+                }
+
+                let name = self.next_name("fn");
+                self.type_names.insert(ty.clone(), name.clone());
+
+                let return_type = self.c_type(&fn_type.return_type);
+                let params = self.c_param_list(
+                    &fn_type
+                        .params
+                        .iter()
+                        .map(|p| p.ty.clone())
+                        .collect::<Vec<_>>(),
+                );
+                let _ = writeln!(
+                    self.forward_declarations,
+                    "typedef {} (*{}_code)({});",
+                    return_type, name, params
+                );
+                let _ = writeln!(
+                    self.forward_declarations,
+                    "typedef struct {{ {}_code fn; void* env; }} {};",
+                    name, name
+                );
+
+                name
+            }
+            Type::Buffer { size, alignment } => {
+                if let Some(name) = self.type_names.get(ty) {
+                    return name.clone();
+                }
+
+                let name = format!("willow_buffer_{}_{}", size, alignment);
+                self.type_names.insert(ty.clone(), name.clone());
+                let _ = writeln!(
+                    self.forward_declarations,
+                    "typedef struct {{ _Alignas({}) uint8_t bytes[{}]; }} {};",
+                    alignment,
+                    (*size).max(1),
+                    name
+                );
+
+                name
+            }
+            Type::Array { item_type, size } => {
+                if let Some(name) = self.type_names.get(ty) {
+                    return name.clone();
+                }
+
+                // Wrapped in a struct so arrays can be copied by assignment
+                let item_c_type = self.c_type(item_type);
+                let name = self.next_name("array");
+                self.type_names.insert(ty.clone(), name.clone());
+                let _ = writeln!(
+                    self.type_definitions,
+                    "typedef struct {{ {} items[{}]; }} {};\n",
+                    item_c_type,
+                    (*size).max(1),
+                    name
+                );
+
+                name
+            }
+            Type::Unknown => {
+                panic!("INTERNAL COMPILER ERROR: Unknown type reached code generation")
+            }
+        }
+    }
+
+    fn struct_type(&mut self, ty: &Type, kind: &StructKind) -> String {
+        if let Some(name) = self.type_names.get(ty) {
+            return name.clone();
+        }
+
+        let name = match kind {
+            StructKind::String => "willow_string".to_string(),
+            StructKind::UserDefined(_) => self.next_name("struct"),
+            StructKind::Tag(_) => self.next_name("tag"),
+            StructKind::Union { .. } => self.next_name("union"),
+            StructKind::List(_) => self.next_name("list"),
+            StructKind::Slice(_) => self.next_name("slice"),
+        };
+
+        // Registered before the fields so pointers back to this struct resolve to the
+        // forward declaration instead of recursing
+        self.type_names.insert(ty.clone(), name.clone());
+        let _ = writeln!(
+            self.forward_declarations,
+            "typedef struct {} {};",
+            name, name
+        );
+
+        let mut body = String::new();
+        for (index, (_, field_type)) in kind.fields(self.program).iter().enumerate() {
+            if self.size_of(field_type) == 0 {
+                continue;
+            }
+            let field_c_type = self.c_type(field_type);
+            let field_name = self.field_name(kind, index);
+            let _ = writeln!(body, "    {} {};", field_c_type, field_name);
+        }
+        if body.is_empty() {
+            body.push_str("    uint8_t unused;\n");
+        }
+
+        let _ = writeln!(self.type_definitions, "struct {} {{\n{}}};\n", name, body);
+
+        name
+    }
+
+    pub fn field_name(&self, kind: &StructKind, index: usize) -> String {
+        let (name_id, _) = kind.fields(self.program)[index];
+        let name = self.program.string_interner.resolve(name_id);
+
+        let is_valid = !name.is_empty()
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+            && !name.starts_with(|c: char| c.is_ascii_digit())
+            && !C_KEYWORDS.contains(&name.as_str());
+
+        if is_valid {
+            name
+        } else {
+            format!("field_{}", index)
+        }
+    }
+
+    /// Zero-sized parameters have no C representation and are dropped from
+    /// signatures and call sites alike
+    /// Every function takes the environment pointer of closures as its last parameter,
+    /// so all functions of one type can be called through the same pointer
+    fn c_param_list(&mut self, params: &[Type]) -> String {
+        let mut params: Vec<String> = params
+            .iter()
+            .filter(|ty| get_layout_of(ty, self.program).size > 0)
+            .map(|ty| self.c_type(ty))
+            .collect();
+        params.push("void*".to_string());
+
+        params.join(", ")
+    }
+
+    fn function_signature(&mut self, f: &CheckedFnDecl) -> String {
+        let return_type = self.c_type(&f.return_type);
+        let entry = &f.body.as_ref().unwrap().entry_block;
+        let param_ids = &f.body.as_ref().unwrap().blocks[entry].params;
+
+        let mut params: Vec<String> = param_ids
+            .iter()
+            .filter(|id| Some(**id) != f.environment)
+            .filter_map(|id| {
+                let ty = self.program.get_value_id_type(id);
+                (self.size_of(&ty) > 0).then(|| format!("{} v{}", self.c_type(&ty), id.0))
+            })
+            .collect();
+        params.push("void* env".to_string());
+
+        format!(
+            "static {} {}({})",
+            return_type,
+            function_symbol(self.program, f.id),
+            params.join(", ")
+        )
+    }
+
+    /// Returns an expression converting `expr` from `from` to the representation of
+    /// `to`, or None when the target type has no runtime representation
+    pub fn convert(&mut self, expr: &str, from: &Type, to: &Type) -> Option<String> {
+        if self.size_of(to) == 0 {
+            return None;
+        }
+
+        let from_c = self.c_type(from);
+        let to_c = self.c_type(to);
+        if from_c == to_c {
+            return Some(expr.to_string());
+        }
+
+        let is_scalar = |ty: &Type| {
+            is_integer(ty)
+                || is_float(ty)
+                || matches!(ty, Type::Bool | Type::Pointer { .. })
+        };
+        if is_scalar(from) && is_scalar(to) {
+            return Some(format!("(({})({}))", to_c, expr));
+        }
+
+        let key = (from.clone(), to.clone());
+        let helper = match self.conversions.get(&key) {
+            Some(helper) => helper.clone(),
+            None => {
+                let helper = self.next_name("convert");
+                let mut body = String::new();
+
+                let _ = writeln!(body, "static {} {}({} value) {{", to_c, helper, from_c);
+                let _ = writeln!(body, "    {} result;", to_c);
+                let _ = writeln!(body, "    memset(&result, 0, sizeof(result));");
+
+                match (
+                    tagged_payload(from, self.program),
+                    tagged_payload(to, self.program),
+                ) {
+                    (Some((from_offset, from_size)), Some((to_offset, to_size))) => {
+                        let _ = writeln!(body, "    memcpy(&result, &value, 2);");
+                        let size = from_size.min(to_size);
+                        if size > 0 {
+                            let _ = writeln!(
+                                body,
+                                "    memcpy((uint8_t*)&result + {}, (uint8_t*)&value + {}, \
+                                 {});",
+                                to_offset, from_offset, size
+                            );
+                        }
+                    }
+                    _ => {
+                        let size = self.size_of(from).min(self.size_of(to));
+                        let _ = writeln!(body, "    memcpy(&result, &value, {});", size);
+                    }
+                }
+
+                let _ = writeln!(body, "    return result;\n}}\n");
+                self.helpers.push_str(&body);
+                self.conversions.insert(key, helper.clone());
+
+                helper
+            }
+        };
+
+        Some(format!("{}({})", helper, expr))
+    }
+
+    /// Returns the name of a helper comparing two aggregates (tags, unions) through
+    /// pointers to them
+    pub fn equality_helper(&mut self, left: &Type, right: &Type) -> String {
+        let key = (left.clone(), right.clone());
+        if let Some(helper) = self.equalities.get(&key) {
+            return helper.clone();
+        }
+
+        let helper = self.next_name("equals");
+        let mut body = String::new();
+        let _ = writeln!(
+            body,
+            "static uint8_t {}(const void* left, const void* right) {{",
+            helper
+        );
+        let _ = writeln!(body, "    const uint8_t* a = left;");
+        let _ = writeln!(body, "    const uint8_t* b = right;");
+        self.compare_memory(&mut body, left, 0, right, 0, 1);
+        let _ = writeln!(body, "    return 1;\n}}\n");
+
+        self.helpers.push_str(&body);
+        self.equalities.insert(key, helper.clone());
+
+        helper
+    }
+
+    fn compare_memory(
+        &mut self,
+        out: &mut String,
+        left: &Type,
+        left_offset: usize,
+        right: &Type,
+        right_offset: usize,
+        depth: usize,
+    ) {
+        let indent = "    ".repeat(depth);
+
+        if let (Some((left_payload, _)), Some((right_payload, _))) = (
+            tagged_payload(left, self.program),
+            tagged_payload(right, self.program),
+        ) {
+            let _ = writeln!(
+                out,
+                "{}if (memcmp(a + {}, b + {}, 2) != 0) return 0;",
+                indent, left_offset, right_offset
+            );
+
+            let variants = match left {
+                Type::Struct(StructKind::Tag(tag)) => vec![tag.clone()],
+                Type::Struct(StructKind::Union { variants }) => variants.clone(),
+                _ => unreachable!(),
+            };
+            if variants.iter().all(|v| v.value_type.is_none()) {
+                return;
+            }
+
+            let _ = writeln!(out, "{}{{", indent);
+            let _ = writeln!(out, "{}    uint16_t id;", indent);
+            let _ = writeln!(out, "{}    memcpy(&id, a + {}, 2);", indent, left_offset);
+            let _ = writeln!(out, "{}    switch (id) {{", indent);
+            for variant in variants {
+                let Some(payload_type) = variant.value_type else {
+                    continue;
+                };
+                let _ = writeln!(out, "{}    case {}:", indent, variant.id.0);
+                self.compare_memory(
+                    out,
+                    &payload_type,
+                    left_offset + left_payload,
+                    &payload_type,
+                    right_offset + right_payload,
+                    depth + 2,
+                );
+                let _ = writeln!(out, "{}        break;", indent);
+            }
+            let _ = writeln!(out, "{}    }}", indent);
+            let _ = writeln!(out, "{}}}", indent);
+            return;
+        }
+
+        match left {
+            Type::F32 | Type::F64 => {
+                let c_type = self.c_type(left);
+                let _ = writeln!(out, "{}{{", indent);
+                let _ = writeln!(out, "{}    {} x, y;", indent, c_type);
+                let _ = writeln!(
+                    out,
+                    "{}    memcpy(&x, a + {}, sizeof(x));",
+                    indent, left_offset
+                );
+                let _ = writeln!(
+                    out,
+                    "{}    memcpy(&y, b + {}, sizeof(y));",
+                    indent, right_offset
+                );
+                let _ = writeln!(out, "{}    if (!(x == y)) return 0;", indent);
+                let _ = writeln!(out, "{}}}", indent);
+            }
+            Type::Pointer { constraint, .. }
+                if matches!(**constraint, Type::Struct(StructKind::String)) =>
+            {
+                let _ = writeln!(out, "{}{{", indent);
+                let _ = writeln!(out, "{}    const void* x;", indent);
+                let _ = writeln!(out, "{}    const void* y;", indent);
+                let _ = writeln!(
+                    out,
+                    "{}    memcpy(&x, a + {}, sizeof(x));",
+                    indent, left_offset
+                );
+                let _ = writeln!(
+                    out,
+                    "{}    memcpy(&y, b + {}, sizeof(y));",
+                    indent, right_offset
+                );
+                let _ =
+                    writeln!(out, "{}    if (!{}(x, y)) return 0;", indent, STRING_EQ_FN);
+                let _ = writeln!(out, "{}}}", indent);
+            }
+            _ => {
+                let size = self.size_of(left);
+                if size > 0 {
+                    let _ = writeln!(
+                        out,
+                        "{}if (memcmp(a + {}, b + {}, {}) != 0) return 0;",
+                        indent, left_offset, right_offset, size
+                    );
+                }
+            }
+        }
+    }
+
+    fn constant_data(&self) -> String {
+        let mut constants: Vec<_> = self.program.constant_data.iter().collect();
+        constants.sort_by_key(|(id, _)| id.0);
+
+        let mut out = String::new();
+        for (id, bytes) in constants {
+            let values: Vec<String> = if bytes.is_empty() {
+                vec!["0".to_string()]
+            } else {
+                bytes.iter().map(|b| b.to_string()).collect()
+            };
+
+            let _ = writeln!(
+                out,
+                "static const uint8_t {}[] = {{ {} }};",
+                constant_name(id.0),
+                values.join(", ")
+            );
+        }
+
+        out
+    }
+
+    fn runtime(&self) -> String {
+        let string_fields = StructKind::String.fields(self.program);
+        let field_offset = |name| {
+            let index = string_fields
+                .iter()
+                .position(|(field, _)| *field == name)
+                .expect("INTERNAL COMPILER ERROR: String header is missing a field");
+            get_field_offset(&StructKind::String, index, self.program)
+        };
+        let len_offset = field_offset(self.program.common_identifiers.len);
+        let ptr_offset = field_offset(self.program.common_identifiers.ptr);
+
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "static inline uint8_t {}(const void* left, const void* right) {{",
+            STRING_EQ_FN
+        );
+        let _ = writeln!(out, "    uintptr_t left_len, right_len;");
+        let _ = writeln!(out, "    const void* left_ptr;");
+        let _ = writeln!(out, "    const void* right_ptr;");
+        let _ = writeln!(
+            out,
+            "    memcpy(&left_len, (const uint8_t*)left + {}, sizeof(left_len));",
+            len_offset
+        );
+        let _ = writeln!(
+            out,
+            "    memcpy(&right_len, (const uint8_t*)right + {}, sizeof(right_len));",
+            len_offset
+        );
+        let _ = writeln!(out, "    if (left_len != right_len) return 0;");
+        let _ = writeln!(
+            out,
+            "    memcpy(&left_ptr, (const uint8_t*)left + {}, sizeof(left_ptr));",
+            ptr_offset
+        );
+        let _ = writeln!(
+            out,
+            "    memcpy(&right_ptr, (const uint8_t*)right + {}, sizeof(right_ptr));",
+            ptr_offset
+        );
+        let _ = writeln!(
+            out,
+            "    return memcmp(left_ptr, right_ptr, left_len) == 0;\n}}"
+        );
+
+        out
+    }
+
+    fn entry_point(&mut self, entry_fn: &CheckedFnDecl) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "int main(void) {{");
+
+        let call = format!("{}(NULL)", function_symbol(self.program, entry_fn.id));
+        if is_integer(&entry_fn.return_type) {
+            let _ = writeln!(out, "    return (int){};", call);
+        } else {
+            let _ = writeln!(out, "    {};", call);
+            let _ = writeln!(out, "    return 0;");
+        }
+
+        let _ = writeln!(out, "}}");
+        out
+    }
+}
+
+pub(crate) fn constant_name(id: usize) -> String {
+    format!("willow_const_{}", id)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::Path, process::Command};
+
+    use crate::{
+        ast::DeclarationId,
+        codegen::{assert_runs_like_interpreter, c::generate_c_source, CodegenError},
+        compile::check_source,
+        hir::ProgramBuilder,
+    };
+    use pretty_assertions::assert_eq;
+
+    /// Compares the C emitted for `source` with `snapshots/<name>.c`, which is
+    /// rewritten instead when `WILLOW_UPDATE_SNAPSHOTS` is set
+    fn assert_snapshot(name: &str, source: &str) {
+        let (program, entry) = check_source(name, source);
+        let emitted = generate_c_source(&program, entry).unwrap();
+
+        let snapshot_path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("src/codegen/c/snapshots")
+            .join(format!("{}.c", name));
+        if std::env::var_os("WILLOW_UPDATE_SNAPSHOTS").is_some() {
+            fs::write(&snapshot_path, &emitted).unwrap();
+        }
+
+        assert_eq!(fs::read_to_string(&snapshot_path).unwrap(), emitted);
+    }
+
+    fn build_c_executable(
+        program: &ProgramBuilder,
+        entry: DeclarationId,
+        output_path: &Path,
+    ) -> Result<(), CodegenError> {
+        let source_path = output_path.with_extension("c");
+        fs::write(&source_path, generate_c_source(program, entry)?)?;
+
+        let output = Command::new("cc")
+            .arg("-w")
+            .arg("-o")
+            .arg(output_path)
+            .arg(&source_path)
+            .arg("-lm")
+            .output();
+        let _ = fs::remove_file(&source_path);
+
+        let output = output?;
+        if !output.status.success() {
+            return Err(CodegenError::LinkerFailed(
+                String::from_utf8_lossy(&output.stderr).into_owned(),
+            ));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_function_call_snapshot() {
+        let source = r#"
+            fn add(a: i32, b: i32): i32 {
+                a + b
+            }
+
+            fn main(): i32 {
+                add(1i32, 2i32)
+            }
+        "#;
+
+        assert_snapshot("function_call", source);
+    }
+
+    #[test]
+    fn test_union_match_snapshot() {
+        let source = r#"
+            type Shape = #Circle(i64) | #Square(i64);
+
+            fn area(shape: Shape): i64 {
+                match shape {
+                    Circle(r) => 3 * r * r,
+                    Square(s) => s * s,
+                }
+            }
+
+            fn main(): i64 {
+                area(#Square(4))
+            }
+        "#;
+
+        assert_snapshot("union_match", source);
+    }
+
+    #[test]
+    fn test_struct_and_loop_snapshot() {
+        let source = r#"
+            fn main(): i64 {
+                let point: { x: i64, y: i64 } = { x: 2, y: 5 };
+                let mut total = 0;
+                while total < point.y {
+                    total += point.x;
+                }
+                total
+            }
+        "#;
+
+        assert_snapshot("struct_and_loop", source);
+    }
+
+    #[test]
+    fn test_runs_like_interpreter() {
+        let source = r#"
+            type Shape = #Circle(f64) | #Square(f64) | #Empty;
+            type Option<T> = #some(T) | #none;
+
+            fn area(s: Shape): f64 {
+                match s {
+                    Circle(r) => r * r * 3f64,
+                    Square(x) => x * x,
+                    Empty => 0f64,
+                }
+            }
+
+            fn unwrap_or<T>(opt: Option<T>, default: T): T {
+                match opt {
+                    some(v) => v,
+                    none => default,
+                }
+            }
+
+            fn sum(values: [i64]): i64 {
+                let mut total = 0;
+                for value in values {
+                    total += value;
+                }
+                total
+            }
+
+            fn main(): i64 {
+                let base = 10;
+                fn scaled(x: i64): i64 {
+                    x * base
+                }
+                let items = [1, 2, 3, 4, 5, 6];
+                let point: { x: i64, y: i64 } = { x: 2, y: 5 };
+                let none: Option<i64> = #none;
+                let mut r = 0;
+                if "hello" == "hello" { r += 1; }
+                if area(#Square(4f64)) + area(#Circle(1f64)) > 18.5f64 { r += 10; }
+                r + scaled(point.x) + sum(items[1usize..5usize]) + unwrap_or(none, 50)
+            }
+        "#;
+
+        assert_runs_like_interpreter("c_program", source, build_c_executable);
+    }
+}
@@ -0,0 +1,51 @@
+#include <math.h>
+#include <stdint.h>
+#include <stdlib.h>
+#include <string.h>
+
+
+
+static inline uint8_t willow_string_eq(const void* left, const void* right) {
+    uintptr_t left_len, right_len;
+    const void* left_ptr;
+    const void* right_ptr;
+    memcpy(&left_len, (const uint8_t*)left + 8, sizeof(left_len));
+    memcpy(&right_len, (const uint8_t*)right + 8, sizeof(right_len));
+    if (left_len != right_len) return 0;
+    memcpy(&left_ptr, (const uint8_t*)left + 16, sizeof(left_ptr));
+    memcpy(&right_ptr, (const uint8_t*)right + 16, sizeof(right_ptr));
+    return memcmp(left_ptr, right_ptr, left_len) == 0;
+}
+
+static int32_t willow_add_0(int32_t v0, int32_t v2, void* env);
+static int32_t willow_main_1(void* env);
+
+static int32_t willow_add_0(int32_t v0, int32_t v2, void* env) {
+    int32_t* v1;
+    int32_t s1[1];
+    int32_t* v3;
+    int32_t s3[1];
+    int32_t v4;
+    int32_t v5;
+    int32_t v6;
+bb0:;
+    v1 = s1;
+    *v1 = v0;
+    v3 = s3;
+    *v3 = v2;
+    v4 = (*v1);
+    v5 = (*v3);
+    v6 = (int32_t)((uint64_t)(int32_t)v4 + (uint64_t)(int32_t)v5);
+    return v6;
+}
+
+static int32_t willow_main_1(void* env) {
+    int32_t v7;
+bb0:;
+    v7 = willow_add_0(((int32_t)1), ((int32_t)2), NULL);
+    return v7;
+}
+
+int main(void) {
+    return (int)willow_main_1(NULL);
+}
@@ -0,0 +1,113 @@
+#include <math.h>
+#include <stdint.h>
+#include <stdlib.h>
+#include <string.h>
+
+typedef struct willow_struct_1 willow_struct_1;
+
+struct willow_struct_1 {
+    int64_t x;
+    int64_t y;
+};
+
+
+static inline uint8_t willow_string_eq(const void* left, const void* right) {
+    uintptr_t left_len, right_len;
+    const void* left_ptr;
+    const void* right_ptr;
+    memcpy(&left_len, (const uint8_t*)left + 8, sizeof(left_len));
+    memcpy(&right_len, (const uint8_t*)right + 8, sizeof(right_len));
+    if (left_len != right_len) return 0;
+    memcpy(&left_ptr, (const uint8_t*)left + 16, sizeof(left_ptr));
+    memcpy(&right_ptr, (const uint8_t*)right + 16, sizeof(right_ptr));
+    return memcmp(left_ptr, right_ptr, left_len) == 0;
+}
+
+static int64_t willow_main_2(void* env);
+
+static int64_t willow_main_2(void* env) {
+    willow_struct_1* v0;
+    int64_t* v1;
+    int64_t* v2;
+    willow_struct_1** v3;
+    willow_struct_1* s3[1];
+    willow_struct_1** v4;
+    int64_t* v5;
+    int64_t s5[1];
+    int64_t v6;
+    int64_t* v7;
+    int64_t* v8;
+    willow_struct_1** v10;
+    int64_t v9;
+    willow_struct_1* v11;
+    int64_t* v12;
+    int64_t v13;
+    uint8_t v14;
+    int64_t* v15;
+    willow_struct_1** v17;
+    int64_t v16;
+    willow_struct_1* v18;
+    int64_t* v19;
+    int64_t v20;
+    int64_t v21;
+    int64_t* v22;
+    int64_t v23;
+bb0:;
+    v0 = (willow_struct_1*)malloc(16 * (size_t)((uintptr_t)UINT64_C(1)));
+    v1 = (int64_t*)&v0->x;
+    *v1 = ((int64_t)2);
+    v2 = (int64_t*)&v0->y;
+    *v2 = ((int64_t)5);
+    v3 = s3;
+    *v3 = v0;
+    v4 = v3;
+    v5 = s5;
+    v6 = ((int64_t)0);
+    *v5 = v6;
+    v7 = v5;
+    {
+        int64_t* arg0 = v7;
+        willow_struct_1** arg1 = v4;
+        v8 = arg0;
+        v10 = arg1;
+        goto bb1;
+    }
+bb1:;
+    v9 = (*v8);
+    v11 = (*v10);
+    v12 = (int64_t*)&v11->y;
+    v13 = (*v12);
+    v14 = (int64_t)v9 < (int64_t)v13;
+    if (v14) {
+        int64_t* arg0 = v8;
+        willow_struct_1** arg1 = v10;
+        v15 = arg0;
+        v17 = arg1;
+        goto bb2;
+    } else {
+        int64_t* arg0 = v8;
+        v22 = arg0;
+        goto bb3;
+    }
+bb2:;
+    v16 = (*v15);
+    v18 = (*v17);
+    v19 = (int64_t*)&v18->x;
+    v20 = (*v19);
+    v21 = (int64_t)((uint64_t)(int64_t)v16 + (uint64_t)(int64_t)v20);
+    *v15 = v21;
+    {
+        int64_t* arg0 = v15;
+        willow_struct_1** arg1 = v17;
+        v8 = arg0;
+        v10 = arg1;
+        goto bb1;
+    }
+bb3:;
+    v23 = (*v22);
+    return v23;
+}
+
+int main(void) {
+    return (int)willow_main_2(NULL);
+}
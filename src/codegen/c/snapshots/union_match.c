@@ -0,0 +1,149 @@
+#include <math.h>
+#include <stdint.h>
+#include <stdlib.h>
+#include <string.h>
+
+typedef struct willow_union_1 willow_union_1;
+typedef struct { _Alignas(8) uint8_t bytes[8]; } willow_buffer_8_8;
+typedef struct willow_tag_3 willow_tag_3;
+
+struct willow_union_1 {
+    uint16_t id;
+    willow_buffer_8_8 value;
+};
+
+struct willow_tag_3 {
+    uint16_t id;
+    int64_t value;
+};
+
+
+static inline uint8_t willow_string_eq(const void* left, const void* right) {
+    uintptr_t left_len, right_len;
+    const void* left_ptr;
+    const void* right_ptr;
+    memcpy(&left_len, (const uint8_t*)left + 8, sizeof(left_len));
+    memcpy(&right_len, (const uint8_t*)right + 8, sizeof(right_len));
+    if (left_len != right_len) return 0;
+    memcpy(&left_ptr, (const uint8_t*)left + 16, sizeof(left_ptr));
+    memcpy(&right_ptr, (const uint8_t*)right + 16, sizeof(right_ptr));
+    return memcmp(left_ptr, right_ptr, left_len) == 0;
+}
+
+static int64_t willow_area_1(willow_union_1 v0, void* env);
+static int64_t willow_main_2(void* env);
+
+static int64_t willow_convert_2(willow_buffer_8_8 value) {
+    int64_t result;
+    memset(&result, 0, sizeof(result));
+    memcpy(&result, &value, 8);
+    return result;
+}
+
+static willow_union_1 willow_convert_4(willow_tag_3 value) {
+    willow_union_1 result;
+    memset(&result, 0, sizeof(result));
+    memcpy(&result, &value, 2);
+    memcpy((uint8_t*)&result + 8, (uint8_t*)&value + 8, 8);
+    return result;
+}
+
+static int64_t willow_area_1(willow_union_1 v0, void* env) {
+    willow_union_1* v1;
+    willow_union_1 s1[1];
+    uint16_t* v2;
+    uint16_t v3;
+    uint8_t v4;
+    int64_t v21;
+    willow_union_1* v5;
+    willow_buffer_8_8* v6;
+    int64_t v7;
+    int64_t* v8;
+    int64_t s8[1];
+    int64_t v9;
+    int64_t v10;
+    int64_t v11;
+    int64_t v12;
+    uint8_t v13;
+    willow_union_1* v14;
+    willow_buffer_8_8* v15;
+    int64_t v16;
+    int64_t* v17;
+    int64_t s17[1];
+    int64_t v18;
+    int64_t v19;
+    int64_t v20;
+bb0:;
+    v1 = s1;
+    *v1 = v0;
+    v2 = (uint16_t*)&v1->id;
+    v3 = (*v2);
+    v4 = ((uint64_t)v3 == (uint64_t)((uint16_t)0u));
+    if (v4) {
+        goto bb2;
+    } else {
+        goto bb3;
+    }
+bb1:;
+    return v21;
+bb2:;
+    v5 = v1;
+    v6 = (willow_buffer_8_8*)&v5->value;
+    v7 = willow_convert_2((*v6));
+    v8 = s8;
+    *v8 = v7;
+    v9 = (*v8);
+    v10 = (int64_t)((uint64_t)(int64_t)((int64_t)3) * (uint64_t)(int64_t)v9);
+    v11 = (*v8);
+    v12 = (int64_t)((uint64_t)(int64_t)v10 * (uint64_t)(int64_t)v11);
+    {
+        int64_t arg0 = v12;
+        v21 = arg0;
+        goto bb1;
+    }
+bb3:;
+    v13 = ((uint64_t)v3 == (uint64_t)((uint16_t)1u));
+    if (v13) {
+        goto bb4;
+    } else {
+        goto bb5;
+    }
+bb4:;
+    v14 = v1;
+    v15 = (willow_buffer_8_8*)&v14->value;
+    v16 = willow_convert_2((*v15));
+    v17 = s17;
+    *v17 = v16;
+    v18 = (*v17);
+    v19 = (*v17);
+    v20 = (int64_t)((uint64_t)(int64_t)v18 * (uint64_t)(int64_t)v19);
+    {
+        int64_t arg0 = v20;
+        v21 = arg0;
+        goto bb1;
+    }
+bb5:;
+    abort();
+}
+
+static int64_t willow_main_2(void* env) {
+    willow_tag_3* v22;
+    willow_tag_3 s22[1];
+    uint16_t* v23;
+    int64_t* v24;
+    willow_tag_3 v25;
+    int64_t v26;
+bb0:;
+    v22 = s22;
+    v23 = (uint16_t*)&v22->id;
+    *v23 = ((uint16_t)1u);
+    v24 = (int64_t*)&v22->value;
+    *v24 = ((int64_t)4);
+    v25 = (*v22);
+    v26 = willow_area_1(willow_convert_4(v25), NULL);
+    return v26;
+}
+
+int main(void) {
+    return (int)willow_main_2(NULL);
+}
@@ -1 +1,157 @@
+use crate::{
+    ast::DeclarationId,
+    hir::{
+        cfg::{Instruction, ValueId},
+        types::{
+            checked_declaration::CheckedDeclaration,
+            checked_type::{StructKind, Type},
+        },
+        utils::layout::{get_field_offset, get_layout_of},
+        ProgramBuilder,
+    },
+};
 
+pub mod c;
+pub mod x86_64;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CodegenTarget {
+    /// Assemble and link a native executable for x86-64 Linux
+    #[default]
+    X86_64,
+    /// Emit a single self-contained C source file
+    C,
+}
+
+#[derive(Debug)]
+pub enum CodegenError {
+    /// The entry module does not declare a `main` function
+    MissingEntryPoint,
+    /// The `main` function declares parameters, which the entry point cannot provide
+    InvalidEntryPoint,
+    /// The instruction has no lowering in this backend yet
+    UnsupportedInstruction(&'static str),
+    Io(std::io::Error),
+    /// The system assembler/linker exited unsuccessfully
+    LinkerFailed(String),
+}
+
+impl From<std::io::Error> for CodegenError {
+    fn from(error: std::io::Error) -> Self {
+        CodegenError::Io(error)
+    }
+}
+
+/// Symbol name of a function, shared by all backends so the output stays readable
+pub fn function_symbol(program: &ProgramBuilder, id: DeclarationId) -> String {
+    match program.get_declaration(id) {
+        CheckedDeclaration::Function(f) => format!(
+            "willow_{}_{}",
+            program.string_interner.resolve(f.identifier.name),
+            id.0
+        ),
+        _ => panic!(
+            "INTERNAL COMPILER ERROR: Expected declaration {} to be a function",
+            id.0
+        ),
+    }
+}
+
+/// Tags and unions share the `{ id: u16, value }` shape, but the offset and size of
+/// the payload depend on the concrete type, so moving a value between the two
+/// representations has to copy the parts separately.
+pub fn tagged_payload(ty: &Type, program: &ProgramBuilder) -> Option<(usize, usize)> {
+    match ty {
+        Type::Struct(kind @ StructKind::Tag(tag)) => match &tag.value_type {
+            Some(value_type) => Some((
+                get_field_offset(kind, 1, program),
+                get_layout_of(value_type, program).size,
+            )),
+            None => Some((2, 0)),
+        },
+        Type::Struct(kind @ StructKind::Union { .. }) => {
+            let size = match &kind.fields(program)[1].1 {
+                Type::Buffer { size, .. } => *size,
+                _ => 0,
+            };
+            Some((get_field_offset(kind, 1, program), size))
+        }
+        _ => None,
+    }
+}
+
+/// Returns the value defined by the instruction, if any
+pub fn instruction_destination(instruction: &Instruction) -> Option<ValueId> {
+    match instruction {
+        Instruction::StackAlloc { destination, .. }
+        | Instruction::HeapAlloc { destination, .. }
+        | Instruction::Load { destination, .. }
+        | Instruction::LoadConstant { destination, .. }
+        | Instruction::UnaryOp { destination, .. }
+        | Instruction::BinaryOp { destination, .. }
+        | Instruction::TypeCast { destination, .. }
+        | Instruction::MakeClosure { destination, .. }
+        | Instruction::GetFieldPtr { destination, .. }
+        | Instruction::GetElementPtr { destination, .. } => Some(*destination),
+        Instruction::FunctionCall { destination, .. } => *destination,
+        Instruction::FileOpen { destination_fd, .. } => Some(*destination_fd),
+        Instruction::FileRead {
+            value_destination, ..
+        }
+        | Instruction::SocketConnect {
+            value_destination, ..
+        } => Some(*value_destination),
+        Instruction::HeapFree { .. }
+        | Instruction::Store { .. }
+        | Instruction::FileWrite { .. }
+        | Instruction::FileClose { .. }
+        | Instruction::SocketSend { .. }
+        | Instruction::SocketClose { .. }
+        | Instruction::Nop => None,
+    }
+}
+
+/// Builds the executable for `source` with `build`, runs it and asserts that it
+/// exits with the integer the interpreter returns for the same program, printing
+/// nothing. Skipped when the system has no C compiler driver.
+#[cfg(test)]
+pub(crate) fn assert_runs_like_interpreter(
+    name: &str,
+    source: &str,
+    build: fn(
+        &ProgramBuilder,
+        DeclarationId,
+        &std::path::Path,
+    ) -> Result<(), CodegenError>,
+) {
+    use std::process::Command;
+
+    if Command::new("cc").arg("--version").output().is_err() {
+        eprintln!("Skipping {}, no C compiler driver was found", name);
+        return;
+    }
+
+    let (program, entry) = crate::compile::check_source(name, source);
+    let expected = match crate::interpret::run(&program, entry) {
+        Ok(crate::interpret::RuntimeValue::Int(value)) => (value & 0xff) as i32,
+        other => panic!(
+            "Expected the program to return an integer, found {:?}",
+            other
+        ),
+    };
+
+    let executable =
+        std::env::temp_dir().join(format!("willow-exe-{}-{}", std::process::id(), name));
+    build(&program, entry, &executable).unwrap();
+    let output = Command::new(&executable).output();
+    let _ = std::fs::remove_file(&executable);
+    let output = output.unwrap();
+
+    assert_eq!(
+        (
+            output.status.code(),
+            String::from_utf8_lossy(&output.stdout).as_ref()
+        ),
+        (Some(expected), "")
+    );
+}
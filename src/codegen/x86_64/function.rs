@@ -0,0 +1,1152 @@
+use std::{
+    collections::HashMap,
+    fmt::{self, Write},
+};
+
+use crate::{
+    codegen::{
+        function_symbol, instruction_destination, tagged_payload,
+        x86_64::{constant_label, STRING_EQ_LABEL},
+        CodegenError,
+    },
+    hir::{
+        cfg::{
+            BasicBlock, BasicBlockId, BinaryOperationKind, ControlFlowGraph, Instruction,
+            Terminator, UnaryOperationKind, Value, ValueId,
+        },
+        types::{
+            checked_declaration::{CheckedDeclaration, CheckedFnDecl},
+            checked_type::{StructKind, Type},
+        },
+        utils::{
+            layout::{get_field_offset, get_layout_of},
+            numeric::{
+                get_integer_bit_width, get_numeric_type_rank, is_float, is_integer,
+                is_signed,
+            },
+        },
+        ProgramBuilder,
+    },
+    tokenize::NumberKind,
+};
+
+/// Size of the callee-saved registers (%rbx, %r12) pushed right below %rbp
+const SAVED_REGISTERS_SIZE: usize = 16;
+
+/// Function values are `{ fn: ptr, env: ptr }`, this is the offset of the environment
+const FUNCTION_ENV_OFFSET: usize = 8;
+
+macro_rules! emit {
+    ($self:ident, $($arg:tt)*) => {{
+        $self.out.push_str("    ");
+        let _ = writeln!($self.out, $($arg)*);
+    }};
+}
+
+/// A memory operand of the form `offset(base)`
+#[derive(Clone, Copy)]
+struct Mem {
+    base: &'static str,
+    offset: i64,
+}
+
+impl Mem {
+    fn new(base: &'static str, offset: i64) -> Self {
+        Self { base, offset }
+    }
+
+    fn at(self, delta: usize) -> Self {
+        Self {
+            base: self.base,
+            offset: self.offset + delta as i64,
+        }
+    }
+}
+
+impl fmt::Display for Mem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}({})", self.offset, self.base)
+    }
+}
+
+/// Computes where each argument lives in the outgoing argument area and the total
+/// (16-byte aligned) size of that area. Shared by call sites and callee prologues.
+fn argument_area(types: &[Type], program: &ProgramBuilder) -> (Vec<usize>, usize) {
+    let mut offsets = Vec::with_capacity(types.len());
+    let mut cursor: usize = 0;
+
+    for ty in types {
+        let layout = get_layout_of(ty, program);
+        cursor = cursor.next_multiple_of(layout.alignment.max(8));
+        offsets.push(cursor);
+        cursor += layout.size;
+    }
+
+    (offsets, cursor.next_multiple_of(16))
+}
+
+fn sized_register(register: &str, size: usize) -> &'static str {
+    match (register, size) {
+        ("rax", 8) => "%rax",
+        ("rax", 4) => "%eax",
+        ("rax", 2) => "%ax",
+        ("rax", 1) => "%al",
+        ("rcx", 8) => "%rcx",
+        ("rcx", 4) => "%ecx",
+        ("rcx", 2) => "%cx",
+        ("rcx", 1) => "%cl",
+        ("r11", 8) => "%r11",
+        ("r11", 4) => "%r11d",
+        ("r11", 2) => "%r11w",
+        ("r11", 1) => "%r11b",
+        _ => panic!(
+            "INTERNAL COMPILER ERROR: No {}-byte form of register {}",
+            size, register
+        ),
+    }
+}
+
+fn size_suffix(size: usize) -> char {
+    match size {
+        8 => 'q',
+        4 => 'l',
+        2 => 'w',
+        1 => 'b',
+        _ => panic!("INTERNAL COMPILER ERROR: Invalid scalar size {}", size),
+    }
+}
+
+fn is_scalar(ty: &Type) -> bool {
+    is_integer(ty) || is_float(ty) || matches!(ty, Type::Bool | Type::Pointer { .. })
+}
+
+fn is_string_pointer(ty: &Type) -> bool {
+    matches!(
+        ty,
+        Type::Pointer { constraint, .. }
+            if matches!(**constraint, Type::Struct(StructKind::String))
+    )
+}
+
+/// Returns the raw bits of a literal, sign-extended to 64 bits for signed integers
+fn literal_bits(value: &Value) -> i64 {
+    match value {
+        Value::VoidLiteral => 0,
+        Value::BoolLiteral(b) => *b as i64,
+        Value::NumberLiteral(kind) => match kind {
+            NumberKind::I64(n) => *n,
+            NumberKind::I32(n) => *n as i64,
+            NumberKind::I16(n) => *n as i64,
+            NumberKind::I8(n) => *n as i64,
+            NumberKind::ISize(n) => *n as i64,
+            NumberKind::U64(n) => *n as i64,
+            NumberKind::U32(n) => *n as i64,
+            NumberKind::U16(n) => *n as i64,
+            NumberKind::U8(n) => *n as i64,
+            NumberKind::USize(n) => *n as i64,
+            NumberKind::F32(n) => n.to_bits() as i64,
+            NumberKind::F64(n) => n.to_bits() as i64,
+        },
+        Value::Function(_) | Value::Use(_) => {
+            panic!("INTERNAL COMPILER ERROR: Expected a literal value")
+        }
+    }
+}
+
+/// Lowers a single function. Every SSA value gets its own slot in the stack frame,
+/// instructions load their operands into scratch registers and write the result
+/// back, so no register allocation is needed.
+///
+/// Willow functions use their own calling convention: arguments are laid out in
+/// an argument area on the caller's stack (see `argument_area`) and the caller
+/// passes a pointer to the return slot in %rdi.
+pub struct FunctionEmitter<'a> {
+    program: &'a ProgramBuilder,
+    decl: &'a CheckedFnDecl,
+    cfg: &'a ControlFlowGraph,
+    label: String,
+    out: String,
+
+    slots: HashMap<ValueId, i64>,
+    stack_regions: HashMap<ValueId, i64>,
+    return_ptr_slot: Option<i64>,
+    /// Temporary area used to copy block arguments without clobbering each other
+    scratch_slot: i64,
+    frame_size: usize,
+    label_counter: usize,
+}
+
+impl<'a> FunctionEmitter<'a> {
+    pub fn new(program: &'a ProgramBuilder, decl: &'a CheckedFnDecl) -> Self {
+        let cfg = decl.body.as_ref().unwrap_or_else(|| {
+            panic!(
+                "INTERNAL COMPILER ERROR: Function {} has no body to lower",
+                decl.id.0
+            )
+        });
+
+        let mut emitter = Self {
+            program,
+            decl,
+            cfg,
+            label: function_symbol(program, decl.id),
+            out: String::new(),
+            slots: HashMap::new(),
+            stack_regions: HashMap::new(),
+            return_ptr_slot: None,
+            scratch_slot: 0,
+            frame_size: 0,
+            label_counter: 0,
+        };
+        emitter.layout_frame();
+
+        emitter
+    }
+
+    pub fn emit(mut self) -> Result<String, CodegenError> {
+        let _ = writeln!(self.out, "{}:", self.label);
+        emit!(self, "pushq %rbp");
+        emit!(self, "movq %rsp, %rbp");
+        emit!(self, "pushq %rbx");
+        emit!(self, "pushq %r12");
+        if self.frame_size > 0 {
+            emit!(self, "subq ${}, %rsp", self.frame_size);
+        }
+        if let Some(slot) = self.return_ptr_slot {
+            emit!(self, "movq %rdi, {}", Mem::new("%rbp", slot));
+        }
+
+        for block in self.sorted_blocks() {
+            let _ = writeln!(self.out, "{}:", self.block_label(block.id));
+
+            for instruction in &block.instructions {
+                self.emit_instruction(instruction)?;
+            }
+
+            match &block.terminator {
+                Some(terminator) => self.emit_terminator(terminator),
+                None => emit!(self, "ud2"),
+            }
+        }
+
+        Ok(self.out)
+    }
+
+    fn sorted_blocks(&self) -> Vec<&'a BasicBlock> {
+        let mut blocks: Vec<&BasicBlock> = self.cfg.blocks.values().collect();
+        let entry = self.cfg.entry_block;
+        blocks.sort_by_key(|b| (b.id != entry, b.id.0));
+        blocks
+    }
+
+    fn layout_frame(&mut self) {
+        let program = self.program;
+        let mut cursor = SAVED_REGISTERS_SIZE;
+        let mut alloc = |size: usize, alignment: usize| -> i64 {
+            cursor += size;
+            cursor = cursor.next_multiple_of(alignment.max(1));
+            -(cursor as i64)
+        };
+
+        let entry = &self.cfg.blocks[&self.cfg.entry_block];
+        let param_types: Vec<Type> =
+            entry.params.iter().map(|p| self.type_of(*p)).collect();
+        let (param_offsets, _) = argument_area(&param_types, program);
+        for (param, offset) in entry.params.iter().zip(param_offsets) {
+            // Skip the saved %rbp and the return address
+            self.slots.insert(*param, 16 + offset as i64);
+        }
+
+        if get_layout_of(&self.decl.return_type, program).size > 0 {
+            self.return_ptr_slot = Some(alloc(8, 8));
+        }
+
+        let mut scratch_size = 0;
+        for block in self.sorted_blocks() {
+            let mut params_size: usize = 0;
+            for param in &block.params {
+                let layout = get_layout_of(&self.type_of(*param), program);
+                params_size =
+                    params_size.next_multiple_of(layout.alignment) + layout.size;
+
+                if block.id != entry.id {
+                    self.slots
+                        .insert(*param, alloc(layout.size, layout.alignment));
+                }
+            }
+            scratch_size = scratch_size.max(params_size);
+
+            for instruction in &block.instructions {
+                if let Some(destination) = instruction_destination(instruction) {
+                    let layout = get_layout_of(&self.type_of(destination), program);
+                    self.slots
+                        .insert(destination, alloc(layout.size, layout.alignment));
+                }
+
+                if let Instruction::StackAlloc { destination, count } = instruction {
+                    let layout = get_layout_of(&self.pointee_of(*destination), program);
+                    self.stack_regions.insert(
+                        *destination,
+                        alloc(layout.size * count, layout.alignment),
+                    );
+                }
+            }
+        }
+
+        self.scratch_slot = alloc(scratch_size, 16);
+        self.frame_size = cursor.next_multiple_of(16) - SAVED_REGISTERS_SIZE;
+    }
+
+    fn type_of(&self, id: ValueId) -> Type {
+        self.program.get_value_id_type(&id)
+    }
+
+    fn value_type(&self, value: &Value) -> Type {
+        self.program.get_value_type(value)
+    }
+
+    fn size_of(&self, ty: &Type) -> usize {
+        get_layout_of(ty, self.program).size
+    }
+
+    fn pointee_of(&self, ptr: ValueId) -> Type {
+        match self.type_of(ptr) {
+            Type::Pointer { constraint, .. } => *constraint,
+            other => panic!(
+                "INTERNAL COMPILER ERROR: Expected value {} to be a pointer, found {:?}",
+                ptr.0, other
+            ),
+        }
+    }
+
+    fn slot(&self, id: ValueId) -> Mem {
+        let offset = self.slots.get(&id).unwrap_or_else(|| {
+            panic!(
+                "INTERNAL COMPILER ERROR: Value {} has no stack slot in function {}",
+                id.0, self.label
+            )
+        });
+        Mem::new("%rbp", *offset)
+    }
+
+    fn block_label(&self, id: BasicBlockId) -> String {
+        format!(".L{}_bb{}", self.label, id.0)
+    }
+
+    fn new_label(&mut self) -> String {
+        self.label_counter += 1;
+        format!(".L{}_t{}", self.label, self.label_counter)
+    }
+
+    fn emit_instruction(
+        &mut self,
+        instruction: &Instruction,
+    ) -> Result<(), CodegenError> {
+        match instruction {
+            Instruction::StackAlloc { destination, .. } => {
+                let region = Mem::new("%rbp", self.stack_regions[destination]);
+                emit!(self, "leaq {}, %rax", region);
+                emit!(self, "movq %rax, {}", self.slot(*destination));
+            }
+            Instruction::HeapAlloc { destination, count } => {
+                let size = self.size_of(&self.pointee_of(*destination));
+                self.load_int(count, "rax");
+                emit!(self, "imulq ${}, %rax, %rdi", size);
+                emit!(self, "call malloc@PLT");
+                emit!(self, "movq %rax, {}", self.slot(*destination));
+            }
+            Instruction::HeapFree { ptr } => {
+                emit!(self, "movq {}, %rdi", self.slot(*ptr));
+                emit!(self, "call free@PLT");
+            }
+            Instruction::Store { ptr, value } => {
+                let target_type = self.pointee_of(*ptr);
+                emit!(self, "movq {}, %rdi", self.slot(*ptr));
+                self.store_value(value, Mem::new("%rdi", 0), &target_type);
+            }
+            Instruction::Load { destination, ptr } => {
+                let source_type = self.pointee_of(*ptr);
+                let destination_type = self.type_of(*destination);
+                emit!(self, "movq {}, %rsi", self.slot(*ptr));
+                self.copy_value(
+                    Mem::new("%rsi", 0),
+                    &source_type,
+                    self.slot(*destination),
+                    &destination_type,
+                );
+            }
+            Instruction::LoadConstant {
+                destination,
+                constant_id,
+            } => {
+                emit!(self, "leaq {}(%rip), %rax", constant_label(constant_id.0));
+                emit!(self, "movq %rax, {}", self.slot(*destination));
+            }
+            Instruction::UnaryOp {
+                op_kind,
+                destination,
+                operand,
+            } => self.emit_unary_op(op_kind, *destination, operand),
+            Instruction::BinaryOp {
+                op_kind,
+                destination,
+                left,
+                right,
+            } => self.emit_binary_op(op_kind, *destination, left, right),
+            Instruction::TypeCast {
+                destination,
+                operand,
+                target_type,
+            } => self.emit_type_cast(*destination, operand, target_type),
+            Instruction::FunctionCall {
+                destination,
+                function_rvalue,
+                args,
+            } => self.emit_function_call(*destination, function_rvalue, args),
+            Instruction::MakeClosure {
+                destination,
+                function,
+                environment,
+            } => {
+                let slot = self.slot(*destination);
+                emit!(
+                    self,
+                    "leaq {}(%rip), %rax",
+                    function_symbol(self.program, *function)
+                );
+                emit!(self, "movq %rax, {}", slot);
+                emit!(self, "movq {}, %rax", self.slot(*environment));
+                emit!(self, "movq %rax, {}", slot.at(FUNCTION_ENV_OFFSET));
+            }
+            Instruction::GetFieldPtr {
+                destination,
+                base_ptr,
+                field_index,
+            } => {
+                let offset = match self.pointee_of(*base_ptr) {
+                    Type::Struct(kind) => {
+                        get_field_offset(&kind, *field_index, self.program)
+                    }
+                    other => panic!(
+                        "INTERNAL COMPILER ERROR: GetFieldPtr on a pointer to non-struct \
+                         type {:?}",
+                        other
+                    ),
+                };
+                emit!(self, "movq {}, %rax", self.slot(*base_ptr));
+                if offset > 0 {
+                    emit!(self, "addq ${}, %rax", offset);
+                }
+                emit!(self, "movq %rax, {}", self.slot(*destination));
+            }
+            Instruction::GetElementPtr {
+                destination,
+                base_ptr,
+                index,
+            } => {
+                let element_size = self.size_of(&self.pointee_of(*base_ptr));
+                self.load_int(index, "rcx");
+                emit!(self, "imulq ${}, %rcx, %rcx", element_size);
+                emit!(self, "movq {}, %rax", self.slot(*base_ptr));
+                emit!(self, "addq %rcx, %rax");
+                emit!(self, "movq %rax, {}", self.slot(*destination));
+            }
+            Instruction::Nop => {}
+            Instruction::FileOpen { .. } => {
+                return Err(CodegenError::UnsupportedInstruction("FileOpen"))
+            }
+            Instruction::FileWrite { .. } => {
+                return Err(CodegenError::UnsupportedInstruction("FileWrite"))
+            }
+            Instruction::FileRead { .. } => {
+                return Err(CodegenError::UnsupportedInstruction("FileRead"))
+            }
+            Instruction::FileClose { .. } => {
+                return Err(CodegenError::UnsupportedInstruction("FileClose"))
+            }
+            Instruction::SocketConnect { .. } => {
+                return Err(CodegenError::UnsupportedInstruction("SocketConnect"))
+            }
+            Instruction::SocketSend { .. } => {
+                return Err(CodegenError::UnsupportedInstruction("SocketSend"))
+            }
+            Instruction::SocketClose { .. } => {
+                return Err(CodegenError::UnsupportedInstruction("SocketClose"))
+            }
+        };
+
+        Ok(())
+    }
+
+    fn emit_terminator(&mut self, terminator: &Terminator) {
+        match terminator {
+            Terminator::Jump { target, args } => {
+                self.emit_block_args(*target, args);
+                emit!(self, "jmp {}", self.block_label(*target));
+            }
+            Terminator::CondJump {
+                condition,
+                true_target,
+                true_args,
+                false_target,
+                false_args,
+            } => {
+                let false_path = self.new_label();
+                self.load_int(condition, "rax");
+                emit!(self, "testb %al, %al");
+                emit!(self, "jz {}", false_path);
+                self.emit_block_args(*true_target, true_args);
+                emit!(self, "jmp {}", self.block_label(*true_target));
+                let _ = writeln!(self.out, "{}:", false_path);
+                self.emit_block_args(*false_target, false_args);
+                emit!(self, "jmp {}", self.block_label(*false_target));
+            }
+            Terminator::Return { value } => {
+                if let (Some(value), Some(slot)) = (value, self.return_ptr_slot) {
+                    let return_type = self.decl.return_type.clone();
+                    emit!(self, "movq {}, %rdi", Mem::new("%rbp", slot));
+                    self.store_value(value, Mem::new("%rdi", 0), &return_type);
+                }
+                emit!(self, "leaq -{}(%rbp), %rsp", SAVED_REGISTERS_SIZE);
+                emit!(self, "popq %r12");
+                emit!(self, "popq %rbx");
+                emit!(self, "popq %rbp");
+                emit!(self, "ret");
+            }
+            Terminator::Unreachable => emit!(self, "ud2"),
+        }
+    }
+
+    /// Copies the arguments into the scratch area first, so a block may receive its
+    /// own parameters in a different order without overwriting them mid-copy.
+    fn emit_block_args(&mut self, target: BasicBlockId, args: &[Value]) {
+        let params = self.cfg.blocks[&target].params.clone();
+        if params.len() != args.len() {
+            panic!(
+                "INTERNAL COMPILER ERROR: Block {} expects {} arguments, received {}",
+                target.0,
+                params.len(),
+                args.len()
+            );
+        }
+
+        let scratch = Mem::new("%rbp", self.scratch_slot);
+        let mut staged = Vec::with_capacity(params.len());
+        let mut cursor: usize = 0;
+
+        for (param, arg) in params.iter().zip(args) {
+            let param_type = self.type_of(*param);
+            let layout = get_layout_of(&param_type, self.program);
+            cursor = cursor.next_multiple_of(layout.alignment);
+
+            self.store_value(arg, scratch.at(cursor), &param_type);
+            staged.push((*param, cursor, layout.size));
+
+            cursor += layout.size;
+        }
+
+        for (param, offset, size) in staged {
+            self.copy_bytes(scratch.at(offset), self.slot(param), size);
+        }
+    }
+
+    fn emit_function_call(
+        &mut self,
+        destination: Option<ValueId>,
+        function: &Value,
+        args: &[Value],
+    ) {
+        let param_types: Vec<Type> = match function {
+            Value::Function(id) => match self.program.get_declaration(*id) {
+                CheckedDeclaration::Function(f) => {
+                    f.params.iter().map(|p| p.ty.clone()).collect()
+                }
+                _ => panic!(
+                    "INTERNAL COMPILER ERROR: Value::Function(DeclarationId) contained \
+                     non-function declaration id"
+                ),
+            },
+            other => match self.value_type(other) {
+                Type::Fn(fn_type) => {
+                    fn_type.params.iter().map(|p| p.ty.clone()).collect()
+                }
+                ty => panic!(
+                    "INTERNAL COMPILER ERROR: Cannot call value of type {:?}",
+                    ty
+                ),
+            },
+        };
+
+        // Calls through a function value pass its environment as a trailing argument,
+        // functions which capture nothing never read it
+        let mut area_types = param_types.clone();
+        if let Value::Use(_) = function {
+            area_types.push(Type::Pointer {
+                constraint: Box::new(Type::U8),
+                narrowed_to: Box::new(Type::U8),
+            });
+        }
+
+        let (offsets, area_size) = argument_area(&area_types, self.program);
+        if area_size > 0 {
+            emit!(self, "subq ${}, %rsp", area_size);
+        }
+
+        for ((arg, param_type), offset) in args.iter().zip(&param_types).zip(&offsets) {
+            self.store_value(arg, Mem::new("%rsp", *offset as i64), param_type);
+        }
+        if let (Value::Use(id), Some(env_offset)) = (function, offsets.get(args.len())) {
+            self.copy_bytes(
+                self.slot(*id).at(FUNCTION_ENV_OFFSET),
+                Mem::new("%rsp", *env_offset as i64),
+                8,
+            );
+        }
+
+        if let Some(destination) = destination {
+            emit!(self, "leaq {}, %rdi", self.slot(destination));
+        }
+
+        match function {
+            Value::Function(id) => {
+                emit!(self, "call {}", function_symbol(self.program, *id));
+            }
+            Value::Use(id) => {
+                emit!(self, "movq {}, %r11", self.slot(*id));
+                emit!(self, "call *%r11");
+            }
+            other => panic!(
+                "INTERNAL COMPILER ERROR: Cannot call literal value {:?}",
+                other
+            ),
+        }
+
+        if area_size > 0 {
+            emit!(self, "addq ${}, %rsp", area_size);
+        }
+    }
+
+    fn emit_unary_op(
+        &mut self,
+        op_kind: &UnaryOperationKind,
+        destination: ValueId,
+        operand: &Value,
+    ) {
+        let ty = self.value_type(operand);
+        self.load_int(operand, "rax");
+
+        match op_kind {
+            UnaryOperationKind::Not => emit!(self, "xorq $1, %rax"),
+            UnaryOperationKind::Neg => match ty {
+                Type::F32 => emit!(self, "xorl $0x80000000, %eax"),
+                Type::F64 => emit!(self, "btcq $63, %rax"),
+                _ => emit!(self, "negq %rax"),
+            },
+            UnaryOperationKind::BitNot => emit!(self, "notq %rax"),
+        }
+
+        self.store_register("rax", destination);
+    }
+
+    fn emit_binary_op(
+        &mut self,
+        op_kind: &BinaryOperationKind,
+        destination: ValueId,
+        left: &Value,
+        right: &Value,
+    ) {
+        let left_type = self.value_type(left);
+        let right_type = self.value_type(right);
+        let common_type =
+            if get_numeric_type_rank(&left_type) >= get_numeric_type_rank(&right_type) {
+                left_type.clone()
+            } else {
+                right_type.clone()
+            };
+
+        match op_kind {
+            BinaryOperationKind::Equal | BinaryOperationKind::NotEqual => {
+                self.emit_equality(left, right);
+                if matches!(op_kind, BinaryOperationKind::NotEqual) {
+                    emit!(self, "xorb $1, %al");
+                }
+                self.store_register("rax", destination);
+            }
+            BinaryOperationKind::LessThan
+            | BinaryOperationKind::LessThanOrEqual
+            | BinaryOperationKind::GreaterThan
+            | BinaryOperationKind::GreaterThanOrEqual => {
+                if is_float(&common_type) {
+                    let suffix = if common_type == Type::F32 { "ss" } else { "sd" };
+                    self.load_float(left, "%xmm0", &common_type);
+                    self.load_float(right, "%xmm1", &common_type);
+
+                    // `ucomis` sets CF on unordered operands, so only the "above"
+                    // conditions are used to keep comparisons with NaN false
+                    let (lhs, rhs, set) = match op_kind {
+                        BinaryOperationKind::LessThan => ("%xmm0", "%xmm1", "seta"),
+                        BinaryOperationKind::LessThanOrEqual => {
+                            ("%xmm0", "%xmm1", "setae")
+                        }
+                        BinaryOperationKind::GreaterThan => ("%xmm1", "%xmm0", "seta"),
+                        _ => ("%xmm1", "%xmm0", "setae"),
+                    };
+                    emit!(self, "ucomi{} {}, {}", suffix, lhs, rhs);
+                    emit!(self, "{} %al", set);
+                } else {
+                    self.load_int(left, "rax");
+                    self.load_int(right, "rcx");
+                    emit!(self, "cmpq %rcx, %rax");
+
+                    let signed = is_signed(&common_type);
+                    let set = match (op_kind, signed) {
+                        (BinaryOperationKind::LessThan, true) => "setl",
+                        (BinaryOperationKind::LessThan, false) => "setb",
+                        (BinaryOperationKind::LessThanOrEqual, true) => "setle",
+                        (BinaryOperationKind::LessThanOrEqual, false) => "setbe",
+                        (BinaryOperationKind::GreaterThan, true) => "setg",
+                        (BinaryOperationKind::GreaterThan, false) => "seta",
+                        (_, true) => "setge",
+                        (_, false) => "setae",
+                    };
+                    emit!(self, "{} %al", set);
+                }
+                self.store_register("rax", destination);
+            }
+            BinaryOperationKind::Add
+            | BinaryOperationKind::Subtract
+            | BinaryOperationKind::Multiply
+            | BinaryOperationKind::Divide
+            | BinaryOperationKind::Modulo => {
+                if is_float(&common_type) {
+                    let suffix = if common_type == Type::F32 { "ss" } else { "sd" };
+                    self.load_float(left, "%xmm0", &common_type);
+                    self.load_float(right, "%xmm1", &common_type);
+
+                    match op_kind {
+                        BinaryOperationKind::Add => {
+                            emit!(self, "add{} %xmm1, %xmm0", suffix)
+                        }
+                        BinaryOperationKind::Subtract => {
+                            emit!(self, "sub{} %xmm1, %xmm0", suffix)
+                        }
+                        BinaryOperationKind::Multiply => {
+                            emit!(self, "mul{} %xmm1, %xmm0", suffix)
+                        }
+                        BinaryOperationKind::Divide => {
+                            emit!(self, "div{} %xmm1, %xmm0", suffix)
+                        }
+                        _ => {
+                            // a - trunc(a / b) * b
+                            emit!(self, "movap{} %xmm0, %xmm2", &suffix[1..]);
+                            emit!(self, "div{} %xmm1, %xmm2", suffix);
+                            emit!(self, "round{} $3, %xmm2, %xmm2", suffix);
+                            emit!(self, "mul{} %xmm1, %xmm2", suffix);
+                            emit!(self, "sub{} %xmm2, %xmm0", suffix);
+                        }
+                    }
+                    self.store_xmm("%xmm0", destination);
+                } else {
+                    self.load_int(left, "rax");
+                    self.load_int(right, "rcx");
+
+                    match op_kind {
+                        BinaryOperationKind::Add => emit!(self, "addq %rcx, %rax"),
+                        BinaryOperationKind::Subtract => emit!(self, "subq %rcx, %rax"),
+                        BinaryOperationKind::Multiply => emit!(self, "imulq %rcx, %rax"),
+                        _ => {
+                            if is_signed(&common_type) {
+                                emit!(self, "cqto");
+                                emit!(self, "idivq %rcx");
+                            } else {
+                                emit!(self, "xorl %edx, %edx");
+                                emit!(self, "divq %rcx");
+                            }
+                            if matches!(op_kind, BinaryOperationKind::Modulo) {
+                                emit!(self, "movq %rdx, %rax");
+                            }
+                        }
+                    }
+                    self.store_register("rax", destination);
+                }
+            }
+            BinaryOperationKind::BitAnd
+            | BinaryOperationKind::BitOr
+            | BinaryOperationKind::BitXor => {
+                self.load_int(left, "rax");
+                self.load_int(right, "rcx");
+
+                let instruction = match op_kind {
+                    BinaryOperationKind::BitAnd => "andq",
+                    BinaryOperationKind::BitOr => "orq",
+                    _ => "xorq",
+                };
+                emit!(self, "{} %rcx, %rax", instruction);
+                self.store_register("rax", destination);
+            }
+            // Operands are extended to 64 bits, so shifting the whole register and
+            // truncating gives the result for narrower types too
+            BinaryOperationKind::ShiftLeft | BinaryOperationKind::ShiftRight => {
+                self.load_int(left, "rax");
+                self.load_int(right, "rcx");
+                emit!(
+                    self,
+                    "andq ${}, %rcx",
+                    get_integer_bit_width(&left_type) - 1
+                );
+
+                let instruction = match op_kind {
+                    BinaryOperationKind::ShiftLeft => "shlq",
+                    _ if is_signed(&left_type) => "sarq",
+                    _ => "shrq",
+                };
+                emit!(self, "{} %cl, %rax", instruction);
+                self.store_register("rax", destination);
+            }
+        }
+    }
+
+    fn emit_type_cast(&mut self, destination: ValueId, operand: &Value, target: &Type) {
+        let source = self.value_type(operand);
+
+        if is_float(target) {
+            if is_float(&source) {
+                self.load_float(operand, "%xmm0", target);
+            } else {
+                let suffix = if *target == Type::F32 { "ss" } else { "sd" };
+                self.load_int(operand, "rax");
+                emit!(self, "cvtsi2{}q %rax, %xmm0", suffix);
+            }
+            self.store_xmm("%xmm0", destination);
+        } else if is_float(&source) {
+            let suffix = if source == Type::F32 { "ss" } else { "sd" };
+            self.load_float(operand, "%xmm0", &source);
+            emit!(self, "cvtt{}2si %xmm0, %rax", suffix);
+            self.store_register("rax", destination);
+        } else if is_scalar(&source) && is_scalar(target) {
+            self.load_int(operand, "rax");
+            self.store_register("rax", destination);
+        } else {
+            self.store_value(operand, self.slot(destination), target);
+        }
+    }
+
+    /// Leaves 1 in %al if both values are equal, 0 otherwise
+    fn emit_equality(&mut self, left: &Value, right: &Value) {
+        let left_type = self.value_type(left);
+        let right_type = self.value_type(right);
+
+        if is_float(&left_type) {
+            let suffix = if left_type == Type::F32 { "ss" } else { "sd" };
+            self.load_float(left, "%xmm0", &left_type);
+            self.load_float(right, "%xmm1", &left_type);
+            emit!(self, "ucomi{} %xmm1, %xmm0", suffix);
+            emit!(self, "sete %al");
+            emit!(self, "setnp %cl");
+            emit!(self, "andb %cl, %al");
+        } else if is_string_pointer(&left_type) && is_string_pointer(&right_type) {
+            self.load_int(left, "rax");
+            self.load_int(right, "rcx");
+            emit!(self, "movq %rax, %rdi");
+            emit!(self, "movq %rcx, %rsi");
+            emit!(self, "call {}", STRING_EQ_LABEL);
+        } else if is_scalar(&left_type) {
+            self.load_int(left, "rax");
+            self.load_int(right, "rcx");
+            emit!(self, "cmpq %rcx, %rax");
+            emit!(self, "sete %al");
+        } else {
+            let (left_id, right_id) = match (left, right) {
+                (Value::Use(l), Value::Use(r)) => (*l, *r),
+                _ => panic!(
+                    "INTERNAL COMPILER ERROR: Expected aggregate operands to be values"
+                ),
+            };
+            let not_equal = self.new_label();
+            let done = self.new_label();
+
+            emit!(self, "leaq {}, %rbx", self.slot(left_id));
+            emit!(self, "leaq {}, %r12", self.slot(right_id));
+            self.compare_memory(&left_type, 0, &right_type, 0, &not_equal);
+            emit!(self, "movb $1, %al");
+            emit!(self, "jmp {}", done);
+            let _ = writeln!(self.out, "{}:", not_equal);
+            emit!(self, "xorl %eax, %eax");
+            let _ = writeln!(self.out, "{}:", done);
+        }
+    }
+
+    /// Compares the value at `left_offset(%rbx)` with the value at
+    /// `right_offset(%r12)`, jumping to `not_equal` when they differ
+    fn compare_memory(
+        &mut self,
+        left_type: &Type,
+        left_offset: usize,
+        right_type: &Type,
+        right_offset: usize,
+        not_equal: &str,
+    ) {
+        let left_mem = Mem::new("%rbx", left_offset as i64);
+        let right_mem = Mem::new("%r12", right_offset as i64);
+
+        if let (Some((left_payload, _)), Some((right_payload, _))) = (
+            tagged_payload(left_type, self.program),
+            tagged_payload(right_type, self.program),
+        ) {
+            emit!(self, "movzwl {}, %eax", left_mem);
+            emit!(self, "cmpw {}, %ax", right_mem);
+            emit!(self, "jne {}", not_equal);
+
+            let (variants, is_union) = match left_type {
+                Type::Struct(StructKind::Tag(tag)) => (vec![tag.clone()], false),
+                Type::Struct(StructKind::Union { variants }) => (variants.clone(), true),
+                _ => unreachable!(),
+            };
+
+            let done = self.new_label();
+            for variant in variants {
+                let Some(payload_type) = variant.value_type else {
+                    continue;
+                };
+
+                let next = self.new_label();
+                if is_union {
+                    emit!(self, "cmpw ${}, {}", variant.id.0, left_mem);
+                    emit!(self, "jne {}", next);
+                }
+                self.compare_memory(
+                    &payload_type,
+                    left_offset + left_payload,
+                    &payload_type,
+                    right_offset + right_payload,
+                    not_equal,
+                );
+                emit!(self, "jmp {}", done);
+                let _ = writeln!(self.out, "{}:", next);
+            }
+            let _ = writeln!(self.out, "{}:", done);
+            return;
+        }
+
+        if is_float(left_type) {
+            let suffix = if *left_type == Type::F32 { "ss" } else { "sd" };
+            emit!(self, "mov{} {}, %xmm0", suffix, left_mem);
+            emit!(self, "ucomi{} {}, %xmm0", suffix, right_mem);
+            emit!(self, "jne {}", not_equal);
+            emit!(self, "jp {}", not_equal);
+        } else if is_string_pointer(left_type) {
+            emit!(self, "movq {}, %rdi", left_mem);
+            emit!(self, "movq {}, %rsi", right_mem);
+            emit!(self, "call {}", STRING_EQ_LABEL);
+            emit!(self, "testb %al, %al");
+            emit!(self, "jz {}", not_equal);
+        } else {
+            let size = self.size_of(left_type);
+            let mut done = 0;
+            while done < size {
+                let chunk = [8, 4, 2, 1]
+                    .into_iter()
+                    .find(|c| *c <= size - done)
+                    .unwrap();
+                let register = sized_register("r11", chunk);
+                let suffix = size_suffix(chunk);
+                emit!(self, "mov{} {}, {}", suffix, left_mem.at(done), register);
+                emit!(self, "cmp{} {}, {}", suffix, right_mem.at(done), register);
+                emit!(self, "jne {}", not_equal);
+                done += chunk;
+            }
+        }
+    }
+
+    /// Loads an integer-like value (integers, bools, pointers, function pointers or
+    /// the raw bits of a float) into a 64-bit register, extending it by its signedness
+    fn load_int(&mut self, value: &Value, register: &str) {
+        let full = sized_register(register, 8);
+
+        match value {
+            Value::Use(id) => {
+                let ty = self.type_of(*id);
+                let size = self.size_of(&ty);
+                let signed = is_integer(&ty) && is_signed(&ty);
+                let mem = self.slot(*id);
+
+                match (size, signed) {
+                    (8, _) => emit!(self, "movq {}, {}", mem, full),
+                    (4, true) => emit!(self, "movslq {}, {}", mem, full),
+                    (4, false) => {
+                        emit!(self, "movl {}, {}", mem, sized_register(register, 4))
+                    }
+                    (2, true) => emit!(self, "movswq {}, {}", mem, full),
+                    (2, false) => emit!(self, "movzwq {}, {}", mem, full),
+                    (1, true) => emit!(self, "movsbq {}, {}", mem, full),
+                    (1, false) => emit!(self, "movzbq {}, {}", mem, full),
+                    (0, _) => emit!(self, "xorq {}, {}", full, full),
+                    _ => panic!(
+                        "INTERNAL COMPILER ERROR: Cannot load a value of type {:?} into a \
+                         register",
+                        ty
+                    ),
+                }
+            }
+            Value::Function(id) => {
+                emit!(
+                    self,
+                    "leaq {}(%rip), {}",
+                    function_symbol(self.program, *id),
+                    full
+                );
+            }
+            literal => emit!(self, "movabsq ${}, {}", literal_bits(literal), full),
+        }
+    }
+
+    /// Loads a float into an xmm register, converting it to `target` precision
+    fn load_float(&mut self, value: &Value, xmm: &str, target: &Type) {
+        let source = self.value_type(value);
+
+        match value {
+            Value::Use(id) => {
+                let suffix = if source == Type::F32 { "ss" } else { "sd" };
+                emit!(self, "mov{} {}, {}", suffix, self.slot(*id), xmm);
+            }
+            literal => {
+                emit!(self, "movabsq ${}, %r11", literal_bits(literal));
+                if source == Type::F32 {
+                    emit!(self, "movd %r11d, {}", xmm);
+                } else {
+                    emit!(self, "movq %r11, {}", xmm);
+                }
+            }
+        }
+
+        match (&source, target) {
+            (Type::F32, Type::F64) => emit!(self, "cvtss2sd {}, {}", xmm, xmm),
+            (Type::F64, Type::F32) => emit!(self, "cvtsd2ss {}, {}", xmm, xmm),
+            _ => {}
+        }
+    }
+
+    fn store_register(&mut self, register: &str, destination: ValueId) {
+        let size = self.size_of(&self.type_of(destination));
+        if size == 0 {
+            return;
+        }
+
+        emit!(
+            self,
+            "mov{} {}, {}",
+            size_suffix(size),
+            sized_register(register, size),
+            self.slot(destination)
+        );
+    }
+
+    fn store_xmm(&mut self, xmm: &str, destination: ValueId) {
+        let suffix = if self.type_of(destination) == Type::F32 {
+            "ss"
+        } else {
+            "sd"
+        };
+        emit!(self, "mov{} {}, {}", suffix, xmm, self.slot(destination));
+    }
+
+    /// Writes `value` to `destination`, converting it to the representation of
+    /// `destination_type`. Only clobbers %r11.
+    fn store_value(&mut self, value: &Value, destination: Mem, destination_type: &Type) {
+        let size = self.size_of(destination_type);
+        if size == 0 {
+            return;
+        }
+
+        match value {
+            Value::Use(id) => {
+                let source_type = self.type_of(*id);
+                self.copy_value(
+                    self.slot(*id),
+                    &source_type,
+                    destination,
+                    destination_type,
+                );
+            }
+            Value::VoidLiteral => {}
+            Value::Function(id) => {
+                emit!(
+                    self,
+                    "leaq {}(%rip), %r11",
+                    function_symbol(self.program, *id)
+                );
+                emit!(self, "movq %r11, {}", destination);
+                emit!(self, "movq $0, {}", destination.at(FUNCTION_ENV_OFFSET));
+            }
+            literal => {
+                let bits = literal_bits(literal);
+                if size == 8 {
+                    emit!(self, "movabsq ${}, %r11", bits);
+                    emit!(self, "movq %r11, {}", destination);
+                } else {
+                    let mask = (1i64 << (size * 8)) - 1;
+                    emit!(
+                        self,
+                        "mov{} ${}, {}",
+                        size_suffix(size),
+                        bits & mask,
+                        destination
+                    );
+                }
+            }
+        }
+    }
+
+    /// Copies a value between two memory locations. Tags and unions are moved
+    /// id-first and payload-second since their payload offsets may differ.
+    fn copy_value(
+        &mut self,
+        source: Mem,
+        source_type: &Type,
+        destination: Mem,
+        destination_type: &Type,
+    ) {
+        let destination_size = self.size_of(destination_type);
+        if destination_size == 0 {
+            return;
+        }
+
+        match (
+            tagged_payload(source_type, self.program),
+            tagged_payload(destination_type, self.program),
+        ) {
+            (
+                Some((source_offset, source_size)),
+                Some((destination_offset, destination_size)),
+            ) => {
+                self.copy_bytes(source, destination, 2);
+                self.copy_bytes(
+                    source.at(source_offset),
+                    destination.at(destination_offset),
+                    source_size.min(destination_size),
+                );
+            }
+            _ => {
+                let source_size = self.size_of(source_type);
+                self.copy_bytes(source, destination, source_size.min(destination_size));
+            }
+        }
+    }
+
+    fn copy_bytes(&mut self, source: Mem, destination: Mem, size: usize) {
+        let mut done = 0;
+        while done < size {
+            let chunk = [8, 4, 2, 1]
+                .into_iter()
+                .find(|c| *c <= size - done)
+                .unwrap();
+            let register = sized_register("r11", chunk);
+            let suffix = size_suffix(chunk);
+
+            emit!(self, "mov{} {}, {}", suffix, source.at(done), register);
+            emit!(self, "mov{} {}, {}", suffix, register, destination.at(done));
+
+            done += chunk;
+        }
+    }
+}
@@ -0,0 +1,334 @@
+use std::{
+    fmt::Write,
+    fs,
+    path::Path,
+    process::Command,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use crate::{
+    ast::DeclarationId,
+    codegen::{function_symbol, x86_64::function::FunctionEmitter, CodegenError},
+    hir::{
+        types::{
+            checked_declaration::{CheckedDeclaration, CheckedFnDecl},
+            checked_type::StructKind,
+        },
+        utils::{
+            layout::{get_field_offset, get_layout_of},
+            numeric::{is_integer, is_signed},
+        },
+        ProgramBuilder,
+    },
+};
+
+mod function;
+
+/// Runtime helper comparing two string headers by content, emitted once per program
+const STRING_EQ_LABEL: &str = "willow_rt_string_eq";
+
+/// Lowers every function of the program to GNU assembler (AT&T syntax) for x86-64
+/// Linux. The output defines a C `main` which calls `entry` and uses its result as
+/// the process exit code when it is an integer.
+pub fn generate_assembly(
+    program: &ProgramBuilder,
+    entry: DeclarationId,
+) -> Result<String, CodegenError> {
+    let mut out = String::new();
+    let _ = writeln!(out, "    .text");
+
+    let mut functions: Vec<&CheckedFnDecl> = program
+        .declarations
+        .values()
+        .filter_map(|decl| match decl {
+            CheckedDeclaration::Function(f) if f.body.is_some() => Some(f),
+            _ => None,
+        })
+        .collect();
+    functions.sort_by_key(|f| f.id.0);
+
+    for f in functions {
+        out.push_str(&FunctionEmitter::new(program, f).emit()?);
+    }
+
+    emit_entry_point(program, entry, &mut out)?;
+    emit_runtime(program, &mut out);
+    emit_constant_data(program, &mut out);
+
+    let _ = writeln!(out, "    .section .note.GNU-stack,\"\",@progbits");
+
+    Ok(out)
+}
+
+/// Generates the assembly for the program and links it into an executable at
+/// `output_path` using the system C compiler driver.
+pub fn build_executable(
+    program: &ProgramBuilder,
+    entry: DeclarationId,
+    output_path: &Path,
+) -> Result<(), CodegenError> {
+    // Every build of the process writes its own file, builds may run concurrently
+    static NEXT_ASSEMBLY: AtomicUsize = AtomicUsize::new(0);
+
+    let assembly = generate_assembly(program, entry)?;
+
+    let assembly_path = std::env::temp_dir().join(format!(
+        "willow-{}-{}.s",
+        std::process::id(),
+        NEXT_ASSEMBLY.fetch_add(1, Ordering::Relaxed)
+    ));
+    fs::write(&assembly_path, assembly)?;
+
+    let result = Command::new("cc")
+        .arg("-o")
+        .arg(output_path)
+        .arg(&assembly_path)
+        .output();
+
+    let _ = fs::remove_file(&assembly_path);
+
+    let output = result?;
+    if !output.status.success() {
+        return Err(CodegenError::LinkerFailed(
+            String::from_utf8_lossy(&output.stderr).into_owned(),
+        ));
+    }
+
+    Ok(())
+}
+
+pub(crate) fn constant_label(id: usize) -> String {
+    format!(".Lwillow_const_{}", id)
+}
+
+fn emit_entry_point(
+    program: &ProgramBuilder,
+    entry: DeclarationId,
+    out: &mut String,
+) -> Result<(), CodegenError> {
+    let entry_fn = match program.get_declaration(entry) {
+        CheckedDeclaration::Function(f) => f,
+        _ => return Err(CodegenError::MissingEntryPoint),
+    };
+
+    if !entry_fn.params.is_empty() {
+        return Err(CodegenError::InvalidEntryPoint);
+    }
+
+    let return_layout = get_layout_of(&entry_fn.return_type, program);
+    let return_area = return_layout.size.div_ceil(16).max(1) * 16;
+
+    let _ = writeln!(out, "    .globl main");
+    let _ = writeln!(out, "main:");
+    let _ = writeln!(out, "    pushq %rbp");
+    let _ = writeln!(out, "    movq %rsp, %rbp");
+    let _ = writeln!(out, "    subq ${}, %rsp", return_area);
+    let _ = writeln!(out, "    leaq -{}(%rbp), %rdi", return_area);
+    let _ = writeln!(out, "    call {}", function_symbol(program, entry));
+
+    let exit_code_load = if is_integer(&entry_fn.return_type) {
+        match (return_layout.size, is_signed(&entry_fn.return_type)) {
+            (1, true) => Some("movsbl"),
+            (1, false) => Some("movzbl"),
+            (2, true) => Some("movswl"),
+            (2, false) => Some("movzwl"),
+            _ => Some("movl"),
+        }
+    } else {
+        None
+    };
+
+    match exit_code_load {
+        Some(load) => {
+            let _ = writeln!(out, "    {} -{}(%rbp), %eax", load, return_area);
+        }
+        None => {
+            let _ = writeln!(out, "    xorl %eax, %eax");
+        }
+    }
+
+    let _ = writeln!(out, "    leave");
+    let _ = writeln!(out, "    ret");
+
+    Ok(())
+}
+
+fn emit_runtime(program: &ProgramBuilder, out: &mut String) {
+    let string_fields = StructKind::String.fields(program);
+    let field_offset = |name| {
+        let index = string_fields
+            .iter()
+            .position(|(field, _)| *field == name)
+            .expect("INTERNAL COMPILER ERROR: String header is missing a field");
+        get_field_offset(&StructKind::String, index, program)
+    };
+    let len_offset = field_offset(program.common_identifiers.len);
+    let ptr_offset = field_offset(program.common_identifiers.ptr);
+
+    let _ = writeln!(out, "{}:", STRING_EQ_LABEL);
+    let _ = writeln!(out, "    movq {}(%rdi), %rdx", len_offset);
+    let _ = writeln!(out, "    cmpq {}(%rsi), %rdx", len_offset);
+    let _ = writeln!(out, "    jne .L{}_false", STRING_EQ_LABEL);
+    let _ = writeln!(out, "    movq {}(%rdi), %rdi", ptr_offset);
+    let _ = writeln!(out, "    movq {}(%rsi), %rsi", ptr_offset);
+    let _ = writeln!(out, "    subq $8, %rsp");
+    let _ = writeln!(out, "    call memcmp@PLT");
+    let _ = writeln!(out, "    addq $8, %rsp");
+    let _ = writeln!(out, "    testl %eax, %eax");
+    let _ = writeln!(out, "    sete %al");
+    let _ = writeln!(out, "    ret");
+    let _ = writeln!(out, ".L{}_false:", STRING_EQ_LABEL);
+    let _ = writeln!(out, "    xorl %eax, %eax");
+    let _ = writeln!(out, "    ret");
+}
+
+fn emit_constant_data(program: &ProgramBuilder, out: &mut String) {
+    let mut constants: Vec<_> = program.constant_data.iter().collect();
+    constants.sort_by_key(|(id, _)| id.0);
+
+    let _ = writeln!(out, "    .section .rodata");
+    for (id, bytes) in constants {
+        let _ = writeln!(out, "{}:", constant_label(id.0));
+        if bytes.is_empty() {
+            let _ = writeln!(out, "    .byte 0");
+            continue;
+        }
+
+        for chunk in bytes.chunks(16) {
+            let line: Vec<String> = chunk.iter().map(|b| b.to_string()).collect();
+            let _ = writeln!(out, "    .byte {}", line.join(", "));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::codegen::{assert_runs_like_interpreter, x86_64::build_executable};
+
+    #[test]
+    fn test_recursion_and_loops() {
+        let source = r#"
+            fn fib(n: i32): i32 {
+                if n < 2i32 { n } else { fib(n - 1i32) + fib(n - 2i32) }
+            }
+
+            fn main(): i32 {
+                let mut acc: i32 = 0i32;
+                let mut i: i32 = 0i32;
+                while i < 5i32 {
+                    acc += i;
+                    i++;
+                }
+                for j in 0i32..10i32 {
+                    if j == 3i32 { continue }
+                    if j == 8i32 { break }
+                    acc += j;
+                }
+                fib(10i32) - acc
+            }
+        "#;
+
+        assert_runs_like_interpreter("x86_loops", source, build_executable);
+    }
+
+    #[test]
+    fn test_structs_unions_and_function_pointers() {
+        let source = r#"
+            type Shape = #Circle(f64) | #Square(f64) | #Empty;
+            type Point = { x: i32, y: i64, tag: u8 };
+
+            fn area(s: Shape): f64 {
+                match s {
+                    Circle(r) => r * r * 3f64,
+                    Square(x) => x * x,
+                    Empty => 0f64,
+                }
+            }
+
+            fn apply(f: fn(a: i32): i32, v: i32): i32 {
+                f(v)
+            }
+
+            fn double(a: i32): i32 {
+                a * 2i32
+            }
+
+            fn main(): i32 {
+                let mut p: Point = { x: 3i32, y: 40i64, tag: 7u8 };
+                p.x += 2i32;
+                let total: f64 = area(#Square(4f64)) + area(#Circle(1f64)) + area(#Empty);
+                let mut r: i32 = 0i32;
+                if "hello" == "hello" { r = r + 1i32; }
+                if "hello" == "world" { r = r + 100i32; }
+                if total > 18.5f64 { r = r + 10i32; }
+                r + p.x + apply(double, 5i32)
+            }
+        "#;
+
+        assert_runs_like_interpreter("x86_shapes", source, build_executable);
+    }
+
+    #[test]
+    fn test_closures_and_generics() {
+        let source = r#"
+            type Option<T> = #some(T) | #none;
+
+            fn unwrap_or<T>(opt: Option<T>, default: T): T {
+                match opt {
+                    some(v) => v,
+                    none => default,
+                }
+            }
+
+            fn make_adder(n: i32): fn(x: i32): i32 {
+                fn adder(x: i32): i32 {
+                    x + n
+                }
+                adder
+            }
+
+            fn main(): i32 {
+                let base: i32 = 10i32;
+                fn scaled(x: i32): i32 {
+                    x * base
+                }
+                let add5 = make_adder(5i32);
+                let some: Option<i32> = #some(3i32);
+                let none: Option<i32> = #none;
+                add5(1i32) + scaled(2i32) + unwrap_or(some, 0i32) + unwrap_or(none, 50i32)
+            }
+        "#;
+
+        assert_runs_like_interpreter("x86_closures", source, build_executable);
+    }
+
+    #[test]
+    fn test_lists_arrays_and_slices() {
+        let source = r#"
+            fn sum(values: [i64]): i64 {
+                let mut total = 0;
+                for value in values {
+                    total += value;
+                }
+                total
+            }
+
+            fn main(): i64 {
+                let items = [1, 2, 3, 4, 5, 6];
+                let mut fixed: [i64; 3] = [7; 3];
+                fixed[1usize] = 20;
+                let mut list = i64[]::from_array(fixed);
+                list[0usize] = 0;
+                let mut window = fixed[1usize..3usize];
+                window[1usize] = 5;
+                let mut total = 0;
+                for x in list {
+                    total += x;
+                }
+                sum(items[1usize..5usize]) + fixed[0usize] + total + sum(window) + fixed[2usize]
+            }
+        "#;
+
+        assert_runs_like_interpreter("x86_lists", source, build_executable);
+    }
+}
@@ -0,0 +1,221 @@
+use std::{fmt::Write, path::PathBuf};
+
+use crate::ast::Span;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiagnosticLabel {
+    pub span: Span,
+    pub message: String,
+    /// The primary label marks the location of the problem, secondary labels point
+    /// at related code
+    pub is_primary: bool,
+}
+
+impl DiagnosticLabel {
+    pub fn primary(span: Span) -> Self {
+        Self {
+            span,
+            message: String::new(),
+            is_primary: true,
+        }
+    }
+
+    pub fn secondary(span: Span) -> Self {
+        Self {
+            span,
+            message: String::new(),
+            is_primary: false,
+        }
+    }
+
+    pub fn with_message(mut self, message: impl Into<String>) -> Self {
+        self.message = message.into();
+        self
+    }
+}
+
+/// A single reported problem, independent of how it is rendered. Errors which are
+/// not tied to a source location have neither a path nor labels.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// Stable identifier such as `T5`, `P17` or `S3`
+    pub code: Option<String>,
+    pub severity: Severity,
+    pub message: String,
+    pub path: Option<PathBuf>,
+    pub labels: Vec<DiagnosticLabel>,
+}
+
+impl Diagnostic {
+    pub fn error(path: PathBuf, code: String) -> Self {
+        Self {
+            code: Some(code),
+            severity: Severity::Error,
+            message: String::new(),
+            path: Some(path),
+            labels: Vec::new(),
+        }
+    }
+
+    pub fn without_location(message: String) -> Self {
+        Self {
+            code: None,
+            severity: Severity::Error,
+            message,
+            path: None,
+            labels: Vec::new(),
+        }
+    }
+
+    pub fn with_message(mut self, message: impl Into<String>) -> Self {
+        self.message = message.into();
+        self
+    }
+
+    pub fn with_label(mut self, label: DiagnosticLabel) -> Self {
+        self.labels.push(label);
+        self
+    }
+
+    pub fn primary_label(&self) -> Option<&DiagnosticLabel> {
+        self.labels.iter().find(|l| l.is_primary)
+    }
+
+    /// Serializes the diagnostic as a single line of JSON:
+    ///
+    /// ```text
+    /// {"code":"S5","severity":"error","message":"Undeclared identifier",
+    ///  "file":"main.wl","labels":[{"message":"...","is_primary":true,
+    ///  "byte_start":21,"byte_end":22,"line_start":2,"column_start":5,
+    ///  "line_end":2,"column_end":6}]}
+    /// ```
+    ///
+    /// Lines and columns start at 1, byte offsets at 0 with an exclusive end.
+    pub fn to_json(&self) -> String {
+        let mut out = String::from("{");
+
+        let _ = write!(
+            out,
+            "\"code\":{},\"severity\":\"{}\",\"message\":{},\"file\":{},\"labels\":[",
+            self.code.as_deref().map_or("null".to_string(), json_string),
+            self.severity.as_str(),
+            json_string(&self.message),
+            self.path
+                .as_ref()
+                .map_or("null".to_string(), |p| json_string(&p.to_string_lossy()))
+        );
+
+        for (i, label) in self.labels.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            let span = &label.span;
+            let _ = write!(
+                out,
+                "{{\"message\":{},\"is_primary\":{},\"byte_start\":{},\"byte_end\":{},\
+                 \"line_start\":{},\"column_start\":{},\"line_end\":{},\"column_end\":{}}}",
+                json_string(&label.message),
+                label.is_primary,
+                span.start.byte_offset,
+                span.end.byte_offset,
+                span.start.line,
+                span.start.col,
+                span.end.line,
+                span.end.col
+            );
+        }
+
+        out.push_str("]}");
+        out
+    }
+}
+
+pub(crate) fn json_string(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::{
+        ast::{Position, Span},
+        compile::diagnostics::{Diagnostic, DiagnosticLabel},
+    };
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_to_json() {
+        let span = Span {
+            start: Position {
+                line: 2,
+                col: 5,
+                byte_offset: 21,
+            },
+            end: Position {
+                line: 2,
+                col: 6,
+                byte_offset: 22,
+            },
+        };
+        let diagnostic =
+            Diagnostic::error(PathBuf::from("dir/main.wl"), "S5".to_string())
+                .with_message("Undeclared identifier")
+                .with_label(
+                    DiagnosticLabel::primary(span)
+                        .with_message("Undeclared identifier \"x\""),
+                )
+                .with_label(
+                    DiagnosticLabel::secondary(Span::default()).with_message("a\tb"),
+                );
+
+        assert_eq!(
+            diagnostic.to_json(),
+            "{\"code\":\"S5\",\"severity\":\"error\",\"message\":\"Undeclared \
+             identifier\",\"file\":\"dir/main.wl\",\"labels\":[{\"message\":\"Undeclared \
+             identifier \\\"x\\\"\",\"is_primary\":true,\"byte_start\":21,\"byte_end\":22,\
+             \"line_start\":2,\"column_start\":5,\"line_end\":2,\"column_end\":6},\
+             {\"message\":\"a\\tb\",\"is_primary\":false,\"byte_start\":0,\"byte_end\":0,\
+             \"line_start\":0,\"column_start\":0,\"line_end\":0,\"column_end\":0}]}"
+        );
+
+        assert_eq!(
+            Diagnostic::without_location("Linking failed".to_string()).to_json(),
+            "{\"code\":null,\"severity\":\"error\",\"message\":\"Linking failed\",\
+             \"file\":null,\"labels\":[]}"
+        );
+    }
+}
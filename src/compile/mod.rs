@@ -1,12 +1,14 @@
 use std::{
-    collections::HashSet,
+    collections::{HashMap, HashSet},
     fs,
     path::{Path, PathBuf},
     sync::{atomic::AtomicUsize, Arc, Mutex},
 };
 
+pub mod diagnostics;
 pub mod file_cache;
 pub mod interner;
+pub mod repl;
 pub mod report_errors;
 
 use crate::{
@@ -14,37 +16,71 @@ use crate::{
         decl::Declaration,
         expr::{Expr, ExprKind},
         stmt::{Stmt, StmtKind},
+        DeclarationId,
     },
+    codegen::{self, CodegenError, CodegenTarget},
     compile::{
         file_cache::FileCache,
         interner::{Interners, SharedStringInterner, SharedTagInterner},
     },
-    hir::{errors::SemanticError, ProgramBuilder},
+    format::{self, FormatError},
+    hir::{errors::SemanticError, printer, ProgramBuilder},
+    interpret::{self, RuntimeError, RuntimeValue},
     parse::{Parser, ParsingError},
     tokenize::{TokenizationError, Tokenizer},
 };
 
 pub struct Compiler {
+    pub target: CodegenTarget,
+    /// Where `compile` writes its output, next to the entry module when unset
+    pub output_path: Option<PathBuf>,
+    pub error_format: ErrorFormat,
+    /// Whether reports may use ANSI colors
+    pub color: bool,
     interners: Interners,
     files: Arc<Mutex<FileCache>>,
-    errors: Vec<CompilationError>,
+    /// Sources used instead of reading the file at the canonical path
+    source_overrides: Arc<HashMap<PathBuf, String>>,
     decl_id_counter: Arc<AtomicUsize>,
 }
 
 impl Default for Compiler {
     fn default() -> Self {
         Self {
+            target: CodegenTarget::default(),
+            output_path: None,
+            error_format: ErrorFormat::default(),
+            color: true,
             interners: Interners {
                 string_interner: Arc::new(SharedStringInterner::default()),
                 tag_interner: Arc::new(SharedTagInterner::default()),
             },
             files: Arc::new(Mutex::new(FileCache::default())),
-            errors: Vec::new(),
+            source_overrides: Arc::new(HashMap::new()),
             decl_id_counter: Arc::new(AtomicUsize::new(0)),
         }
     }
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ErrorFormat {
+    /// Full reports with the annotated source
+    #[default]
+    Human,
+    /// Reports without the empty lines between their parts
+    Short,
+    /// One JSON object per diagnostic and line, for editors and CI
+    Json,
+}
+
+/// The textual representations `Compiler::emit` can produce
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmitKind {
+    Hir,
+    C,
+    Assembly,
+}
+
 #[derive(Debug)]
 pub enum CompilationError {
     CouldNotReadFile {
@@ -68,6 +104,32 @@ pub enum CompilationError {
         path: PathBuf,
         errors: Vec<SemanticError>,
     },
+    Codegen(CodegenError),
+    Runtime(RuntimeError),
+}
+
+/// Either the value produced by a compiler stage or every error which was found,
+/// the caller decides how to report them with `Compiler::report_errors`
+pub type CompileResult<T> = Result<T, Vec<CompilationError>>;
+
+/// The outcome of checking a program which keeps the HIR even when errors were
+/// found, for tools such as the language server
+pub struct Analysis {
+    /// None when tokenization or parsing failed, since no HIR is built then
+    pub program: Option<ProgramBuilder>,
+    /// The `main` function of the entry module, if it declares one
+    pub entry_fn: Option<DeclarationId>,
+    pub errors: Vec<CompilationError>,
+}
+
+/// A program which passed type-checking
+pub struct CompiledProgram {
+    pub program: ProgramBuilder,
+    /// The `main` function of the entry module
+    pub entry_fn: DeclarationId,
+    /// Where the generated executable or source file was written, None when no code
+    /// was generated
+    pub output_path: Option<PathBuf>,
 }
 
 #[derive(Debug)]
@@ -80,45 +142,147 @@ pub struct ParallelParseResult {
 }
 
 impl Compiler {
-    pub fn compile(&mut self, main_path: PathBuf) {
-        let parsed_modules = self.parallel_parse_modules(main_path);
-        let mut modules_to_compile = Vec::new();
-
-        for m in parsed_modules {
-            match m {
-                Err(e) => {
-                    self.errors.push(e);
-                }
-                Ok(module) => {
-                    let has_tokenization_errors = !module.tokenization_errors.is_empty();
-                    let has_parsing_errors = !module.parsing_errors.is_empty();
-
-                    if has_tokenization_errors {
-                        self.errors.push(CompilationError::Tokenization {
-                            path: module.path.clone(),
-                            errors: module.tokenization_errors.clone(),
-                        });
-                    }
+    /// Checks the program and generates code for `target`, writing the executable or
+    /// C source to `output_path`
+    pub fn compile(&mut self, main_path: PathBuf) -> CompileResult<CompiledProgram> {
+        let mut compiled = self.check(&main_path)?;
 
-                    if has_parsing_errors {
-                        self.errors.push(CompilationError::Parsing {
-                            path: module.path.clone(),
-                            errors: module.parsing_errors.clone(),
-                        });
-                    }
+        let (output_path, result) = match self.target {
+            CodegenTarget::X86_64 => {
+                let output_path = self
+                    .output_path
+                    .clone()
+                    .unwrap_or_else(|| main_path.with_extension(""));
+                let result = codegen::x86_64::build_executable(
+                    &compiled.program,
+                    compiled.entry_fn,
+                    &output_path,
+                );
+                (output_path, result)
+            }
+            CodegenTarget::C => {
+                let output_path = self
+                    .output_path
+                    .clone()
+                    .unwrap_or_else(|| main_path.with_extension("c"));
+                let result =
+                    codegen::c::generate_c_source(&compiled.program, compiled.entry_fn)
+                        .and_then(|source| Ok(fs::write(&output_path, source)?));
+                (output_path, result)
+            }
+        };
 
-                    if !has_tokenization_errors && !has_parsing_errors {
-                        modules_to_compile.push(module);
-                    }
-                }
-            };
+        result.map_err(|e| vec![CompilationError::Codegen(e)])?;
+
+        compiled.output_path = Some(output_path);
+        Ok(compiled)
+    }
+
+    /// Type-checks the program and executes its `main` function with the HIR
+    /// interpreter instead of generating code
+    pub fn run(&mut self, main_path: PathBuf) -> CompileResult<RuntimeValue> {
+        let compiled = self.check(&main_path)?;
+
+        interpret::run(&compiled.program, compiled.entry_fn)
+            .map_err(|e| vec![CompilationError::Runtime(e)])
+    }
+
+    /// Type-checks the program and renders it as HIR text, C source or assembly
+    pub fn emit(&mut self, main_path: PathBuf, kind: EmitKind) -> CompileResult<String> {
+        let compiled = self.check(&main_path)?;
+        let (program, entry_fn) = (&compiled.program, compiled.entry_fn);
+
+        let result = match kind {
+            EmitKind::Hir => Ok(printer::print_program(program)),
+            EmitKind::C => codegen::c::generate_c_source(program, entry_fn),
+            EmitKind::Assembly => codegen::x86_64::generate_assembly(program, entry_fn),
+        };
+
+        result.map_err(|e| vec![CompilationError::Codegen(e)])
+    }
+
+    /// Formats a single module without checking its imports. Returns None when the
+    /// module is formatted already, otherwise its formatted source code.
+    pub fn format(&mut self, path: &Path) -> CompileResult<Option<String>> {
+        let path = path.canonicalize().unwrap_or(path.to_path_buf());
+        let source = match fs::read_to_string(&path) {
+            Ok(source) => source,
+            Err(error) => {
+                return Err(vec![CompilationError::CouldNotReadFile { path, error }])
+            }
+        };
+
+        let result = format::format_source(&source);
+        self.files
+            .lock()
+            .unwrap()
+            .insert(path.clone(), source.clone());
+
+        match result {
+            Ok(formatted) if formatted == source => Ok(None),
+            Ok(formatted) => Ok(Some(formatted)),
+            Err(FormatError::Tokenization(errors)) => {
+                Err(vec![CompilationError::Tokenization { path, errors }])
+            }
+            Err(FormatError::Parsing(errors)) => {
+                Err(vec![CompilationError::Parsing { path, errors }])
+            }
         }
+    }
+
+    /// Parses and type-checks the program rooted at `main_path`. Returns the checked
+    /// program together with its entry function, or every error which was found.
+    pub fn check(&mut self, main_path: &Path) -> CompileResult<CompiledProgram> {
+        let analysis = self.analyze(main_path);
+        if !analysis.errors.is_empty() {
+            return Err(analysis.errors);
+        }
+
+        let (Some(program), Some(entry_fn)) = (analysis.program, analysis.entry_fn)
+        else {
+            return Err(vec![CompilationError::Codegen(
+                CodegenError::MissingEntryPoint,
+            )]);
+        };
 
-        if !self.errors.is_empty() {
-            self.report_errors();
-            return;
+        Ok(CompiledProgram {
+            program,
+            entry_fn,
+            output_path: None,
+        })
+    }
+
+    /// Parses and type-checks the program rooted at `main_path` without requiring an
+    /// entry point, collecting every error instead of stopping at the first stage
+    /// which fails
+    pub fn analyze(&mut self, main_path: &Path) -> Analysis {
+        // Overridden modules, e.g. unsaved editor buffers, need not exist on disk
+        if !self.source_overrides.contains_key(&resolve_path(main_path)) {
+            if let Err(error) = main_path.canonicalize() {
+                return Analysis {
+                    program: None,
+                    entry_fn: None,
+                    errors: vec![CompilationError::CouldNotReadFile {
+                        path: main_path.to_path_buf(),
+                        error,
+                    }],
+                };
+            }
         }
 
+        let parsed_modules = self.parallel_parse_modules(main_path.to_path_buf());
+        let (modules_to_compile, mut errors) = collect_parsed_modules(parsed_modules);
+
+        if !errors.is_empty() {
+            return Analysis {
+                program: None,
+                entry_fn: None,
+                errors,
+            };
+        }
+
+        let entry_fn = self.find_entry_fn(main_path, &modules_to_compile);
+
         let mut program_builder = ProgramBuilder::new(
             self.interners.string_interner.clone(),
             self.interners.tag_interner.clone(),
@@ -127,55 +291,70 @@ impl Compiler {
 
         program_builder.build(modules_to_compile);
 
-        for (path, mb) in program_builder.modules {
-            if !mb.errors.is_empty() {
-                self.errors.push(CompilationError::Semantic {
-                    path,
-                    errors: mb.errors,
-                });
-            }
-        }
+        errors.extend(take_semantic_errors(&mut program_builder));
 
-        if !program_builder.errors.is_empty() {
-            // These are global program errors
-            self.errors.push(CompilationError::Semantic {
-                path: PathBuf::from("Global"),
-                errors: program_builder.errors,
-            });
+        Analysis {
+            program: Some(program_builder),
+            entry_fn,
+            errors,
         }
+    }
 
-        if !self.errors.is_empty() {
-            self.report_errors();
-            return;
-        }
+    /// Uses `source` as the content of the module at `path` instead of reading the
+    /// file, e.g. for unsaved editor buffers
+    pub fn set_source(&mut self, path: PathBuf, source: String) {
+        let path = resolve_path(&path);
+        Arc::make_mut(&mut self.source_overrides).insert(path, source);
+    }
 
-        println!("Compilation successful (HIR generated)");
+    pub fn interners(&self) -> &Interners {
+        &self.interners
+    }
+
+    /// Finds the `main` function declared in the entry module
+    fn find_entry_fn(
+        &self,
+        main_path: &Path,
+        modules: &[ParallelParseResult],
+    ) -> Option<DeclarationId> {
+        let main_path = resolve_path(main_path);
+        let main_module = modules.iter().find(|m| m.path == main_path)?;
+
+        main_module.declarations.iter().find_map(|decl| match decl {
+            Declaration::Fn(f)
+                if self.interners.string_interner.resolve(f.identifier.name)
+                    == "main" =>
+            {
+                Some(f.id)
+            }
+            _ => None,
+        })
     }
 
     pub fn parallel_parse_modules(
         &self,
         main_path: PathBuf,
     ) -> Vec<Result<ParallelParseResult, CompilationError>> {
-        let canonical_main = main_path
-            .canonicalize()
-            .expect("Could not find the main module");
+        let canonical_main = resolve_path(&main_path);
 
         let visited = Arc::new(Mutex::new(HashSet::new()));
         let all_results = Arc::new(Mutex::new(Vec::new()));
 
         rayon::scope(|s| {
+            #[allow(clippy::too_many_arguments)]
             fn parse_recursive(
                 path: PathBuf,
                 s: &rayon::Scope,
                 interners: Interners,
                 files: Arc<Mutex<FileCache>>,
+                source_overrides: Arc<HashMap<PathBuf, String>>,
                 visited: Arc<Mutex<HashSet<PathBuf>>>,
                 all_results: Arc<
                     Mutex<Vec<Result<ParallelParseResult, CompilationError>>>,
                 >,
                 decl_id_counter: Arc<AtomicUsize>,
             ) {
-                let path = path.canonicalize().expect("Could not find a module");
+                let path = resolve_path(&path);
 
                 {
                     let mut visited_guard = visited.lock().unwrap();
@@ -184,7 +363,11 @@ impl Compiler {
                     }
                 }
 
-                let source_code = match fs::read_to_string(&path) {
+                let source = match source_overrides.get(&path) {
+                    Some(source) => Ok(source.clone()),
+                    None => fs::read_to_string(&path),
+                };
+                let source_code = match source {
                     Ok(sc) => sc,
                     Err(e) => {
                         all_results.lock().unwrap().push(Err(
@@ -209,6 +392,7 @@ impl Compiler {
                     let cloned_interners = interners.clone();
                     let decl_id_counter = decl_id_counter.clone();
                     let files = Arc::clone(&files);
+                    let source_overrides = Arc::clone(&source_overrides);
                     let visited = Arc::clone(&visited);
                     let all_results = Arc::clone(&all_results);
 
@@ -218,6 +402,7 @@ impl Compiler {
                             s,
                             cloned_interners,
                             files,
+                            source_overrides,
                             visited,
                             all_results,
                             decl_id_counter,
@@ -243,6 +428,7 @@ impl Compiler {
                 s,
                 self.interners.clone(),
                 self.files.clone(),
+                self.source_overrides.clone(),
                 visited,
                 all_results.clone(),
                 self.decl_id_counter.clone(),
@@ -256,6 +442,80 @@ impl Compiler {
     }
 }
 
+/// The canonical form of `path`, or its absolute form for a module which does not
+/// exist on disk, e.g. a new editor buffer which was never saved
+pub fn resolve_path(path: &Path) -> PathBuf {
+    path.canonicalize()
+        .or_else(|_| std::path::absolute(path))
+        .unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Splits parsed modules into the ones which can be type-checked and the errors of
+/// the others
+fn collect_parsed_modules(
+    parsed_modules: Vec<Result<ParallelParseResult, CompilationError>>,
+) -> (Vec<ParallelParseResult>, Vec<CompilationError>) {
+    let mut errors = Vec::new();
+    let mut modules_to_compile = Vec::new();
+
+    for m in parsed_modules {
+        match m {
+            Err(e) => {
+                errors.push(e);
+            }
+            Ok(module) => {
+                let has_tokenization_errors = !module.tokenization_errors.is_empty();
+                let has_parsing_errors = !module.parsing_errors.is_empty();
+
+                if has_tokenization_errors {
+                    errors.push(CompilationError::Tokenization {
+                        path: module.path.clone(),
+                        errors: module.tokenization_errors.clone(),
+                    });
+                }
+
+                if has_parsing_errors {
+                    errors.push(CompilationError::Parsing {
+                        path: module.path.clone(),
+                        errors: module.parsing_errors.clone(),
+                    });
+                }
+
+                if !has_tokenization_errors && !has_parsing_errors {
+                    modules_to_compile.push(module);
+                }
+            }
+        };
+    }
+
+    (modules_to_compile, errors)
+}
+
+/// Moves the errors found while building the HIR out of the program
+fn take_semantic_errors(program_builder: &mut ProgramBuilder) -> Vec<CompilationError> {
+    let mut errors = Vec::new();
+
+    for (path, mb) in program_builder.modules.iter_mut() {
+        let module_errors = mb.take_errors();
+        if !module_errors.is_empty() {
+            errors.push(CompilationError::Semantic {
+                path: path.clone(),
+                errors: module_errors,
+            });
+        }
+    }
+
+    if !program_builder.errors.is_empty() {
+        // These are global program errors
+        errors.push(CompilationError::Semantic {
+            path: PathBuf::from("Global"),
+            errors: std::mem::take(&mut program_builder.errors),
+        });
+    }
+
+    errors
+}
+
 fn find_dependencies(
     current_module_path: &Path,
     statements: &[Stmt],
@@ -294,9 +554,753 @@ fn find_dependencies(
             StmtKind::TypeAliasDecl(decl) => {
                 declarations.push(Declaration::TypeAlias(decl.clone()));
             }
+            StmtKind::ImplDecl(decl) => {
+                declarations.push(Declaration::Impl(decl.clone()));
+            }
+            StmtKind::ConstDecl(decl) => {
+                declarations.push(Declaration::Const(decl.clone()));
+            }
             _ => {}
         }
     }
 
     (dependencies, errors, declarations)
 }
+
+/// Writes `source` to a fresh temporary directory as the entry module
+#[cfg(test)]
+pub(crate) fn write_test_program(name: &str, source: &str) -> PathBuf {
+    let dir =
+        std::env::temp_dir().join(format!("willow-test-{}-{}", std::process::id(), name));
+    fs::create_dir_all(&dir).unwrap();
+    let main_path = dir.join("main.wl");
+    fs::write(&main_path, source).unwrap();
+    main_path
+}
+
+/// Writes `source` as the entry module of a temporary program and checks it
+#[cfg(test)]
+pub(crate) fn check_source(name: &str, source: &str) -> (ProgramBuilder, DeclarationId) {
+    let main_path = write_test_program(name, source);
+
+    let result = Compiler::default().check(&main_path);
+    let _ = fs::remove_dir_all(main_path.parent().unwrap());
+
+    match result {
+        Ok(compiled) => (compiled.program, compiled.entry_fn),
+        Err(errors) => panic!("Test program should type-check, found {:?}", errors),
+    }
+}
+
+/// Checks `source` as the entry module of a temporary program which is expected to
+/// fail, the kinds of the semantic errors it reports
+#[cfg(test)]
+pub(crate) fn semantic_errors(
+    source: &str,
+) -> Vec<crate::hir::errors::SemanticErrorKind> {
+    semantic_errors_with_modules(source, &[])
+}
+
+/// Writes `source` as the entry module of a fresh temporary program, with `modules`
+/// next to it as `(file name, source)`
+#[cfg(test)]
+fn write_test_program_with_modules(source: &str, modules: &[(&str, &str)]) -> PathBuf {
+    static NEXT_PROGRAM: AtomicUsize = AtomicUsize::new(0);
+    let id = NEXT_PROGRAM.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    let main_path = write_test_program(&format!("program-{}", id), source);
+    for (name, module_source) in modules {
+        fs::write(main_path.with_file_name(name), module_source).unwrap();
+    }
+    main_path
+}
+
+/// Runs `source` with the interpreter as the entry module of a temporary program, with
+/// `modules` written next to it as `(file name, source)`
+#[cfg(test)]
+pub(crate) fn run_with_modules(
+    source: &str,
+    modules: &[(&str, &str)],
+) -> CompileResult<RuntimeValue> {
+    let main_path = write_test_program_with_modules(source, modules);
+
+    let result = Compiler::default().run(main_path.clone());
+    let _ = fs::remove_dir_all(main_path.parent().unwrap());
+    result
+}
+
+/// Like `semantic_errors`, with `modules` written next to the entry module as
+/// `(file name, source)`
+#[cfg(test)]
+pub(crate) fn semantic_errors_with_modules(
+    source: &str,
+    modules: &[(&str, &str)],
+) -> Vec<crate::hir::errors::SemanticErrorKind> {
+    let main_path = write_test_program_with_modules(source, modules);
+
+    let result = Compiler::default().check(&main_path);
+    let _ = fs::remove_dir_all(main_path.parent().unwrap());
+
+    match result {
+        Ok(_) => panic!("Expected the test program to fail type-checking"),
+        Err(errors) => errors
+            .into_iter()
+            .flat_map(|error| match error {
+                CompilationError::Semantic { errors, .. } => errors,
+                other => panic!("Expected only semantic errors, found {:?}", other),
+            })
+            .map(|error| error.kind)
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::{
+        codegen::CodegenError,
+        compile::{
+            run_with_modules, semantic_errors, semantic_errors_with_modules,
+            write_test_program, CompilationError, Compiler, EmitKind,
+        },
+        hir::{errors::SemanticErrorKind, types::checked_type::Type},
+        interpret::RuntimeValue,
+    };
+
+    #[test]
+    fn test_errors_are_returned() {
+        let main_path =
+            write_test_program("returned_errors", "fn main(): i32 {\n    x\n}\n");
+
+        let canonical_path = main_path.canonicalize().unwrap();
+        let errors = match Compiler::default().check(&main_path) {
+            Ok(_) => panic!("Expected the program to fail type-checking"),
+            Err(errors) => errors,
+        };
+        let _ = fs::remove_dir_all(main_path.parent().unwrap());
+
+        assert!(matches!(
+            errors.as_slice(),
+            [CompilationError::Semantic { path, errors }]
+                if path == &canonical_path && errors.len() == 1
+        ));
+    }
+
+    #[test]
+    fn test_missing_entry_point() {
+        let main_path =
+            write_test_program("missing_entry", "fn helper(): i32 {\n    1i32\n}\n");
+
+        let result = Compiler::default().emit(main_path.clone(), EmitKind::Hir);
+        let _ = fs::remove_dir_all(main_path.parent().unwrap());
+
+        assert!(matches!(
+            result.as_ref().map_err(Vec::as_slice),
+            Err([CompilationError::Codegen(CodegenError::MissingEntryPoint)])
+        ));
+    }
+
+    #[test]
+    fn test_run_returns_value() {
+        let main_path =
+            write_test_program("run_value", "fn main(): i32 {\n    6i32 * 7i32\n}\n");
+
+        let result = Compiler::default().run(main_path.clone());
+        let _ = fs::remove_dir_all(main_path.parent().unwrap());
+
+        assert_eq!(result.ok(), Some(RuntimeValue::Int(42)));
+    }
+
+    #[test]
+    fn test_namespace_import() {
+        let source = "from \"./math.wl\" as math\n\nfn main(): i32 {\n    math::double(21i32)\n}\n";
+        let math = "export fn double(x: i32): i32 {\n    x * 2i32\n}\n";
+
+        let result = run_with_modules(source, &[("math.wl", math)]);
+        assert_eq!(result.ok(), Some(RuntimeValue::Int(42)));
+    }
+
+    #[test]
+    fn test_namespace_import_errors() {
+        let source = "from \"./math.wl\" as math
+
+fn main(): i32 {
+    let m = math;
+    let x: math = 1i32;
+    math::hidden()
+}
+";
+        let math = "export fn double(x: i32): i32 {
+    x * 2i32
+}
+
+fn hidden(): i32 {
+    1i32
+}
+";
+
+        assert!(matches!(
+            semantic_errors_with_modules(source, &[("math.wl", math)]).as_slice(),
+            [
+                SemanticErrorKind::CannotUseModuleAsValue,
+                SemanticErrorKind::CannotUseModuleAsType,
+                SemanticErrorKind::SymbolNotExported { .. },
+            ]
+        ));
+    }
+
+    #[test]
+    fn test_static_access_errors() {
+        let source = "type Counter = { count: i64 };
+
+impl Counter {
+    fn new(): Counter {
+        { count: 0 }
+    }
+}
+
+fn main(): i64 {
+    let f = i64[]::with_capacity;
+    let n = i64::foo();
+    let s = string::nope;
+    let c = Counter::missing;
+    0
+}
+";
+
+        assert!(matches!(
+            semantic_errors(source).as_slice(),
+            [
+                SemanticErrorKind::CannotUseBuiltinFnAsValue,
+                SemanticErrorKind::CannotStaticAccess(Type::I64),
+                SemanticErrorKind::AccessToUndefinedStaticField(_),
+                SemanticErrorKind::AccessToUndefinedStaticField(_),
+            ]
+        ));
+    }
+
+    #[test]
+    fn test_imported_constants() {
+        let source = "const TWICE = LIMIT * 2;
+
+from \"./config.wl\" { LIMIT, NAME }
+from \"./config.wl\" as config
+from \"./derived.wl\" { TRIPLE }
+
+fn main(): i64 {
+    const LOCAL = 2;
+    let bonus = if NAME == \"willow\" { 1 } else { 0 };
+    LIMIT + config::DOUBLE_LIMIT * LOCAL + bonus + TWICE * 100 + TRIPLE * 1000
+}
+";
+        let config = "export const LIMIT: i64 = 8;
+export const NAME = \"willow\";
+const HIDDEN = true;
+export const DOUBLE_LIMIT = LIMIT;
+";
+        let derived = "from \"./config.wl\" { LIMIT }
+
+export const TRIPLE = LIMIT * 3;
+";
+
+        let result =
+            run_with_modules(source, &[("config.wl", config), ("derived.wl", derived)]);
+        assert_eq!(result.ok(), Some(RuntimeValue::Int(25 + 1600 + 24000)));
+    }
+
+    #[test]
+    fn test_diagnostics_have_secondary_labels() {
+        let source = "fn f(): i32 {\n    1i32\n}\n\nfn f(): i32 {\n    2i32\n}\n";
+        let main_path = write_test_program("secondary_labels", source);
+
+        let mut compiler = Compiler::default();
+        let errors = match compiler.check(&main_path) {
+            Ok(_) => panic!("Expected a duplicate identifier error"),
+            Err(errors) => errors,
+        };
+        let _ = fs::remove_dir_all(main_path.parent().unwrap());
+
+        let diagnostics = compiler.diagnostics(&errors);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code.as_deref(), Some("S24"));
+
+        let lines: Vec<(usize, bool)> = diagnostics[0]
+            .labels
+            .iter()
+            .map(|l| (l.span.start.line, l.is_primary))
+            .collect();
+        assert_eq!(lines, vec![(5, true), (1, false)]);
+    }
+
+    #[test]
+    fn test_assignment_to_immutable_variable() {
+        let source = "fn main(): i32 {
+    let p = { x: 1i32 };
+    p.x = 2i32;
+    let n = 0i32;
+    n += 1i32;
+    let mut m = 0i32;
+    m = p.x + n;
+    m
+}
+";
+
+        assert!(matches!(
+            semantic_errors(source).as_slice(),
+            [
+                SemanticErrorKind::AssignmentToImmutable(_),
+                SemanticErrorKind::AssignmentToImmutable(_),
+            ]
+        ));
+    }
+
+    #[test]
+    fn test_assignment_to_immutable_parameter() {
+        let source = "type Counter = { count: i32 };
+
+impl Counter {
+    fn reset(self): void {
+        self.count = 0i32;
+    }
+
+    fn bump(mut self): void {
+        self.count += 1i32;
+    }
+
+    fn bump_twice(self): void {
+        self.bump();
+        self.bump();
+    }
+}
+
+fn clamp(n: i32, mut limit: i32): i32 {
+    limit -= 1i32;
+    n = limit;
+    n
+}
+
+fn main(): i32 {
+    let counter: Counter = { count: 0i32 };
+    counter.bump();
+    let mut other: Counter = { count: 0i32 };
+    other.bump();
+    clamp(other.count, 2i32)
+}
+";
+
+        assert!(matches!(
+            semantic_errors(source).as_slice(),
+            [
+                SemanticErrorKind::AssignmentToImmutable(_),
+                SemanticErrorKind::MutatingMethodOnImmutable { .. },
+                SemanticErrorKind::MutatingMethodOnImmutable { .. },
+                SemanticErrorKind::AssignmentToImmutable(_),
+                SemanticErrorKind::MutatingMethodOnImmutable { .. },
+            ]
+        ));
+    }
+
+    #[test]
+    fn test_assignment_to_receiver() {
+        let source = "type Bag = { n: i64 };
+type Shape = #square(i64) | #circle(i64);
+
+impl Bag {
+    fn reset(mut self): void {
+        self = { n: 7 };
+    }
+}
+
+impl Shape {
+    fn grow(mut self): void {
+        self = #square(100);
+    }
+}
+
+fn main(): i64 {
+    let mut bag: Bag = { n: 1 };
+    bag.reset();
+    bag.n
+}
+";
+
+        assert!(matches!(
+            semantic_errors(source).as_slice(),
+            [
+                SemanticErrorKind::AssignmentToReceiver,
+                SemanticErrorKind::AssignmentToReceiver,
+            ]
+        ));
+    }
+
+    #[test]
+    fn test_method_errors() {
+        let source = "type Counter = { count: i64 };
+
+impl Counter {
+    fn new(): Counter {
+        { count: 0 }
+    }
+
+    fn get(self): i64 {
+        self.count
+    }
+
+    fn wrap<T>(self, value: T): T {
+        value
+    }
+}
+
+fn main(): i64 {
+    impl Counter {
+        fn reset(mut self): void {
+            self.count = 0;
+        }
+    }
+    let counter = Counter::new();
+    let get = counter.get;
+    counter.new();
+    counter.get()
+}
+";
+
+        assert!(matches!(
+            semantic_errors(source).as_slice(),
+            [
+                SemanticErrorKind::MethodCannotBeGeneric,
+                SemanticErrorKind::ImplMustBeDeclaredAtTopLevel,
+                SemanticErrorKind::CannotUseMethodAsValue,
+                SemanticErrorKind::FnHasNoReceiver(_),
+            ]
+        ));
+    }
+
+    #[test]
+    fn test_assignment_to_captured_variable() {
+        let source = "fn main(): i32 {
+    let mut count: i32 = 0i32;
+    fn bump(): i32 {
+        count = count + 1i32;
+        count
+    }
+    bump()
+}
+";
+
+        assert!(matches!(
+            semantic_errors(source).as_slice(),
+            [SemanticErrorKind::AssignmentToImmutable(_)]
+        ));
+    }
+
+    #[test]
+    fn test_match_errors() {
+        let program = |main_body: &str| {
+            format!(
+                "type Shape = #Circle(i64) | #Square(i64) | #Empty;\n\n\
+                 fn get(): Shape {{\n    #Empty\n}}\n\n\
+                 fn main(): i64 {{\n    let s: Shape = get();\n    {}\n}}\n",
+                main_body
+            )
+        };
+
+        assert!(matches!(
+            semantic_errors(&program("match s { Circle(r) => r, Empty => 0 }")).as_slice(),
+            [SemanticErrorKind::NonExhaustiveMatch { missing_patterns }]
+                if missing_patterns.len() == 1
+        ));
+        assert!(matches!(
+            semantic_errors(&program(
+                "match s { Circle(r) => r, Square(q) => q, Empty => 0, Empty => 1 }"
+            ))
+            .as_slice(),
+            [SemanticErrorKind::UnreachableMatchArm]
+        ));
+        assert!(matches!(
+            semantic_errors(&program(
+                "match s, s { Circle(r) => r, Square(q), Empty => q, Empty, Empty => 0 }"
+            ))
+            .as_slice(),
+            [SemanticErrorKind::MatchPatternCountMismatch {
+                expected: 2,
+                received: 1
+            }]
+        ));
+        assert!(matches!(
+            semantic_errors(&program(
+                "match s { Circle(r) => r, Square(q) => q, Empty => 0, Triangle => 3 }"
+            ))
+            .as_slice(),
+            [SemanticErrorKind::UndefinedMatchVariant { .. }]
+        ));
+        assert!(matches!(
+            semantic_errors(&program(
+                "match s { Circle(r) => r, Square(q) => q, Empty(e) => e }"
+            ))
+            .as_slice(),
+            [SemanticErrorKind::MatchVariantHasNoValue(_)]
+        ));
+        assert!(matches!(
+            semantic_errors(&program("let n = 5;\n    match n { Empty => 0 }"))
+                .as_slice(),
+            [SemanticErrorKind::CannotMatchNonUnion(Type::I64)]
+        ));
+    }
+
+    #[test]
+    fn test_for_loop_errors() {
+        let source = "fn main(): i64 {
+    for x in 5 {}
+    let r = 0..5;
+    0
+}
+";
+
+        assert!(matches!(
+            semantic_errors(source).as_slice(),
+            [
+                SemanticErrorKind::CannotIterate(Type::I64),
+                SemanticErrorKind::RangeOutsideForLoop,
+            ]
+        ));
+    }
+
+    #[test]
+    fn test_bitwise_operators_on_floats() {
+        let source = "fn main(): i64 {
+    let a = 1.5f64 & 2.0f64;
+    let b = ~1.0f64;
+    0
+}
+";
+
+        assert!(matches!(
+            semantic_errors(source).as_slice(),
+            [
+                SemanticErrorKind::ExpectedAnIntegerOperand,
+                SemanticErrorKind::ExpectedAnIntegerOperand,
+            ]
+        ));
+    }
+
+    #[test]
+    fn test_non_constant_initializer() {
+        let source = "fn f(): i64 {
+    1
+}
+
+const X = f();
+
+fn main(): i64 {
+    let y = 2;
+    const Z = y + 1;
+    0
+}
+";
+
+        assert!(matches!(
+            semantic_errors(source).as_slice(),
+            [
+                SemanticErrorKind::ExpectedConstantExpression,
+                SemanticErrorKind::ExpectedConstantExpression,
+            ]
+        ));
+    }
+
+    #[test]
+    fn test_constant_overflow_and_division_by_zero() {
+        let source = "const SMALL: u8 = 255u8 + 1u8;
+const RATIO = 1 / 0;
+
+fn main(): i64 {
+    let x = 5;
+    let y = x % 0;
+    let z = 100i8 * 2i8;
+    let shifted = 1u8 << 9u8;
+    x
+}
+";
+
+        assert!(matches!(
+            semantic_errors(source).as_slice(),
+            [
+                SemanticErrorKind::ConstantOverflow(Type::U8),
+                SemanticErrorKind::DivisionByZero,
+                SemanticErrorKind::DivisionByZero,
+                SemanticErrorKind::ConstantOverflow(Type::I8),
+            ]
+        ));
+    }
+
+    #[test]
+    fn test_recursive_constants() {
+        let source = "const FIRST = SECOND + 1;
+const SECOND = FIRST * 2;
+const DEPENDENT = FIRST + 1;
+const SELF = SELF;
+const FINE = 3;
+
+fn main(): i64 {
+    FINE
+}
+";
+
+        assert!(matches!(
+            semantic_errors(source).as_slice(),
+            [
+                SemanticErrorKind::RecursiveConstant(pair),
+                SemanticErrorKind::RecursiveConstant(single),
+            ] if pair.len() == 2 && single.len() == 1
+        ));
+    }
+
+    #[test]
+    fn test_array_index_out_of_bounds() {
+        let source = "fn main(): i64 {
+    let mut values = [0; 3];
+    values[2usize] = 1;
+    values[3usize] = 1;
+    let last = values[7usize];
+    let i = 5usize;
+    values[i]
+}
+";
+
+        assert!(matches!(
+            semantic_errors(source).as_slice(),
+            [
+                SemanticErrorKind::IndexOutOfBounds { index: 3, size: 3 },
+                SemanticErrorKind::IndexOutOfBounds { index: 7, size: 3 },
+            ]
+        ));
+    }
+
+    #[test]
+    fn test_array_sizes() {
+        let source = "fn main(): i64 {
+    let huge = [0; 1000000000000];
+    let big: [u8; 2000000] = [];
+    let short: [i64; 3] = [1, 2];
+    0
+}
+";
+
+        let errors = semantic_errors(source);
+        assert!(matches!(
+            errors.as_slice(),
+            [
+                SemanticErrorKind::ArrayTooLarge {
+                    item_type: Type::I64,
+                    size: 1000000000000,
+                },
+                SemanticErrorKind::ArrayTooLarge {
+                    item_type: Type::U8,
+                    size: 2000000,
+                },
+                SemanticErrorKind::TypeMismatch {
+                    expected: Type::Array { size: 3, .. },
+                    received: Type::Array { size: 2, .. },
+                },
+                ..
+            ]
+        ));
+    }
+
+    #[test]
+    fn test_invalid_slices() {
+        let source = "fn main(): i64 {
+    let items = [1, 2, 3];
+    let backwards = items[2usize..1usize];
+    let signed = items[0..2usize];
+    0
+}
+";
+
+        assert!(matches!(
+            semantic_errors(source).as_slice(),
+            [
+                SemanticErrorKind::InvalidSliceRange { start: 2, end: 1 },
+                SemanticErrorKind::TypeMismatch {
+                    expected: Type::USize,
+                    received: Type::I64,
+                },
+            ]
+        ));
+    }
+
+    #[test]
+    fn test_generic_instance_errors_are_reported_once() {
+        let source = "fn bad<T>(x: T): i64 {
+    undefined_thing
+}
+
+fn main(): i64 {
+    bad(1) + bad(true)
+}
+";
+
+        assert!(matches!(
+            semantic_errors(source).as_slice(),
+            [SemanticErrorKind::UndeclaredIdentifier(_)]
+        ));
+    }
+
+    #[test]
+    fn test_generic_errors() {
+        let source = "type Box<T> = { value: T };
+type Nested<T> = { inner: Nested<T> };
+
+fn identity<T>(x: T): T {
+    x
+}
+
+fn zero<T>(): i64 {
+    0
+}
+
+fn unwrap(nested: Nested<i64>): i64 {
+    0
+}
+
+fn main(): i64 {
+    let boxed: Box<i64, bool> = { value: 1 };
+    let f = identity;
+    fn inner<T>(x: T): T {
+        x
+    }
+    zero()
+}
+";
+
+        assert!(matches!(
+            semantic_errors(source).as_slice(),
+            [
+                SemanticErrorKind::RecursiveGenericTypeAlias(_),
+                SemanticErrorKind::GenericArgumentCountMismatch {
+                    expected: 1,
+                    received: 2,
+                },
+                SemanticErrorKind::CannotUseGenericFnAsValue,
+                SemanticErrorKind::GenericFnMustBeDeclaredAtTopLevel,
+                SemanticErrorKind::CannotInferGenericArgument(_),
+            ]
+        ));
+    }
+
+    #[test]
+    fn test_generic_instances_too_deep() {
+        let source = "fn grow<T>(x: T, n: i64): i64 {
+    if n == 0 { 0 } else { grow([x], n - 1) }
+}
+
+fn main(): i64 {
+    grow(1, 3)
+}
+";
+
+        assert!(matches!(
+            semantic_errors(source).as_slice(),
+            [SemanticErrorKind::GenericInstancesTooDeep(_)]
+        ));
+    }
+}
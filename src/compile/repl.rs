@@ -0,0 +1,543 @@
+use std::{
+    io::{self, BufRead, IsTerminal, Write},
+    path::PathBuf,
+};
+
+use crate::{
+    ast::{
+        decl::Declaration,
+        expr::{BlockContents, Expr, ExprKind},
+        stmt::{Stmt, StmtKind},
+        DeclarationId, IdentifierNode, Position, Span,
+    },
+    compile::{
+        collect_parsed_modules, interner::StringId, take_semantic_errors,
+        CompilationError, CompileResult, Compiler,
+    },
+    hir::{
+        cfg::ValueId,
+        types::{
+            checked_declaration::{CheckedDeclaration, CheckedVarDecl},
+            checked_type::Type,
+        },
+        utils::type_to_string::type_to_string,
+        FunctionBuilder, HIRContext, ModuleBuilder, ProgramBuilder,
+    },
+    interpret::{self, RuntimeValue, Session},
+    parse::{Parser, ParsingError, ParsingErrorKind},
+    tokenize::{PunctuationKind, Token, TokenKind, TokenizationErrorKind, Tokenizer},
+};
+
+/// File name of the module the entries are declared in. Imports are resolved relative
+/// to the working directory.
+const REPL_MODULE: &str = "<repl>";
+
+/// Evaluates entries one after another while keeping the declarations of earlier
+/// entries in scope. Every entry is type-checked as the body of a function, which is
+/// then executed with the HIR interpreter in one session, so values made by earlier
+/// entries stay in its memory.
+pub struct Repl {
+    pub compiler: Compiler,
+    program: ProgramBuilder,
+    path: PathBuf,
+    /// Every entry so far, one after another. The spans of all entries point into this
+    /// text, so errors are reported at the entry they come from.
+    source: String,
+    /// Variables declared by the earlier entries which ran successfully, with the
+    /// addresses of their slots in the session's memory
+    variables: Vec<(CheckedVarDecl, u64)>,
+    session: Session,
+    entry_name: StringId,
+}
+
+impl Repl {
+    pub fn new(compiler: Compiler) -> Repl {
+        let path = std::env::current_dir()
+            .unwrap_or_default()
+            .join(REPL_MODULE);
+
+        let mut program = ProgramBuilder::new(
+            compiler.interners.string_interner.clone(),
+            compiler.interners.tag_interner.clone(),
+            compiler.decl_id_counter.clone(),
+        );
+        program
+            .modules
+            .insert(path.clone(), ModuleBuilder::new(path.clone()));
+
+        let entry_name = compiler.interners.string_interner.intern("<entry>");
+
+        Repl {
+            compiler,
+            program,
+            path,
+            source: String::new(),
+            variables: Vec::new(),
+            session: Session::default(),
+            entry_name,
+        }
+    }
+
+    /// Reads entries from stdin until the end of input and prints the value and type of
+    /// each one, or its errors
+    pub fn run_stdio(&mut self) -> io::Result<()> {
+        let stdin = io::stdin();
+        let interactive = stdin.is_terminal();
+        let mut lines = stdin.lock().lines();
+        let mut input = String::new();
+
+        loop {
+            if interactive {
+                print!("{}", if input.is_empty() { ">> " } else { ".. " });
+                io::stdout().flush()?;
+            }
+
+            let Some(line) = lines.next() else {
+                break;
+            };
+            if !input.is_empty() {
+                input.push('\n');
+            }
+            input.push_str(&line?);
+
+            if !self.is_incomplete(&input) {
+                self.eval_and_print(&std::mem::take(&mut input));
+            }
+        }
+
+        // Report what is wrong with an unfinished last entry
+        self.eval_and_print(&input);
+
+        Ok(())
+    }
+
+    fn eval_and_print(&mut self, input: &str) {
+        if input.trim().is_empty() {
+            return;
+        }
+
+        match self.eval(input) {
+            Ok(Some((value, ty))) => {
+                println!(
+                    "{}: {}",
+                    value,
+                    type_to_string(&ty, self.compiler.interners())
+                );
+            }
+            Ok(None) => {}
+            Err(errors) => self.compiler.report_errors(&errors),
+        }
+    }
+
+    /// Whether the input ends inside of a delimiter or documentation, so more lines have
+    /// to be read before it can be evaluated
+    pub fn is_incomplete(&self, input: &str) -> bool {
+        let (tokens, errors) =
+            Tokenizer::tokenize(input, self.compiler.interners.string_interner.clone());
+
+        if errors
+            .iter()
+            .any(|e| e.kind == TokenizationErrorKind::UnterminatedDoc)
+        {
+            return true;
+        }
+
+        let mut depth: isize = 0;
+        for token in tokens {
+            match token.kind {
+                TokenKind::Punctuation(
+                    PunctuationKind::LBrace
+                    | PunctuationKind::LParen
+                    | PunctuationKind::LBracket,
+                ) => depth += 1,
+                TokenKind::Punctuation(
+                    PunctuationKind::RBrace
+                    | PunctuationKind::RParen
+                    | PunctuationKind::RBracket,
+                ) => depth -= 1,
+                _ => {}
+            }
+        }
+
+        depth > 0
+    }
+
+    /// Type-checks and runs an entry. Returns the value of its final expression
+    /// together with its type, None when the entry has no final expression.
+    pub fn eval(&mut self, input: &str) -> CompileResult<Option<(RuntimeValue, Type)>> {
+        let start = Position {
+            line: self.source.matches('\n').count() + 1,
+            col: 1,
+            byte_offset: self.source.len(),
+        };
+        self.source.push_str(input);
+        self.source.push('\n');
+        self.compiler
+            .files
+            .lock()
+            .unwrap()
+            .insert(self.path.clone(), self.source.clone());
+
+        let interner = self.compiler.interners.string_interner.clone();
+        let (mut tokens, mut tokenization_errors) =
+            Tokenizer::tokenize(input, interner.clone());
+        for token in &mut tokens {
+            token.span = shift_span(token.span, start);
+        }
+        for error in &mut tokenization_errors {
+            error.span = shift_span(error.span, start);
+        }
+        if !tokenization_errors.is_empty() {
+            return Err(vec![CompilationError::Tokenization {
+                path: self.path.clone(),
+                errors: tokenization_errors,
+            }]);
+        }
+
+        // The entry is parsed as the contents of a code block, so its last expression
+        // doesn't need a semicolon
+        let end = tokens.last().map_or(start, |t| t.span.end);
+        tokens.insert(0, punctuation(PunctuationKind::LBrace, start));
+        tokens.push(punctuation(PunctuationKind::RBrace, end));
+
+        let mut parser = Parser {
+            offset: 0,
+            tokens,
+            checkpoint_offset: 0,
+            interner,
+            decl_id_counter: self.compiler.decl_id_counter.clone(),
+        };
+        let parsed = parser.parse_codeblock_expr().and_then(|block| {
+            match parser.tokens.get(parser.offset) {
+                Some(token) => Err(ParsingError {
+                    kind: ParsingErrorKind::UnexpectedTokenAfterFinalExpression {
+                        found: token.clone(),
+                    },
+                    span: token.span,
+                }),
+                None => Ok(block),
+            }
+        });
+        let block = parsed.map_err(|e| {
+            vec![CompilationError::Parsing {
+                path: self.path.clone(),
+                errors: vec![e],
+            }]
+        })?;
+
+        self.eval_block(block)
+    }
+
+    fn eval_block(
+        &mut self,
+        block: BlockContents,
+    ) -> CompileResult<Option<(RuntimeValue, Type)>> {
+        let mut declarations = Vec::new();
+        let mut imports = Vec::new();
+        let mut statements = Vec::new();
+
+        for stmt in block.statements {
+            match &stmt.kind {
+                StmtKind::Expression(Expr {
+                    kind: ExprKind::Fn(decl),
+                    ..
+                }) => declarations.push(Declaration::Fn(*decl.clone())),
+                StmtKind::TypeAliasDecl(decl) => {
+                    declarations.push(Declaration::TypeAlias(decl.clone()))
+                }
+                StmtKind::ImplDecl(decl) => {
+                    declarations.push(Declaration::Impl(decl.clone()))
+                }
+                StmtKind::ConstDecl(decl) => {
+                    declarations.push(Declaration::Const(decl.clone()))
+                }
+                StmtKind::From { .. } => imports.push(stmt),
+                _ => statements.push(stmt),
+            }
+        }
+
+        // A function typed last is parsed as the block's final expression
+        let mut final_expr = block.final_expr;
+        if let Some(expr) = final_expr.take_if(|e| matches!(e.kind, ExprKind::Fn(_))) {
+            if let ExprKind::Fn(decl) = expr.kind {
+                declarations.push(Declaration::Fn(*decl));
+            }
+        }
+
+        self.load_imports(&imports)?;
+
+        let mut mb = self
+            .program
+            .modules
+            .remove(&self.path)
+            .expect("The REPL module is never removed");
+        // Declarations of an entry with errors are rolled back
+        let scopes = mb.scopes.clone();
+
+        let constants = self
+            .program
+            .build_signatures(&mut mb, &declarations, &imports);
+        self.program.build_imports(&mut mb, &imports, constants);
+        self.program.build_impls(&mut mb, &declarations);
+        self.program.build_bodies(&mut mb, &declarations);
+
+        let entry_id = self.program.new_declaration_id();
+        // Variables declared at the top of the entry outlive it
+        let declared_variables: Vec<DeclarationId> = statements
+            .iter()
+            .filter_map(|stmt| match &stmt.kind {
+                StmtKind::VarDecl(decl) => Some(decl.id),
+                _ => None,
+            })
+            .collect();
+        let body = BlockContents {
+            statements,
+            final_expr,
+            span: block.span,
+        };
+        let (variables, addresses): (Vec<CheckedVarDecl>, Vec<u64>) =
+            self.variables.iter().cloned().unzip();
+        let mut ctx = HIRContext {
+            program_builder: &mut self.program,
+            module_builder: &mut mb,
+        };
+        let ty = FunctionBuilder::build_entry(
+            &mut ctx,
+            entry_id,
+            IdentifierNode {
+                name: self.entry_name,
+                span: block.span,
+            },
+            &variables,
+            body,
+        );
+
+        self.program.modules.insert(self.path.clone(), mb);
+        self.program.build_generic_instances();
+
+        let mb = self
+            .program
+            .modules
+            .get_mut(&self.path)
+            .expect("The REPL module is never removed");
+        let errors = mb.take_errors();
+        if !errors.is_empty() {
+            mb.scopes = scopes;
+        }
+
+        if !errors.is_empty() {
+            return Err(vec![CompilationError::Semantic {
+                path: self.path.clone(),
+                errors,
+            }]);
+        }
+
+        let declared_variables: Vec<CheckedVarDecl> = declared_variables
+            .into_iter()
+            .filter_map(|id| match self.program.get_declaration(id) {
+                CheckedDeclaration::Var(var) => Some(var.clone()),
+                _ => None,
+            })
+            .collect();
+        let slots: Vec<ValueId> = declared_variables.iter().map(|var| var.ptr).collect();
+
+        let (value, kept_addresses) = interpret::run_in_session(
+            &self.program,
+            entry_id,
+            &addresses,
+            &slots,
+            &mut self.session,
+        )
+        .map_err(|e| vec![CompilationError::Runtime(e)])?;
+
+        for var in declared_variables {
+            let Some(address) = kept_addresses.get(&var.ptr) else {
+                continue;
+            };
+            self.variables
+                .retain(|(earlier, _)| earlier.identifier.name != var.identifier.name);
+            self.variables.push((var, *address));
+        }
+
+        Ok((ty != Type::Void).then_some((value, ty)))
+    }
+
+    /// Parses and type-checks the modules imported by an entry which aren't loaded yet
+    fn load_imports(&mut self, imports: &[Stmt]) -> CompileResult<()> {
+        let mut errors = Vec::new();
+
+        for stmt in imports {
+            let StmtKind::From { path, .. } = &stmt.kind else {
+                continue;
+            };
+
+            let mut target_path = self.path.clone();
+            target_path.pop();
+            target_path.push(&path.value);
+
+            // Missing modules are reported when the import is built
+            let Ok(canonical_path) = target_path.canonicalize() else {
+                continue;
+            };
+            if self.program.modules.contains_key(&canonical_path) {
+                continue;
+            }
+
+            let parsed_modules = self.compiler.parallel_parse_modules(canonical_path);
+            let (modules, parsing_errors) = collect_parsed_modules(parsed_modules);
+            if !parsing_errors.is_empty() {
+                errors.extend(parsing_errors);
+                continue;
+            }
+
+            let new_modules = modules
+                .into_iter()
+                .filter(|m| !self.program.modules.contains_key(&m.path))
+                .collect();
+            self.program.build(new_modules);
+            errors.extend(take_semantic_errors(&mut self.program));
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// Moves a span of an entry, which was tokenized on its own, to the entry's position
+/// in the session's source
+fn shift_span(span: Span, start: Position) -> Span {
+    let shift = |position: Position| Position {
+        line: position.line + start.line - 1,
+        col: position.col,
+        byte_offset: position.byte_offset + start.byte_offset,
+    };
+
+    Span {
+        start: shift(span.start),
+        end: shift(span.end),
+    }
+}
+
+fn punctuation(kind: PunctuationKind, position: Position) -> Token {
+    Token {
+        kind: TokenKind::Punctuation(kind),
+        span: Span {
+            start: position,
+            end: position,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        compile::{repl::Repl, Compiler},
+        hir::types::checked_type::Type,
+        interpret::RuntimeValue,
+    };
+    use pretty_assertions::assert_eq;
+
+    fn eval(repl: &mut Repl, input: &str) -> Option<(RuntimeValue, Type)> {
+        match repl.eval(input) {
+            Ok(result) => result,
+            Err(errors) => {
+                panic!("Expected \"{}\" to evaluate, found {:?}", input, errors)
+            }
+        }
+    }
+
+    #[test]
+    fn test_entries_share_declarations() {
+        let mut repl = Repl::new(Compiler::default());
+
+        assert_eq!(eval(&mut repl, "let mut x = 20i32;"), None);
+        assert_eq!(
+            eval(&mut repl, "fn double(n: i32): i32 {\n    n * 2i32\n}"),
+            None
+        );
+        assert_eq!(
+            eval(&mut repl, "double(x) + 2i32"),
+            Some((RuntimeValue::Int(42), Type::I32))
+        );
+
+        // Assignments are kept for the following entries
+        assert_eq!(eval(&mut repl, "x = 1i32;"), None);
+        assert_eq!(
+            eval(&mut repl, "double(x)"),
+            Some((RuntimeValue::Int(2), Type::I32))
+        );
+    }
+
+    #[test]
+    fn test_values_live_across_entries() {
+        let mut repl = Repl::new(Compiler::default());
+
+        assert_eq!(
+            eval(
+                &mut repl,
+                "let mut point: { x: i64, y: i64 } = { x: 1, y: 2 };"
+            ),
+            None
+        );
+        assert_eq!(eval(&mut repl, "let greeting = \"hi\";"), None);
+        assert_eq!(eval(&mut repl, "point.x += 40;"), None);
+        assert_eq!(
+            eval(
+                &mut repl,
+                "if greeting == \"hi\" { point.x + point.y } else { 0 }"
+            ),
+            Some((RuntimeValue::Int(43), Type::I64))
+        );
+
+        // A new variable shadows the earlier one of the same name
+        assert_eq!(eval(&mut repl, "let greeting = 7;"), None);
+        assert_eq!(
+            eval(&mut repl, "greeting"),
+            Some((RuntimeValue::Int(7), Type::I64))
+        );
+    }
+
+    #[test]
+    fn test_runtime_errors_keep_earlier_effects() {
+        let mut repl = Repl::new(Compiler::default());
+
+        assert_eq!(
+            eval(&mut repl, "let mut count = 0;\nlet mut zero = 0;"),
+            None
+        );
+
+        // Entries are not replayed, so the increment happens once
+        assert!(repl.eval("count += 1;\nlet broken = 1 / zero;").is_err());
+        assert_eq!(
+            eval(&mut repl, "count"),
+            Some((RuntimeValue::Int(1), Type::I64))
+        );
+        assert!(repl.eval("broken").is_err());
+    }
+
+    #[test]
+    fn test_failed_entries_are_discarded() {
+        let mut repl = Repl::new(Compiler::default());
+
+        assert!(repl.eval("let y = missing;").is_err());
+        assert!(repl.eval("fn f(): i32 {\n    true\n}").is_err());
+
+        // Neither `y` nor `f` was declared
+        assert_eq!(
+            eval(&mut repl, "fn f(): bool {\n    true\n}\nlet y = f();\ny"),
+            Some((RuntimeValue::Bool(true), Type::Bool))
+        );
+    }
+
+    #[test]
+    fn test_incomplete_input() {
+        let repl = Repl::new(Compiler::default());
+
+        assert!(repl.is_incomplete("fn f(): i32 {"));
+        assert!(repl.is_incomplete("let x = [1i32,"));
+        assert!(!repl.is_incomplete("fn f(): i32 {\n    1i32\n}"));
+    }
+}
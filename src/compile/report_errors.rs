@@ -1,31 +1,95 @@
-use ariadne::{Color, Label, Report};
+use std::ops::Range;
+
+use ariadne::{Color, Config, Label, Report, ReportKind};
 
 use crate::{
-    compile::{CompilationError, Compiler},
+    ast::Span,
+    codegen::CodegenError,
+    compile::{
+        diagnostics::{Diagnostic, DiagnosticLabel, Severity},
+        CompilationError, Compiler, ErrorFormat,
+    },
     hir::{
         errors::SemanticErrorKind,
-        utils::type_to_string::{token_kind_to_string, type_to_string},
+        utils::{
+            generics::MAX_GENERIC_INSTANCE_DEPTH,
+            layout::MAX_ARRAY_SIZE,
+            type_to_string::{token_kind_to_string, type_to_string},
+        },
     },
+    interpret::RuntimeError,
     parse::ParsingErrorKind,
     tokenize::TokenizationErrorKind,
 };
 
 impl Compiler {
-    pub fn report_errors(&self) {
+    /// Renders the errors returned by a compiler stage to stderr in `error_format`
+    pub fn report_errors(&self, errors: &[CompilationError]) {
+        let diagnostics = self.diagnostics(errors);
+
+        if self.error_format == ErrorFormat::Json {
+            for diagnostic in &diagnostics {
+                eprintln!("{}", diagnostic.to_json());
+            }
+            return;
+        }
+
         let mut cache = self.files.lock().unwrap();
+        let config = Config::default()
+            .with_color(self.color)
+            .with_compact(self.error_format == ErrorFormat::Short);
 
-        for error in &self.errors {
+        for diagnostic in diagnostics {
+            let (Some(path), Some(primary)) =
+                (&diagnostic.path, diagnostic.primary_label())
+            else {
+                eprintln!("{}", diagnostic.message);
+                continue;
+            };
+
+            let kind = match diagnostic.severity {
+                Severity::Error => ReportKind::Error,
+                Severity::Warning => ReportKind::Warning,
+            };
+            let mut report =
+                Report::build(kind, (path.clone(), byte_range(primary.span)))
+                    .with_config(config)
+                    .with_message(&diagnostic.message);
+            if let Some(code) = &diagnostic.code {
+                report = report.with_code(code);
+            }
+
+            for label in &diagnostic.labels {
+                let color = if label.is_primary {
+                    Color::Red
+                } else {
+                    Color::Blue
+                };
+                report = report.with_label(
+                    Label::new((path.clone(), byte_range(label.span)))
+                        .with_message(&label.message)
+                        .with_color(color),
+                );
+            }
+
+            let _ = report.finish().eprint(&mut *cache);
+        }
+    }
+
+    /// Converts compilation errors into renderer independent diagnostics, one for each
+    /// tokenization, parsing and semantic error
+    pub fn diagnostics(&self, errors: &[CompilationError]) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+
+        for error in errors {
             match error {
                 CompilationError::Tokenization { path, errors } => {
                     errors.iter().for_each(|e| {
-                        let span = e.span.start.byte_offset..e.span.end.byte_offset;
-                        let report = Report::build(
-                            ariadne::ReportKind::Error,
-                            (path.clone(), span.clone()),
-                        )
-                        .with_code(format!("T{}", e.kind.code()));
-                        let label =
-                            Label::new((path.clone(), span)).with_color(Color::Red);
+                        let report = Diagnostic::error(
+                            path.clone(),
+                            format!("T{}", e.kind.code()),
+                        );
+                        let label = DiagnosticLabel::primary(e.span);
 
                         let final_report =
                             match &e.kind {
@@ -75,19 +139,16 @@ impl Compiler {
                                     )),
                             };
 
-                        let _ = final_report.finish().print(&mut *cache);
+                        diagnostics.push(final_report);
                     });
                 }
                 CompilationError::Parsing { path, errors } => {
                     errors.iter().for_each(|e| {
-                        let span = e.span.start.byte_offset..e.span.end.byte_offset;
-                        let report = Report::build(
-                            ariadne::ReportKind::Error,
-                            (path.clone(), span.clone()),
-                        )
-                        .with_code(format!("P{}", e.kind.code()));
-                        let label =
-                            Label::new((path.clone(), span)).with_color(Color::Red);
+                        let report = Diagnostic::error(
+                            path.clone(),
+                            format!("P{}", e.kind.code()),
+                        );
+                        let label = DiagnosticLabel::primary(e.span);
 
                         let final_report = match &e.kind {
                             ParsingErrorKind::DocMustBeFollowedByDeclaration => report
@@ -221,19 +282,14 @@ impl Compiler {
                             }
                         };
 
-                        let _ = final_report.finish().print(&mut *cache);
+                        diagnostics.push(final_report);
                     });
                 }
                 CompilationError::Semantic { path, errors } => {
                     errors.iter().for_each(|e| {
-                        let span = e.span.start.byte_offset..e.span.end.byte_offset;
-                        let report = Report::build(
-                            ariadne::ReportKind::Error,
-                            (path.clone(), span.clone()),
-                        )
-                        .with_code(format!("S{}", e.kind.code()));
-                        let label =
-                            Label::new((path.clone(), span)).with_color(Color::Red);
+                        let report =
+                            Diagnostic::error(path.clone(), format!("S{}", e.kind.code()));
+                        let label = DiagnosticLabel::primary(e.span);
 
                         let final_report = match &e.kind {
                             SemanticErrorKind::CannotNarrowNonUnion(ref ty) => {
@@ -250,6 +306,11 @@ impl Compiler {
                                 .with_label(label.with_message(
                                     "Expected this value to have a numeric type",
                                 )),
+                            SemanticErrorKind::ExpectedAnIntegerOperand => report
+                                .with_message("Expected an integer operand")
+                                .with_label(label.with_message(
+                                    "Expected this value to have an integer type",
+                                )),
                             SemanticErrorKind::MixedSignedAndUnsigned => report
                                 .with_message("Mixed signed and unsigned operands")
                                 .with_label(label.with_message(
@@ -320,6 +381,23 @@ impl Compiler {
                                 .with_label(
                                     label.with_message("Invalid assignment target"),
                                 ),
+                            SemanticErrorKind::AssignmentToImmutable(variable) => {
+                                let name =
+                                    self.interners.string_interner.resolve(variable.name);
+                                report
+                                    .with_message("Assignment to an immutable variable")
+                                    .with_label(label.with_message(format!(
+                                        "\"{}\" cannot be assigned to, only let mut variables \
+                                         and mut parameters can",
+                                        name
+                                    )))
+                            }
+                            SemanticErrorKind::AssignmentToReceiver => report
+                                .with_message("Assignment to the receiver")
+                                .with_label(label.with_message(
+                                    "Methods cannot replace self, assign to its fields \
+                                     instead",
+                                )),
                             SemanticErrorKind::TypeMismatch { expected, received } => {
                                 let expected_type_str =
                                     type_to_string(expected, &self.interners);
@@ -454,15 +532,33 @@ impl Compiler {
                                         joined
                                     )))
                             }
-                            SemanticErrorKind::DuplicateIdentifier(id) => {
-                                let identifier_name =
-                                    self.interners.string_interner.resolve(id.name);
-                                report.with_message("Duplicate identifier").with_label(
-                                    label.with_message(format!(
-                                        "Duplicate identifier declaration \"{}\"",
-                                        identifier_name
-                                    )),
-                                )
+                            SemanticErrorKind::DuplicateIdentifier {
+                                identifier,
+                                previous,
+                            } => {
+                                let identifier_name = self
+                                    .interners
+                                    .string_interner
+                                    .resolve(identifier.name);
+                                let report =
+                                    report.with_message("Duplicate identifier").with_label(
+                                        label.with_message(format!(
+                                            "Duplicate identifier declaration \"{}\"",
+                                            identifier_name
+                                        )),
+                                    );
+
+                                match previous {
+                                    Some(span) => report.with_label(
+                                        DiagnosticLabel::secondary(*span).with_message(
+                                            format!(
+                                                "\"{}\" is first declared here",
+                                                identifier_name
+                                            ),
+                                        ),
+                                    ),
+                                    None => report,
+                                }
                             }
                             SemanticErrorKind::CannotIndex(ty) => report
                                 .with_message("Cannot index type")
@@ -563,6 +659,59 @@ impl Compiler {
                                     "Could not find module at path \"{}\"",
                                     path_buf.display()
                                 ))),
+                            SemanticErrorKind::ExpectedConstantExpression => report
+                                .with_message("Expected a constant expression")
+                                .with_label(label.with_message(
+                                    "This value cannot be computed at compile time",
+                                )),
+                            SemanticErrorKind::DivisionByZero => report
+                                .with_message("Division by zero")
+                                .with_label(label.with_message("This divisor is zero")),
+                            SemanticErrorKind::ConstantOverflow(ty) => report
+                                .with_message("Arithmetic overflow")
+                                .with_label(label.with_message(format!(
+                                    "The result does not fit in type \"{}\"",
+                                    type_to_string(ty, &self.interners)
+                                ))),
+                            SemanticErrorKind::RecursiveConstant(cycle) => {
+                                let names: Vec<_> = cycle
+                                    .iter()
+                                    .chain(cycle.first())
+                                    .map(|c| {
+                                        format!(
+                                            "\"{}\"",
+                                            self.interners.string_interner.resolve(c.name)
+                                        )
+                                    })
+                                    .collect();
+                                report.with_message("Recursive constant").with_label(
+                                    label.with_message(format!(
+                                        "Constant refers to itself: {}",
+                                        names.join(" -> ")
+                                    )),
+                                )
+                            }
+                            SemanticErrorKind::IndexOutOfBounds { index, size } => report
+                                .with_message("Index out of bounds")
+                                .with_label(label.with_message(format!(
+                                    "Index {} is out of bounds for an array of size {}",
+                                    index, size
+                                ))),
+                            SemanticErrorKind::InvalidSliceRange { start, end } => report
+                                .with_message("Invalid slice range")
+                                .with_label(label.with_message(format!(
+                                    "The slice starts at {} which is past its end at {}",
+                                    start, end
+                                ))),
+                            SemanticErrorKind::ArrayTooLarge { item_type, size } => report
+                                .with_message("Array is too large")
+                                .with_label(label.with_message(format!(
+                                    "{} items of type \"{}\" take more than the {} bytes \
+                                     allowed for an array",
+                                    size,
+                                    type_to_string(item_type, &self.interners),
+                                    MAX_ARRAY_SIZE
+                                ))),
                             SemanticErrorKind::CannotDeclareGlobalVariable => report
                                 .with_message("Global variables not allowed")
                                 .with_label(label.with_message(
@@ -613,12 +762,6 @@ impl Compiler {
                                     )),
                                 )
                             }
-                            SemanticErrorKind::ClosuresNotSupportedYet => report
-                                .with_message("Closures not supported")
-                                .with_label(label.with_message(
-                                    "Capturing variables from outer scopes (closures) \
-                                     is not supported yet",
-                                )),
                             SemanticErrorKind::ValuedTagInIsExpression => report
                                 .with_message(
                                     "Valued tag not allowed in `::is()` expression",
@@ -628,31 +771,328 @@ impl Compiler {
                                      identifier. Remove the value type (e.g., use \
                                      `#Tag` instead of `#Tag(Type)`)",
                                 )),
+                            SemanticErrorKind::CannotMatchNonUnion(ty) => report
+                                .with_message("Cannot match on a non-union type")
+                                .with_label(label.with_message(format!(
+                                    "Type \"{}\" is not a tag union, `match` can only be \
+                                     used on tag unions",
+                                    type_to_string(ty, &self.interners)
+                                ))),
+                            SemanticErrorKind::NonExhaustiveMatch { missing_patterns } => {
+                                let patterns: Vec<String> = missing_patterns
+                                    .iter()
+                                    .map(|pattern| {
+                                        let tags: Vec<String> = pattern
+                                            .iter()
+                                            .map(|tag_id| {
+                                                let name_id = self
+                                                    .interners
+                                                    .tag_interner
+                                                    .resolve(*tag_id);
+                                                self.interners
+                                                    .string_interner
+                                                    .resolve(name_id)
+                                            })
+                                            .collect();
+                                        format!("\"{}\"", tags.join(", "))
+                                    })
+                                    .collect();
+
+                                report.with_message("Non-exhaustive match").with_label(
+                                    label.with_message(format!(
+                                        "The following patterns are not covered: {}",
+                                        patterns.join(", ")
+                                    )),
+                                )
+                            }
+                            SemanticErrorKind::UnreachableMatchArm => report
+                                .with_message("Unreachable match arm")
+                                .with_label(label.with_message(
+                                    "This pattern is already covered by a previous arm",
+                                )),
+                            SemanticErrorKind::MatchPatternCountMismatch {
+                                expected,
+                                received,
+                            } => {
+                                let s = if *expected > 1 { "s" } else { "" };
+                                report
+                                    .with_message("Match pattern count mismatch")
+                                    .with_label(label.with_message(format!(
+                                        "This match has {} subject{}, but this arm \
+                                         provides {} pattern(s)",
+                                        expected, s, received
+                                    )))
+                            }
+                            SemanticErrorKind::UndefinedMatchVariant { variant, subject } => {
+                                let name =
+                                    self.interners.string_interner.resolve(variant.name);
+                                report.with_message("Undefined variant").with_label(
+                                    label.with_message(format!(
+                                        "Variant \"{}\" is not a member of \"{}\"",
+                                        name,
+                                        type_to_string(subject, &self.interners)
+                                    )),
+                                )
+                            }
+                            SemanticErrorKind::MatchVariantHasNoValue(variant) => {
+                                let name =
+                                    self.interners.string_interner.resolve(variant.name);
+                                report
+                                    .with_message("Variant has no value to bind")
+                                    .with_label(label.with_message(format!(
+                                        "Variant \"{}\" does not carry a value, remove \
+                                         the binding",
+                                        name
+                                    )))
+                            }
+                            SemanticErrorKind::GenericArgumentCountMismatch {
+                                expected,
+                                received,
+                            } => {
+                                let s = if *expected == 1 { "" } else { "s" };
+                                report
+                                    .with_message("Generic argument count mismatch")
+                                    .with_label(label.with_message(format!(
+                                        "This type expects {} type argument{}, but \
+                                         instead received {}",
+                                        expected, s, received
+                                    )))
+                            }
+                            SemanticErrorKind::CannotInferGenericArgument(param) => {
+                                let name =
+                                    self.interners.string_interner.resolve(param.name);
+                                report
+                                    .with_message("Cannot infer generic argument")
+                                    .with_label(label.with_message(format!(
+                                        "Type parameter \"{}\" is not used by any of \
+                                         the arguments of this call",
+                                        name
+                                    )))
+                            }
+                            SemanticErrorKind::GenericFnMustBeDeclaredAtTopLevel => {
+                                report
+                                    .with_message(
+                                        "Generic functions must be declared in the file \
+                                         scope",
+                                    )
+                                    .with_label(label.with_message(
+                                        "Generic functions must be declared in the file \
+                                         scope",
+                                    ))
+                            }
+                            SemanticErrorKind::CannotUseGenericFnAsValue => report
+                                .with_message("Cannot use generic function as a value")
+                                .with_label(label.with_message(
+                                    "Generic functions can only be called directly",
+                                )),
+                            SemanticErrorKind::RecursiveGenericTypeAlias(alias) => {
+                                let name =
+                                    self.interners.string_interner.resolve(alias.name);
+                                report
+                                    .with_message("Recursive generic type alias")
+                                    .with_label(label.with_message(format!(
+                                        "Type alias \"{}\" refers to itself",
+                                        name
+                                    )))
+                            }
+                            SemanticErrorKind::GenericInstancesTooDeep(function) => {
+                                let name =
+                                    self.interners.string_interner.resolve(function.name);
+                                report
+                                    .with_message("Generic instances nested too deeply")
+                                    .with_label(label.with_message(format!(
+                                        "Instantiating \"{}\" nests more than {} \
+                                         generic instances, it may call itself with \
+                                         ever growing type arguments",
+                                        name, MAX_GENERIC_INSTANCE_DEPTH
+                                    )))
+                            }
+                            SemanticErrorKind::CannotUseModuleAsValue => report
+                                .with_message("Cannot use module as a value")
+                                .with_label(label.with_message(
+                                    "Reach the items of the module with ::",
+                                )),
+                            SemanticErrorKind::CannotUseModuleAsType => report
+                                .with_message("Cannot use module as a type")
+                                .with_label(label.with_message(
+                                    "Cannot use module as a type",
+                                )),
+                            SemanticErrorKind::CannotUseBuiltinFnAsValue => report
+                                .with_message("Cannot use built-in function as a value")
+                                .with_label(label.with_message(
+                                    "Built-in functions can only be called directly",
+                                )),
+                            SemanticErrorKind::ImplMustBeDeclaredAtTopLevel => report
+                                .with_message("Impl blocks must be declared in the file scope")
+                                .with_label(label.with_message(
+                                    "Impl blocks must be declared in the file scope",
+                                )),
+                            SemanticErrorKind::MethodCannotBeGeneric => report
+                                .with_message("Methods cannot be generic")
+                                .with_label(label.with_message(
+                                    "Declare a generic function outside of the impl block",
+                                )),
+                            SemanticErrorKind::CannotUseMethodAsValue => report
+                                .with_message("Cannot use method as a value")
+                                .with_label(label.with_message(
+                                    "Methods can only be called directly",
+                                )),
+                            SemanticErrorKind::FnHasNoReceiver(function) => {
+                                let name =
+                                    self.interners.string_interner.resolve(function.name);
+                                report
+                                    .with_message("Function has no receiver")
+                                    .with_label(label.with_message(format!(
+                                        "\"{}\" does not take self, call it with ::",
+                                        name
+                                    )))
+                            }
+                            SemanticErrorKind::MutatingMethodOnImmutable {
+                                method,
+                                variable,
+                            } => {
+                                let method =
+                                    self.interners.string_interner.resolve(method.name);
+                                let variable =
+                                    self.interners.string_interner.resolve(variable.name);
+                                report
+                                    .with_message("Mutating method on an immutable variable")
+                                    .with_label(label.with_message(format!(
+                                        "\"{}\" takes mut self, but \"{}\" is not \
+                                         declared mut",
+                                        method, variable
+                                    )))
+                            }
+                            SemanticErrorKind::CannotIterate(ty) => report
+                                .with_message("Cannot iterate type")
+                                .with_label(label.with_message(format!(
+                                    "Type \"{}\" cannot be iterated with a for loop",
+                                    type_to_string(ty, &self.interners)
+                                ))),
+                            SemanticErrorKind::RangeOutsideForLoop => report
+                                .with_message("Range outside of a for loop")
+                                .with_label(label.with_message(
+                                    "Ranges can only be iterated by a for loop",
+                                )),
                         };
 
-                        let _ = final_report.finish().print(&mut *cache);
+                        diagnostics.push(final_report);
                     });
                 }
                 CompilationError::CouldNotReadFile { path, error } => {
-                    println!(
+                    diagnostics.push(Diagnostic::without_location(format!(
                         "Could not read file at path \"{}\", error {}",
                         path.display(),
                         error
-                    )
+                    )))
+                }
+                CompilationError::Codegen(error) => {
+                    let message = match error {
+                        CodegenError::MissingEntryPoint => {
+                            "The entry module does not declare a \"main\" function"
+                                .to_string()
+                        }
+                        CodegenError::InvalidEntryPoint => {
+                            "The \"main\" function must not declare any parameters"
+                                .to_string()
+                        }
+                        CodegenError::UnsupportedInstruction(name) => format!(
+                            "Code generation for the \"{}\" instruction is not supported \
+                             yet",
+                            name
+                        ),
+                        CodegenError::Io(error) => {
+                            format!(
+                                "Could not write the compiled program, error: {}",
+                                error
+                            )
+                        }
+                        CodegenError::LinkerFailed(stderr) => {
+                            format!("Linking the compiled program failed:\n{}", stderr)
+                        }
+                    };
+                    diagnostics.push(Diagnostic::without_location(message));
+                }
+                CompilationError::Runtime(error) => {
+                    let message = match error {
+                        RuntimeError::InvalidEntryPoint => {
+                            "The \"main\" function must not declare any parameters"
+                                .to_string()
+                        }
+                        RuntimeError::NullPointer => {
+                            "Runtime error: null pointer dereference".to_string()
+                        }
+                        RuntimeError::InvalidPointer(ptr) => {
+                            format!("Runtime error: invalid pointer {:#x}", ptr)
+                        }
+                        RuntimeError::UseAfterFree(ptr) => format!(
+                            "Runtime error: pointer {:#x} used after it was freed",
+                            ptr
+                        ),
+                        RuntimeError::DoubleFree(ptr) => {
+                            format!("Runtime error: pointer {:#x} was freed twice", ptr)
+                        }
+                        RuntimeError::InvalidFree(ptr) => format!(
+                            "Runtime error: pointer {:#x} does not point to a heap \
+                             allocation",
+                            ptr
+                        ),
+                        RuntimeError::WriteToConstant(ptr) => format!(
+                            "Runtime error: write through pointer {:#x} into constant data",
+                            ptr
+                        ),
+                        RuntimeError::OutOfBounds {
+                            ptr,
+                            size,
+                            allocation_size,
+                        } => format!(
+                            "Runtime error: access of {} bytes at {:#x} is outside of its \
+                             allocation of {} bytes",
+                            size, ptr, allocation_size
+                        ),
+                        RuntimeError::DivisionByZero => {
+                            "Runtime error: division by zero".to_string()
+                        }
+                        RuntimeError::InvalidTagId(id) => {
+                            format!("Runtime error: value holds unknown variant id {}", id)
+                        }
+                        RuntimeError::MissingFunctionBody(name) => {
+                            format!("Runtime error: function \"{}\" has no body", name)
+                        }
+                        RuntimeError::StackOverflow => {
+                            "Runtime error: stack overflow".to_string()
+                        }
+                        RuntimeError::OutOfMemory(size) => format!(
+                            "Runtime error: out of memory allocating {} bytes",
+                            size
+                        ),
+                        RuntimeError::Unreachable => {
+                            "Runtime error: reached unreachable code".to_string()
+                        }
+                        RuntimeError::UnsupportedInstruction(name) => format!(
+                            "The interpreter does not support the \"{}\" instruction yet",
+                            name
+                        ),
+                    };
+                    diagnostics.push(Diagnostic::without_location(message));
                 }
                 CompilationError::ModuleNotFound {
                     importing_module,
                     target_path,
                     error,
-                } => {
-                    println!(
-                        "Module not found \"{}\", imported from \"{}\", error: {}",
-                        target_path.display(),
-                        importing_module.display(),
-                        error
-                    )
-                }
+                } => diagnostics.push(Diagnostic::without_location(format!(
+                    "Module not found \"{}\", imported from \"{}\", error: {}",
+                    target_path.display(),
+                    importing_module.display(),
+                    error
+                ))),
             };
         }
+
+        diagnostics
     }
 }
+
+fn byte_range(span: Span) -> Range<usize> {
+    span.start.byte_offset..span.end.byte_offset
+}
@@ -0,0 +1,442 @@
+use std::{
+    fmt,
+    iter::Peekable,
+    sync::{atomic::AtomicUsize, Arc},
+    vec::IntoIter,
+};
+
+use crate::{
+    ast::{
+        decl::FnDecl,
+        expr::{BlockContents, Expr, ExprKind, MatchArm, MatchPattern},
+        stmt::{CompoundOperator, Stmt, StmtKind},
+        type_annotation::TypeAnnotation,
+        visitor::{walk_block, walk_expr, walk_stmt, walk_type, ASTVisitor},
+    },
+    compile::interner::SharedStringInterner,
+    parse::{Parser, ParsingError},
+    tokenize::{
+        trivia::{Trivia, TriviaToken, TriviaTokens},
+        TokenizationError, Tokenizer,
+    },
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyntaxKind {
+    Module,
+
+    FnDecl,
+    TypeAliasDecl,
+    ImplDecl,
+    VarDecl,
+    ConstDecl,
+    ExprStmt,
+    BreakStmt,
+    ContinueStmt,
+    ReturnStmt,
+    AssignmentStmt,
+    FromStmt,
+    WhileStmt,
+    ForStmt,
+
+    BinaryExpr,
+    RangeExpr,
+    PrefixExpr,
+    StructExpr,
+    AccessExpr,
+    StaticAccessExpr,
+    TypeExpr,
+    IndexExpr,
+    TypeCastExpr,
+    IsVariantExpr,
+    TagExpr,
+    CallExpr,
+    Literal,
+    NameRef,
+    FnExpr,
+    MatchExpr,
+    IfExpr,
+    ListExpr,
+    ArrayExpr,
+    BlockExpr,
+
+    Block,
+    Param,
+    MatchArm,
+    Type,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SyntaxElement {
+    Node(SyntaxNode),
+    Token(TriviaToken),
+}
+
+/// A node of the concrete syntax tree. Unlike the AST it keeps every token, including
+/// punctuation, and the trivia around them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxNode {
+    pub kind: SyntaxKind,
+    pub children: Vec<SyntaxElement>,
+}
+
+impl SyntaxNode {
+    fn new(kind: SyntaxKind) -> SyntaxNode {
+        SyntaxNode {
+            kind,
+            children: Vec::new(),
+        }
+    }
+
+    /// The source text of the node, including the trivia of its tokens
+    pub fn text(&self) -> String {
+        let mut out = String::new();
+        self.write_text(&mut out);
+        out
+    }
+
+    fn write_text(&self, out: &mut String) {
+        for child in &self.children {
+            match child {
+                SyntaxElement::Node(node) => node.write_text(out),
+                SyntaxElement::Token(token) => token.write_full_text(out),
+            }
+        }
+    }
+
+    /// Every token of the node in source order
+    pub fn tokens(&self) -> Vec<&TriviaToken> {
+        let mut tokens = Vec::new();
+        self.collect_tokens(&mut tokens);
+        tokens
+    }
+
+    fn collect_tokens<'a>(&'a self, tokens: &mut Vec<&'a TriviaToken>) {
+        for child in &self.children {
+            match child {
+                SyntaxElement::Node(node) => node.collect_tokens(tokens),
+                SyntaxElement::Token(token) => tokens.push(token),
+            }
+        }
+    }
+
+    /// The node itself and every node below it, in source order
+    pub fn descendants(&self) -> Vec<&SyntaxNode> {
+        let mut nodes = vec![self];
+        for child in &self.children {
+            if let SyntaxElement::Node(node) = child {
+                nodes.extend(node.descendants());
+            }
+        }
+        nodes
+    }
+}
+
+/// A lossless syntax tree of a module, which reproduces the source byte for byte,
+/// even if it contains errors. Tokens which aren't covered by any AST node, e.g.
+/// after a parsing error, are kept as tokens of the enclosing node.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxTree {
+    pub root: SyntaxNode,
+    /// Trivia after the last token, see `TriviaTokens::end_trivia`
+    pub end_trivia: Vec<Trivia>,
+}
+
+impl SyntaxTree {
+    /// Tokenizes and parses a module into a syntax tree, together with the errors
+    /// found on the way
+    pub fn parse(
+        source: &str,
+        interner: Arc<SharedStringInterner>,
+    ) -> (SyntaxTree, Vec<TokenizationError>, Vec<ParsingError>) {
+        let (tokens, tokenization_errors) =
+            Tokenizer::tokenize_with_trivia(source, interner.clone());
+        let (statements, parsing_errors) =
+            Parser::parse(tokens.tokens(), interner, Arc::new(AtomicUsize::new(0)));
+
+        (
+            SyntaxTree::build(tokens, &statements),
+            tokenization_errors,
+            parsing_errors,
+        )
+    }
+
+    /// Arranges the tokens into nodes following the spans of the statements they
+    /// were parsed into
+    pub fn build(tokens: TriviaTokens, statements: &[Stmt]) -> SyntaxTree {
+        let mut builder = Builder {
+            tokens: tokens.tokens.into_iter().peekable(),
+            stack: vec![SyntaxNode::new(SyntaxKind::Module)],
+        };
+
+        for stmt in statements {
+            builder.visit_stmt(stmt);
+        }
+        builder.flush(|_| true);
+
+        SyntaxTree {
+            root: builder.stack.pop().expect("Module node is never finished"),
+            end_trivia: tokens.end_trivia,
+        }
+    }
+
+    pub fn to_source(&self) -> String {
+        let mut out = self.root.text();
+        for trivia in &self.end_trivia {
+            out.push_str(&trivia.text);
+        }
+        out
+    }
+}
+
+impl fmt::Display for SyntaxTree {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_source())
+    }
+}
+
+/// Builds the tree while walking the AST. Starting a node moves every token before
+/// it into the current node, finishing one moves the tokens up to its end into it.
+/// Tokens are consumed strictly in order, so the tree stays lossless even where spans
+/// overlap or are out of order.
+struct Builder {
+    tokens: Peekable<IntoIter<TriviaToken>>,
+    stack: Vec<SyntaxNode>,
+}
+
+impl Builder {
+    fn flush(&mut self, take: impl Fn(&TriviaToken) -> bool) {
+        while let Some(token) = self.tokens.next_if(&take) {
+            self.current().children.push(SyntaxElement::Token(token));
+        }
+    }
+
+    fn current(&mut self) -> &mut SyntaxNode {
+        self.stack
+            .last_mut()
+            .expect("Module node is never finished")
+    }
+
+    fn start_node(&mut self, kind: SyntaxKind, start: usize) {
+        self.flush(|t| t.token.span.start.byte_offset < start);
+        self.stack.push(SyntaxNode::new(kind));
+    }
+
+    fn finish_node(&mut self, end: usize) {
+        self.flush(|t| t.token.span.end.byte_offset <= end);
+        let node = self.stack.pop().expect("Node to be started");
+        self.current().children.push(SyntaxElement::Node(node));
+    }
+}
+
+fn stmt_kind(stmt: &Stmt) -> SyntaxKind {
+    match &stmt.kind {
+        StmtKind::Expression(_) => SyntaxKind::ExprStmt,
+        StmtKind::TypeAliasDecl(_) => SyntaxKind::TypeAliasDecl,
+        StmtKind::ImplDecl(_) => SyntaxKind::ImplDecl,
+        StmtKind::VarDecl(_) => SyntaxKind::VarDecl,
+        StmtKind::ConstDecl(_) => SyntaxKind::ConstDecl,
+        StmtKind::Break => SyntaxKind::BreakStmt,
+        StmtKind::Continue => SyntaxKind::ContinueStmt,
+        StmtKind::Return { .. } => SyntaxKind::ReturnStmt,
+        StmtKind::Assignment { .. }
+        | StmtKind::CompoundAssignment { .. }
+        | StmtKind::Increment { .. } => SyntaxKind::AssignmentStmt,
+        StmtKind::From { .. } => SyntaxKind::FromStmt,
+        StmtKind::While { .. } => SyntaxKind::WhileStmt,
+        StmtKind::For { .. } => SyntaxKind::ForStmt,
+    }
+}
+
+fn expr_kind(expr: &Expr) -> SyntaxKind {
+    match &expr.kind {
+        ExprKind::Not { .. } | ExprKind::Neg { .. } | ExprKind::BitNot { .. } => {
+            SyntaxKind::PrefixExpr
+        }
+        ExprKind::Add { .. }
+        | ExprKind::Subtract { .. }
+        | ExprKind::Multiply { .. }
+        | ExprKind::Divide { .. }
+        | ExprKind::Modulo { .. }
+        | ExprKind::LessThan { .. }
+        | ExprKind::LessThanOrEqual { .. }
+        | ExprKind::GreaterThan { .. }
+        | ExprKind::GreaterThanOrEqual { .. }
+        | ExprKind::Equal { .. }
+        | ExprKind::NotEqual { .. }
+        | ExprKind::And { .. }
+        | ExprKind::Or { .. }
+        | ExprKind::BitAnd { .. }
+        | ExprKind::BitOr { .. }
+        | ExprKind::BitXor { .. }
+        | ExprKind::ShiftLeft { .. }
+        | ExprKind::ShiftRight { .. } => SyntaxKind::BinaryExpr,
+        ExprKind::Range { .. } => SyntaxKind::RangeExpr,
+        ExprKind::Struct(_) => SyntaxKind::StructExpr,
+        ExprKind::Access { .. } => SyntaxKind::AccessExpr,
+        ExprKind::StaticAccess { .. } => SyntaxKind::StaticAccessExpr,
+        ExprKind::Type(_) => SyntaxKind::TypeExpr,
+        ExprKind::Index { .. } => SyntaxKind::IndexExpr,
+        ExprKind::TypeCast { .. } => SyntaxKind::TypeCastExpr,
+        ExprKind::IsVariant { .. } => SyntaxKind::IsVariantExpr,
+        ExprKind::Tag { .. } => SyntaxKind::TagExpr,
+        ExprKind::FnCall { .. } => SyntaxKind::CallExpr,
+        ExprKind::BoolLiteral(_) | ExprKind::Number(_) | ExprKind::String(_) => {
+            SyntaxKind::Literal
+        }
+        ExprKind::Identifier(_) => SyntaxKind::NameRef,
+        ExprKind::Fn(_) => SyntaxKind::FnExpr,
+        ExprKind::Match { .. } => SyntaxKind::MatchExpr,
+        ExprKind::If { .. } => SyntaxKind::IfExpr,
+        ExprKind::List(_) => SyntaxKind::ListExpr,
+        ExprKind::Array { .. } => SyntaxKind::ArrayExpr,
+        ExprKind::CodeBlock(_) => SyntaxKind::BlockExpr,
+    }
+}
+
+impl<'ast> ASTVisitor<'ast> for Builder {
+    fn visit_stmt(&mut self, stmt: &'ast Stmt) {
+        // Declarations own their documentation, which precedes the statement's span
+        let start = stmt
+            .documentation()
+            .map_or(stmt.span.start, |doc| doc.span.start);
+
+        if let StmtKind::Expression(Expr {
+            kind: ExprKind::Fn(decl),
+            ..
+        }) = &stmt.kind
+        {
+            self.start_node(SyntaxKind::FnDecl, start.byte_offset);
+            self.visit_fn_decl(decl);
+        } else {
+            self.start_node(stmt_kind(stmt), start.byte_offset);
+            walk_stmt(self, stmt);
+        }
+        self.finish_node(stmt.span.end.byte_offset);
+    }
+
+    fn visit_expr(&mut self, expr: &'ast Expr) {
+        self.start_node(expr_kind(expr), expr.span.start.byte_offset);
+        walk_expr(self, expr);
+        self.finish_node(expr.span.end.byte_offset);
+    }
+
+    fn visit_block(&mut self, block: &'ast BlockContents) {
+        self.start_node(SyntaxKind::Block, block.span.start.byte_offset);
+        walk_block(self, block);
+        self.finish_node(block.span.end.byte_offset);
+    }
+
+    fn visit_type(&mut self, ty: &'ast TypeAnnotation) {
+        self.start_node(SyntaxKind::Type, ty.span.start.byte_offset);
+        walk_type(self, ty);
+        self.finish_node(ty.span.end.byte_offset);
+    }
+
+    // The default visits the value first, nodes have to be started in source order
+    fn visit_assignment_stmt(&mut self, target: &'ast Expr, value: &'ast Expr) {
+        self.visit_expr(target);
+        self.visit_expr(value);
+    }
+
+    fn visit_compound_assignment_stmt(
+        &mut self,
+        target: &'ast Expr,
+        _operator: CompoundOperator,
+        value: &'ast Expr,
+    ) {
+        self.visit_expr(target);
+        self.visit_expr(value);
+    }
+
+    fn visit_fn_decl(&mut self, decl: &'ast FnDecl) {
+        for param in &decl.params {
+            self.start_node(SyntaxKind::Param, param.identifier.span.start.byte_offset);
+            self.visit_type(&param.constraint);
+            self.finish_node(param.constraint.span.end.byte_offset);
+        }
+        self.visit_type(&decl.return_type);
+        self.visit_block(&decl.body);
+    }
+
+    fn visit_match_arm(&mut self, arm: &'ast MatchArm) {
+        let start = match arm.pattern.first() {
+            Some(MatchPattern::Variant(v) | MatchPattern::VariantWithValue(v, _)) => {
+                v.span.start
+            }
+            None => arm.expression.span.start,
+        };
+
+        self.start_node(SyntaxKind::MatchArm, start.byte_offset);
+        self.visit_expr(&arm.expression);
+        self.finish_node(arm.expression.span.end.byte_offset);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{
+        compile::interner::SharedStringInterner,
+        cst::{SyntaxKind, SyntaxTree},
+    };
+    use pretty_assertions::assert_eq;
+
+    fn kinds(tree: &SyntaxTree) -> Vec<SyntaxKind> {
+        tree.root.descendants().iter().map(|n| n.kind).collect()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let source = "// Adds two numbers\r\n---Adds---\nfn add(a: i32, b: i32): i32 {\n  a + b // sum\n}\n\n\nlet x = add(1, 2); \n";
+        let interner = Arc::new(SharedStringInterner::default());
+        let (tree, tokenization_errors, parsing_errors) =
+            SyntaxTree::parse(source, interner);
+
+        assert!(tokenization_errors.is_empty());
+        assert!(parsing_errors.is_empty());
+        assert_eq!(tree.to_source(), source);
+        assert_eq!(
+            kinds(&tree),
+            vec![
+                SyntaxKind::Module,
+                SyntaxKind::FnDecl,
+                SyntaxKind::Param,
+                SyntaxKind::Type,
+                SyntaxKind::Param,
+                SyntaxKind::Type,
+                SyntaxKind::Type,
+                SyntaxKind::Block,
+                SyntaxKind::BinaryExpr,
+                SyntaxKind::NameRef,
+                SyntaxKind::NameRef,
+                SyntaxKind::VarDecl,
+                SyntaxKind::CallExpr,
+                SyntaxKind::NameRef,
+                SyntaxKind::Literal,
+                SyntaxKind::Literal,
+            ]
+        );
+
+        // The documentation belongs to the declaration, the comment above to its doc
+        let fn_decl = tree.root.descendants()[1];
+        assert_eq!(fn_decl.tokens()[0].text, "---Adds---");
+        assert_eq!(
+            fn_decl.text(),
+            "// Adds two numbers\r\n---Adds---\nfn add(a: i32, b: i32): i32 {\n  a + b // sum\n}"
+        );
+    }
+
+    #[test]
+    fn test_round_trip_with_errors() {
+        let source = "let x = 1 +;\n  let y = \"unterminated\nlet @ z = 2;\n";
+        let interner = Arc::new(SharedStringInterner::default());
+        let (tree, tokenization_errors, parsing_errors) =
+            SyntaxTree::parse(source, interner);
+
+        assert!(!tokenization_errors.is_empty() || !parsing_errors.is_empty());
+        assert_eq!(tree.to_source(), source);
+        assert_eq!(tree.to_string(), source);
+    }
+}
@@ -0,0 +1,1357 @@
+use std::sync::{atomic::AtomicUsize, Arc};
+
+use crate::{
+    ast::{
+        decl::{FnDecl, Param},
+        expr::{BlockContents, Expr, ExprKind, MatchArm, MatchPattern},
+        stmt::{Stmt, StmtKind},
+        type_annotation::{TagAnnotation, TypeAnnotation, TypeAnnotationKind},
+        IdentifierNode, Span,
+    },
+    compile::interner::SharedStringInterner,
+    parse::{DocAnnotation, Parser, ParsingError},
+    tokenize::{trivia::TriviaKind, TokenizationError, Tokenizer},
+};
+
+/// Lines longer than this are broken up where the layout allows it
+pub const MAX_WIDTH: usize = 90;
+
+const INDENT: &str = "    ";
+
+/// Binding power of prefix operators, see `prefix_bp` in the parser
+const PREFIX_PRECEDENCE: u8 = 21;
+/// Binding power of calls, accesses, indexing and literals
+const ATOM_PRECEDENCE: u8 = 22;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FormatError {
+    Tokenization(Vec<TokenizationError>),
+    Parsing(Vec<ParsingError>),
+}
+
+/// Formats a module into the canonical layout. Only source code without
+/// tokenization or parsing errors is formatted, since the printer works on the AST.
+pub fn format_source(source: &str) -> Result<String, FormatError> {
+    let interner = Arc::new(SharedStringInterner::default());
+    let (tokens, tokenization_errors) =
+        Tokenizer::tokenize_with_trivia(source, interner.clone());
+    if !tokenization_errors.is_empty() {
+        return Err(FormatError::Tokenization(tokenization_errors));
+    }
+
+    let comments = tokens
+        .trivia()
+        .filter(|trivia| trivia.kind == TriviaKind::Comment)
+        .map(|trivia| Comment {
+            start: trivia.span.start.byte_offset,
+            end: trivia.span.end.byte_offset,
+        })
+        .collect();
+
+    let (statements, parsing_errors) =
+        Parser::parse(tokens.tokens(), interner, Arc::new(AtomicUsize::new(0)));
+    if !parsing_errors.is_empty() {
+        return Err(FormatError::Parsing(parsing_errors));
+    }
+
+    let mut formatter = Formatter {
+        source,
+        comments,
+        next_comment: 0,
+    };
+
+    let mut out = formatter.items(&statements, None, source.len(), 0);
+    if !out.is_empty() {
+        out.push('\n');
+    }
+
+    Ok(out)
+}
+
+/// The byte range of an ordinary `//` comment
+#[derive(Debug, Clone, Copy)]
+struct Comment {
+    start: usize,
+    end: usize,
+}
+
+/// Renders the AST back into source code. Every function returns text whose first
+/// line is not indented, following lines are indented relative to `indent` levels.
+/// `col` is the column the text starts at, used to decide whether a construct fits
+/// on a single line.
+struct Formatter<'a> {
+    source: &'a str,
+    comments: Vec<Comment>,
+    /// Comments are printed in source order, every comment before this one has been
+    /// printed already
+    next_comment: usize,
+}
+
+fn indentation(level: usize) -> String {
+    INDENT.repeat(level)
+}
+
+/// The column after appending `text` at `col`
+fn column_after(col: usize, text: &str) -> usize {
+    match text.rfind('\n') {
+        Some(i) => text[i + 1..].chars().count(),
+        None => col + text.chars().count(),
+    }
+}
+
+/// Whether `text` fits on the line, leaving room for a following `;` or `,`
+fn fits(col: usize, text: &str) -> bool {
+    !text.contains('\n') && col + text.chars().count() < MAX_WIDTH
+}
+
+/// Whether the expression ends with a block, such statements need no semicolon
+fn is_block_like(expr: &Expr) -> bool {
+    matches!(
+        expr.kind,
+        ExprKind::If { .. }
+            | ExprKind::Match { .. }
+            | ExprKind::CodeBlock(_)
+            | ExprKind::Fn(_)
+    )
+}
+
+fn binary_operator(kind: &ExprKind) -> Option<(&Expr, &'static str, &Expr)> {
+    let operator = match kind {
+        ExprKind::Or { left, right } => (left, "||", right),
+        ExprKind::And { left, right } => (left, "&&", right),
+        ExprKind::Equal { left, right } => (left, "==", right),
+        ExprKind::NotEqual { left, right } => (left, "!=", right),
+        ExprKind::LessThan { left, right } => (left, "<", right),
+        ExprKind::LessThanOrEqual { left, right } => (left, "<=", right),
+        ExprKind::GreaterThan { left, right } => (left, ">", right),
+        ExprKind::GreaterThanOrEqual { left, right } => (left, ">=", right),
+        ExprKind::Add { left, right } => (left, "+", right),
+        ExprKind::Subtract { left, right } => (left, "-", right),
+        ExprKind::Multiply { left, right } => (left, "*", right),
+        ExprKind::Divide { left, right } => (left, "/", right),
+        ExprKind::Modulo { left, right } => (left, "%", right),
+        ExprKind::BitOr { left, right } => (left, "|", right),
+        ExprKind::BitXor { left, right } => (left, "^", right),
+        ExprKind::BitAnd { left, right } => (left, "&", right),
+        ExprKind::ShiftLeft { left, right } => (left, "<<", right),
+        ExprKind::ShiftRight { left, right } => (left, ">>", right),
+        _ => return None,
+    };
+
+    Some((operator.0, operator.1, operator.2))
+}
+
+/// The left binding power of the operator, matching `infix_bp` in the parser
+fn precedence(kind: &ExprKind) -> u8 {
+    match kind {
+        ExprKind::Range { .. } => 0,
+        ExprKind::Or { .. } => 1,
+        ExprKind::And { .. } => 3,
+        ExprKind::Equal { .. } | ExprKind::NotEqual { .. } => 5,
+        ExprKind::LessThan { .. }
+        | ExprKind::LessThanOrEqual { .. }
+        | ExprKind::GreaterThan { .. }
+        | ExprKind::GreaterThanOrEqual { .. } => 7,
+        ExprKind::BitOr { .. } => 9,
+        ExprKind::BitXor { .. } => 11,
+        ExprKind::BitAnd { .. } => 13,
+        ExprKind::ShiftLeft { .. } | ExprKind::ShiftRight { .. } => 15,
+        ExprKind::Add { .. } | ExprKind::Subtract { .. } => 17,
+        ExprKind::Multiply { .. } | ExprKind::Divide { .. } | ExprKind::Modulo { .. } => {
+            19
+        }
+        ExprKind::Not { .. } | ExprKind::Neg { .. } | ExprKind::BitNot { .. } => {
+            PREFIX_PRECEDENCE
+        }
+        _ => ATOM_PRECEDENCE,
+    }
+}
+
+/// How the items of `Formatter::list` are delimited
+struct Delimiters {
+    open: &'static str,
+    close: &'static str,
+    /// Whether a single-line list has spaces inside its delimiters, e.g. `{ a: 1 }`
+    padded: bool,
+}
+
+const PARENS: Delimiters = Delimiters {
+    open: "(",
+    close: ")",
+    padded: false,
+};
+const BRACKETS: Delimiters = Delimiters {
+    open: "[",
+    close: "]",
+    padded: false,
+};
+/// An item of `Formatter::list`, comments between items are kept next to them
+trait ListItem {
+    /// The byte offsets the item starts and ends at in the source
+    fn source_range(&self) -> (usize, usize);
+}
+
+impl ListItem for Expr {
+    fn source_range(&self) -> (usize, usize) {
+        (self.span.start.byte_offset, self.span.end.byte_offset)
+    }
+}
+
+impl ListItem for Param {
+    fn source_range(&self) -> (usize, usize) {
+        (
+            self.identifier.span.start.byte_offset,
+            self.constraint.span.end.byte_offset,
+        )
+    }
+}
+
+impl ListItem for MatchArm {
+    fn source_range(&self) -> (usize, usize) {
+        let start =
+            self.pattern
+                .first()
+                .map_or(self.expression.span.start, |p| match p {
+                    MatchPattern::VariantWithValue(variant, _) => variant.span.start,
+                    MatchPattern::Variant(variant) => variant.span.start,
+                });
+        (start.byte_offset, self.expression.span.end.byte_offset)
+    }
+}
+
+/// A field of a struct literal
+impl ListItem for (IdentifierNode, Expr) {
+    fn source_range(&self) -> (usize, usize) {
+        (self.0.span.start.byte_offset, self.1.span.end.byte_offset)
+    }
+}
+
+/// An imported name and its alias
+impl ListItem for (IdentifierNode, Option<IdentifierNode>) {
+    fn source_range(&self) -> (usize, usize) {
+        let last = self.1.unwrap_or(self.0);
+        (self.0.span.start.byte_offset, last.span.end.byte_offset)
+    }
+}
+
+const BRACES: Delimiters = Delimiters {
+    open: "{",
+    close: "}",
+    padded: true,
+};
+
+impl<'a> Formatter<'a> {
+    fn text(&self, span: Span) -> &'a str {
+        &self.source[span.start.byte_offset..span.end.byte_offset]
+    }
+
+    fn identifier(&self, identifier: &IdentifierNode) -> &'a str {
+        self.text(identifier.span)
+    }
+
+    fn comment_text(&self, comment: Comment) -> &'a str {
+        self.source[comment.start..comment.end].trim_end()
+    }
+
+    /// Takes the next comment if it starts before `offset`
+    fn take_comment_before(&mut self, offset: usize) -> Option<Comment> {
+        let comment = *self.comments.get(self.next_comment)?;
+        if comment.start < offset {
+            self.next_comment += 1;
+            Some(comment)
+        } else {
+            None
+        }
+    }
+
+    /// Whether at least one empty line separates the two offsets in the source
+    fn has_blank_line_between(&self, start: usize, end: usize) -> bool {
+        start < end && self.source[start..end].matches('\n').count() >= 2
+    }
+
+    /// Renders the statements and final expression of a block or module, one per
+    /// line, with the comments which are located before `end`. At most one empty
+    /// line from the source is kept between items.
+    fn items(
+        &mut self,
+        statements: &[Stmt],
+        final_expr: Option<&Expr>,
+        end: usize,
+        indent: usize,
+    ) -> String {
+        let prefix = indentation(indent);
+        let mut lines: Vec<String> = Vec::new();
+        let mut previous_end: Option<usize> = None;
+
+        let items = statements
+            .iter()
+            .map(|stmt| {
+                let start = stmt
+                    .documentation()
+                    .map_or(stmt.span.start, |doc| doc.span.start);
+                (
+                    start.byte_offset,
+                    stmt.span.end.byte_offset,
+                    Some(stmt),
+                    None,
+                )
+            })
+            .chain(final_expr.map(|expr| {
+                (
+                    expr.span.start.byte_offset,
+                    expr.span.end.byte_offset,
+                    None,
+                    Some(expr),
+                )
+            }));
+
+        for (start, item_end, stmt, expr) in items {
+            while let Some(comment) = self.take_comment_before(start) {
+                if previous_end
+                    .is_some_and(|prev| self.has_blank_line_between(prev, comment.start))
+                {
+                    lines.push(String::new());
+                }
+                lines.push(format!("{}{}", prefix, self.comment_text(comment)));
+                previous_end = Some(comment.end);
+            }
+
+            if previous_end.is_some_and(|prev| self.has_blank_line_between(prev, start)) {
+                lines.push(String::new());
+            }
+
+            let mut line = match (stmt, expr) {
+                (Some(stmt), _) => self.stmt(stmt, indent),
+                (None, Some(expr)) => self.expr(expr, indent, prefix.len()),
+                (None, None) => unreachable!(),
+            };
+            line.insert_str(0, &prefix);
+
+            // Comments inside of an expression cannot be placed where they were, so
+            // they follow the item instead of being dropped
+            let mut inner_comments = Vec::new();
+            while let Some(comment) = self.take_comment_before(item_end) {
+                inner_comments.push(format!("{}{}", prefix, self.comment_text(comment)));
+            }
+
+            previous_end = Some(item_end);
+
+            if let Some(comment) = self.comments.get(self.next_comment).copied() {
+                let is_trailing = !self.source[item_end..comment.start].contains('\n');
+                if is_trailing && inner_comments.is_empty() {
+                    self.next_comment += 1;
+                    line.push(' ');
+                    line.push_str(self.comment_text(comment));
+                    previous_end = Some(comment.end);
+                }
+            }
+
+            lines.push(line);
+            lines.extend(inner_comments);
+        }
+
+        while let Some(comment) = self.take_comment_before(end) {
+            if previous_end
+                .is_some_and(|prev| self.has_blank_line_between(prev, comment.start))
+            {
+                lines.push(String::new());
+            }
+            lines.push(format!("{}{}", prefix, self.comment_text(comment)));
+            previous_end = Some(comment.end);
+        }
+
+        lines.join("\n")
+    }
+
+    fn block(&mut self, block: &BlockContents, indent: usize) -> String {
+        let end = block.span.end.byte_offset;
+        let contents = self.items(
+            &block.statements,
+            block.final_expr.as_deref(),
+            end,
+            indent + 1,
+        );
+
+        if contents.is_empty() {
+            "{}".to_string()
+        } else {
+            format!("{{\n{}\n{}}}", contents, indentation(indent))
+        }
+    }
+
+    fn doc_prefix(&self, doc: Option<&DocAnnotation>, indent: usize) -> String {
+        match doc {
+            Some(doc) => format!("{}\n{}", self.text(doc.span), indentation(indent)),
+            None => String::new(),
+        }
+    }
+
+    fn stmt(&mut self, stmt: &Stmt, indent: usize) -> String {
+        let col = indent * INDENT.len();
+
+        match &stmt.kind {
+            StmtKind::Expression(expr) => {
+                let text = self.expr(expr, indent, col);
+                if is_block_like(expr) {
+                    text
+                } else {
+                    format!("{};", text)
+                }
+            }
+            StmtKind::TypeAliasDecl(decl) => {
+                let mut out = self.doc_prefix(decl.documentation.as_ref(), indent);
+                if decl.is_exported {
+                    out.push_str("export ");
+                }
+                out.push_str("type ");
+                out.push_str(self.identifier(&decl.identifier));
+                out.push_str(&self.generic_params(&decl.generic_params));
+                out.push_str(" = ");
+
+                // Struct declarations always list one field per line
+                let value = match &decl.value.kind {
+                    TypeAnnotationKind::Struct(fields) if !fields.is_empty() => {
+                        let end = decl.value.span.end.byte_offset;
+                        self.params(fields, &BRACES, indent, col, end, true)
+                    }
+                    _ => {
+                        self.type_annotation(&decl.value, indent, column_after(col, &out))
+                    }
+                };
+                out.push_str(&value);
+                out.push(';');
+                out
+            }
+            StmtKind::ImplDecl(decl) => {
+                let methods: Vec<String> = decl
+                    .methods
+                    .iter()
+                    .map(|method| {
+                        let method_indent = indent + 1;
+                        let text = self.fn_decl(
+                            method,
+                            method_indent,
+                            method_indent * INDENT.len(),
+                        );
+                        format!("{}{}", indentation(method_indent), text)
+                    })
+                    .collect();
+
+                let target = self.identifier(&decl.target);
+                if methods.is_empty() {
+                    format!("impl {} {{}}", target)
+                } else {
+                    format!(
+                        "impl {} {{\n{}\n{}}}",
+                        target,
+                        methods.join("\n\n"),
+                        indentation(indent)
+                    )
+                }
+            }
+            StmtKind::VarDecl(decl) => {
+                let mut out = self.doc_prefix(decl.documentation.as_ref(), indent);
+                out.push_str(if decl.is_mutable { "let mut " } else { "let " });
+                out.push_str(self.identifier(&decl.identifier));
+                if let Some(constraint) = &decl.constraint {
+                    out.push_str(": ");
+                    let text =
+                        self.type_annotation(constraint, indent, column_after(col, &out));
+                    out.push_str(&text);
+                }
+                out.push_str(" = ");
+                let value = self.expr(&decl.value, indent, column_after(col, &out));
+                out.push_str(&value);
+                out.push(';');
+                out
+            }
+            StmtKind::ConstDecl(decl) => {
+                let mut out = self.doc_prefix(decl.documentation.as_ref(), indent);
+                if decl.is_exported {
+                    out.push_str("export ");
+                }
+                out.push_str("const ");
+                out.push_str(self.identifier(&decl.identifier));
+                if let Some(constraint) = &decl.constraint {
+                    out.push_str(": ");
+                    let text =
+                        self.type_annotation(constraint, indent, column_after(col, &out));
+                    out.push_str(&text);
+                }
+                out.push_str(" = ");
+                let value = self.expr(&decl.value, indent, column_after(col, &out));
+                out.push_str(&value);
+                out.push(';');
+                out
+            }
+            StmtKind::Break => "break".to_string(),
+            StmtKind::Continue => "continue".to_string(),
+            StmtKind::Return { value } => {
+                format!(
+                    "return {};",
+                    self.expr(value, indent, col + "return ".len())
+                )
+            }
+            StmtKind::Assignment { target, value } => {
+                let target = self.expr(target, indent, col);
+                let value_col = column_after(col, &target) + " = ".len();
+                format!("{} = {};", target, self.expr(value, indent, value_col))
+            }
+            StmtKind::CompoundAssignment {
+                target,
+                operator,
+                value,
+            } => {
+                let target = self.expr(target, indent, col);
+                let operator = operator.to_string();
+                let value_col = column_after(col, &target) + operator.len() + 2;
+                format!(
+                    "{} {} {};",
+                    target,
+                    operator,
+                    self.expr(value, indent, value_col)
+                )
+            }
+            StmtKind::Increment { target, operator } => {
+                format!(
+                    "{}{};",
+                    self.expr(target, indent, col),
+                    operator.to_string()
+                )
+            }
+            StmtKind::From {
+                path,
+                namespace: Some(namespace),
+                ..
+            } => format!(
+                "from {} as {}",
+                self.text(path.span),
+                self.identifier(namespace)
+            ),
+            StmtKind::From {
+                path, identifiers, ..
+            } => {
+                let prefix = format!("from {} ", self.text(path.span));
+                let list = self.list(
+                    identifiers,
+                    &BRACES,
+                    indent,
+                    col + prefix.chars().count(),
+                    stmt.span.end.byte_offset,
+                    false,
+                    |f, (identifier, alias), _, _| match alias {
+                        Some(alias) => format!(
+                            "{}: {}",
+                            f.identifier(identifier),
+                            f.identifier(alias)
+                        ),
+                        None => f.identifier(identifier).to_string(),
+                    },
+                );
+                format!("{}{}", prefix, list)
+            }
+            StmtKind::While { condition, body } => {
+                let condition = self.expr(condition, indent, col + "while ".len());
+                format!("while {} {}", condition, self.block(body, indent))
+            }
+            StmtKind::For {
+                item,
+                iterable,
+                body,
+            } => {
+                let prefix = format!("for {} in ", self.identifier(item));
+                let iterable = self.expr(iterable, indent, col + prefix.chars().count());
+                format!("{}{} {}", prefix, iterable, self.block(body, indent))
+            }
+        }
+    }
+
+    /// Renders comma separated items on one line when they fit, otherwise one item
+    /// per line with a trailing comma. `end` is where the list closes in the source,
+    /// a list with comments keeps them in place on separate lines.
+    #[allow(clippy::too_many_arguments)]
+    fn list<T: ListItem>(
+        &mut self,
+        items: &[T],
+        delimiters: &Delimiters,
+        indent: usize,
+        col: usize,
+        end: usize,
+        force_break: bool,
+        mut render: impl FnMut(&mut Self, &T, usize, usize) -> String,
+    ) -> String {
+        if items.is_empty() {
+            return format!("{}{}", delimiters.open, delimiters.close);
+        }
+
+        let first_comment = self.next_comment;
+        let has_comments = self
+            .comments
+            .get(self.next_comment)
+            .is_some_and(|comment| comment.start < end);
+
+        if !force_break && !has_comments {
+            let padding = if delimiters.padded { " " } else { "" };
+            let mut flat = format!("{}{}", delimiters.open, padding);
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    flat.push_str(", ");
+                }
+                let item_col = column_after(col, &flat);
+                flat.push_str(&render(self, item, indent, item_col));
+            }
+            flat.push_str(padding);
+            flat.push_str(delimiters.close);
+
+            if fits(col, &flat) {
+                return flat;
+            }
+
+            // The items are rendered again, including their comments
+            self.next_comment = first_comment;
+        }
+
+        let item_indent = indent + 1;
+        let prefix = indentation(item_indent);
+        let mut out = format!("{}\n", delimiters.open);
+        for item in items {
+            let (item_start, item_end) = item.source_range();
+            while let Some(comment) = self.take_comment_before(item_start) {
+                out.push_str(&format!("{}{}\n", prefix, self.comment_text(comment)));
+            }
+
+            out.push_str(&prefix);
+            out.push_str(&render(self, item, item_indent, prefix.len()));
+            out.push(',');
+
+            if let Some(comment) = self.comments.get(self.next_comment).copied() {
+                let is_trailing = comment.start < end
+                    && !self.source[item_end..comment.start].contains('\n');
+                if is_trailing {
+                    self.next_comment += 1;
+                    out.push(' ');
+                    out.push_str(self.comment_text(comment));
+                }
+            }
+            out.push('\n');
+        }
+        while let Some(comment) = self.take_comment_before(end) {
+            out.push_str(&format!("{}{}\n", prefix, self.comment_text(comment)));
+        }
+        out.push_str(&indentation(indent));
+        out.push_str(delimiters.close);
+        out
+    }
+
+    fn params(
+        &mut self,
+        params: &[Param],
+        delimiters: &Delimiters,
+        indent: usize,
+        col: usize,
+        end: usize,
+        force_break: bool,
+    ) -> String {
+        self.list(
+            params,
+            delimiters,
+            indent,
+            col,
+            end,
+            force_break,
+            |f, param, indent, col| {
+                let name = match param.is_mutable {
+                    true => format!("mut {}", f.identifier(&param.identifier)),
+                    false => f.identifier(&param.identifier).to_string(),
+                };
+                // A bare `self` receiver, its type comes from the impl block
+                if param.constraint.span == param.identifier.span {
+                    return name;
+                }
+                let constraint_col = col + name.chars().count() + 2;
+                format!(
+                    "{}: {}",
+                    name,
+                    f.type_annotation(&param.constraint, indent, constraint_col)
+                )
+            },
+        )
+    }
+
+    fn fn_decl(&mut self, decl: &FnDecl, indent: usize, col: usize) -> String {
+        let mut out = self.doc_prefix(decl.documentation.as_ref(), indent);
+        if decl.is_exported {
+            out.push_str("export ");
+        }
+        out.push_str("fn ");
+        out.push_str(self.identifier(&decl.identifier));
+        out.push_str(&self.generic_params(&decl.generic_params));
+
+        let params = self.params(
+            &decl.params,
+            &PARENS,
+            indent,
+            column_after(col, &out),
+            decl.return_type.span.start.byte_offset,
+            false,
+        );
+        out.push_str(&params);
+        out.push_str(": ");
+
+        let return_type =
+            self.type_annotation(&decl.return_type, indent, column_after(col, &out));
+        out.push_str(&return_type);
+        out.push(' ');
+        out.push_str(&self.block(&decl.body, indent));
+        out
+    }
+
+    fn generic_params(&self, params: &[IdentifierNode]) -> String {
+        if params.is_empty() {
+            return String::new();
+        }
+
+        let names: Vec<&str> = params.iter().map(|p| self.identifier(p)).collect();
+        format!("<{}>", names.join(", "))
+    }
+
+    /// Renders an operand, wrapped in parentheses when its operator binds weaker
+    /// than `min_precedence`
+    fn operand(
+        &mut self,
+        expr: &Expr,
+        min_precedence: u8,
+        indent: usize,
+        col: usize,
+    ) -> String {
+        if precedence(&expr.kind) < min_precedence {
+            format!("({})", self.expr(expr, indent, col + 1))
+        } else {
+            self.expr(expr, indent, col)
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr, indent: usize, col: usize) -> String {
+        if let Some((left, operator, right)) = binary_operator(&expr.kind) {
+            // Operators are left associative, so an operand on the right with the
+            // same precedence needs parentheses
+            let precedence = precedence(&expr.kind);
+            let left = self.operand(left, precedence, indent, col);
+            let right_col = column_after(col, &left) + operator.len() + 2;
+            let right = self.operand(right, precedence + 1, indent, right_col);
+            return format!("{} {} {}", left, operator, right);
+        }
+
+        match &expr.kind {
+            ExprKind::Not { right } => {
+                format!(
+                    "!{}",
+                    self.operand(right, PREFIX_PRECEDENCE, indent, col + 1)
+                )
+            }
+            ExprKind::BitNot { right } => {
+                format!(
+                    "~{}",
+                    self.operand(right, PREFIX_PRECEDENCE, indent, col + 1)
+                )
+            }
+            ExprKind::Neg { right } => {
+                // `--x` reads as a decrement and `---x` starts a documentation comment
+                let min_precedence = match right.kind {
+                    ExprKind::Neg { .. } => ATOM_PRECEDENCE,
+                    _ => PREFIX_PRECEDENCE,
+                };
+                format!("-{}", self.operand(right, min_precedence, indent, col + 1))
+            }
+            ExprKind::Struct(fields) => self.list(
+                fields,
+                &BRACES,
+                indent,
+                col,
+                expr.span.end.byte_offset,
+                false,
+                |f, (name, value), indent, col| {
+                    let name = f.identifier(name);
+                    let value_col = col + name.chars().count() + 2;
+                    format!("{}: {}", name, f.expr(value, indent, value_col))
+                },
+            ),
+            ExprKind::Range { start, end } => {
+                let start = self.operand(start, 1, indent, col);
+                let end_col = column_after(col, &start) + 2;
+                format!("{}..{}", start, self.operand(end, 1, indent, end_col))
+            }
+            ExprKind::Access { left, field } => {
+                let left = self.operand(left, ATOM_PRECEDENCE, indent, col);
+                format!("{}.{}", left, self.identifier(field))
+            }
+            ExprKind::Type(ty) => self.type_annotation(ty, indent, col),
+            ExprKind::StaticAccess { left, field } => {
+                let left = self.operand(left, ATOM_PRECEDENCE, indent, col);
+                format!("{}::{}", left, self.identifier(field))
+            }
+            ExprKind::Index { left, index } => {
+                let left = self.operand(left, ATOM_PRECEDENCE, indent, col);
+                let index_col = column_after(col, &left) + 1;
+                format!("{}[{}]", left, self.expr(index, indent, index_col))
+            }
+            ExprKind::TypeCast { left, target } => {
+                let left = self.operand(left, ATOM_PRECEDENCE, indent, col);
+                let target_col = column_after(col, &left) + "::as(".len();
+                let target = self.type_annotation(target, indent, target_col);
+                format!("{}::as({})", left, target)
+            }
+            ExprKind::IsVariant { left, variants } => {
+                let left = self.operand(left, ATOM_PRECEDENCE, indent, col);
+                let variants_col = column_after(col, &left) + "::is(".len();
+                let variants = self.tags(variants, indent, variants_col);
+                format!("{}::is({})", left, variants)
+            }
+            ExprKind::Tag { name, value } => match value {
+                Some(value) => {
+                    let name = self.identifier(name);
+                    let value_col = col + name.chars().count() + 2;
+                    format!("#{}({})", name, self.expr(value, indent, value_col))
+                }
+                None => format!("#{}", self.identifier(name)),
+            },
+            ExprKind::FnCall { left, args } => {
+                let left = self.operand(left, ATOM_PRECEDENCE, indent, col);
+                let args_col = column_after(col, &left);
+                let args = self.list(
+                    args,
+                    &PARENS,
+                    indent,
+                    args_col,
+                    expr.span.end.byte_offset,
+                    false,
+                    |f, arg, indent, col| f.expr(arg, indent, col),
+                );
+                format!("{}{}", left, args)
+            }
+            ExprKind::BoolLiteral(value) => value.to_string(),
+            ExprKind::Identifier(identifier) => self.identifier(identifier).to_string(),
+            // Literals are kept as written, e.g. with their escape sequences
+            ExprKind::String(value) => self.text(value.span).to_string(),
+            ExprKind::Number(_) => {
+                // The span of a parenthesized expression includes the parentheses
+                self.text(expr.span)
+                    .trim_matches(|c: char| c == '(' || c == ')' || c.is_whitespace())
+                    .to_string()
+            }
+            ExprKind::Fn(decl) => self.fn_decl(decl, indent, col),
+            ExprKind::Match { conditions, arms } => {
+                let mut out = "match ".to_string();
+                for (i, condition) in conditions.iter().enumerate() {
+                    if i > 0 {
+                        out.push_str(", ");
+                    }
+                    let condition = self.expr(condition, indent, column_after(col, &out));
+                    out.push_str(&condition);
+                }
+                out.push(' ');
+
+                let end = expr.span.end.byte_offset;
+                let arms = self.list(
+                    arms,
+                    &BRACES,
+                    indent,
+                    col,
+                    end,
+                    true,
+                    |f, arm, indent, col| f.match_arm(arm, indent, col),
+                );
+                out.push_str(&arms);
+                out
+            }
+            ExprKind::If {
+                branches,
+                else_branch,
+            } => {
+                let mut out = String::new();
+                for (i, (condition, body)) in branches.iter().enumerate() {
+                    if i > 0 {
+                        out.push_str(" else ");
+                    }
+                    out.push_str("if ");
+                    let condition = self.expr(condition, indent, column_after(col, &out));
+                    out.push_str(&condition);
+                    out.push(' ');
+                    out.push_str(&self.block(body, indent));
+                }
+                if let Some(else_branch) = else_branch {
+                    out.push_str(" else ");
+                    out.push_str(&self.block(else_branch, indent));
+                }
+                out
+            }
+            ExprKind::List(items) => self.list(
+                items,
+                &BRACKETS,
+                indent,
+                col,
+                expr.span.end.byte_offset,
+                false,
+                |f, item, indent, col| f.expr(item, indent, col),
+            ),
+            ExprKind::Array { value, size } => {
+                let value = self.expr(value, indent, col + 1);
+                let size_col = column_after(col + 1, &value) + 2;
+                format!("[{}; {}]", value, self.expr(size, indent, size_col))
+            }
+            ExprKind::CodeBlock(block) => self.block(block, indent),
+            _ => unreachable!("Binary operators are handled above"),
+        }
+    }
+
+    fn match_arm(&mut self, arm: &MatchArm, indent: usize, col: usize) -> String {
+        let patterns: Vec<String> = arm
+            .pattern
+            .iter()
+            .map(|pattern| match pattern {
+                MatchPattern::VariantWithValue(variant, binding) => {
+                    format!("{}({})", self.identifier(variant), self.identifier(binding))
+                }
+                MatchPattern::Variant(variant) => self.identifier(variant).to_string(),
+            })
+            .collect();
+
+        let mut out = patterns.join(", ");
+        out.push_str(" => ");
+        let expression = self.expr(&arm.expression, indent, column_after(col, &out));
+        out.push_str(&expression);
+        out
+    }
+
+    /// Renders the variants of a union separated by `|`, one per line when they do
+    /// not fit
+    fn tags(&mut self, tags: &[TagAnnotation], indent: usize, col: usize) -> String {
+        let first_comment = self.next_comment;
+
+        let mut flat = String::new();
+        for (i, tag) in tags.iter().enumerate() {
+            if i > 0 {
+                flat.push_str(" | ");
+            }
+            let tag = self.tag(tag, indent, column_after(col, &flat));
+            flat.push_str(&tag);
+        }
+
+        if tags.len() == 1 || fits(col, &flat) {
+            return flat;
+        }
+
+        self.next_comment = first_comment;
+
+        let variant_indent = indent + 1;
+        let separator = format!("\n{}| ", indentation(variant_indent));
+        let mut out = String::new();
+        for (i, tag) in tags.iter().enumerate() {
+            let tag_col = if i == 0 {
+                col
+            } else {
+                out.push_str(&separator);
+                column_after(0, &separator)
+            };
+            let tag = self.tag(tag, variant_indent, tag_col);
+            out.push_str(&tag);
+        }
+        out
+    }
+
+    fn tag(&mut self, tag: &TagAnnotation, indent: usize, col: usize) -> String {
+        let name = self.identifier(&tag.identifier);
+        match &tag.value_type {
+            Some(value_type) => {
+                let value_col = col + name.chars().count() + 2;
+                format!(
+                    "#{}({})",
+                    name,
+                    self.type_annotation(value_type, indent, value_col)
+                )
+            }
+            None => format!("#{}", name),
+        }
+    }
+
+    fn type_annotation(
+        &mut self,
+        annotation: &TypeAnnotation,
+        indent: usize,
+        col: usize,
+    ) -> String {
+        match &annotation.kind {
+            TypeAnnotationKind::Void => "void".to_string(),
+            TypeAnnotationKind::Bool => "bool".to_string(),
+            TypeAnnotationKind::U8 => "u8".to_string(),
+            TypeAnnotationKind::U16 => "u16".to_string(),
+            TypeAnnotationKind::U32 => "u32".to_string(),
+            TypeAnnotationKind::U64 => "u64".to_string(),
+            TypeAnnotationKind::USize => "usize".to_string(),
+            TypeAnnotationKind::ISize => "isize".to_string(),
+            TypeAnnotationKind::I8 => "i8".to_string(),
+            TypeAnnotationKind::I16 => "i16".to_string(),
+            TypeAnnotationKind::I32 => "i32".to_string(),
+            TypeAnnotationKind::I64 => "i64".to_string(),
+            TypeAnnotationKind::F32 => "f32".to_string(),
+            TypeAnnotationKind::F64 => "f64".to_string(),
+            TypeAnnotationKind::String => "string".to_string(),
+            TypeAnnotationKind::Identifier(identifier) => {
+                self.identifier(identifier).to_string()
+            }
+            TypeAnnotationKind::GenericInstance { identifier, args } => {
+                let mut out = format!("{}<", self.identifier(identifier));
+                for (index, arg) in args.iter().enumerate() {
+                    if index > 0 {
+                        out.push_str(", ");
+                    }
+                    let text = self.type_annotation(arg, indent, column_after(col, &out));
+                    out.push_str(&text);
+                }
+                out.push('>');
+                out
+            }
+            TypeAnnotationKind::Struct(fields) => {
+                let end = annotation.span.end.byte_offset;
+                self.params(fields, &BRACES, indent, col, end, false)
+            }
+            TypeAnnotationKind::Tag(tag) => self.tag(tag, indent, col),
+            TypeAnnotationKind::Union(tags) => self.tags(tags, indent, col),
+            TypeAnnotationKind::List(item) => {
+                // A union or function type would otherwise take the brackets
+                if matches!(
+                    item.kind,
+                    TypeAnnotationKind::Union(_) | TypeAnnotationKind::FnType { .. }
+                ) {
+                    format!("({})[]", self.type_annotation(item, indent, col + 1))
+                } else {
+                    format!("{}[]", self.type_annotation(item, indent, col))
+                }
+            }
+            TypeAnnotationKind::Slice(item_type) => {
+                format!("[{}]", self.type_annotation(item_type, indent, col + 1))
+            }
+            TypeAnnotationKind::Array { item_type, size } => {
+                let item_type = self.type_annotation(item_type, indent, col + 1);
+                let size_col = column_after(col + 1, &item_type) + 2;
+                format!("[{}; {}]", item_type, self.expr(size, indent, size_col))
+            }
+            TypeAnnotationKind::FnType {
+                params,
+                return_type,
+            } => {
+                let params_end = return_type.span.start.byte_offset;
+                let params = self.params(
+                    params,
+                    &PARENS,
+                    indent,
+                    col + "fn".len(),
+                    params_end,
+                    false,
+                );
+                let return_col = column_after(col + "fn".len(), &params) + 2;
+                format!(
+                    "fn{}: {}",
+                    params,
+                    self.type_annotation(return_type, indent, return_col)
+                )
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::format::{format_source, FormatError};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_canonical_layout() {
+        let source = "// Users are identified by either kind of id
+type User = { id: #U32(u32) | #UUID(string), name: string };
+---The first user---
+export fn first(  ): User { {id:#U32(1u32), name: \"a\\n\"} }
+
+
+
+fn main(): i32 {
+    let u: User = first();   // trailing
+    let list = [1i32,2i32];
+    if (1i32 + 2i32) * 3i32 > 4i32 { u.id = #UUID(\"x\"); } else if !true { break } else {}
+
+    match u.id { U32(v) => 1i32, UUID(s) => { 2i32 } }
+    while u.id::is(#U32) {
+        // nothing to do
+    }
+    -(-list[0])
+}
+";
+
+        let formatted = format_source(source).unwrap();
+        assert_eq!(
+            formatted,
+            "// Users are identified by either kind of id
+type User = {
+    id: #U32(u32) | #UUID(string),
+    name: string,
+};
+---The first user---
+export fn first(): User {
+    { id: #U32(1u32), name: \"a\\n\" }
+}
+
+fn main(): i32 {
+    let u: User = first(); // trailing
+    let list = [1i32, 2i32];
+    if (1i32 + 2i32) * 3i32 > 4i32 {
+        u.id = #UUID(\"x\");
+    } else if !true {
+        break
+    } else {}
+
+    match u.id {
+        U32(v) => 1i32,
+        UUID(s) => {
+            2i32
+        },
+    }
+    while u.id::is(#U32) {
+        // nothing to do
+    }
+    -(-list[0])
+}
+"
+        );
+
+        assert_eq!(format_source(&formatted).unwrap(), formatted);
+    }
+
+    #[test]
+    fn test_long_lines_are_broken() {
+        let source = "from \"./some/module/with/a/long/path.wl\" { first_function, second_function: other, third_function }
+fn main(): void {
+    let value = call_with_many_arguments(first_argument, second_argument, third_argument, fourth);
+}
+type Shape = #Circle(f32) | #Rectangle({ width: f32, height: f32 }) | #Triangle(TrianglePoints);
+";
+
+        let formatted = format_source(source).unwrap();
+        assert_eq!(
+            formatted,
+            "from \"./some/module/with/a/long/path.wl\" {
+    first_function,
+    second_function: other,
+    third_function,
+}
+fn main(): void {
+    let value = call_with_many_arguments(
+        first_argument,
+        second_argument,
+        third_argument,
+        fourth,
+    );
+}
+type Shape = #Circle(f32)
+    | #Rectangle({ width: f32, height: f32 })
+    | #Triangle(TrianglePoints);
+"
+        );
+
+        assert_eq!(format_source(&formatted).unwrap(), formatted);
+    }
+
+    #[test]
+    fn test_generic_params() {
+        let source = "type Pair<A,B> = {first: A, second: B};
+fn first< A , B >(p: Pair< A,Pair<B, A> >): A { p.first }
+";
+
+        let formatted = format_source(source).unwrap();
+        assert_eq!(
+            formatted,
+            "type Pair<A, B> = {
+    first: A,
+    second: B,
+};
+fn first<A, B>(p: Pair<A, Pair<B, A>>): A {
+    p.first
+}
+"
+        );
+    }
+
+    #[test]
+    fn test_static_access() {
+        let source = "from \"./lib.wl\"   as lib
+fn main(): i32 { let items = List< i32 >::with_capacity(8usize); lib::len(items) }
+";
+
+        let formatted = format_source(source).unwrap();
+        assert_eq!(
+            formatted,
+            "from \"./lib.wl\" as lib
+fn main(): i32 {
+    let items = List<i32>::with_capacity(8usize);
+    lib::len(items)
+}
+"
+        );
+    }
+
+    #[test]
+    fn test_impl_block() {
+        let source =
+            "impl Counter { fn bump(mut  self,by:i32):void { self.count = self.count + by; }
+fn zero(mut n:i32):i32{n = 0i32; n} }
+";
+
+        let formatted = format_source(source).unwrap();
+        assert_eq!(
+            formatted,
+            "impl Counter {
+    fn bump(mut self, by: i32): void {
+        self.count = self.count + by;
+    }
+
+    fn zero(mut n: i32): i32 {
+        n = 0i32;
+        n
+    }
+}
+"
+        );
+    }
+
+    #[test]
+    fn test_for_loop() {
+        let source =
+            "fn main():void { for i in 0 .. n+1 { print(i); } for x in xs {} }\n";
+
+        let formatted = format_source(source).unwrap();
+        assert_eq!(
+            formatted,
+            "fn main(): void {
+    for i in 0..n + 1 {
+        print(i);
+    }
+    for x in xs {}
+}
+"
+        );
+    }
+
+    #[test]
+    fn test_compound_assignment() {
+        let source =
+            "fn main():void { let  mut x=0; x+=1; xs[i]  *= 2; p.count%=n-1; x ++; i--; }\n";
+
+        let formatted = format_source(source).unwrap();
+        assert_eq!(
+            formatted,
+            "fn main(): void {
+    let mut x = 0;
+    x += 1;
+    xs[i] *= 2;
+    p.count %= n - 1;
+    x++;
+    i--;
+}
+"
+        );
+    }
+
+    #[test]
+    fn test_bitwise_operators() {
+        let source = "fn main():void { let x = a<<2|b&~c^d>>1; let y = (a|b)&c; }\n";
+
+        let formatted = format_source(source).unwrap();
+        assert_eq!(
+            formatted,
+            "fn main(): void {
+    let x = a << 2 | b & ~c ^ d >> 1;
+    let y = (a | b) & c;
+}
+"
+        );
+    }
+
+    #[test]
+    fn test_comments_inside_lists() {
+        let source = "fn main(): i64 {
+    let p = {
+ // the x
+ x: 1, // one
+ y: 2,
+};
+    add(p.x,   // left
+        p.y)
+}
+";
+
+        let formatted = format_source(source).unwrap();
+        let expected = "fn main(): i64 {
+    let p = {
+        // the x
+        x: 1, // one
+        y: 2,
+    };
+    add(
+        p.x, // left
+        p.y,
+    )
+}
+";
+        assert_eq!(formatted, expected);
+        assert_eq!(format_source(&formatted).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_const_decl() {
+        let source = "export   const LIMIT:i64=64;\nconst NAME = \"w\";\n";
+
+        let formatted = format_source(source).unwrap();
+        assert_eq!(
+            formatted,
+            "export const LIMIT: i64 = 64;
+const NAME = \"w\";
+"
+        );
+    }
+
+    #[test]
+    fn test_array_type_and_literal() {
+        let source = "fn main(): i64 { let a:[i64;3]=[0;3]; a[0usize] }";
+
+        let formatted = format_source(source).unwrap();
+        assert_eq!(
+            formatted,
+            "fn main(): i64 {
+    let a: [i64; 3] = [0; 3];
+    a[0usize]
+}
+"
+        );
+    }
+
+    #[test]
+    fn test_slice_type_and_range_index() {
+        let source = "fn tail(s:string):[ u8 ] { s[ 1usize..s.len ] }";
+
+        let formatted = format_source(source).unwrap();
+        assert_eq!(
+            formatted,
+            "fn tail(s: string): [u8] {
+    s[1usize..s.len]
+}
+"
+        );
+    }
+
+    #[test]
+    fn test_invalid_source_is_not_formatted() {
+        assert!(matches!(
+            format_source("fn main(): i32 { let = 1; }"),
+            Err(FormatError::Parsing(_))
+        ));
+    }
+}
@@ -98,11 +98,20 @@ pub enum Instruction {
         operand: Value,
         target_type: Type,
     },
+    /// Calls through a function value (anything other than `Value::Function`) also
+    /// pass the environment pointer of that value as a trailing hidden argument
     FunctionCall {
         destination: Option<ValueId>,
         function_rvalue: Value,
         args: Vec<Value>,
     },
+    /// Pairs a closure with the pointer to its captured environment, forming a
+    /// function value
+    MakeClosure {
+        destination: ValueId,
+        function: DeclarationId,
+        environment: ValueId,
+    },
     GetFieldPtr {
         destination: ValueId,
         base_ptr: ValueId,
@@ -120,6 +129,7 @@ pub enum Instruction {
 pub enum UnaryOperationKind {
     Not,
     Neg,
+    BitNot,
 }
 
 #[derive(Clone, Debug)]
@@ -135,6 +145,11 @@ pub enum BinaryOperationKind {
     GreaterThanOrEqual,
     Equal,
     NotEqual,
+    BitAnd,
+    BitOr,
+    BitXor,
+    ShiftLeft,
+    ShiftRight,
 }
 
 #[derive(Clone, Debug)]
@@ -2,16 +2,20 @@ use std::{collections::HashSet, path::PathBuf};
 
 use crate::{
     ast::{IdentifierNode, Span},
-    compile::interner::StringId,
+    compile::interner::{StringId, TagId},
     hir::types::checked_type::Type,
 };
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum SemanticErrorKind {
     CannotNarrowNonUnion(Type),
     ValuedTagInIsExpression,
     UnreachableCode,
-    DuplicateIdentifier(IdentifierNode),
+    DuplicateIdentifier {
+        identifier: IdentifierNode,
+        /// Where the name was declared first, if it comes from a declaration
+        previous: Option<Span>,
+    },
     DuplicateUnionVariant(IdentifierNode),
     CannotIndex(Type),
     FromStatementMustBeDeclaredAtTopLevel,
@@ -39,6 +43,25 @@ pub enum SemanticErrorKind {
     BreakKeywordOutsideLoop,
     ContinueKeywordOutsideLoop,
     InvalidLValue,
+    AssignmentToImmutable(IdentifierNode),
+    AssignmentToReceiver,
+    ExpectedConstantExpression,
+    DivisionByZero,
+    ConstantOverflow(Type),
+    /// The constants of the cycle in the order they refer to each other
+    RecursiveConstant(Vec<IdentifierNode>),
+    IndexOutOfBounds {
+        index: usize,
+        size: usize,
+    },
+    InvalidSliceRange {
+        start: usize,
+        end: usize,
+    },
+    ArrayTooLarge {
+        item_type: Type,
+        size: usize,
+    },
     TypeMismatch {
         expected: Type,
         received: Type,
@@ -73,10 +96,46 @@ pub enum SemanticErrorKind {
         module_path: PathBuf,
         symbol: IdentifierNode,
     },
-    ClosuresNotSupportedYet,
+    CannotMatchNonUnion(Type),
+    NonExhaustiveMatch {
+        missing_patterns: Vec<Vec<TagId>>,
+    },
+    UnreachableMatchArm,
+    MatchPatternCountMismatch {
+        expected: usize,
+        received: usize,
+    },
+    UndefinedMatchVariant {
+        variant: IdentifierNode,
+        subject: Type,
+    },
+    MatchVariantHasNoValue(IdentifierNode),
+    GenericArgumentCountMismatch {
+        expected: usize,
+        received: usize,
+    },
+    CannotInferGenericArgument(IdentifierNode),
+    GenericFnMustBeDeclaredAtTopLevel,
+    CannotUseGenericFnAsValue,
+    RecursiveGenericTypeAlias(IdentifierNode),
+    GenericInstancesTooDeep(IdentifierNode),
+    CannotUseModuleAsValue,
+    CannotUseModuleAsType,
+    CannotUseBuiltinFnAsValue,
+    ImplMustBeDeclaredAtTopLevel,
+    MethodCannotBeGeneric,
+    CannotUseMethodAsValue,
+    FnHasNoReceiver(IdentifierNode),
+    MutatingMethodOnImmutable {
+        method: IdentifierNode,
+        variable: IdentifierNode,
+    },
+    CannotIterate(Type),
+    RangeOutsideForLoop,
+    ExpectedAnIntegerOperand,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct SemanticError {
     pub kind: SemanticErrorKind,
     pub span: Span,
@@ -124,9 +183,40 @@ impl SemanticErrorKind {
             SemanticErrorKind::UseOfUninitializedVariable(..) => 38,
             SemanticErrorKind::DuplicateUnionVariant(_) => 39,
             SemanticErrorKind::SymbolNotExported { .. } => 40,
-            SemanticErrorKind::ClosuresNotSupportedYet => 41,
             SemanticErrorKind::ValuedTagInIsExpression => 42,
             SemanticErrorKind::CannotNarrowNonUnion(_) => 43,
+            SemanticErrorKind::CannotMatchNonUnion(_) => 44,
+            SemanticErrorKind::NonExhaustiveMatch { .. } => 45,
+            SemanticErrorKind::UnreachableMatchArm => 46,
+            SemanticErrorKind::MatchPatternCountMismatch { .. } => 47,
+            SemanticErrorKind::UndefinedMatchVariant { .. } => 48,
+            SemanticErrorKind::MatchVariantHasNoValue(_) => 49,
+            SemanticErrorKind::GenericArgumentCountMismatch { .. } => 50,
+            SemanticErrorKind::CannotInferGenericArgument(_) => 51,
+            SemanticErrorKind::GenericFnMustBeDeclaredAtTopLevel => 52,
+            SemanticErrorKind::CannotUseGenericFnAsValue => 53,
+            SemanticErrorKind::RecursiveGenericTypeAlias(_) => 54,
+            SemanticErrorKind::CannotUseModuleAsValue => 55,
+            SemanticErrorKind::CannotUseModuleAsType => 56,
+            SemanticErrorKind::CannotUseBuiltinFnAsValue => 57,
+            SemanticErrorKind::ImplMustBeDeclaredAtTopLevel => 58,
+            SemanticErrorKind::MethodCannotBeGeneric => 59,
+            SemanticErrorKind::CannotUseMethodAsValue => 60,
+            SemanticErrorKind::FnHasNoReceiver(_) => 61,
+            SemanticErrorKind::CannotIterate(_) => 62,
+            SemanticErrorKind::RangeOutsideForLoop => 63,
+            SemanticErrorKind::ExpectedAnIntegerOperand => 64,
+            SemanticErrorKind::AssignmentToImmutable(_) => 65,
+            SemanticErrorKind::ExpectedConstantExpression => 66,
+            SemanticErrorKind::DivisionByZero => 67,
+            SemanticErrorKind::ConstantOverflow(_) => 68,
+            SemanticErrorKind::IndexOutOfBounds { .. } => 69,
+            SemanticErrorKind::InvalidSliceRange { .. } => 70,
+            SemanticErrorKind::ArrayTooLarge { .. } => 71,
+            SemanticErrorKind::GenericInstancesTooDeep(_) => 72,
+            SemanticErrorKind::MutatingMethodOnImmutable { .. } => 73,
+            SemanticErrorKind::RecursiveConstant(_) => 74,
+            SemanticErrorKind::AssignmentToReceiver => 75,
         }
     }
 }
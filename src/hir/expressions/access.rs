@@ -1,6 +1,12 @@
 use crate::{
     ast::{expr::Expr, IdentifierNode},
-    hir::{cfg::Value, FunctionBuilder, HIRContext},
+    hir::{
+        cfg::{Value, ValueId},
+        errors::{SemanticError, SemanticErrorKind},
+        types::checked_type::Type,
+        FunctionBuilder, HIRContext,
+    },
+    tokenize::NumberKind,
 };
 
 impl FunctionBuilder {
@@ -10,20 +16,115 @@ impl FunctionBuilder {
         left: Box<Expr>,
         field: IdentifierNode,
     ) -> Value {
-        let (current_base_ptr_id, _original_base_ptr_id) =
-            match self.build_lvalue_expr(ctx, *left) {
-                Ok(id) => id,
-                Err(e) => return Value::Use(self.report_error_and_get_poison(ctx, e)),
-            };
+        // Fields of other values, e.g. an element or the result of a call, are read
+        // from the value
+        let Some((current_base_ptr_id, _)) = self.build_place_ptr(ctx, &left) else {
+            let value = self.build_expr(ctx, *left);
+            return self.build_value_field(ctx, value, field);
+        };
+
+        let base_type = ctx.program_builder.get_value_id_type(&current_base_ptr_id);
+        if let Some(len) = array_len(ctx, &base_type, field) {
+            return len;
+        }
 
         let field_ptr_id = match self.emit_get_field_ptr(ctx, current_base_ptr_id, field)
         {
             Ok(id) => id,
-            Err(e) => return Value::Use(self.report_error_and_get_poison(ctx, e)),
+            Err(e) => {
+                let e = self.method_access_error(ctx, current_base_ptr_id, field, e);
+                return Value::Use(self.report_error_and_get_poison(ctx, e));
+            }
         };
 
         let final_value_id = self.emit_load(ctx, field_ptr_id);
 
         Value::Use(final_value_id)
     }
+
+    /// Reads a field of a value which is not necessarily stored in a variable, e.g.
+    /// the result of a call
+    pub fn build_value_field(
+        &mut self,
+        ctx: &mut HIRContext,
+        value: Value,
+        field: IdentifierNode,
+    ) -> Value {
+        let value_type = ctx.program_builder.get_value_type(&value);
+        if let Some(len) = array_len(ctx, &value_type, field) {
+            return len;
+        }
+
+        let base_ptr = match (&value, value_type) {
+            (_, Type::Unknown) => {
+                return Value::Use(self.alloc_value(ctx, Type::Unknown))
+            }
+            (Value::Use(id), Type::Pointer { .. }) => *id,
+            (_, ty) => {
+                let slot = self.emit_stack_alloc(ctx, ty, 1);
+                self.emit_store(ctx, slot, value, field.span);
+                slot
+            }
+        };
+
+        match self.emit_get_field_ptr(ctx, base_ptr, field) {
+            Ok(field_ptr) => Value::Use(self.emit_load(ctx, field_ptr)),
+            Err(e) => {
+                let e = self.method_access_error(ctx, base_ptr, field, e);
+                Value::Use(self.report_error_and_get_poison(ctx, e))
+            }
+        }
+    }
+
+    /// Methods are not fields, referring to one without calling it gets its own error
+    fn method_access_error(
+        &self,
+        ctx: &HIRContext,
+        base_ptr: ValueId,
+        field: IdentifierNode,
+        error: SemanticError,
+    ) -> SemanticError {
+        let is_method = match ctx.program_builder.get_value_id_type(&base_ptr) {
+            Type::Pointer { narrowed_to, .. } => ctx
+                .program_builder
+                .find_method(&narrowed_to, field.name)
+                .or_else(|| {
+                    // Struct variables are pointers to the struct pointer
+                    let outer = Type::Pointer {
+                        constraint: narrowed_to.clone(),
+                        narrowed_to,
+                    };
+                    ctx.program_builder.find_method(&outer, field.name)
+                })
+                .is_some(),
+            _ => false,
+        };
+
+        if is_method && matches!(error.kind, SemanticErrorKind::AccessToUndefinedField(_))
+        {
+            SemanticError {
+                kind: SemanticErrorKind::CannotUseMethodAsValue,
+                span: field.span,
+            }
+        } else {
+            error
+        }
+    }
+}
+
+/// The `len` of an array is part of its type, `ty` is the array or a pointer to it
+fn array_len(ctx: &HIRContext, ty: &Type, field: IdentifierNode) -> Option<Value> {
+    let array_type = match ty {
+        Type::Pointer { narrowed_to, .. } => narrowed_to,
+        ty => ty,
+    };
+
+    match array_type {
+        Type::Array { size, .. }
+            if field.name == ctx.program_builder.common_identifiers.len =>
+        {
+            Some(Value::NumberLiteral(NumberKind::USize(*size)))
+        }
+        _ => None,
+    }
 }
@@ -0,0 +1,182 @@
+use crate::{
+    ast::{
+        expr::{Expr, ExprKind},
+        Span,
+    },
+    hir::{
+        cfg::{BinaryOperationKind, Terminator, Value},
+        types::checked_type::Type,
+        utils::{check_type::check_array_type, const_eval::eval_const_usize},
+        FunctionBuilder, HIRContext,
+    },
+    tokenize::NumberKind,
+};
+
+impl FunctionBuilder {
+    /// `[value; size]`, the array lives on the stack and `value` is evaluated once
+    /// and copied into every item
+    pub fn build_array_literal_expr(
+        &mut self,
+        ctx: &mut HIRContext,
+        value: Box<Expr>,
+        size: Box<Expr>,
+        expr_span: Span,
+    ) -> Value {
+        let value_span = value.span;
+        let item_value = self.build_expr(ctx, *value);
+        let item_type = ctx.program_builder.get_value_type(&item_value);
+
+        let size = match eval_const_usize(ctx, &size) {
+            Ok(size) => size,
+            Err(e) => return Value::Use(self.report_error_and_get_poison(ctx, e)),
+        };
+
+        if item_type == Type::Unknown {
+            return Value::Use(self.alloc_value(ctx, Type::Unknown));
+        }
+
+        let array_type = match check_array_type(ctx, item_type, size, expr_span) {
+            Ok(ty) => ty,
+            Err(e) => return Value::Use(self.report_error_and_get_poison(ctx, e)),
+        };
+        let array_ptr = self.emit_stack_alloc(ctx, array_type, 1);
+        let items_ptr = self.emit_array_items_ptr(ctx, array_ptr);
+
+        let index_ptr = self.emit_stack_alloc(ctx, Type::USize, 1);
+        self.emit_store(
+            ctx,
+            index_ptr,
+            Value::NumberLiteral(NumberKind::USize(0)),
+            expr_span,
+        );
+
+        let header_block = self.new_basic_block();
+        let body_block = self.new_basic_block();
+        let exit_block = self.new_basic_block();
+
+        self.set_basic_block_terminator(Terminator::Jump {
+            target: header_block,
+            args: vec![],
+        });
+
+        self.use_basic_block(header_block);
+        let index_ptr_in_header = self.use_value_in_block(ctx, header_block, index_ptr);
+        let index = Value::Use(self.emit_load(ctx, index_ptr_in_header));
+        let is_filling = self
+            .emit_binary_op(
+                ctx,
+                BinaryOperationKind::LessThan,
+                index,
+                expr_span,
+                Value::NumberLiteral(NumberKind::USize(size)),
+                expr_span,
+            )
+            .expect("INTERNAL COMPILER ERROR: Expected array index to be a usize");
+        self.set_basic_block_terminator(Terminator::CondJump {
+            condition: Value::Use(is_filling),
+            true_target: body_block,
+            true_args: vec![],
+            false_target: exit_block,
+            false_args: vec![],
+        });
+
+        self.seal_block(ctx, body_block);
+        self.use_basic_block(body_block);
+
+        let index_ptr_in_body = self.use_value_in_block(ctx, body_block, index_ptr);
+        let index = Value::Use(self.emit_load(ctx, index_ptr_in_body));
+        let items_ptr = self.use_value_in_block(ctx, body_block, items_ptr);
+        let item_ptr = self
+            .emit_get_element_ptr(ctx, items_ptr, index.clone())
+            .expect("INTERNAL COMPILER ERROR: Expected array index to be a usize");
+        let item_value = match item_value {
+            Value::Use(id) => Value::Use(self.use_value_in_block(ctx, body_block, id)),
+            literal => literal,
+        };
+        self.emit_store(ctx, item_ptr, item_value, value_span);
+
+        let next = self
+            .emit_binary_op(
+                ctx,
+                BinaryOperationKind::Add,
+                index,
+                expr_span,
+                Value::NumberLiteral(NumberKind::USize(1)),
+                expr_span,
+            )
+            .expect("INTERNAL COMPILER ERROR: Expected array index to be a usize");
+        self.emit_store(ctx, index_ptr_in_body, Value::Use(next), expr_span);
+
+        self.set_basic_block_terminator(Terminator::Jump {
+            target: header_block,
+            args: vec![],
+        });
+
+        self.seal_block(ctx, header_block);
+        self.seal_block(ctx, exit_block);
+        self.use_basic_block(exit_block);
+
+        let array_ptr = self.use_value_in_block(ctx, exit_block, array_ptr);
+        Value::Use(self.emit_load(ctx, array_ptr))
+    }
+
+    /// Builds `expr` where a value of `expected` type is wanted, a list literal
+    /// becomes an array when an array is expected
+    pub fn build_expr_for_type(
+        &mut self,
+        ctx: &mut HIRContext,
+        expr: Expr,
+        expected: &Type,
+    ) -> Value {
+        match (expr.kind, expected) {
+            (ExprKind::List(items), Type::Array { item_type, .. }) => {
+                self.build_array_from_items(ctx, items, item_type, expr.span)
+            }
+            (kind, _) => self.build_expr(
+                ctx,
+                Expr {
+                    kind,
+                    span: expr.span,
+                },
+            ),
+        }
+    }
+
+    /// `[a, b, c]` as an array of `item_type`, the size is the number of items
+    fn build_array_from_items(
+        &mut self,
+        ctx: &mut HIRContext,
+        items: Vec<Expr>,
+        item_type: &Type,
+        expr_span: Span,
+    ) -> Value {
+        let array_type =
+            match check_array_type(ctx, item_type.clone(), items.len(), expr_span) {
+                Ok(ty) => ty,
+                Err(e) => return Value::Use(self.report_error_and_get_poison(ctx, e)),
+            };
+        let item_values: Vec<(Value, Span)> = items
+            .into_iter()
+            .map(|item| {
+                let item_span = item.span;
+                (self.build_expr_for_type(ctx, item, item_type), item_span)
+            })
+            .collect();
+
+        let array_ptr = self.emit_stack_alloc(ctx, array_type, 1);
+        let items_ptr = self.emit_array_items_ptr(ctx, array_ptr);
+
+        for (index, (item_value, item_span)) in item_values.into_iter().enumerate() {
+            let item_ptr = self
+                .emit_get_element_ptr(
+                    ctx,
+                    items_ptr,
+                    Value::NumberLiteral(NumberKind::USize(index)),
+                )
+                .expect("INTERNAL COMPILER ERROR: Expected array index to be a usize");
+            self.emit_store(ctx, item_ptr, item_value, item_span);
+        }
+
+        Value::Use(self.emit_load(ctx, array_ptr))
+    }
+}
@@ -3,49 +3,30 @@
 use std::collections::{HashMap, HashSet};
 
 use crate::{
-    ast::{decl::FnDecl, expr::BlockContents},
+    ast::{decl::FnDecl, expr::BlockContents, DeclarationId, IdentifierNode},
     hir::{
-        cfg::{BasicBlock, BasicBlockId, ControlFlowGraph, Terminator, Value},
+        cfg::{BasicBlock, BasicBlockId, ControlFlowGraph, Terminator, Value, ValueId},
         errors::{SemanticError, SemanticErrorKind},
-        types::checked_declaration::{
-            CheckedDeclaration, CheckedFnDecl, CheckedParam, CheckedVarDecl,
+        types::{
+            checked_declaration::{
+                CheckedDeclaration, CheckedFnDecl, CheckedParam, CheckedVarDecl, FnType,
+            },
+            checked_type::{StructKind, Type},
         },
         utils::{
-            check_is_assignable::check_is_assignable, check_type::check_type_annotation,
+            check_is_assignable::check_is_assignable,
+            check_type::{check_params, check_type_annotation},
+            layout::pack_struct,
+            scope::ScopeKind,
+            var_capture_analyzer::analyze_captures,
         },
         FunctionBuilder, HIRContext,
     },
+    tokenize::NumberKind,
 };
 
 impl FunctionBuilder {
-    pub fn build(ctx: &mut HIRContext, fn_decl: FnDecl) -> Value {
-        if !ctx.module_builder.is_file_scope() {
-            ctx.module_builder.errors.push(SemanticError {
-                kind: SemanticErrorKind::ClosuresNotSupportedYet,
-                span: fn_decl.identifier.span,
-            });
-            return Value::VoidLiteral;
-        }
-
-        let FnDecl {
-            id: decl_id,
-            identifier,
-            params,
-            return_type,
-            body,
-            is_exported,
-            ..
-        } = fn_decl;
-
-        let checked_params: Vec<CheckedParam> = params
-            .iter()
-            .map(|p| CheckedParam {
-                identifier: p.identifier,
-                ty: check_type_annotation(ctx, &p.constraint),
-            })
-            .collect();
-        let checked_return_type = check_type_annotation(ctx, &return_type);
-
+    fn new(return_type: Type) -> FunctionBuilder {
         let entry_block_id = BasicBlockId(0);
         let cfg = ControlFlowGraph {
             blocks: HashMap::from([(
@@ -60,9 +41,9 @@ impl FunctionBuilder {
             entry_block: entry_block_id,
         };
 
-        let mut inner_builder = FunctionBuilder {
+        let mut builder = FunctionBuilder {
             cfg,
-            return_type: checked_return_type.clone(),
+            return_type,
             current_block_id: entry_block_id,
             predecessors: HashMap::new(),
             block_value_maps: HashMap::new(),
@@ -71,41 +52,239 @@ impl FunctionBuilder {
             incomplete_params: HashMap::new(),
             predicates: HashMap::new(),
             block_id_counter: 1,
-            value_id_counter: 0,
         };
-        inner_builder.sealed_blocks.insert(entry_block_id);
+        builder.sealed_blocks.insert(entry_block_id);
 
-        ctx.module_builder
-            .enter_scope(crate::hir::utils::scope::ScopeKind::Function);
+        builder
+    }
 
-        for param in &checked_params {
-            let arg_ssa_val =
-                inner_builder.append_block_param(ctx, entry_block_id, param.ty.clone());
+    /// Builds a function declared at file scope
+    pub fn build(ctx: &mut HIRContext, fn_decl: FnDecl) -> Value {
+        let decl_id = fn_decl.id;
+        let checked_params = check_params(ctx, &fn_decl.params);
+        let checked_return_type = check_type_annotation(ctx, &fn_decl.return_type);
+        FunctionBuilder::build_function(
+            ctx,
+            fn_decl,
+            checked_params,
+            checked_return_type,
+            None,
+        );
 
-            let stack_ptr = inner_builder.emit_stack_alloc(ctx, param.ty.clone(), 1);
-            inner_builder.emit_store(
+        Value::Function(decl_id)
+    }
+
+    /// Builds a function declared inside another function and binds it to its name.
+    /// Variables it uses from the enclosing function are copied into a heap allocated
+    /// environment when the declaration is reached, the closure sees their values at
+    /// that point and cannot assign to them.
+    pub fn build_fn_expr(&mut self, ctx: &mut HIRContext, fn_decl: FnDecl) -> Value {
+        if !fn_decl.generic_params.is_empty() {
+            return Value::Use(self.report_error_and_get_poison(
                 ctx,
-                stack_ptr,
-                Value::Use(arg_ssa_val),
-                param.identifier.span,
+                SemanticError {
+                    kind: SemanticErrorKind::GenericFnMustBeDeclaredAtTopLevel,
+                    span: fn_decl.identifier.span,
+                },
+            ));
+        }
+
+        let decl_id = fn_decl.id;
+        let identifier = fn_decl.identifier;
+        let checked_params = check_params(ctx, &fn_decl.params);
+        let checked_return_type = check_type_annotation(ctx, &fn_decl.return_type);
+
+        let captures: Vec<CheckedParam> =
+            analyze_captures(ctx, identifier, &checked_params, &fn_decl.body)
+                .into_iter()
+                .map(|(identifier, ty)| CheckedParam { identifier, ty })
+                .collect();
+
+        let environment = (!captures.is_empty())
+            .then(|| pack_struct(ctx.program_builder, StructKind::UserDefined(captures)));
+
+        let fn_type = Type::Fn(FnType {
+            params: checked_params.clone(),
+            return_type: Box::new(checked_return_type.clone()),
+        });
+
+        // The signature is declared before the body is built so the function can call
+        // itself
+        let signature = CheckedDeclaration::Function(CheckedFnDecl {
+            id: decl_id,
+            identifier,
+            params: checked_params.clone(),
+            return_type: checked_return_type.clone(),
+            body: None,
+            is_exported: false,
+            environment: None,
+        });
+
+        let Some(environment) = environment else {
+            // Without captures the function is called directly, like one declared at
+            // file scope
+            ctx.module_builder
+                .scope_insert(ctx.program_builder, identifier, signature);
+            FunctionBuilder::build_function(
+                ctx,
+                fn_decl,
+                checked_params,
+                checked_return_type,
+                None,
             );
 
-            let param_decl_id = ctx.program_builder.new_declaration_id();
-            let decl = CheckedVarDecl {
-                id: param_decl_id,
-                ptr: stack_ptr,
-                identifier: param.identifier,
-                documentation: None,
-                constraint: param.ty.clone(),
+            return Value::Function(decl_id);
+        };
+
+        ctx.program_builder.declarations.insert(decl_id, signature);
+        FunctionBuilder::build_function(
+            ctx,
+            fn_decl,
+            checked_params,
+            checked_return_type,
+            Some(environment.clone()),
+        );
+
+        let env_ptr = self.build_environment(ctx, environment);
+        let value = Value::Use(self.emit_make_closure(ctx, decl_id, env_ptr));
+
+        let ptr = self.emit_stack_alloc(ctx, fn_type.clone(), 1);
+        self.emit_store(ctx, ptr, value.clone(), identifier.span);
+        let var_decl = CheckedVarDecl {
+            id: ctx.program_builder.new_declaration_id(),
+            ptr,
+            identifier,
+            documentation: None,
+            constraint: fn_type,
+            is_mutable: false,
+        };
+        ctx.module_builder.scope_insert(
+            ctx.program_builder,
+            identifier,
+            CheckedDeclaration::Var(var_decl),
+        );
+
+        value
+    }
+
+    /// Copies the current values of the captured variables into a new environment
+    fn build_environment(
+        &mut self,
+        ctx: &mut HIRContext,
+        environment: StructKind,
+    ) -> ValueId {
+        let StructKind::UserDefined(captures) = environment.clone() else {
+            panic!("INTERNAL COMPILER ERROR: Expected a user defined environment struct");
+        };
+
+        let env_ptr = self
+            .emit_heap_alloc(
+                ctx,
+                Type::Struct(environment),
+                Value::NumberLiteral(NumberKind::USize(1)),
+            )
+            .expect("INTERNAL COMPILER ERROR: Environment count is a usize literal");
+
+        for capture in captures {
+            let var_ptr = match ctx
+                .module_builder
+                .scope_lookup(capture.identifier.name)
+                .map(|id| ctx.program_builder.get_declaration(id))
+            {
+                Some(CheckedDeclaration::Var(var)) => var.ptr,
+                _ => panic!(
+                    "INTERNAL COMPILER ERROR: Captured identifier does not resolve to a \
+                     variable"
+                ),
             };
+            let var_ptr = self.use_value_in_block(ctx, self.current_block_id, var_ptr);
+            let value = self.emit_load(ctx, var_ptr);
 
-            ctx.module_builder.scope_insert(
-                ctx.program_builder,
-                param.identifier,
-                CheckedDeclaration::Var(decl),
+            let field_ptr = self
+                .emit_get_field_ptr(ctx, env_ptr, capture.identifier)
+                .expect("INTERNAL COMPILER ERROR: Environment is missing a capture");
+            self.emit_store(ctx, field_ptr, Value::Use(value), capture.identifier.span);
+        }
+
+        env_ptr
+    }
+
+    /// Builds the body of a function and inserts its declaration. Closures receive
+    /// the pointer to their `environment` as an extra last parameter of the entry
+    /// block, the captured variables are copied out of it into immutable local
+    /// variables and the closure's own name is bound to a closure over the same
+    /// environment.
+    pub fn build_function(
+        ctx: &mut HIRContext,
+        fn_decl: FnDecl,
+        checked_params: Vec<CheckedParam>,
+        checked_return_type: Type,
+        environment: Option<StructKind>,
+    ) {
+        let FnDecl {
+            id: decl_id,
+            identifier,
+            params,
+            body,
+            is_exported,
+            ..
+        } = fn_decl;
+
+        let mut inner_builder = FunctionBuilder::new(checked_return_type.clone());
+        let entry_block_id = inner_builder.cfg.entry_block;
+
+        ctx.module_builder.enter_scope(ScopeKind::Function);
+
+        for (param, declared) in checked_params.iter().zip(&params) {
+            let arg_ssa_val =
+                inner_builder.append_block_param(ctx, entry_block_id, param.ty.clone());
+            inner_builder.bind_local(
+                ctx,
+                param,
+                Value::Use(arg_ssa_val),
+                declared.is_mutable,
             );
         }
 
+        let env_param = environment.map(|environment| {
+            let env_type = Type::Struct(environment.clone());
+            let env_param = inner_builder.append_block_param(
+                ctx,
+                entry_block_id,
+                Type::Pointer {
+                    constraint: Box::new(env_type.clone()),
+                    narrowed_to: Box::new(env_type),
+                },
+            );
+
+            if let StructKind::UserDefined(captures) = environment {
+                for capture in &captures {
+                    let field_ptr = inner_builder
+                        .emit_get_field_ptr(ctx, env_param, capture.identifier)
+                        .expect(
+                            "INTERNAL COMPILER ERROR: Environment is missing a capture",
+                        );
+                    let value = inner_builder.emit_load(ctx, field_ptr);
+                    // Assigning to a copy would not change the captured variable
+                    inner_builder.bind_local(ctx, capture, Value::Use(value), false);
+                }
+            }
+
+            let closure = inner_builder.emit_make_closure(ctx, decl_id, env_param);
+            let fn_type = ctx.program_builder.get_value_id_type(&closure);
+            inner_builder.bind_local(
+                ctx,
+                &CheckedParam {
+                    identifier,
+                    ty: fn_type,
+                },
+                Value::Use(closure),
+                false,
+            );
+
+            env_param
+        });
+
         inner_builder.build_fn_body(ctx, body);
         ctx.module_builder.exit_scope();
 
@@ -116,13 +295,98 @@ impl FunctionBuilder {
             return_type: checked_return_type,
             body: Some(inner_builder.cfg),
             is_exported,
+            environment: env_param,
         };
 
         ctx.program_builder
             .declarations
             .insert(decl_id, CheckedDeclaration::Function(checked_fn_decl));
+    }
+
+    /// Builds a function which returns the final expression of `body`, its return type
+    /// is inferred from that expression. Used for the entries of the REPL, the
+    /// `variables` of earlier entries are passed as pointers to the slots they live in,
+    /// so assignments to them outlast the entry.
+    pub fn build_entry(
+        ctx: &mut HIRContext,
+        decl_id: DeclarationId,
+        identifier: IdentifierNode,
+        variables: &[CheckedVarDecl],
+        body: BlockContents,
+    ) -> Type {
+        let mut builder = FunctionBuilder::new(Type::Void);
+        let entry_block_id = builder.cfg.entry_block;
+
+        ctx.module_builder.enter_scope(ScopeKind::Function);
+        let mut params = Vec::new();
+        for var in variables {
+            let ty = Type::Pointer {
+                constraint: Box::new(var.constraint.clone()),
+                narrowed_to: Box::new(var.constraint.clone()),
+            };
+            let ptr = builder.append_block_param(ctx, entry_block_id, ty.clone());
+            params.push(CheckedParam {
+                identifier: var.identifier,
+                ty,
+            });
+            ctx.module_builder.scope_insert(
+                ctx.program_builder,
+                var.identifier,
+                CheckedDeclaration::Var(CheckedVarDecl { ptr, ..var.clone() }),
+            );
+        }
+        let final_value = builder.build_codeblock_expr(ctx, body);
+        ctx.module_builder.exit_scope();
+
+        let return_type = ctx.program_builder.get_value_type(&final_value);
+        if builder.get_current_basic_block().terminator.is_none() {
+            builder.set_basic_block_terminator(Terminator::Return {
+                value: Some(final_value),
+            });
+        }
+
+        let checked_fn_decl = CheckedFnDecl {
+            id: decl_id,
+            identifier,
+            params,
+            return_type: return_type.clone(),
+            body: Some(builder.cfg),
+            is_exported: false,
+            environment: None,
+        };
+        ctx.program_builder
+            .declarations
+            .insert(decl_id, CheckedDeclaration::Function(checked_fn_decl));
 
-        Value::Function(decl_id)
+        return_type
+    }
+
+    /// Stores `value` in a new stack slot and declares it as the variable `param`
+    pub fn bind_local(
+        &mut self,
+        ctx: &mut HIRContext,
+        param: &CheckedParam,
+        value: Value,
+        is_mutable: bool,
+    ) {
+        let stack_ptr = self.emit_stack_alloc(ctx, param.ty.clone(), 1);
+        self.emit_store(ctx, stack_ptr, value, param.identifier.span);
+
+        let param_decl_id = ctx.program_builder.new_declaration_id();
+        let decl = CheckedVarDecl {
+            id: param_decl_id,
+            ptr: stack_ptr,
+            identifier: param.identifier,
+            documentation: None,
+            constraint: param.ty.clone(),
+            is_mutable,
+        };
+
+        ctx.module_builder.scope_insert(
+            ctx.program_builder,
+            param.identifier,
+            CheckedDeclaration::Var(decl),
+        );
     }
 
     fn build_fn_body(&mut self, ctx: &mut HIRContext, body: BlockContents) {
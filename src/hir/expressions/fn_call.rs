@@ -1,6 +1,17 @@
 use crate::{
-    ast::{expr::Expr, Span},
-    hir::{cfg::Value, FunctionBuilder, HIRContext},
+    ast::{
+        expr::{Expr, ExprKind},
+        DeclarationId, IdentifierNode, Span,
+    },
+    hir::{
+        cfg::Value,
+        errors::{SemanticError, SemanticErrorKind},
+        expressions::static_access::StaticItem,
+        statements::assignment::immutable_root,
+        types::{checked_declaration::CheckedDeclaration, checked_type::Type},
+        utils::generics::instantiate_generic_fn,
+        FunctionBuilder, HIRContext, SymbolReference,
+    },
 };
 
 impl FunctionBuilder {
@@ -11,17 +22,225 @@ impl FunctionBuilder {
         args: Vec<Expr>,
         span: Span,
     ) -> Value {
+        if let ExprKind::Identifier(identifier) = left.kind {
+            let template = ctx
+                .module_builder
+                .scope_lookup(identifier.name)
+                .filter(|id| ctx.program_builder.generic_templates.contains_key(id));
+
+            if let Some(template) = template {
+                ctx.module_builder.resolve_identifier(identifier);
+                return self.build_generic_fn_call_expr(ctx, template, args, span);
+            }
+        }
+
+        if let ExprKind::StaticAccess { left, field } = left.kind {
+            return self.build_static_call_expr(ctx, *left, field, args, span);
+        }
+
+        if let ExprKind::Access { left, field } = left.kind {
+            return self.build_method_call_expr(ctx, *left, field, args, span);
+        }
+
         let function_value = self.build_expr(ctx, *left);
+        self.build_call(ctx, function_value, args, span)
+    }
+
+    /// Calls `left::field(args)`, either an item of a module or a built-in static
+    /// function
+    fn build_static_call_expr(
+        &mut self,
+        ctx: &mut HIRContext,
+        left: Expr,
+        field: IdentifierNode,
+        args: Vec<Expr>,
+        span: Span,
+    ) -> Value {
+        match self.resolve_static_access(ctx, left, field) {
+            Ok(Some(StaticItem::Declaration(decl_id)))
+                if ctx.program_builder.generic_templates.contains_key(&decl_id) =>
+            {
+                self.build_generic_fn_call_expr(ctx, decl_id, args, span)
+            }
+            Ok(Some(StaticItem::Declaration(decl_id))) => {
+                let function_value =
+                    self.build_declaration_value(ctx, decl_id, field.span);
+                self.build_call(ctx, function_value, args, span)
+            }
+            Ok(Some(StaticItem::Builtin(builtin))) => {
+                self.build_builtin_static_call(ctx, builtin, args, span)
+            }
+            Ok(None) => Value::Use(self.alloc_value(ctx, Type::Unknown)),
+            Err(e) => Value::Use(self.report_error_and_get_poison(ctx, e)),
+        }
+    }
+
+    /// Calls `receiver.field(args)`. A field of the receiver holding a function takes
+    /// precedence over a method of its type, methods get the receiver as their first
+    /// argument. Struct receivers are pointers to their fields, so methods can change
+    /// them.
+    fn build_method_call_expr(
+        &mut self,
+        ctx: &mut HIRContext,
+        receiver: Expr,
+        field: IdentifierNode,
+        args: Vec<Expr>,
+        span: Span,
+    ) -> Value {
+        // Variables are narrowed by their value, methods belong to their declared type
+        let declared_type = match &receiver.kind {
+            ExprKind::Identifier(identifier) => ctx
+                .module_builder
+                .scope_lookup(identifier.name)
+                .and_then(|id| match ctx.program_builder.get_declaration(id) {
+                    CheckedDeclaration::Var(var) => Some(var.constraint.clone()),
+                    _ => None,
+                }),
+            _ => None,
+        };
+
+        let receiver_root = immutable_root(ctx, &receiver);
+        let receiver_value = self.build_expr(ctx, receiver);
+        let receiver_type = declared_type
+            .unwrap_or_else(|| ctx.program_builder.get_value_type(&receiver_value));
+
+        let has_field = match &receiver_type {
+            Type::Pointer { narrowed_to, .. } => match &**narrowed_to {
+                Type::Struct(s) => s.get_field(ctx.program_builder, field.name).is_some(),
+                _ => false,
+            },
+            Type::Struct(s) => s.get_field(ctx.program_builder, field.name).is_some(),
+            _ => false,
+        };
+
+        let method = ctx
+            .program_builder
+            .find_method(&receiver_type, field.name)
+            .filter(|_| !has_field);
+
+        let Some(method) = method else {
+            let function_value = self.build_value_field(ctx, receiver_value, field);
+            return self.build_call(ctx, function_value, args, span);
+        };
+
+        ctx.module_builder.references.push(SymbolReference {
+            span: field.span,
+            decl_id: method.decl_id,
+        });
+
+        if !method.has_receiver {
+            return Value::Use(self.report_error_and_get_poison(
+                ctx,
+                SemanticError {
+                    kind: SemanticErrorKind::FnHasNoReceiver(field),
+                    span: field.span,
+                },
+            ));
+        }
+
+        if method.mutates_receiver {
+            if let Some(variable) = receiver_root {
+                return Value::Use(self.report_error_and_get_poison(
+                    ctx,
+                    SemanticError {
+                        kind: SemanticErrorKind::MutatingMethodOnImmutable {
+                            method: field,
+                            variable,
+                        },
+                        span,
+                    },
+                ));
+            }
+        }
+
+        let mut arg_values = vec![receiver_value];
+        let method_value = Value::Function(method.decl_id);
+        arg_values.extend(self.build_args(ctx, &method_value, args, 1));
+
+        match self.emit_function_call(ctx, method_value, arg_values, span) {
+            Ok(Some(return_value_id)) => Value::Use(return_value_id),
+            Ok(None) => Value::VoidLiteral,
+            Err(e) => Value::Use(self.report_error_and_get_poison(ctx, e)),
+        }
+    }
+
+    fn build_call(
+        &mut self,
+        ctx: &mut HIRContext,
+        function_value: Value,
+        args: Vec<Expr>,
+        span: Span,
+    ) -> Value {
+        let arg_values = self.build_args(ctx, &function_value, args, 0);
+
+        // The callee was reported already
+        if ctx.program_builder.get_value_type(&function_value) == Type::Unknown {
+            return Value::Use(self.alloc_value(ctx, Type::Unknown));
+        }
+
+        match self.emit_function_call(ctx, function_value, arg_values, span) {
+            Ok(Some(return_value_id)) => Value::Use(return_value_id),
+            Ok(None) => Value::VoidLiteral,
+            Err(e) => Value::Use(self.report_error_and_get_poison(ctx, e)),
+        }
+    }
 
+    /// Calls the instance of a generic function for the types of the arguments
+    fn build_generic_fn_call_expr(
+        &mut self,
+        ctx: &mut HIRContext,
+        template: DeclarationId,
+        args: Vec<Expr>,
+        span: Span,
+    ) -> Value {
         let arg_values: Vec<Value> = args
             .into_iter()
             .map(|arg_expr| self.build_expr(ctx, arg_expr))
             .collect();
+        let arg_types: Vec<Type> = arg_values
+            .iter()
+            .map(|value| ctx.program_builder.get_value_type(value))
+            .collect();
 
-        match self.emit_function_call(ctx, function_value, arg_values, span) {
+        let result = instantiate_generic_fn(ctx, template, &arg_types, span).and_then(
+            |instance| {
+                self.emit_function_call(ctx, Value::Function(instance), arg_values, span)
+            },
+        );
+
+        match result {
             Ok(Some(return_value_id)) => Value::Use(return_value_id),
             Ok(None) => Value::VoidLiteral,
             Err(e) => Value::Use(self.report_error_and_get_poison(ctx, e)),
         }
     }
+
+    /// Builds the arguments of a call to `function_value`, the parameters from
+    /// `first_param` on give the types the arguments are built for
+    fn build_args(
+        &mut self,
+        ctx: &mut HIRContext,
+        function_value: &Value,
+        args: Vec<Expr>,
+        first_param: usize,
+    ) -> Vec<Value> {
+        let param_types: Vec<Type> =
+            match ctx.program_builder.get_value_type(function_value) {
+                Type::Fn(fn_type) => fn_type
+                    .params
+                    .into_iter()
+                    .skip(first_param)
+                    .map(|param| param.ty)
+                    .collect(),
+                _ => Vec::new(),
+            };
+
+        args.into_iter()
+            .enumerate()
+            .map(|(index, arg_expr)| match param_types.get(index) {
+                Some(param_type) => self.build_expr_for_type(ctx, arg_expr, param_type),
+                None => self.build_expr(ctx, arg_expr),
+            })
+            .collect()
+    }
 }
@@ -1,9 +1,9 @@
 use crate::{
-    ast::IdentifierNode,
+    ast::{DeclarationId, IdentifierNode, Span},
     hir::{
         cfg::Value,
         errors::{SemanticError, SemanticErrorKind},
-        types::checked_declaration::CheckedDeclaration,
+        types::checked_declaration::{CheckedDeclaration, ConstValue},
         FunctionBuilder, HIRContext,
     },
 };
@@ -14,46 +14,8 @@ impl FunctionBuilder {
         ctx: &mut HIRContext,
         identifier: IdentifierNode,
     ) -> Value {
-        let maybe_decl = ctx
-            .module_builder
-            .scope_lookup(identifier.name)
-            .map(|id| ctx.program_builder.get_declaration(id));
-
-        match maybe_decl {
-            Some(decl) => match decl {
-                CheckedDeclaration::Var(checked_var_decl) => {
-                    let ptr_val = self.use_value_in_block(
-                        ctx,
-                        self.current_block_id,
-                        checked_var_decl.ptr,
-                    );
-
-                    Value::Use(self.emit_load(ctx, ptr_val))
-                }
-                CheckedDeclaration::UninitializedVar { identifier, .. } => {
-                    Value::Use(self.report_error_and_get_poison(
-                        ctx,
-                        SemanticError {
-                            kind: SemanticErrorKind::UseOfUninitializedVariable(
-                                *identifier,
-                            ),
-                            span: identifier.span,
-                        },
-                    ))
-                }
-                CheckedDeclaration::TypeAlias(decl) => {
-                    Value::Use(self.report_error_and_get_poison(
-                        ctx,
-                        SemanticError {
-                            kind: SemanticErrorKind::CannotUseTypeDeclarationAsValue,
-                            span: decl.identifier.span,
-                        },
-                    ))
-                }
-                CheckedDeclaration::Function(checked_fn_decl) => {
-                    Value::Function(checked_fn_decl.id)
-                }
-            },
+        match ctx.module_builder.resolve_identifier(identifier) {
+            Some(decl_id) => self.build_declaration_value(ctx, decl_id, identifier.span),
             None => Value::Use(self.report_error_and_get_poison(
                 ctx,
                 SemanticError {
@@ -63,4 +25,88 @@ impl FunctionBuilder {
             )),
         }
     }
+
+    /// The value of the declaration an expression refers to by name
+    pub fn build_declaration_value(
+        &mut self,
+        ctx: &mut HIRContext,
+        decl_id: DeclarationId,
+        span: Span,
+    ) -> Value {
+        match ctx.program_builder.get_declaration(decl_id) {
+            CheckedDeclaration::Var(checked_var_decl) => {
+                let ptr_val = self.use_value_in_block(
+                    ctx,
+                    self.current_block_id,
+                    checked_var_decl.ptr,
+                );
+
+                Value::Use(self.emit_load(ctx, ptr_val))
+            }
+            CheckedDeclaration::Const(decl) => {
+                let value = decl.value.clone();
+                self.build_const_value(ctx, value, span)
+            }
+            CheckedDeclaration::UninitializedVar { identifier, .. } => {
+                Value::Use(self.report_error_and_get_poison(
+                    ctx,
+                    SemanticError {
+                        kind: SemanticErrorKind::UseOfUninitializedVariable(*identifier),
+                        span: identifier.span,
+                    },
+                ))
+            }
+            CheckedDeclaration::TypeAlias(decl) => {
+                Value::Use(self.report_error_and_get_poison(
+                    ctx,
+                    SemanticError {
+                        kind: SemanticErrorKind::CannotUseTypeDeclarationAsValue,
+                        span: decl.identifier.span,
+                    },
+                ))
+            }
+            CheckedDeclaration::Module(_) => {
+                Value::Use(self.report_error_and_get_poison(
+                    ctx,
+                    SemanticError {
+                        kind: SemanticErrorKind::CannotUseModuleAsValue,
+                        span,
+                    },
+                ))
+            }
+            CheckedDeclaration::Function(checked_fn_decl)
+                if ctx
+                    .program_builder
+                    .generic_templates
+                    .contains_key(&checked_fn_decl.id) =>
+            {
+                Value::Use(self.report_error_and_get_poison(
+                    ctx,
+                    SemanticError {
+                        kind: SemanticErrorKind::CannotUseGenericFnAsValue,
+                        span,
+                    },
+                ))
+            }
+            CheckedDeclaration::Function(checked_fn_decl) => {
+                Value::Function(checked_fn_decl.id)
+            }
+        }
+    }
+
+    /// Emits a value computed at compile time as a literal
+    pub fn build_const_value(
+        &mut self,
+        ctx: &mut HIRContext,
+        value: ConstValue,
+        span: Span,
+    ) -> Value {
+        match value {
+            ConstValue::Number(kind) => Value::NumberLiteral(kind),
+            ConstValue::Bool(value) => Value::BoolLiteral(value),
+            ConstValue::String { constant_id, len } => {
+                self.build_string_from_constant(ctx, constant_id, len, span)
+            }
+        }
+    }
 }
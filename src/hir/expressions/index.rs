@@ -1,13 +1,16 @@
 use crate::{
-    ast::{expr::Expr, IdentifierNode, Span},
+    ast::{
+        expr::{Expr, ExprKind},
+        IdentifierNode, Span,
+    },
     hir::{
-        cfg::{BinaryOperationKind, Terminator, Value},
+        cfg::{BinaryOperationKind, Terminator, Value, ValueId},
         errors::{SemanticError, SemanticErrorKind},
         types::{
             checked_declaration::TagType,
             checked_type::{StructKind, Type},
         },
-        utils::check_is_assignable::check_is_assignable,
+        utils::{check_is_assignable::check_is_assignable, const_eval::int_value},
         FunctionBuilder, HIRContext,
     },
     tokenize::NumberKind,
@@ -20,13 +23,21 @@ impl FunctionBuilder {
         left: Box<Expr>,
         index: Box<Expr>,
     ) -> Value {
+        let index = match *index {
+            Expr {
+                kind: ExprKind::Range { start, end },
+                span,
+            } => return self.build_slice_expr(ctx, *left, *start, *end, span),
+            index => index,
+        };
+
         let left_span = left.span;
         let index_span = index.span;
 
         let list_val = self.build_expr(ctx, *left);
         let list_type = ctx.program_builder.get_value_type(&list_val);
 
-        let index_val = self.build_expr(ctx, *index);
+        let index_val = self.build_expr(ctx, index);
         let index_type = ctx.program_builder.get_value_type(&index_val);
 
         // TODO: maybe allow smaller unsigned integers?
@@ -43,8 +54,32 @@ impl FunctionBuilder {
             ));
         }
 
-        let element_type = match list_type {
-            Type::Struct(StructKind::List(inner)) => *inner,
+        // Lists are held by pointer, arrays and slices by value
+        let (list_ptr_id, element_type) = match (&list_val, &list_type) {
+            (_, Type::Unknown) => {
+                return Value::Use(self.alloc_value(ctx, Type::Unknown))
+            }
+            (_, Type::Array { .. }) => {
+                return self.build_array_index(ctx, list_val, index_val, index_span)
+            }
+            (_, Type::Struct(StructKind::Slice(inner))) => {
+                let element_type = (**inner).clone();
+                let slice_ptr = self.emit_stack_alloc(ctx, list_type.clone(), 1);
+                self.emit_store(ctx, slice_ptr, list_val.clone(), left_span);
+                (slice_ptr, element_type)
+            }
+            (Value::Use(id), Type::Pointer { narrowed_to, .. }) => match &**narrowed_to {
+                Type::Struct(StructKind::List(inner)) => (*id, (**inner).clone()),
+                _ => {
+                    return Value::Use(self.report_error_and_get_poison(
+                        ctx,
+                        SemanticError {
+                            kind: SemanticErrorKind::CannotIndex(list_type),
+                            span: left_span,
+                        },
+                    ));
+                }
+            },
             _ => {
                 return Value::Use(self.report_error_and_get_poison(
                     ctx,
@@ -75,15 +110,10 @@ impl FunctionBuilder {
         };
 
         let mut variants = vec![none_variant.clone(), some_variant.clone()];
-        variants.sort_by(|a, b| a.id.0.cmp(&b.id.0));
+        variants.sort_by_key(|a| a.id.0);
 
         let result_union_type = Type::Struct(StructKind::Union { variants });
 
-        let list_ptr_id = match list_val {
-            Value::Use(id) => id,
-            _ => panic!("INTERNAL ERROR: List value should be a pointer"),
-        };
-
         let len_field_id = IdentifierNode {
             name: ctx.program_builder.common_identifiers.len,
             span: left_span,
@@ -162,13 +192,12 @@ impl FunctionBuilder {
         let some_val_ptr = self.emit_get_field_ptr(ctx, some_ptr, val_field).unwrap();
         self.emit_store(ctx, some_val_ptr, element_val, Span::default());
 
+        // Tags are assignable to the union, the merge block param widens them
         let some_val = Value::Use(self.emit_load(ctx, some_ptr));
-        let cast_some =
-            self.emit_type_cast(ctx, some_val, left_span, result_union_type.clone());
 
         self.set_basic_block_terminator(Terminator::Jump {
             target: merge_block,
-            args: vec![Value::Use(cast_some)],
+            args: vec![some_val],
         });
 
         self.seal_block(ctx, fail_block);
@@ -186,12 +215,10 @@ impl FunctionBuilder {
         );
 
         let none_val = Value::Use(self.emit_load(ctx, none_ptr));
-        let cast_none =
-            self.emit_type_cast(ctx, none_val, left_span, result_union_type.clone());
 
         self.set_basic_block_terminator(Terminator::Jump {
             target: merge_block,
-            args: vec![Value::Use(cast_none)],
+            args: vec![none_val],
         });
 
         self.seal_block(ctx, merge_block);
@@ -199,4 +226,107 @@ impl FunctionBuilder {
 
         Value::Use(result_param)
     }
+
+    /// Arrays are indexed by value, an index out of bounds aborts the program
+    fn build_array_index(
+        &mut self,
+        ctx: &mut HIRContext,
+        array_value: Value,
+        index_value: Value,
+        index_span: Span,
+    ) -> Value {
+        let array_type = ctx.program_builder.get_value_type(&array_value);
+        let array_ptr = self.emit_stack_alloc(ctx, array_type, 1);
+        self.emit_store(ctx, array_ptr, array_value, index_span);
+
+        match self.build_array_item_ptr(ctx, array_ptr, index_value, index_span) {
+            Ok(item_ptr) => Value::Use(self.emit_load(ctx, item_ptr)),
+            Err(e) => Value::Use(self.report_error_and_get_poison(ctx, e)),
+        }
+    }
+
+    /// The pointer to an item of the array `array_ptr` points to. Constant indices
+    /// are checked against the size of the array here, other indices abort the
+    /// program when they are out of bounds.
+    pub fn build_array_item_ptr(
+        &mut self,
+        ctx: &mut HIRContext,
+        array_ptr: ValueId,
+        index_value: Value,
+        index_span: Span,
+    ) -> Result<ValueId, SemanticError> {
+        let size = match ctx.program_builder.get_value_id_type(&array_ptr) {
+            Type::Pointer { constraint, .. } => match *constraint {
+                Type::Array { size, .. } => size,
+                other => panic!(
+                    "INTERNAL COMPILER ERROR: Expected a pointer to an array, found {:?}",
+                    other
+                ),
+            },
+            other => panic!(
+                "INTERNAL COMPILER ERROR: Expected a pointer to an array, found {:?}",
+                other
+            ),
+        };
+
+        if let Value::NumberLiteral(kind) = &index_value {
+            let index = int_value(kind).unwrap_or_default();
+            if index >= size as i128 {
+                return Err(SemanticError {
+                    kind: SemanticErrorKind::IndexOutOfBounds {
+                        index: index as usize,
+                        size,
+                    },
+                    span: index_span,
+                });
+            }
+
+            let items_ptr = self.emit_array_items_ptr(ctx, array_ptr);
+            return self.emit_get_element_ptr(ctx, items_ptr, index_value);
+        }
+
+        let in_bounds = self.emit_binary_op(
+            ctx,
+            BinaryOperationKind::LessThan,
+            index_value.clone(),
+            index_span,
+            Value::NumberLiteral(NumberKind::USize(size)),
+            index_span,
+        )?;
+
+        self.build_abort_unless(ctx, Value::Use(in_bounds));
+        let in_bounds_block = self.current_block_id;
+
+        let array_ptr = self.use_value_in_block(ctx, in_bounds_block, array_ptr);
+        let items_ptr = self.emit_array_items_ptr(ctx, array_ptr);
+        let index_value = match index_value {
+            Value::Use(id) => {
+                Value::Use(self.use_value_in_block(ctx, in_bounds_block, id))
+            }
+            literal => literal,
+        };
+
+        self.emit_get_element_ptr(ctx, items_ptr, index_value)
+    }
+
+    /// Continues in a new block when `condition` holds, the program aborts otherwise
+    pub fn build_abort_unless(&mut self, ctx: &mut HIRContext, condition: Value) {
+        let continue_block = self.new_basic_block();
+        let abort_block = self.new_basic_block();
+
+        self.set_basic_block_terminator(Terminator::CondJump {
+            condition,
+            true_target: continue_block,
+            true_args: vec![],
+            false_target: abort_block,
+            false_args: vec![],
+        });
+
+        self.seal_block(ctx, abort_block);
+        self.use_basic_block(abort_block);
+        self.set_basic_block_terminator(Terminator::Unreachable);
+
+        self.seal_block(ctx, continue_block);
+        self.use_basic_block(continue_block);
+    }
 }
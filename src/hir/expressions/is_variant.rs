@@ -19,13 +19,17 @@ impl FunctionBuilder {
     ) -> Value {
         let left_span = left.span;
 
-        let union_val = self.build_expr(ctx, *left.clone());
-        let source_id = match union_val {
-            Value::Use(id) => id,
-            _ => {
-                let ty = ctx.program_builder.get_value_type(&union_val);
-                self.emit_type_cast(ctx, union_val, Span::default(), ty)
-            }
+        // Variables and their fields are read in place, the subject is evaluated once
+        let place_ptr = self.build_place_ptr(ctx, &left).map(|(ptr, _)| ptr);
+        let source_id = match place_ptr {
+            Some(ptr) => self.emit_load(ctx, ptr),
+            None => match self.build_expr(ctx, *left) {
+                Value::Use(id) => id,
+                union_val => {
+                    let ty = ctx.program_builder.get_value_type(&union_val);
+                    self.emit_type_cast(ctx, union_val, Span::default(), ty)
+                }
+            },
         };
         let source_ty = ctx.program_builder.get_value_id_type(&source_id);
 
@@ -47,9 +51,9 @@ impl FunctionBuilder {
             ));
         }
 
-        let union_ptr = match self.build_lvalue_expr(ctx, *left) {
-            Ok((ptr, _)) => ptr,
-            Err(_) => {
+        let union_ptr = match place_ptr {
+            Some(ptr) => ptr,
+            None => {
                 let p = self.emit_stack_alloc(ctx, source_ty.clone(), 1);
                 self.emit_store(ctx, p, Value::Use(source_id), left_span);
                 p
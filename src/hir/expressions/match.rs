@@ -1,15 +1,487 @@
+use std::collections::HashSet;
+
 use crate::{
-    ast::expr::{Expr, MatchArm},
-    hir::{cfg::Value, FunctionBuilder, HIRContext},
+    ast::{
+        expr::{Expr, MatchArm, MatchPattern},
+        IdentifierNode, Span,
+    },
+    compile::interner::TagId,
+    hir::{
+        cfg::{BasicBlockId, BinaryOperationKind, Terminator, Value, ValueId},
+        errors::{SemanticError, SemanticErrorKind},
+        types::{
+            checked_declaration::{CheckedDeclaration, CheckedVarDecl, TagType},
+            checked_type::{StructKind, Type},
+        },
+        utils::{
+            scope::ScopeKind,
+            try_unify_types::{intersect_types, try_unify_types},
+        },
+        FunctionBuilder, HIRContext,
+    },
+    tokenize::NumberKind,
 };
 
+/// Upper bound on the amount of missing patterns listed in a single diagnostic
+const MAX_REPORTED_MISSING_PATTERNS: usize = 8;
+
+struct MatchSubject {
+    /// Pointer to the memory holding the union value
+    ptr: ValueId,
+    /// The ValueId which should be remapped to a narrowed pointer inside of arms
+    root: Option<ValueId>,
+    /// The variants of the subject's declared type, which patterns may name
+    declared_variants: Vec<TagType>,
+    /// The variants the subject can hold at this point, which arms have to cover
+    variants: Vec<TagType>,
+    /// The loaded `id` field of the union
+    tag_id: Value,
+}
+
 impl FunctionBuilder {
     pub fn build_match_expr(
         &mut self,
         ctx: &mut HIRContext,
         conditions: Vec<Expr>,
         arms: Vec<MatchArm>,
+        span: Span,
     ) -> Value {
-        todo!("Implement match expression builder")
+        let mut subjects: Vec<MatchSubject> = Vec::with_capacity(conditions.len());
+
+        for condition in conditions {
+            match self.build_match_subject(ctx, condition) {
+                Ok(Some(subject)) => subjects.push(subject),
+                // The error was already reported while building the subject
+                Ok(None) => return Value::Use(self.alloc_value(ctx, Type::Unknown)),
+                Err(e) => return Value::Use(self.report_error_and_get_poison(ctx, e)),
+            }
+        }
+
+        let merge_block_id = self.new_basic_block();
+        let mut branch_results: Vec<(BasicBlockId, Value, Span)> = Vec::new();
+        let mut covered_patterns: Vec<Vec<TagId>> = Vec::with_capacity(arms.len());
+        // Which variants an arm with the wrong number of patterns meant to cover is
+        // unknown, the match is not also reported as non-exhaustive then
+        let mut has_miscounted_arm = false;
+        let mut next_arm_block_id = self.current_block_id;
+
+        for arm in arms {
+            let arm_span = get_arm_span(&arm);
+
+            if arm.pattern.len() != subjects.len() {
+                ctx.module_builder.errors.push(SemanticError {
+                    kind: SemanticErrorKind::MatchPatternCountMismatch {
+                        expected: subjects.len(),
+                        received: arm.pattern.len(),
+                    },
+                    span: arm_span,
+                });
+                has_miscounted_arm = true;
+                continue;
+            }
+
+            let arm_tags = match self.resolve_arm_tags(ctx, &subjects, &arm.pattern) {
+                Ok(tags) => tags,
+                Err(e) => {
+                    ctx.module_builder.errors.push(e);
+                    continue;
+                }
+            };
+
+            let arm_tag_ids: Vec<TagId> = arm_tags.iter().map(|t| t.id).collect();
+
+            // The arm still covers its variants, only its bindings are invalid
+            if let Err(e) = check_pattern_values(&arm_tags, &arm.pattern) {
+                ctx.module_builder.errors.push(e);
+                if !covered_patterns.contains(&arm_tag_ids) {
+                    covered_patterns.push(arm_tag_ids);
+                }
+                continue;
+            }
+
+            if covered_patterns.contains(&arm_tag_ids) {
+                ctx.module_builder.errors.push(SemanticError {
+                    kind: SemanticErrorKind::UnreachableMatchArm,
+                    span: arm_span,
+                });
+                continue;
+            }
+            covered_patterns.push(arm_tag_ids);
+
+            let arm_block_id = self.new_basic_block();
+            let fail_block_id = self.new_basic_block();
+
+            self.use_basic_block(next_arm_block_id);
+
+            for (i, (subject, tag)) in subjects.iter().zip(arm_tags.iter()).enumerate() {
+                let is_last = i == subjects.len() - 1;
+                let pattern_span = get_pattern_identifier(&arm.pattern[i]).span;
+
+                let is_match = match self.emit_binary_op(
+                    ctx,
+                    BinaryOperationKind::Equal,
+                    subject.tag_id.clone(),
+                    pattern_span,
+                    Value::NumberLiteral(NumberKind::U16(tag.id.0)),
+                    pattern_span,
+                ) {
+                    Ok(id) => id,
+                    Err(e) => {
+                        return Value::Use(self.report_error_and_get_poison(ctx, e))
+                    }
+                };
+
+                let success_block_id = if is_last {
+                    arm_block_id
+                } else {
+                    self.new_basic_block()
+                };
+
+                self.set_basic_block_terminator(Terminator::CondJump {
+                    condition: Value::Use(is_match),
+                    true_target: success_block_id,
+                    true_args: vec![],
+                    false_target: fail_block_id,
+                    false_args: vec![],
+                });
+
+                self.seal_block(ctx, success_block_id);
+                self.use_basic_block(success_block_id);
+            }
+
+            if subjects.is_empty() {
+                self.set_basic_block_terminator(Terminator::Jump {
+                    target: arm_block_id,
+                    args: vec![],
+                });
+                self.seal_block(ctx, arm_block_id);
+            }
+
+            self.seal_block(ctx, fail_block_id);
+            next_arm_block_id = fail_block_id;
+
+            self.use_basic_block(arm_block_id);
+            ctx.module_builder.enter_scope(ScopeKind::CodeBlock);
+
+            for ((subject, tag), pattern) in
+                subjects.iter().zip(arm_tags.iter()).zip(arm.pattern.iter())
+            {
+                self.bind_match_pattern(ctx, subject, tag, pattern);
+            }
+
+            let body_span = arm.expression.span;
+            let body_value = self.build_expr(ctx, arm.expression);
+
+            ctx.module_builder.exit_scope();
+
+            if self.get_current_basic_block().terminator.is_none() {
+                branch_results.push((self.current_block_id, body_value, body_span));
+            }
+        }
+
+        let subject_variants: Vec<Vec<TagId>> = subjects
+            .iter()
+            .map(|s| s.variants.iter().map(|v| v.id).collect())
+            .collect();
+        let missing_patterns =
+            find_missing_patterns(&subject_variants, &covered_patterns);
+
+        if !missing_patterns.is_empty() && !has_miscounted_arm {
+            ctx.module_builder.errors.push(SemanticError {
+                kind: SemanticErrorKind::NonExhaustiveMatch { missing_patterns },
+                span,
+            });
+        }
+
+        self.use_basic_block(next_arm_block_id);
+        self.set_basic_block_terminator(Terminator::Unreachable);
+
+        let type_entries: Vec<(Type, Span)> = branch_results
+            .iter()
+            .map(|(_, val, span)| (ctx.program_builder.get_value_type(val), *span))
+            .collect();
+
+        let result_type = match try_unify_types(&type_entries) {
+            Ok(ty) => ty,
+            Err(e) => {
+                ctx.module_builder.errors.push(e);
+                Type::Unknown
+            }
+        };
+
+        let result_param_id = self.append_block_param(ctx, merge_block_id, result_type);
+
+        for (block_id, val, _) in branch_results {
+            self.use_basic_block(block_id);
+            self.set_basic_block_terminator(Terminator::Jump {
+                target: merge_block_id,
+                args: vec![val],
+            });
+        }
+
+        self.seal_block(ctx, merge_block_id);
+        self.use_basic_block(merge_block_id);
+
+        Value::Use(result_param_id)
+    }
+
+    /// Evaluates the subject once. Variables and their fields are matched in place so
+    /// arms can narrow them, other values are matched through a copy.
+    fn build_match_subject(
+        &mut self,
+        ctx: &mut HIRContext,
+        condition: Expr,
+    ) -> Result<Option<MatchSubject>, SemanticError> {
+        let condition_span = condition.span;
+
+        let (ptr, root) = match self.build_place_ptr(ctx, &condition) {
+            Some((ptr, root)) => (ptr, Some(root)),
+            None => {
+                let condition_val = self.build_expr(ctx, condition);
+                let condition_ty = ctx.program_builder.get_value_type(&condition_val);
+                let p = self.emit_stack_alloc(ctx, condition_ty, 1);
+                self.emit_store(ctx, p, condition_val, condition_span);
+                (p, None)
+            }
+        };
+
+        let Type::Pointer {
+            constraint,
+            narrowed_to,
+        } = ctx.program_builder.get_value_id_type(&ptr)
+        else {
+            panic!("INTERNAL COMPILER ERROR: Expected the match subject to be a pointer");
+        };
+
+        let variants = match union_variants(&narrowed_to) {
+            Some(variants) => variants,
+            None if *narrowed_to == Type::Unknown => return Ok(None),
+            None => {
+                return Err(SemanticError {
+                    kind: SemanticErrorKind::CannotMatchNonUnion(*narrowed_to),
+                    span: condition_span,
+                })
+            }
+        };
+        let declared_variants =
+            union_variants(&constraint).unwrap_or_else(|| variants.clone());
+
+        let id_field = IdentifierNode {
+            name: ctx.program_builder.common_identifiers.id,
+            span: condition_span,
+        };
+        let id_ptr = self.emit_get_field_ptr(ctx, ptr, id_field)?;
+        let tag_id = Value::Use(self.emit_load(ctx, id_ptr));
+
+        Ok(Some(MatchSubject {
+            ptr,
+            root,
+            declared_variants,
+            variants,
+            tag_id,
+        }))
+    }
+
+    /// The variants named by the patterns of an arm. A pattern may name any variant of
+    /// the subject's declared type, also one which it cannot hold at this point.
+    fn resolve_arm_tags(
+        &mut self,
+        ctx: &mut HIRContext,
+        subjects: &[MatchSubject],
+        patterns: &[MatchPattern],
+    ) -> Result<Vec<TagType>, SemanticError> {
+        let mut tags = Vec::with_capacity(patterns.len());
+
+        for (subject, pattern) in subjects.iter().zip(patterns.iter()) {
+            let variant = get_pattern_identifier(pattern);
+            let tag_id = ctx.program_builder.tag_interner.intern(&variant.name);
+
+            let tag = subject
+                .declared_variants
+                .iter()
+                .find(|v| v.id == tag_id)
+                .ok_or_else(|| {
+                    let subject_ty = Type::Struct(StructKind::Union {
+                        variants: subject.declared_variants.clone(),
+                    });
+
+                    SemanticError {
+                        kind: SemanticErrorKind::UndefinedMatchVariant {
+                            variant: *variant,
+                            subject: subject_ty,
+                        },
+                        span: variant.span,
+                    }
+                })?;
+
+            tags.push(tag.clone());
+        }
+
+        Ok(tags)
+    }
+
+    /// Narrows the subject to the matched variant within the current (arm) block and
+    /// declares the payload binding if the pattern has one
+    fn bind_match_pattern(
+        &mut self,
+        ctx: &mut HIRContext,
+        subject: &MatchSubject,
+        tag: &TagType,
+        pattern: &MatchPattern,
+    ) {
+        let variant = get_pattern_identifier(pattern);
+        // Narrowed from the declared type, the subject may have been narrowed to other
+        // variants already
+        let narrowed_ptr_ty = match ctx.program_builder.get_value_id_type(&subject.ptr) {
+            Type::Pointer { constraint, .. } => Type::Pointer {
+                narrowed_to: Box::new(intersect_types(&constraint, &[tag.id])),
+                constraint,
+            },
+            ptr_ty => intersect_types(&ptr_ty, &[tag.id]),
+        };
+        let narrowed_ptr = self.emit_type_cast(
+            ctx,
+            Value::Use(subject.ptr),
+            variant.span,
+            narrowed_ptr_ty,
+        );
+
+        if let Some(root) = subject.root {
+            self.map_value(self.current_block_id, root, narrowed_ptr);
+        }
+
+        let binding = match pattern {
+            MatchPattern::VariantWithValue(_, binding) => *binding,
+            MatchPattern::Variant(_) => return,
+        };
+
+        let value_field = IdentifierNode {
+            name: ctx.program_builder.common_identifiers.value,
+            span: binding.span,
+        };
+        let value_ptr = match self.emit_get_field_ptr(ctx, narrowed_ptr, value_field) {
+            Ok(ptr) => ptr,
+            Err(e) => {
+                ctx.module_builder.errors.push(e);
+                return;
+            }
+        };
+        let payload = self.emit_load(ctx, value_ptr);
+        let payload_ty = ctx.program_builder.get_value_id_type(&payload);
+
+        let stack_ptr = self.emit_stack_alloc(ctx, payload_ty.clone(), 1);
+        self.emit_store(ctx, stack_ptr, Value::Use(payload), binding.span);
+
+        let decl = CheckedVarDecl {
+            id: ctx.program_builder.new_declaration_id(),
+            ptr: stack_ptr,
+            identifier: binding,
+            documentation: None,
+            constraint: payload_ty,
+            is_mutable: false,
+        };
+
+        ctx.module_builder.scope_insert(
+            ctx.program_builder,
+            binding,
+            CheckedDeclaration::Var(decl),
+        );
+    }
+}
+
+/// The variants of a union or a single tag
+fn union_variants(ty: &Type) -> Option<Vec<TagType>> {
+    match ty {
+        Type::Struct(StructKind::Union { variants }) => Some(variants.clone()),
+        Type::Struct(StructKind::Tag(tag)) => Some(vec![tag.clone()]),
+        _ => None,
+    }
+}
+
+fn get_pattern_identifier(pattern: &MatchPattern) -> &IdentifierNode {
+    match pattern {
+        MatchPattern::VariantWithValue(variant, _) => variant,
+        MatchPattern::Variant(variant) => variant,
+    }
+}
+
+fn get_arm_span(arm: &MatchArm) -> Span {
+    let start = arm
+        .pattern
+        .first()
+        .map(|p| get_pattern_identifier(p).span.start)
+        .unwrap_or(arm.expression.span.start);
+
+    Span {
+        start,
+        end: arm.expression.span.end,
+    }
+}
+
+/// Patterns can only bind the value of variants which have one
+fn check_pattern_values(
+    tags: &[TagType],
+    patterns: &[MatchPattern],
+) -> Result<(), SemanticError> {
+    for (tag, pattern) in tags.iter().zip(patterns) {
+        if let MatchPattern::VariantWithValue(variant, _) = pattern {
+            if tag.value_type.is_none() {
+                return Err(SemanticError {
+                    kind: SemanticErrorKind::MatchVariantHasNoValue(*variant),
+                    span: variant.span,
+                });
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the combinations of variants (one per subject) which none of the arms cover
+fn find_missing_patterns(
+    subject_variants: &[Vec<TagId>],
+    covered_patterns: &[Vec<TagId>],
+) -> Vec<Vec<TagId>> {
+    let mut missing = Vec::new();
+    let rows: Vec<&[TagId]> = covered_patterns.iter().map(|p| p.as_slice()).collect();
+
+    collect_missing_patterns(subject_variants, &rows, &mut vec![], &mut missing);
+
+    missing
+}
+
+fn collect_missing_patterns(
+    columns: &[Vec<TagId>],
+    rows: &[&[TagId]],
+    prefix: &mut Vec<TagId>,
+    missing: &mut Vec<Vec<TagId>>,
+) {
+    if missing.len() >= MAX_REPORTED_MISSING_PATTERNS {
+        return;
+    }
+
+    let Some((column, rest)) = columns.split_first() else {
+        if rows.is_empty() {
+            missing.push(prefix.clone());
+        }
+        return;
+    };
+
+    let mut seen: HashSet<TagId> = HashSet::new();
+
+    for tag_id in column {
+        if !seen.insert(*tag_id) {
+            continue;
+        }
+
+        let matching_rows: Vec<&[TagId]> = rows
+            .iter()
+            .filter(|row| row.first() == Some(tag_id))
+            .map(|row| &row[1..])
+            .collect();
+
+        prefix.push(*tag_id);
+        collect_missing_patterns(rest, &matching_rows, prefix, missing);
+        prefix.pop();
     }
 }
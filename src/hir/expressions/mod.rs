@@ -1,5 +1,6 @@
 pub mod access;
 pub mod and;
+pub mod array_literal;
 pub mod binary_op;
 pub mod bool_literal;
 pub mod codeblock;
@@ -13,6 +14,7 @@ pub mod list_literal;
 pub mod r#match;
 pub mod number_literal;
 pub mod or;
+pub mod slice;
 pub mod static_access;
 pub mod string;
 pub mod struct_init;
@@ -24,13 +26,31 @@ use crate::{
     ast::expr::{Expr, ExprKind},
     hir::{
         cfg::{BinaryOperationKind, UnaryOperationKind, Value},
+        errors::{SemanticError, SemanticErrorKind},
         expressions::r#if::IfContext,
+        utils::const_eval::{eval_const_expr, is_const_expr},
         FunctionBuilder, HIRContext,
     },
 };
 
 impl FunctionBuilder {
     pub fn build_expr(&mut self, ctx: &mut HIRContext, expr: Expr) -> Value {
+        // Operators on constants are folded, so overflow and division by zero are
+        // reported here instead of at runtime
+        let is_literal = matches!(
+            expr.kind,
+            ExprKind::Number(_)
+                | ExprKind::BoolLiteral(_)
+                | ExprKind::String(_)
+                | ExprKind::Identifier(_)
+        );
+        if !is_literal && is_const_expr(ctx, &expr) {
+            return match eval_const_expr(ctx, &expr) {
+                Ok(value) => self.build_const_value(ctx, value, expr.span),
+                Err(error) => Value::Use(self.report_error_and_get_poison(ctx, error)),
+            };
+        }
+
         match expr.kind {
             ExprKind::IsVariant { left, variants } => {
                 self.build_is_variant_expr(ctx, left, variants)
@@ -41,6 +61,9 @@ impl FunctionBuilder {
             ExprKind::Neg { right } => {
                 self.build_unary_op_expr(ctx, UnaryOperationKind::Neg, right)
             }
+            ExprKind::BitNot { right } => {
+                self.build_unary_op_expr(ctx, UnaryOperationKind::BitNot, right)
+            }
             ExprKind::Add { left, right } => {
                 self.build_binary_op_expr(ctx, left, right, BinaryOperationKind::Add)
             }
@@ -85,6 +108,34 @@ impl FunctionBuilder {
             }
             ExprKind::And { left, right } => self.build_and_expr(ctx, left, right),
             ExprKind::Or { left, right } => self.build_or_expr(ctx, left, right),
+            ExprKind::BitAnd { left, right } => {
+                self.build_binary_op_expr(ctx, left, right, BinaryOperationKind::BitAnd)
+            }
+            ExprKind::BitOr { left, right } => {
+                self.build_binary_op_expr(ctx, left, right, BinaryOperationKind::BitOr)
+            }
+            ExprKind::BitXor { left, right } => {
+                self.build_binary_op_expr(ctx, left, right, BinaryOperationKind::BitXor)
+            }
+            ExprKind::ShiftLeft { left, right } => self.build_binary_op_expr(
+                ctx,
+                left,
+                right,
+                BinaryOperationKind::ShiftLeft,
+            ),
+            ExprKind::ShiftRight { left, right } => self.build_binary_op_expr(
+                ctx,
+                left,
+                right,
+                BinaryOperationKind::ShiftRight,
+            ),
+            ExprKind::Range { .. } => Value::Use(self.report_error_and_get_poison(
+                ctx,
+                SemanticError {
+                    kind: SemanticErrorKind::RangeOutsideForLoop,
+                    span: expr.span,
+                },
+            )),
             ExprKind::Access { left, field } => self.build_access_expr(ctx, left, field),
             ExprKind::StaticAccess { left, field } => {
                 self.build_static_access_expr(ctx, left, field)
@@ -101,17 +152,27 @@ impl FunctionBuilder {
             ExprKind::Identifier(identifier) => {
                 self.build_identifier_expr(ctx, identifier)
             }
-            ExprKind::Fn(decl) => FunctionBuilder::build(ctx, *decl),
+            ExprKind::Type(_) => Value::Use(self.report_error_and_get_poison(
+                ctx,
+                SemanticError {
+                    kind: SemanticErrorKind::CannotUseTypeDeclarationAsValue,
+                    span: expr.span,
+                },
+            )),
+            ExprKind::Fn(decl) => self.build_fn_expr(ctx, *decl),
             ExprKind::If {
                 branches,
                 else_branch,
             } => self.build_if(ctx, branches, else_branch, IfContext::Expression),
             ExprKind::List(items) => self.build_list_literal_expr(ctx, items, expr.span),
+            ExprKind::Array { value, size } => {
+                self.build_array_literal_expr(ctx, value, size, expr.span)
+            }
             ExprKind::CodeBlock(block_contents) => {
                 self.build_codeblock_expr(ctx, block_contents)
             }
             ExprKind::Match { conditions, arms } => {
-                self.build_match_expr(ctx, conditions, arms)
+                self.build_match_expr(ctx, conditions, arms, expr.span)
             }
             ExprKind::Struct(fields) => self.build_struct_init_expr(ctx, fields),
             ExprKind::Tag { name, value } => {
@@ -0,0 +1,208 @@
+use crate::{
+    ast::{expr::Expr, IdentifierNode, Span},
+    hir::{
+        cfg::{BinaryOperationKind, Value},
+        errors::{SemanticError, SemanticErrorKind},
+        types::checked_type::{StructKind, Type},
+        utils::{check_is_assignable::check_is_assignable, const_eval::int_value},
+        FunctionBuilder, HIRContext,
+    },
+    tokenize::NumberKind,
+};
+
+impl FunctionBuilder {
+    /// `items[start..end]`, a view over the items of a list, string, slice or array
+    /// which shares their buffer. A range outside of the items aborts the program.
+    pub fn build_slice_expr(
+        &mut self,
+        ctx: &mut HIRContext,
+        left: Expr,
+        start: Expr,
+        end: Expr,
+        range_span: Span,
+    ) -> Value {
+        let left_span = left.span;
+        let source_value = self.build_expr(ctx, left.clone());
+        let source_type = ctx.program_builder.get_value_type(&source_value);
+
+        // Lists and strings are held by pointer, slices and arrays by value. An array
+        // variable is sliced in place, so the slice views its items instead of a copy.
+        let mut array_size = None;
+        let (source_ptr, item_type) = match (&source_value, &source_type) {
+            (_, Type::Unknown) => {
+                return Value::Use(self.alloc_value(ctx, Type::Unknown))
+            }
+            (_, Type::Array { item_type, size }) => {
+                array_size = Some(*size);
+                let item_type = (**item_type).clone();
+                let array_ptr = match self.build_place_ptr(ctx, &left) {
+                    Some((place_ptr, _)) => place_ptr,
+                    None => {
+                        let array_ptr =
+                            self.emit_stack_alloc(ctx, source_type.clone(), 1);
+                        self.emit_store(ctx, array_ptr, source_value.clone(), left_span);
+                        array_ptr
+                    }
+                };
+                (array_ptr, item_type)
+            }
+            (Value::Use(id), Type::Pointer { narrowed_to, .. }) => match &**narrowed_to {
+                Type::Struct(StructKind::List(item_type)) => (*id, (**item_type).clone()),
+                Type::Struct(StructKind::String) => (*id, Type::U8),
+                _ => {
+                    return Value::Use(self.report_error_and_get_poison(
+                        ctx,
+                        SemanticError {
+                            kind: SemanticErrorKind::CannotIndex(source_type),
+                            span: left_span,
+                        },
+                    ));
+                }
+            },
+            (_, Type::Struct(StructKind::Slice(item_type))) => {
+                let item_type = (**item_type).clone();
+                let slice_ptr = self.emit_stack_alloc(ctx, source_type.clone(), 1);
+                self.emit_store(ctx, slice_ptr, source_value.clone(), left_span);
+                (slice_ptr, item_type)
+            }
+            _ => {
+                return Value::Use(self.report_error_and_get_poison(
+                    ctx,
+                    SemanticError {
+                        kind: SemanticErrorKind::CannotIndex(source_type),
+                        span: left_span,
+                    },
+                ));
+            }
+        };
+
+        let start_span = start.span;
+        let end_span = end.span;
+        let (Some(start), Some(end)) = (
+            self.build_slice_bound(ctx, start),
+            self.build_slice_bound(ctx, end),
+        ) else {
+            return Value::Use(self.alloc_value(ctx, Type::Unknown));
+        };
+
+        if let (Value::NumberLiteral(start_kind), Value::NumberLiteral(end_kind)) =
+            (&start, &end)
+        {
+            let start = int_value(start_kind).unwrap_or_default() as usize;
+            let end = int_value(end_kind).unwrap_or_default() as usize;
+            if start > end {
+                return Value::Use(self.report_error_and_get_poison(
+                    ctx,
+                    SemanticError {
+                        kind: SemanticErrorKind::InvalidSliceRange { start, end },
+                        span: range_span,
+                    },
+                ));
+            }
+        }
+
+        let len_field = IdentifierNode {
+            name: ctx.program_builder.common_identifiers.len,
+            span: left_span,
+        };
+        let ptr_field = IdentifierNode {
+            name: ctx.program_builder.common_identifiers.ptr,
+            span: left_span,
+        };
+
+        let (len, buffer_ptr) = match array_size {
+            Some(size) => (
+                Value::NumberLiteral(NumberKind::USize(size)),
+                self.emit_array_items_ptr(ctx, source_ptr),
+            ),
+            None => {
+                let len_ptr = self.emit_get_field_ptr(ctx, source_ptr, len_field).expect(
+                    "INTERNAL COMPILER ERROR: Expected sliced value to have a len field",
+                );
+                let len = Value::Use(self.emit_load(ctx, len_ptr));
+                let buffer_ptr_ptr = self
+                    .emit_get_field_ptr(ctx, source_ptr, ptr_field)
+                    .expect(
+                    "INTERNAL COMPILER ERROR: Expected sliced value to have a ptr field",
+                );
+                (len, self.emit_load(ctx, buffer_ptr_ptr))
+            }
+        };
+
+        let start_before_end = self
+            .emit_binary_op(
+                ctx,
+                BinaryOperationKind::LessThanOrEqual,
+                start.clone(),
+                start_span,
+                end.clone(),
+                end_span,
+            )
+            .expect("INTERNAL COMPILER ERROR: Expected usize slice bounds");
+        let end_within_len = self
+            .emit_binary_op(
+                ctx,
+                BinaryOperationKind::LessThanOrEqual,
+                end.clone(),
+                end_span,
+                len,
+                left_span,
+            )
+            .expect("INTERNAL COMPILER ERROR: Expected usize slice bounds");
+
+        let items_ptr = self
+            .emit_get_element_ptr(ctx, buffer_ptr, start.clone())
+            .expect("INTERNAL COMPILER ERROR: Expected usize slice bounds");
+        let slice_len = self
+            .emit_binary_op(
+                ctx,
+                BinaryOperationKind::Subtract,
+                end,
+                end_span,
+                start,
+                start_span,
+            )
+            .expect("INTERNAL COMPILER ERROR: Expected usize slice bounds");
+
+        let slice_type = Type::Struct(StructKind::Slice(Box::new(item_type)));
+        let slice_ptr = self.emit_stack_alloc(ctx, slice_type, 1);
+        let slice_len_ptr = self
+            .emit_get_field_ptr(ctx, slice_ptr, len_field)
+            .expect("INTERNAL COMPILER ERROR: Expected slice to have a len field");
+        self.emit_store(ctx, slice_len_ptr, Value::Use(slice_len), range_span);
+        let slice_items_ptr = self
+            .emit_get_field_ptr(ctx, slice_ptr, ptr_field)
+            .expect("INTERNAL COMPILER ERROR: Expected slice to have a ptr field");
+        self.emit_store(ctx, slice_items_ptr, Value::Use(items_ptr), range_span);
+        let slice_value = self.emit_load(ctx, slice_ptr);
+
+        self.build_abort_unless(ctx, Value::Use(start_before_end));
+        self.build_abort_unless(ctx, Value::Use(end_within_len));
+
+        Value::Use(self.use_value_in_block(ctx, self.current_block_id, slice_value))
+    }
+
+    /// A bound of a slice range, `None` after reporting a bound which is not a usize
+    fn build_slice_bound(&mut self, ctx: &mut HIRContext, bound: Expr) -> Option<Value> {
+        let bound_span = bound.span;
+        let bound_value = self.build_expr(ctx, bound);
+        let bound_type = ctx.program_builder.get_value_type(&bound_value);
+
+        if bound_type == Type::Unknown {
+            return None;
+        }
+
+        if !check_is_assignable(&bound_type, &Type::USize) {
+            ctx.module_builder.errors.push(SemanticError {
+                kind: SemanticErrorKind::TypeMismatch {
+                    expected: Type::USize,
+                    received: bound_type,
+                },
+                span: bound_span,
+            });
+            return None;
+        }
+
+        Some(bound_value)
+    }
+}
@@ -1,8 +1,46 @@
+use std::path::Path;
+
 use crate::{
-    ast::{expr::Expr, IdentifierNode},
-    hir::{cfg::Value, FunctionBuilder, HIRContext},
+    ast::{
+        expr::{Expr, ExprKind},
+        DeclarationId, IdentifierNode, Span,
+    },
+    hir::{
+        cfg::{Value, ValueId},
+        errors::{SemanticError, SemanticErrorKind},
+        types::{
+            checked_declaration::CheckedDeclaration,
+            checked_type::{StructKind, Type},
+        },
+        utils::{
+            check_is_assignable::check_is_assignable,
+            check_type::{check_type_annotation, check_type_identifier_annotation},
+        },
+        FunctionBuilder, HIRContext, SymbolReference,
+    },
+    tokenize::NumberKind,
 };
 
+/// What a static access refers to
+pub enum StaticItem {
+    /// An item exported by a module imported under a name, or a function of an impl
+    /// block of a type
+    Declaration(DeclarationId),
+    Builtin(BuiltinStaticFn),
+}
+
+/// Static functions of the built-in types, lowered to plain instructions at the call
+pub enum BuiltinStaticFn {
+    /// `T[]::with_capacity(capacity: usize): T[]`, an empty list with room for
+    /// `capacity` items
+    ListWithCapacity(Type),
+    /// `T[]::from_array(items: [T; N]): T[]`, a list with a copy of the items
+    ListFromArray(Type),
+    /// `string::from_bytes(bytes: u8[]): string`, a string sharing the buffer of the
+    /// list
+    StringFromBytes,
+}
+
 impl FunctionBuilder {
     pub fn build_static_access_expr(
         &mut self,
@@ -10,6 +48,352 @@ impl FunctionBuilder {
         left: Box<Expr>,
         field: IdentifierNode,
     ) -> Value {
-        todo!("Implement static access expression builder")
+        match self.resolve_static_access(ctx, *left, field) {
+            Ok(Some(StaticItem::Declaration(decl_id))) => {
+                self.build_declaration_value(ctx, decl_id, field.span)
+            }
+            Ok(Some(StaticItem::Builtin(_))) => {
+                Value::Use(self.report_error_and_get_poison(
+                    ctx,
+                    SemanticError {
+                        kind: SemanticErrorKind::CannotUseBuiltinFnAsValue,
+                        span: field.span,
+                    },
+                ))
+            }
+            // The left side was reported already
+            Ok(None) => Value::Use(self.alloc_value(ctx, Type::Unknown)),
+            Err(e) => Value::Use(self.report_error_and_get_poison(ctx, e)),
+        }
+    }
+
+    /// Resolves `left::field`, where `left` names either a module or a type. Returns
+    /// `None` when the type on the left is invalid, its error is reported already.
+    pub fn resolve_static_access(
+        &mut self,
+        ctx: &mut HIRContext,
+        left: Expr,
+        field: IdentifierNode,
+    ) -> Result<Option<StaticItem>, SemanticError> {
+        let left_span = left.span;
+
+        let ty = match left.kind {
+            ExprKind::Identifier(identifier) => {
+                let decl_id = ctx.module_builder.scope_lookup(identifier.name);
+                match decl_id.map(|id| ctx.program_builder.get_declaration(id)) {
+                    Some(CheckedDeclaration::Module(module)) => {
+                        let path = module.path.clone();
+                        ctx.module_builder.resolve_identifier(identifier);
+                        return resolve_module_item(ctx, &path, field).map(Some);
+                    }
+                    Some(CheckedDeclaration::TypeAlias(_)) | None => {
+                        check_type_identifier_annotation(ctx, identifier, left_span)?
+                    }
+                    Some(_) => {
+                        let value = self.build_identifier_expr(ctx, identifier);
+                        ctx.program_builder.get_value_type(&value)
+                    }
+                }
+            }
+            ExprKind::Type(annotation) => {
+                let ty = check_type_annotation(ctx, &annotation);
+                if ty == Type::Unknown {
+                    return Ok(None);
+                }
+                ty
+            }
+            _ => {
+                let value = self.build_expr(ctx, left);
+                ctx.program_builder.get_value_type(&value)
+            }
+        };
+
+        if let Some(method) = ctx.program_builder.find_method(&ty, field.name) {
+            ctx.module_builder.references.push(SymbolReference {
+                span: field.span,
+                decl_id: method.decl_id,
+            });
+            return Ok(Some(StaticItem::Declaration(method.decl_id)));
+        }
+
+        let name = ctx.program_builder.string_interner.resolve(field.name);
+        let pointee = match &ty {
+            Type::Pointer { narrowed_to, .. } => Some(&**narrowed_to),
+            _ => None,
+        };
+
+        let builtin = match pointee {
+            Some(Type::Struct(StructKind::List(item_type))) => match name.as_str() {
+                "with_capacity" => {
+                    Some(BuiltinStaticFn::ListWithCapacity((**item_type).clone()))
+                }
+                "from_array" => {
+                    Some(BuiltinStaticFn::ListFromArray((**item_type).clone()))
+                }
+                _ => None,
+            },
+            Some(Type::Struct(StructKind::String)) => {
+                (name == "from_bytes").then_some(BuiltinStaticFn::StringFromBytes)
+            }
+            _ if ctx.program_builder.methods.contains_key(&ty) => None,
+            _ => {
+                return Err(SemanticError {
+                    kind: SemanticErrorKind::CannotStaticAccess(ty),
+                    span: left_span,
+                })
+            }
+        };
+
+        builtin
+            .map(|f| Some(StaticItem::Builtin(f)))
+            .ok_or(SemanticError {
+                kind: SemanticErrorKind::AccessToUndefinedStaticField(field),
+                span: field.span,
+            })
+    }
+
+    /// Calls a built-in static function, the arguments are checked here since there
+    /// is no declaration to check them against
+    pub fn build_builtin_static_call(
+        &mut self,
+        ctx: &mut HIRContext,
+        builtin: BuiltinStaticFn,
+        args: Vec<Expr>,
+        span: Span,
+    ) -> Value {
+        let mut arg_values: Vec<Value> = args
+            .into_iter()
+            .map(|arg_expr| self.build_expr(ctx, arg_expr))
+            .collect();
+
+        if arg_values.len() != 1 {
+            return Value::Use(self.report_error_and_get_poison(
+                ctx,
+                SemanticError {
+                    kind: SemanticErrorKind::FnArgumentCountMismatch {
+                        expected: 1,
+                        received: arg_values.len(),
+                    },
+                    span,
+                },
+            ));
+        }
+
+        let arg = arg_values.remove(0);
+        let arg_type = ctx.program_builder.get_value_type(&arg);
+        if arg_type == Type::Unknown {
+            return Value::Use(self.alloc_value(ctx, Type::Unknown));
+        }
+
+        let expected_arg_type = match &builtin {
+            BuiltinStaticFn::ListWithCapacity(_) => Type::USize,
+            BuiltinStaticFn::ListFromArray(item_type) => Type::Array {
+                item_type: Box::new(item_type.clone()),
+                // Arrays of any size are accepted
+                size: match arg_type {
+                    Type::Array { size, .. } => size,
+                    _ => 0,
+                },
+            },
+            BuiltinStaticFn::StringFromBytes => {
+                let bytes = Box::new(Type::Struct(StructKind::List(Box::new(Type::U8))));
+                Type::Pointer {
+                    constraint: bytes.clone(),
+                    narrowed_to: bytes,
+                }
+            }
+        };
+        if !check_is_assignable(&arg_type, &expected_arg_type) {
+            return Value::Use(self.report_error_and_get_poison(
+                ctx,
+                SemanticError {
+                    kind: SemanticErrorKind::TypeMismatch {
+                        expected: expected_arg_type,
+                        received: arg_type,
+                    },
+                    span,
+                },
+            ));
+        }
+
+        match builtin {
+            BuiltinStaticFn::ListWithCapacity(item_type) => {
+                self.emit_list_with_capacity(ctx, item_type, arg, span)
+            }
+            BuiltinStaticFn::ListFromArray(item_type) => {
+                self.emit_list_from_array(ctx, item_type, arg, span)
+            }
+            BuiltinStaticFn::StringFromBytes => {
+                let Value::Use(bytes_ptr) = arg else {
+                    panic!("INTERNAL COMPILER ERROR: Expected list value to be a pointer")
+                };
+                self.emit_string_from_bytes(ctx, bytes_ptr, span)
+            }
+        }
+    }
+
+    fn emit_list_with_capacity(
+        &mut self,
+        ctx: &mut HIRContext,
+        item_type: Type,
+        capacity: Value,
+        span: Span,
+    ) -> Value {
+        let buffer_ptr = self
+            .emit_heap_alloc(ctx, item_type.clone(), capacity.clone())
+            .expect("INTERNAL COMPILER ERROR: Failed to allocate list buffer");
+
+        self.emit_list_header(
+            ctx,
+            item_type,
+            capacity,
+            Value::NumberLiteral(NumberKind::USize(0)),
+            buffer_ptr,
+            span,
+        )
+    }
+
+    fn emit_list_from_array(
+        &mut self,
+        ctx: &mut HIRContext,
+        item_type: Type,
+        array: Value,
+        span: Span,
+    ) -> Value {
+        let array_type = ctx.program_builder.get_value_type(&array);
+        let Type::Array { size, .. } = array_type else {
+            panic!(
+                "INTERNAL COMPILER ERROR: Expected an array, found {:?}",
+                array_type
+            )
+        };
+
+        // The items are copied into the buffer all at once
+        let array_ptr = self
+            .emit_heap_alloc(ctx, array_type, Value::NumberLiteral(NumberKind::USize(1)))
+            .expect("INTERNAL COMPILER ERROR: Failed to allocate list buffer");
+        self.emit_store(ctx, array_ptr, array, span);
+        let buffer_ptr = self.emit_array_items_ptr(ctx, array_ptr);
+
+        let len = Value::NumberLiteral(NumberKind::USize(size));
+        self.emit_list_header(ctx, item_type, len.clone(), len, buffer_ptr, span)
+    }
+
+    fn emit_list_header(
+        &mut self,
+        ctx: &mut HIRContext,
+        item_type: Type,
+        capacity: Value,
+        len: Value,
+        buffer_ptr: ValueId,
+        span: Span,
+    ) -> Value {
+        let list_type = Type::Struct(StructKind::List(Box::new(item_type)));
+        let header_ptr = self
+            .emit_heap_alloc(ctx, list_type, Value::NumberLiteral(NumberKind::USize(1)))
+            .expect("INTERNAL COMPILER ERROR: Failed to allocate list header");
+
+        let fields = [
+            (ctx.program_builder.common_identifiers.capacity, capacity),
+            (ctx.program_builder.common_identifiers.len, len),
+            (
+                ctx.program_builder.common_identifiers.ptr,
+                Value::Use(buffer_ptr),
+            ),
+        ];
+
+        for (name, value) in fields {
+            let field_ptr = self
+                .emit_get_field_ptr(ctx, header_ptr, IdentifierNode { name, span })
+                .expect("INTERNAL COMPILER ERROR: Failed to set field on List");
+            self.emit_store(ctx, field_ptr, value, span);
+        }
+
+        Value::Use(header_ptr)
+    }
+
+    fn emit_string_from_bytes(
+        &mut self,
+        ctx: &mut HIRContext,
+        bytes_ptr: ValueId,
+        span: Span,
+    ) -> Value {
+        let len_id = IdentifierNode {
+            name: ctx.program_builder.common_identifiers.len,
+            span,
+        };
+        let ptr_id = IdentifierNode {
+            name: ctx.program_builder.common_identifiers.ptr,
+            span,
+        };
+        let is_heap_id = IdentifierNode {
+            name: ctx.program_builder.common_identifiers.is_heap_allocated,
+            span,
+        };
+
+        let len_ptr = self
+            .emit_get_field_ptr(ctx, bytes_ptr, len_id)
+            .expect("INTERNAL COMPILER ERROR: Failed to read len of List");
+        let len = self.emit_load(ctx, len_ptr);
+        let data_ptr = self
+            .emit_get_field_ptr(ctx, bytes_ptr, ptr_id)
+            .expect("INTERNAL COMPILER ERROR: Failed to read ptr of List");
+        let data = self.emit_load(ctx, data_ptr);
+
+        let header_ptr = self
+            .emit_heap_alloc(
+                ctx,
+                Type::Struct(StructKind::String),
+                Value::NumberLiteral(NumberKind::USize(1)),
+            )
+            .expect("INTERNAL COMPILER ERROR: Failed to allocate string header");
+
+        let fields = [
+            (is_heap_id, Value::BoolLiteral(true)),
+            (len_id, Value::Use(len)),
+            (ptr_id, Value::Use(data)),
+        ];
+
+        for (field, value) in fields {
+            let field_ptr = self
+                .emit_get_field_ptr(ctx, header_ptr, field)
+                .expect("INTERNAL COMPILER ERROR: Failed to set field on string");
+            self.emit_store(ctx, field_ptr, value, span);
+        }
+
+        Value::Use(header_ptr)
+    }
+}
+
+/// Looks up an item exported by the module at `path`
+fn resolve_module_item(
+    ctx: &mut HIRContext,
+    path: &Path,
+    field: IdentifierNode,
+) -> Result<StaticItem, SemanticError> {
+    let decl_id = if ctx.module_builder.module.path == path {
+        ctx.module_builder.resolve_export(field.name)
+    } else {
+        ctx.program_builder
+            .modules
+            .get(path)
+            .and_then(|module| module.resolve_export(field.name))
+    };
+
+    match decl_id {
+        Some(decl_id) => {
+            ctx.module_builder.references.push(SymbolReference {
+                span: field.span,
+                decl_id,
+            });
+            Ok(StaticItem::Declaration(decl_id))
+        }
+        None => Err(SemanticError {
+            kind: SemanticErrorKind::SymbolNotExported {
+                module_path: path.to_path_buf(),
+                symbol: field,
+            },
+            span: field.span,
+        }),
     }
 }
@@ -1,7 +1,7 @@
 use crate::{
     ast::{IdentifierNode, Span, StringNode},
     hir::{
-        cfg::Value,
+        cfg::{ConstantId, Value},
         types::checked_type::{StructKind, Type},
         FunctionBuilder, HIRContext,
     },
@@ -19,6 +19,17 @@ impl FunctionBuilder {
             .constant_data
             .insert(constant_id, node.value.as_bytes().to_vec());
 
+        self.build_string_from_constant(ctx, constant_id, node.len, node.span)
+    }
+
+    /// A string header pointing at the bytes of a constant
+    pub fn build_string_from_constant(
+        &mut self,
+        ctx: &mut HIRContext,
+        constant_id: ConstantId,
+        len: usize,
+        span: Span,
+    ) -> Value {
         let string_header_type = Type::Struct(StructKind::String);
 
         let struct_ptr = self
@@ -31,7 +42,7 @@ impl FunctionBuilder {
 
         let is_heap_id = IdentifierNode {
             name: ctx.program_builder.common_identifiers.is_heap_allocated,
-            span: node.span,
+            span,
         };
         let is_heap_ptr = self
             .emit_get_field_ptr(ctx, struct_ptr, is_heap_id)
@@ -41,19 +52,19 @@ impl FunctionBuilder {
 
         let len_id = IdentifierNode {
             name: ctx.program_builder.common_identifiers.len,
-            span: node.span,
+            span,
         };
         let len_ptr = self.emit_get_field_ptr(ctx, struct_ptr, len_id).unwrap();
         self.emit_store(
             ctx,
             len_ptr,
-            Value::NumberLiteral(NumberKind::USize(node.len)),
+            Value::NumberLiteral(NumberKind::USize(len)),
             Span::default(),
         );
 
         let ptr_id = IdentifierNode {
             name: ctx.program_builder.common_identifiers.ptr,
-            span: node.span,
+            span,
         };
         let data_ptr_field = self.emit_get_field_ptr(ctx, struct_ptr, ptr_id).unwrap();
 
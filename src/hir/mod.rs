@@ -8,7 +8,11 @@ use std::{
 };
 
 use crate::{
-    ast::{decl::Declaration, stmt::StmtKind, DeclarationId},
+    ast::{
+        decl::{Declaration, FnDecl},
+        stmt::{Stmt, StmtKind},
+        DeclarationId, Span,
+    },
     compile::{
         interner::{SharedStringInterner, SharedTagInterner, StringId},
         ParallelParseResult,
@@ -18,13 +22,19 @@ use crate::{
             BasicBlockId, CheckedModule, ConstantId, ControlFlowGraph, Value, ValueId,
         },
         errors::SemanticError,
-        statements::{from::build_from_stmt, type_alias_decl::build_type_alias_decl},
+        statements::{
+            const_decl::FileConstants,
+            from::{build_from_stmt, import_path},
+            impl_decl::build_impl_decl,
+            type_alias_decl::build_type_alias_decl,
+        },
         types::{
             checked_declaration::{CheckedDeclaration, CheckedFnDecl, FnType},
             checked_type::Type,
         },
         utils::{
             check_type::{check_params, check_type_annotation},
+            generics::{declare_generic_fn, with_generic_scope},
             scope::{Scope, ScopeKind},
         },
     },
@@ -34,6 +44,8 @@ use crate::{
 pub mod cfg;
 pub mod errors;
 pub mod expressions;
+pub mod parser;
+pub mod printer;
 pub mod statements;
 pub mod types;
 pub mod utils;
@@ -44,12 +56,32 @@ pub struct HIRContext<'a> {
 }
 
 pub struct CommonIdentifiers {
-    ptr: StringId,
-    capacity: StringId,
-    is_heap_allocated: StringId,
-    len: StringId,
-    id: StringId,
-    value: StringId,
+    pub ptr: StringId,
+    pub capacity: StringId,
+    pub is_heap_allocated: StringId,
+    pub len: StringId,
+    pub id: StringId,
+    pub value: StringId,
+    pub self_: StringId,
+}
+
+/// A generic function or type alias, it is checked again for every list of type
+/// arguments it is used with
+#[derive(Debug, Clone)]
+pub struct GenericTemplate {
+    /// The module which declares it, names in the declaration resolve in its file scope
+    pub module: PathBuf,
+    pub declaration: Declaration,
+}
+
+/// A function declared in an `impl` block
+#[derive(Debug, Clone, Copy)]
+pub struct Method {
+    pub decl_id: DeclarationId,
+    /// Whether it takes `self`, those are called on values of the type
+    pub has_receiver: bool,
+    /// Whether it takes `mut self`, those can only be called on mutable places
+    pub mutates_receiver: bool,
 }
 
 pub struct ProgramBuilder {
@@ -57,6 +89,19 @@ pub struct ProgramBuilder {
     pub value_types: HashMap<ValueId, Type>,
 
     pub declarations: HashMap<DeclarationId, CheckedDeclaration>,
+    pub generic_templates: HashMap<DeclarationId, GenericTemplate>,
+    /// Monomorphized generic functions by their template and type arguments
+    pub generic_instances: HashMap<(DeclarationId, Vec<Type>), DeclarationId>,
+    /// Functions of the `impl` blocks by the type they are attached to and their name
+    pub methods: HashMap<Type, HashMap<StringId, Method>>,
+    /// Instances whose bodies are yet to be built, with their template, arguments and
+    /// the depth they were requested at
+    pending_instances: Vec<(DeclarationId, DeclarationId, Vec<Type>, usize)>,
+    /// How many generic instances are nested around the body being built, calls in
+    /// the body of an instance request instances one level deeper
+    instance_depth: usize,
+    /// Generic type aliases currently being expanded
+    expanding_aliases: Vec<DeclarationId>,
     pub constant_data: HashMap<ConstantId, Vec<u8>>,
 
     pub string_interner: Arc<SharedStringInterner>,
@@ -76,6 +121,17 @@ pub struct ModuleBuilder {
     pub errors: Vec<SemanticError>,
     /// Stack of closures
     pub scopes: Vec<Scope>,
+    /// Every identifier of the module which resolved to a declaration, including the
+    /// declaring identifiers themselves. Used by editor tooling.
+    pub references: Vec<SymbolReference>,
+    /// Span of the declaring identifier of everything declared in this module
+    pub definitions: HashMap<DeclarationId, Span>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SymbolReference {
+    pub span: Span,
+    pub decl_id: DeclarationId,
 }
 
 #[derive(Debug, Clone)]
@@ -104,7 +160,6 @@ pub struct FunctionBuilder {
     pub incomplete_params: HashMap<BasicBlockId, Vec<(ValueId, ValueId)>>,
 
     block_id_counter: usize,
-    value_id_counter: usize,
 }
 
 impl ProgramBuilder {
@@ -120,6 +175,7 @@ impl ProgramBuilder {
             is_heap_allocated: string_interner.intern("is_heap_allocated"),
             len: string_interner.intern("len"),
             ptr: string_interner.intern("ptr"),
+            self_: string_interner.intern("self"),
         };
 
         ProgramBuilder {
@@ -131,6 +187,12 @@ impl ProgramBuilder {
             tag_interner,
             common_identifiers,
             declarations: HashMap::new(),
+            generic_templates: HashMap::new(),
+            generic_instances: HashMap::new(),
+            methods: HashMap::new(),
+            pending_instances: vec![],
+            instance_depth: 0,
+            expanding_aliases: vec![],
             constant_id_counter: AtomicUsize::new(0),
             value_id_counter: AtomicUsize::new(0),
             decl_id_counter,
@@ -149,6 +211,27 @@ impl ProgramBuilder {
             .expect("INTERNAL COMPILER ERROR: DeclarationId not found")
     }
 
+    pub fn get_generic_template(&self, id: DeclarationId) -> GenericTemplate {
+        self.generic_templates
+            .get(&id)
+            .cloned()
+            .expect("INTERNAL COMPILER ERROR: Generic template not found")
+    }
+
+    /// Looks up a function of the impl blocks attached to `ty`
+    pub fn find_method(&self, ty: &Type, name: StringId) -> Option<Method> {
+        // Methods are attached to the declared type, narrowing does not change them
+        let ty = match ty {
+            Type::Pointer { constraint, .. } => &Type::Pointer {
+                constraint: constraint.clone(),
+                narrowed_to: constraint.clone(),
+            },
+            ty => ty,
+        };
+
+        self.methods.get(ty)?.get(&name).copied()
+    }
+
     pub fn new_constant_id(&self) -> ConstantId {
         ConstantId(self.constant_id_counter.fetch_add(1, Ordering::SeqCst))
     }
@@ -194,6 +277,8 @@ impl ProgramBuilder {
                     }),
                     CheckedDeclaration::TypeAlias(..)
                     | CheckedDeclaration::Var(..)
+                    | CheckedDeclaration::Const(..)
+                    | CheckedDeclaration::Module(..)
                     | CheckedDeclaration::UninitializedVar { .. } => todo!(),
                 }
             }
@@ -206,97 +291,288 @@ impl ProgramBuilder {
 
     pub fn build(&mut self, results: Vec<ParallelParseResult>) {
         // Pass 1: Signatures
+        let mut constants = HashMap::new();
         for res in &results {
             let mut mb = ModuleBuilder::new(res.path.clone());
-            for decl in &res.declarations {
-                match decl {
-                    Declaration::TypeAlias(d) => {
-                        if d.is_exported {
-                            mb.module.exports.insert(d.identifier.name);
-                        }
-                        let mut ctx = HIRContext {
-                            program_builder: self,
-                            module_builder: &mut mb,
-                        };
-                        build_type_alias_decl(&mut ctx, d.clone(), d.identifier.span);
-                    }
-                    Declaration::Fn(d) => {
-                        if d.is_exported {
-                            mb.module.exports.insert(d.identifier.name);
-                        }
-                        let mut ctx = HIRContext {
-                            program_builder: self,
-                            module_builder: &mut mb,
-                        };
-                        let checked_params = check_params(&mut ctx, &d.params);
-                        let return_type = check_type_annotation(&mut ctx, &d.return_type);
-
-                        let checked_fn = CheckedFnDecl {
-                            id: d.id,
-                            identifier: d.identifier,
-                            params: checked_params,
-                            return_type,
-                            body: None,
-                            is_exported: d.is_exported,
-                        };
-
-                        ctx.module_builder.scope_insert(
-                            ctx.program_builder,
-                            d.identifier,
-                            CheckedDeclaration::Function(checked_fn),
-                        );
-                    }
-                }
-            }
+            let file_constants =
+                self.build_signatures(&mut mb, &res.declarations, &res.statements);
+            constants.insert(res.path.clone(), file_constants);
             self.modules.insert(res.path.clone(), mb);
         }
 
-        // Pass 2: Imports
+        // Pass 2: Imports, a module can export constants built from its own imports
+        let parsed: HashMap<&PathBuf, &ParallelParseResult> =
+            results.iter().map(|res| (&res.path, res)).collect();
+        for res in &results {
+            self.build_imports_in_order(res, &parsed, &mut constants);
+        }
+
+        // Pass 3: Impl blocks, their targets may be imported
         for res in &results {
             let mut mb = self.modules.remove(&res.path).unwrap();
-            {
-                let mut ctx = HIRContext {
-                    program_builder: self,
-                    module_builder: &mut mb,
-                };
-                for stmt in &res.statements {
-                    if let StmtKind::From { path, identifiers } = &stmt.kind {
-                        build_from_stmt(
-                            &mut ctx,
-                            path.clone(),
-                            identifiers.clone(),
-                            stmt.span,
-                        );
-                    }
-                }
-            }
+            self.build_impls(&mut mb, &res.declarations);
             self.modules.insert(res.path.clone(), mb);
         }
 
-        // Pass 3: Bodies
+        // Pass 4: Bodies
         for res in &results {
             let mut mb = self.modules.remove(&res.path).unwrap();
-            for decl in &res.declarations {
-                if let Declaration::Fn(fn_decl) = decl {
+            self.build_bodies(&mut mb, &res.declarations);
+            self.modules.insert(res.path.clone(), mb);
+        }
+
+        // Pass 5: Generic function instances
+        self.build_generic_instances();
+    }
+
+    /// Resolves the imports of a module after the imports of the modules it imports
+    /// from, so their constants built from imported names exist
+    fn build_imports_in_order<'a>(
+        &mut self,
+        res: &ParallelParseResult,
+        parsed: &HashMap<&PathBuf, &ParallelParseResult>,
+        constants: &mut HashMap<PathBuf, FileConstants<'a>>,
+    ) {
+        let Some(file_constants) = constants.remove(&res.path) else {
+            return;
+        };
+
+        for stmt in &res.statements {
+            if let StmtKind::From { path, .. } = &stmt.kind {
+                let dependency = import_path(&res.path, &path.value)
+                    .canonicalize()
+                    .ok()
+                    .and_then(|p| parsed.get(&p).copied());
+                if let Some(dependency) = dependency {
+                    self.build_imports_in_order(dependency, parsed, constants);
+                }
+            }
+        }
+
+        let mut mb = self.modules.remove(&res.path).unwrap();
+        self.build_imports(&mut mb, &res.statements, file_constants);
+        self.modules.insert(res.path.clone(), mb);
+    }
+
+    /// Declares the type aliases, constants and function signatures of a module in its
+    /// file scope. Constants built from names the module imports are returned, to be
+    /// built by `build_imports`.
+    pub fn build_signatures<'a>(
+        &mut self,
+        mb: &mut ModuleBuilder,
+        declarations: &'a [Declaration],
+        imports: &[Stmt],
+    ) -> FileConstants<'a> {
+        let imported = imports
+            .iter()
+            .filter_map(|stmt| match &stmt.kind {
+                StmtKind::From { identifiers, .. } => Some(identifiers),
+                _ => None,
+            })
+            .flatten()
+            .map(|(identifier, alias)| alias.unwrap_or(*identifier).name)
+            .collect();
+        let mut constants = FileConstants::new(declarations, imported);
+        for decl in declarations {
+            match decl {
+                Declaration::TypeAlias(d) => {
+                    if d.is_exported {
+                        mb.module.exports.insert(d.identifier.name);
+                    }
+                    let mut ctx = HIRContext {
+                        program_builder: self,
+                        module_builder: mb,
+                    };
+                    build_type_alias_decl(&mut ctx, d.clone(), d.identifier.span);
+                }
+                Declaration::Fn(d) => {
+                    if d.is_exported {
+                        mb.module.exports.insert(d.identifier.name);
+                    }
                     let mut ctx = HIRContext {
                         program_builder: self,
-                        module_builder: &mut mb,
+                        module_builder: mb,
+                    };
+
+                    if !d.generic_params.is_empty() {
+                        declare_generic_fn(&mut ctx, d);
+                        continue;
+                    }
+
+                    let checked_params = check_params(&mut ctx, &d.params);
+                    let return_type = check_type_annotation(&mut ctx, &d.return_type);
+
+                    let checked_fn = CheckedFnDecl {
+                        id: d.id,
+                        identifier: d.identifier,
+                        params: checked_params,
+                        return_type,
+                        body: None,
+                        is_exported: d.is_exported,
+                        environment: None,
                     };
 
-                    FunctionBuilder::build(&mut ctx, fn_decl.clone());
+                    ctx.module_builder.scope_insert(
+                        ctx.program_builder,
+                        d.identifier,
+                        CheckedDeclaration::Function(checked_fn),
+                    );
+                }
+                Declaration::Const(d) => {
+                    if d.is_exported {
+                        mb.module.exports.insert(d.identifier.name);
+                    }
+                    let mut ctx = HIRContext {
+                        program_builder: self,
+                        module_builder: mb,
+                    };
+                    constants.build(&mut ctx, d);
                 }
+                Declaration::Impl(_) => {}
+            }
+        }
+
+        constants
+    }
+
+    /// Attaches the functions of the module's impl blocks to their types, after the
+    /// imports of the module are resolved
+    pub fn build_impls(&mut self, mb: &mut ModuleBuilder, declarations: &[Declaration]) {
+        let mut ctx = HIRContext {
+            program_builder: self,
+            module_builder: mb,
+        };
+        for decl in declarations {
+            if let Declaration::Impl(impl_decl) = decl {
+                build_impl_decl(&mut ctx, impl_decl);
             }
-            self.modules.insert(res.path.clone(), mb);
+        }
+    }
+
+    /// Brings the symbols of the module's `from` statements into its file scope, the
+    /// imported modules must have their signatures built already. The constants built
+    /// from imported names are built afterwards.
+    pub fn build_imports(
+        &mut self,
+        mb: &mut ModuleBuilder,
+        statements: &[Stmt],
+        constants: FileConstants,
+    ) {
+        let mut ctx = HIRContext {
+            program_builder: self,
+            module_builder: mb,
+        };
+        for stmt in statements {
+            if let StmtKind::From {
+                path,
+                identifiers,
+                namespace,
+            } = &stmt.kind
+            {
+                build_from_stmt(
+                    &mut ctx,
+                    path.clone(),
+                    identifiers.clone(),
+                    *namespace,
+                    stmt.span,
+                );
+            }
+        }
+
+        constants.build_imported(&mut ctx);
+    }
+
+    /// Builds the bodies of the module's functions
+    pub fn build_bodies(&mut self, mb: &mut ModuleBuilder, declarations: &[Declaration]) {
+        for decl in declarations {
+            let fn_decls = match decl {
+                Declaration::Fn(fn_decl) => std::slice::from_ref(fn_decl),
+                Declaration::Impl(impl_decl) => impl_decl.methods.as_slice(),
+                Declaration::TypeAlias(_) | Declaration::Const(_) => continue,
+            };
+
+            for fn_decl in fn_decls {
+                // Generic templates and methods of invalid impl blocks have no
+                // signature
+                if !fn_decl.generic_params.is_empty()
+                    || !self.declarations.contains_key(&fn_decl.id)
+                {
+                    continue;
+                }
+
+                let mut ctx = HIRContext {
+                    program_builder: self,
+                    module_builder: mb,
+                };
+
+                FunctionBuilder::build(&mut ctx, fn_decl.clone());
+            }
+        }
+    }
+
+    /// Builds the bodies of the generic function instances requested while building
+    /// other functions, until no new instances are requested
+    pub fn build_generic_instances(&mut self) {
+        while let Some((instance_id, template_id, args, depth)) =
+            self.pending_instances.pop()
+        {
+            let GenericTemplate {
+                module,
+                declaration: Declaration::Fn(fn_decl),
+            } = self.get_generic_template(template_id)
+            else {
+                panic!("INTERNAL COMPILER ERROR: Expected a generic function template");
+            };
+            let CheckedDeclaration::Function(instance) =
+                self.get_declaration(instance_id)
+            else {
+                panic!("INTERNAL COMPILER ERROR: Expected a generic function instance");
+            };
+            let params = instance.params.clone();
+            let return_type = instance.return_type.clone();
+
+            let mut mb = self.modules.remove(&module).expect(
+                "INTERNAL COMPILER ERROR: Module of a generic function not found",
+            );
+            let mut ctx = HIRContext {
+                program_builder: self,
+                module_builder: &mut mb,
+            };
+            ctx.program_builder.instance_depth = depth;
+            with_generic_scope(&mut ctx, template_id, &args, |ctx| {
+                let fn_decl = FnDecl {
+                    id: instance_id,
+                    is_exported: false,
+                    ..fn_decl
+                };
+                FunctionBuilder::build_function(ctx, fn_decl, params, return_type, None);
+            });
+            self.instance_depth = 0;
+            self.modules.insert(module, mb);
         }
     }
 }
 
 impl ModuleBuilder {
+    /// Moves the errors out of the module. Generic functions are checked once for every
+    /// instance, an error found again at the same place is reported once.
+    pub fn take_errors(&mut self) -> Vec<SemanticError> {
+        let mut errors: Vec<SemanticError> = vec![];
+        for error in std::mem::take(&mut self.errors) {
+            if !errors.contains(&error) {
+                errors.push(error);
+            }
+        }
+        errors
+    }
+
     pub fn new(path: PathBuf) -> Self {
         Self {
             module: CheckedModule::new(path),
             errors: vec![],
             scopes: vec![Scope::new(ScopeKind::File)],
+            references: vec![],
+            definitions: HashMap::new(),
         }
     }
 }
@@ -0,0 +1,1258 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use crate::{
+    ast::{DeclarationId, IdentifierNode, Span},
+    compile::interner::{SharedStringInterner, SharedTagInterner},
+    hir::{
+        cfg::{
+            BasicBlock, BasicBlockId, BinaryOperationKind, ConstantId, ControlFlowGraph,
+            Instruction, Terminator, UnaryOperationKind, Value, ValueId,
+        },
+        types::{
+            checked_declaration::{
+                CheckedDeclaration, CheckedFnDecl, CheckedParam, FnType, TagType,
+            },
+            checked_type::{StructKind, Type},
+        },
+        ProgramBuilder,
+    },
+    tokenize::NumberKind,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum IrParseErrorKind {
+    UnexpectedToken {
+        expected: String,
+        found: String,
+    },
+    UnexpectedEndOfInput,
+    UnknownCharacter(char),
+    UnterminatedString,
+    InvalidEscapeSequence,
+    InvalidNumber(String),
+    UnknownInstruction(String),
+    UnknownType(String),
+    /// A value is used but never defined
+    UndefinedValue(usize),
+    DuplicateValue(usize),
+    DuplicateBlock(usize),
+    /// A jump targets a block the function does not define
+    UndefinedBlock(usize),
+    UndefinedFunction(usize),
+    UndefinedConstant(usize),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct IrParseError {
+    pub line: usize,
+    pub kind: IrParseErrorKind,
+}
+
+/// Parses the textual format produced by `printer::print_program` back into a
+/// ProgramBuilder holding the functions, value types and constant data.
+///
+/// Tag names are resolved through `tag_interner`, so the ids of tags match the
+/// printed program only when it is parsed with the interners of the compiler which
+/// printed it. For hand-written IR, tags get their ids in order of first appearance.
+pub fn parse_program(
+    source: &str,
+    string_interner: Arc<SharedStringInterner>,
+    tag_interner: Arc<SharedTagInterner>,
+) -> Result<ProgramBuilder, IrParseError> {
+    let tokens = tokenize(source)?;
+    let program =
+        ProgramBuilder::new(string_interner, tag_interner, Arc::new(AtomicUsize::new(0)));
+
+    let mut parser = IrParser {
+        tokens,
+        position: 0,
+        program,
+        value_uses: Vec::new(),
+        function_uses: Vec::new(),
+        constant_uses: Vec::new(),
+    };
+
+    parser.parse_program()?;
+    parser.finish()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum IrToken {
+    Identifier(String),
+    /// Numeric literal including its suffix, e.g. `-12i32` or `1.5f64`
+    Number(String),
+    ByteString(Vec<u8>),
+    Punctuation(char),
+    Newline,
+}
+
+impl IrToken {
+    fn describe(&self) -> String {
+        match self {
+            IrToken::Identifier(name) => format!("\"{}\"", name),
+            IrToken::Number(n) => format!("\"{}\"", n),
+            IrToken::ByteString(_) => "byte string".to_string(),
+            IrToken::Punctuation(c) => format!("\"{}\"", c),
+            IrToken::Newline => "end of line".to_string(),
+        }
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<(IrToken, usize)>, IrParseError> {
+    let mut tokens = Vec::new();
+    let chars: Vec<char> = source.chars().collect();
+    let mut line = 1;
+    let mut i = 0;
+
+    let error = |line, kind| IrParseError { line, kind };
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        if c == '\n' {
+            tokens.push((IrToken::Newline, line));
+            line += 1;
+            i += 1;
+        } else if c.is_whitespace() {
+            i += 1;
+        } else if c == ';' {
+            // Comments run until the end of the line
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+        } else if c == 'b' && chars.get(i + 1) == Some(&'"') {
+            i += 2;
+            let mut bytes = Vec::new();
+            loop {
+                match chars.get(i) {
+                    None | Some('\n') => {
+                        return Err(error(line, IrParseErrorKind::UnterminatedString))
+                    }
+                    Some('"') => {
+                        i += 1;
+                        break;
+                    }
+                    Some('\\') => {
+                        let escaped = match chars.get(i + 1) {
+                            Some('n') => b'\n',
+                            Some('r') => b'\r',
+                            Some('t') => b'\t',
+                            Some('0') => b'\0',
+                            Some('\\') => b'\\',
+                            Some('\'') => b'\'',
+                            Some('"') => b'"',
+                            Some('x') => {
+                                let hex: String = chars
+                                    .get(i + 2..i + 4)
+                                    .map(|h| h.iter().collect())
+                                    .unwrap_or_default();
+                                let byte =
+                                    u8::from_str_radix(&hex, 16).map_err(|_| {
+                                        error(
+                                            line,
+                                            IrParseErrorKind::InvalidEscapeSequence,
+                                        )
+                                    })?;
+                                i += 2;
+                                byte
+                            }
+                            _ => {
+                                return Err(error(
+                                    line,
+                                    IrParseErrorKind::InvalidEscapeSequence,
+                                ))
+                            }
+                        };
+                        bytes.push(escaped);
+                        i += 2;
+                    }
+                    Some(other) => {
+                        let mut buffer = [0; 4];
+                        bytes
+                            .extend_from_slice(other.encode_utf8(&mut buffer).as_bytes());
+                        i += 1;
+                    }
+                }
+            }
+            tokens.push((IrToken::ByteString(bytes), line));
+        } else if c.is_ascii_digit()
+            || (c == '-'
+                && chars
+                    .get(i + 1)
+                    .is_some_and(|n| n.is_ascii_digit() || *n == 'i'))
+        {
+            let start = i;
+            i += 1;
+            while i < chars.len() {
+                let n = chars[i];
+                let is_exponent_sign = (n == '-' || n == '+')
+                    && chars[i - 1] == 'e'
+                    && !chars[start..i].contains(&'f');
+                if n.is_ascii_alphanumeric() || n == '_' || n == '.' || is_exponent_sign {
+                    i += 1;
+                } else {
+                    break;
+                }
+            }
+            tokens.push((IrToken::Number(chars[start..i].iter().collect()), line));
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push((IrToken::Identifier(chars[start..i].iter().collect()), line));
+        } else if "@(){}<>:,=|#.".contains(c) {
+            tokens.push((IrToken::Punctuation(c), line));
+            i += 1;
+        } else {
+            return Err(error(line, IrParseErrorKind::UnknownCharacter(c)));
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Parses `<prefix><number>` names such as `v12`, `bb3` or `c0`
+fn parse_numbered_name(name: &str, prefix: &str) -> Option<usize> {
+    name.strip_prefix(prefix)?.parse().ok()
+}
+
+fn parse_number_literal(literal: &str) -> Option<NumberKind> {
+    const SUFFIXES: [&str; 12] = [
+        "isize", "usize", "i64", "i32", "i16", "i8", "u64", "u32", "u16", "u8", "f32",
+        "f64",
+    ];
+
+    let suffix = SUFFIXES.iter().find(|s| literal.ends_with(*s))?;
+    let digits = &literal[..literal.len() - suffix.len()];
+
+    let kind = match *suffix {
+        "isize" => NumberKind::ISize(digits.parse().ok()?),
+        "usize" => NumberKind::USize(digits.parse().ok()?),
+        "i64" => NumberKind::I64(digits.parse().ok()?),
+        "i32" => NumberKind::I32(digits.parse().ok()?),
+        "i16" => NumberKind::I16(digits.parse().ok()?),
+        "i8" => NumberKind::I8(digits.parse().ok()?),
+        "u64" => NumberKind::U64(digits.parse().ok()?),
+        "u32" => NumberKind::U32(digits.parse().ok()?),
+        "u16" => NumberKind::U16(digits.parse().ok()?),
+        "u8" => NumberKind::U8(digits.parse().ok()?),
+        "f32" => NumberKind::F32(digits.parse().ok()?),
+        "f64" => NumberKind::F64(digits.parse().ok()?),
+        _ => unreachable!(),
+    };
+
+    Some(kind)
+}
+
+struct IrParser {
+    tokens: Vec<(IrToken, usize)>,
+    position: usize,
+    program: ProgramBuilder,
+    /// Every use is validated once all definitions are known
+    value_uses: Vec<(ValueId, usize)>,
+    function_uses: Vec<(DeclarationId, usize)>,
+    constant_uses: Vec<(ConstantId, usize)>,
+}
+
+impl IrParser {
+    fn line(&self) -> usize {
+        self.tokens
+            .get(self.position)
+            .or(self.tokens.last())
+            .map(|(_, line)| *line)
+            .unwrap_or(1)
+    }
+
+    fn error(&self, kind: IrParseErrorKind) -> IrParseError {
+        IrParseError {
+            line: self.line(),
+            kind,
+        }
+    }
+
+    fn peek(&self) -> Option<&IrToken> {
+        self.tokens.get(self.position).map(|(token, _)| token)
+    }
+
+    fn next(&mut self) -> Result<IrToken, IrParseError> {
+        let token = self
+            .peek()
+            .cloned()
+            .ok_or_else(|| self.error(IrParseErrorKind::UnexpectedEndOfInput))?;
+        self.position += 1;
+        Ok(token)
+    }
+
+    fn unexpected(&self, expected: &str) -> IrParseError {
+        match self.peek() {
+            Some(token) => self.error(IrParseErrorKind::UnexpectedToken {
+                expected: expected.to_string(),
+                found: token.describe(),
+            }),
+            None => self.error(IrParseErrorKind::UnexpectedEndOfInput),
+        }
+    }
+
+    fn is_punctuation(&self, c: char) -> bool {
+        self.peek() == Some(&IrToken::Punctuation(c))
+    }
+
+    fn is_identifier(&self, name: &str) -> bool {
+        matches!(self.peek(), Some(IrToken::Identifier(n)) if n == name)
+    }
+
+    fn eat_punctuation(&mut self, c: char) -> bool {
+        if self.is_punctuation(c) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect_punctuation(&mut self, c: char) -> Result<(), IrParseError> {
+        if self.eat_punctuation(c) {
+            Ok(())
+        } else {
+            Err(self.unexpected(&format!("\"{}\"", c)))
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<(), IrParseError> {
+        if self.is_identifier(keyword) {
+            self.position += 1;
+            Ok(())
+        } else {
+            Err(self.unexpected(&format!("\"{}\"", keyword)))
+        }
+    }
+
+    fn expect_identifier(&mut self) -> Result<String, IrParseError> {
+        match self.peek() {
+            Some(IrToken::Identifier(name)) => {
+                let name = name.clone();
+                self.position += 1;
+                Ok(name)
+            }
+            _ => Err(self.unexpected("an identifier")),
+        }
+    }
+
+    fn expect_usize(&mut self) -> Result<usize, IrParseError> {
+        match self.peek() {
+            Some(IrToken::Number(n)) => {
+                let n = n.clone();
+                let value = n
+                    .parse()
+                    .map_err(|_| self.error(IrParseErrorKind::InvalidNumber(n)))?;
+                self.position += 1;
+                Ok(value)
+            }
+            _ => Err(self.unexpected("a number")),
+        }
+    }
+
+    fn expect_newline(&mut self) -> Result<(), IrParseError> {
+        match self.peek() {
+            Some(IrToken::Newline) => {
+                self.skip_newlines();
+                Ok(())
+            }
+            None => Ok(()),
+            _ => Err(self.unexpected("end of line")),
+        }
+    }
+
+    fn skip_newlines(&mut self) {
+        while self.peek() == Some(&IrToken::Newline) {
+            self.position += 1;
+        }
+    }
+
+    fn identifier_node(&self, name: &str) -> IdentifierNode {
+        IdentifierNode {
+            name: self.program.string_interner.intern(name),
+            span: Span::default(),
+        }
+    }
+
+    fn parse_program(&mut self) -> Result<(), IrParseError> {
+        self.skip_newlines();
+
+        while self.peek().is_some() {
+            if self.is_identifier("const") {
+                self.parse_constant()?;
+            } else {
+                self.parse_function()?;
+            }
+            self.skip_newlines();
+        }
+
+        Ok(())
+    }
+
+    fn parse_constant(&mut self) -> Result<(), IrParseError> {
+        self.expect_keyword("const")?;
+        let id = self.parse_constant_name()?;
+        self.expect_punctuation('=')?;
+
+        let bytes = match self.next()? {
+            IrToken::ByteString(bytes) => bytes,
+            _ => {
+                self.position -= 1;
+                return Err(self.unexpected("a byte string"));
+            }
+        };
+        self.program.constant_data.insert(id, bytes);
+
+        self.expect_newline()
+    }
+
+    fn parse_constant_name(&mut self) -> Result<ConstantId, IrParseError> {
+        let name = self.expect_identifier()?;
+        match parse_numbered_name(&name, "c") {
+            Some(id) => Ok(ConstantId(id)),
+            None => {
+                self.position -= 1;
+                Err(self.unexpected("a constant name"))
+            }
+        }
+    }
+
+    fn parse_function_name(&mut self) -> Result<(String, DeclarationId), IrParseError> {
+        self.expect_punctuation('@')?;
+        let name = self.expect_identifier()?;
+        self.expect_punctuation('.')?;
+        let id = self.expect_usize()?;
+
+        Ok((name, DeclarationId(id)))
+    }
+
+    fn parse_function(&mut self) -> Result<(), IrParseError> {
+        let is_exported = self.is_identifier("export");
+        if is_exported {
+            self.position += 1;
+        }
+        self.expect_keyword("fn")?;
+
+        let (name, id) = self.parse_function_name()?;
+        let params = self.parse_params('(', ')')?;
+        self.expect_punctuation(':')?;
+        let return_type = self.parse_type()?;
+        let environment = if self.is_identifier("env") {
+            self.position += 1;
+            Some(self.parse_value_use()?)
+        } else {
+            None
+        };
+
+        let body = if self.eat_punctuation('{') {
+            self.expect_newline()?;
+            Some(self.parse_body()?)
+        } else {
+            None
+        };
+
+        self.program.declarations.insert(
+            id,
+            CheckedDeclaration::Function(CheckedFnDecl {
+                id,
+                identifier: self.identifier_node(&name),
+                params,
+                return_type,
+                body,
+                is_exported,
+                environment,
+            }),
+        );
+
+        self.expect_newline()
+    }
+
+    fn parse_params(
+        &mut self,
+        open: char,
+        close: char,
+    ) -> Result<Vec<CheckedParam>, IrParseError> {
+        self.expect_punctuation(open)?;
+
+        let mut params = Vec::new();
+        while !self.eat_punctuation(close) {
+            if !params.is_empty() {
+                self.expect_punctuation(',')?;
+            }
+            let name = self.expect_identifier()?;
+            self.expect_punctuation(':')?;
+            params.push(CheckedParam {
+                identifier: self.identifier_node(&name),
+                ty: self.parse_type()?,
+            });
+        }
+
+        Ok(params)
+    }
+
+    fn parse_body(&mut self) -> Result<ControlFlowGraph, IrParseError> {
+        let mut blocks: HashMap<BasicBlockId, BasicBlock> = HashMap::new();
+        let mut entry_block = None;
+        let mut block_targets = Vec::new();
+
+        while !self.eat_punctuation('}') {
+            let line = self.line();
+            let block = self.parse_block(&mut block_targets)?;
+            entry_block.get_or_insert(block.id);
+
+            if blocks.contains_key(&block.id) {
+                return Err(IrParseError {
+                    line,
+                    kind: IrParseErrorKind::DuplicateBlock(block.id.0),
+                });
+            }
+            blocks.insert(block.id, block);
+        }
+
+        for (target, line) in block_targets {
+            if !blocks.contains_key(&target) {
+                return Err(IrParseError {
+                    line,
+                    kind: IrParseErrorKind::UndefinedBlock(target.0),
+                });
+            }
+        }
+
+        Ok(ControlFlowGraph {
+            entry_block: entry_block.unwrap_or(BasicBlockId(0)),
+            blocks,
+        })
+    }
+
+    fn parse_block_name(&mut self) -> Result<BasicBlockId, IrParseError> {
+        let name = self.expect_identifier()?;
+        match parse_numbered_name(&name, "bb") {
+            Some(id) => Ok(BasicBlockId(id)),
+            None => {
+                self.position -= 1;
+                Err(self.unexpected("a block name"))
+            }
+        }
+    }
+
+    fn parse_block(
+        &mut self,
+        block_targets: &mut Vec<(BasicBlockId, usize)>,
+    ) -> Result<BasicBlock, IrParseError> {
+        let id = self.parse_block_name()?;
+
+        let mut params = Vec::new();
+        if self.eat_punctuation('(') {
+            while !self.eat_punctuation(')') {
+                if !params.is_empty() {
+                    self.expect_punctuation(',')?;
+                }
+                params.push(self.parse_definition()?);
+            }
+        }
+        self.expect_punctuation(':')?;
+        self.expect_newline()?;
+
+        let mut instructions = Vec::new();
+        let mut terminator = None;
+
+        loop {
+            match self.peek() {
+                Some(IrToken::Punctuation('}')) | None => break,
+                Some(IrToken::Identifier(name))
+                    if parse_numbered_name(name, "bb").is_some() =>
+                {
+                    break
+                }
+                _ => {}
+            }
+
+            if let Some(parsed) = self.parse_terminator(block_targets)? {
+                terminator = Some(parsed);
+                self.expect_newline()?;
+                break;
+            }
+
+            instructions.push(self.parse_instruction()?);
+            self.expect_newline()?;
+        }
+
+        Ok(BasicBlock {
+            id,
+            instructions,
+            terminator,
+            params,
+        })
+    }
+
+    /// Parses `vN: type` and records the type of the value
+    fn parse_definition(&mut self) -> Result<ValueId, IrParseError> {
+        let line = self.line();
+        let id = self.parse_value_name()?;
+        self.expect_punctuation(':')?;
+        let ty = self.parse_type()?;
+
+        if self.program.value_types.insert(id, ty).is_some() {
+            return Err(IrParseError {
+                line,
+                kind: IrParseErrorKind::DuplicateValue(id.0),
+            });
+        }
+
+        Ok(id)
+    }
+
+    fn parse_value_name(&mut self) -> Result<ValueId, IrParseError> {
+        let name = self.expect_identifier()?;
+        match parse_numbered_name(&name, "v") {
+            Some(id) => Ok(ValueId(id)),
+            None => {
+                self.position -= 1;
+                Err(self.unexpected("a value name"))
+            }
+        }
+    }
+
+    /// A value name in operand position, validated at the end
+    fn parse_value_use(&mut self) -> Result<ValueId, IrParseError> {
+        let line = self.line();
+        let id = self.parse_value_name()?;
+        self.value_uses.push((id, line));
+        Ok(id)
+    }
+
+    fn parse_value(&mut self) -> Result<Value, IrParseError> {
+        let line = self.line();
+
+        match self.peek().cloned() {
+            Some(IrToken::Punctuation('@')) => {
+                let (_, id) = self.parse_function_name()?;
+                self.function_uses.push((id, line));
+                Ok(Value::Function(id))
+            }
+            Some(IrToken::Number(literal)) => {
+                self.position += 1;
+                parse_number_literal(&literal)
+                    .map(Value::NumberLiteral)
+                    .ok_or(IrParseError {
+                        line,
+                        kind: IrParseErrorKind::InvalidNumber(literal),
+                    })
+            }
+            Some(IrToken::Identifier(name)) => match name.as_str() {
+                "void" => {
+                    self.position += 1;
+                    Ok(Value::VoidLiteral)
+                }
+                "true" | "false" => {
+                    self.position += 1;
+                    Ok(Value::BoolLiteral(name == "true"))
+                }
+                // Non-finite floats are printed without a leading digit
+                "NaNf32" | "NaNf64" | "inff32" | "inff64" => {
+                    self.position += 1;
+                    Ok(Value::NumberLiteral(parse_number_literal(&name).unwrap()))
+                }
+                _ => Ok(Value::Use(self.parse_value_use()?)),
+            },
+            _ => Err(self.unexpected("a value")),
+        }
+    }
+
+    fn parse_values(&mut self, close: char) -> Result<Vec<Value>, IrParseError> {
+        let mut values = Vec::new();
+        while !self.eat_punctuation(close) {
+            if !values.is_empty() {
+                self.expect_punctuation(',')?;
+            }
+            values.push(self.parse_value()?);
+        }
+        Ok(values)
+    }
+
+    fn parse_target(
+        &mut self,
+        block_targets: &mut Vec<(BasicBlockId, usize)>,
+    ) -> Result<(BasicBlockId, Vec<Value>), IrParseError> {
+        let line = self.line();
+        let target = self.parse_block_name()?;
+        block_targets.push((target, line));
+
+        let args = if self.eat_punctuation('(') {
+            self.parse_values(')')?
+        } else {
+            Vec::new()
+        };
+
+        Ok((target, args))
+    }
+
+    fn parse_terminator(
+        &mut self,
+        block_targets: &mut Vec<(BasicBlockId, usize)>,
+    ) -> Result<Option<Terminator>, IrParseError> {
+        let Some(IrToken::Identifier(keyword)) = self.peek().cloned() else {
+            return Ok(None);
+        };
+
+        let terminator = match keyword.as_str() {
+            "jump" => {
+                self.position += 1;
+                let (target, args) = self.parse_target(block_targets)?;
+                Terminator::Jump { target, args }
+            }
+            "cond_jump" => {
+                self.position += 1;
+                let condition = self.parse_value()?;
+                self.expect_punctuation(',')?;
+                let (true_target, true_args) = self.parse_target(block_targets)?;
+                self.expect_punctuation(',')?;
+                let (false_target, false_args) = self.parse_target(block_targets)?;
+                Terminator::CondJump {
+                    condition,
+                    true_target,
+                    true_args,
+                    false_target,
+                    false_args,
+                }
+            }
+            "return" => {
+                self.position += 1;
+                let value = match self.peek() {
+                    Some(IrToken::Newline) | None => None,
+                    _ => Some(self.parse_value()?),
+                };
+                Terminator::Return { value }
+            }
+            "unreachable" => {
+                self.position += 1;
+                Terminator::Unreachable
+            }
+            _ => return Ok(None),
+        };
+
+        Ok(Some(terminator))
+    }
+
+    fn parse_instruction(&mut self) -> Result<Instruction, IrParseError> {
+        let destination = match self.peek() {
+            Some(IrToken::Identifier(name))
+                if parse_numbered_name(name, "v").is_some() =>
+            {
+                let destination = self.parse_definition()?;
+                self.expect_punctuation('=')?;
+                Some(destination)
+            }
+            _ => None,
+        };
+
+        let line = self.line();
+        let op = self.expect_identifier()?;
+
+        let required = |parser: &Self| {
+            destination.ok_or_else(|| parser.unexpected("a destination value"))
+        };
+
+        let instruction = match op.as_str() {
+            "stack_alloc" => Instruction::StackAlloc {
+                destination: required(self)?,
+                count: self.expect_usize()?,
+            },
+            "heap_alloc" => Instruction::HeapAlloc {
+                destination: required(self)?,
+                count: self.parse_value()?,
+            },
+            "heap_free" => Instruction::HeapFree {
+                ptr: self.parse_value_use()?,
+            },
+            "store" => {
+                let ptr = self.parse_value_use()?;
+                self.expect_punctuation(',')?;
+                Instruction::Store {
+                    ptr,
+                    value: self.parse_value()?,
+                }
+            }
+            "load" => Instruction::Load {
+                destination: required(self)?,
+                ptr: self.parse_value_use()?,
+            },
+            "load_constant" => {
+                let line = self.line();
+                let constant_id = self.parse_constant_name()?;
+                self.constant_uses.push((constant_id, line));
+                Instruction::LoadConstant {
+                    destination: required(self)?,
+                    constant_id,
+                }
+            }
+            "file_open" => {
+                let path = self.parse_value()?;
+                self.expect_punctuation(',')?;
+                Instruction::FileOpen {
+                    destination_fd: required(self)?,
+                    path,
+                    mode: self.parse_value()?,
+                }
+            }
+            "file_write" => {
+                let fd = self.parse_value_use()?;
+                self.expect_punctuation(',')?;
+                let data = self.parse_value()?;
+                self.expect_punctuation(',')?;
+                Instruction::FileWrite {
+                    fd,
+                    data,
+                    len: self.parse_value()?,
+                }
+            }
+            "file_read" => {
+                let fd = self.parse_value_use()?;
+                self.expect_punctuation(',')?;
+                let buffer = self.parse_value_use()?;
+                self.expect_punctuation(',')?;
+                Instruction::FileRead {
+                    fd,
+                    buffer,
+                    len: self.parse_value()?,
+                    value_destination: required(self)?,
+                }
+            }
+            "file_close" => Instruction::FileClose {
+                fd: self.parse_value_use()?,
+            },
+            "socket_connect" => {
+                let address = self.parse_value()?;
+                self.expect_punctuation(',')?;
+                Instruction::SocketConnect {
+                    address,
+                    port: self.parse_value()?,
+                    value_destination: required(self)?,
+                }
+            }
+            "socket_send" => {
+                let socket = self.parse_value_use()?;
+                self.expect_punctuation(',')?;
+                Instruction::SocketSend {
+                    socket,
+                    data: self.parse_value()?,
+                }
+            }
+            "socket_close" => Instruction::SocketClose {
+                socket: self.parse_value_use()?,
+            },
+            "not" | "neg" | "bitnot" => Instruction::UnaryOp {
+                op_kind: match op.as_str() {
+                    "not" => UnaryOperationKind::Not,
+                    "neg" => UnaryOperationKind::Neg,
+                    _ => UnaryOperationKind::BitNot,
+                },
+                destination: required(self)?,
+                operand: self.parse_value()?,
+            },
+            "add" | "sub" | "mul" | "div" | "mod" | "lt" | "le" | "gt" | "ge" | "eq"
+            | "ne" | "and" | "or" | "xor" | "shl" | "shr" => {
+                let op_kind = match op.as_str() {
+                    "add" => BinaryOperationKind::Add,
+                    "sub" => BinaryOperationKind::Subtract,
+                    "mul" => BinaryOperationKind::Multiply,
+                    "div" => BinaryOperationKind::Divide,
+                    "mod" => BinaryOperationKind::Modulo,
+                    "lt" => BinaryOperationKind::LessThan,
+                    "le" => BinaryOperationKind::LessThanOrEqual,
+                    "gt" => BinaryOperationKind::GreaterThan,
+                    "ge" => BinaryOperationKind::GreaterThanOrEqual,
+                    "eq" => BinaryOperationKind::Equal,
+                    "ne" => BinaryOperationKind::NotEqual,
+                    "and" => BinaryOperationKind::BitAnd,
+                    "or" => BinaryOperationKind::BitOr,
+                    "xor" => BinaryOperationKind::BitXor,
+                    "shl" => BinaryOperationKind::ShiftLeft,
+                    _ => BinaryOperationKind::ShiftRight,
+                };
+                let left = self.parse_value()?;
+                self.expect_punctuation(',')?;
+                Instruction::BinaryOp {
+                    op_kind,
+                    destination: required(self)?,
+                    left,
+                    right: self.parse_value()?,
+                }
+            }
+            "cast" => {
+                let operand = self.parse_value()?;
+                self.expect_keyword("to")?;
+                Instruction::TypeCast {
+                    destination: required(self)?,
+                    operand,
+                    target_type: self.parse_type()?,
+                }
+            }
+            "call" => {
+                let function_rvalue = self.parse_value()?;
+                self.expect_punctuation('(')?;
+                Instruction::FunctionCall {
+                    destination,
+                    function_rvalue,
+                    args: self.parse_values(')')?,
+                }
+            }
+            "make_closure" => {
+                let (_, function) = self.parse_function_name()?;
+                self.expect_punctuation(',')?;
+                Instruction::MakeClosure {
+                    destination: required(self)?,
+                    function,
+                    environment: self.parse_value_use()?,
+                }
+            }
+            "get_field_ptr" => {
+                let base_ptr = self.parse_value_use()?;
+                self.expect_punctuation(',')?;
+                Instruction::GetFieldPtr {
+                    destination: required(self)?,
+                    base_ptr,
+                    field_index: self.expect_usize()?,
+                }
+            }
+            "get_element_ptr" => {
+                let base_ptr = self.parse_value_use()?;
+                self.expect_punctuation(',')?;
+                Instruction::GetElementPtr {
+                    destination: required(self)?,
+                    base_ptr,
+                    index: self.parse_value()?,
+                }
+            }
+            "nop" => Instruction::Nop,
+            _ => {
+                return Err(IrParseError {
+                    line,
+                    kind: IrParseErrorKind::UnknownInstruction(op),
+                })
+            }
+        };
+
+        Ok(instruction)
+    }
+
+    fn parse_type(&mut self) -> Result<Type, IrParseError> {
+        if self.eat_punctuation('{') {
+            let mut fields = Vec::new();
+            while !self.eat_punctuation('}') {
+                if !fields.is_empty() {
+                    self.expect_punctuation(',')?;
+                }
+                let name = self.expect_identifier()?;
+                self.expect_punctuation(':')?;
+                fields.push(CheckedParam {
+                    identifier: self.identifier_node(&name),
+                    ty: self.parse_type()?,
+                });
+            }
+            return Ok(Type::Struct(StructKind::UserDefined(fields)));
+        }
+
+        if self.is_punctuation('#') {
+            return Ok(Type::Struct(StructKind::Tag(self.parse_tag()?)));
+        }
+
+        if self.eat_punctuation('(') {
+            let mut variants = Vec::new();
+            while !self.eat_punctuation(')') {
+                if !variants.is_empty() {
+                    self.expect_punctuation('|')?;
+                }
+                variants.push(self.parse_tag()?);
+            }
+            return Ok(Type::Struct(StructKind::Union { variants }));
+        }
+
+        let line = self.line();
+        let name = self.expect_identifier()?;
+        let ty = match name.as_str() {
+            "void" => Type::Void,
+            "bool" => Type::Bool,
+            "u8" => Type::U8,
+            "u16" => Type::U16,
+            "u32" => Type::U32,
+            "u64" => Type::U64,
+            "usize" => Type::USize,
+            "isize" => Type::ISize,
+            "i8" => Type::I8,
+            "i16" => Type::I16,
+            "i32" => Type::I32,
+            "i64" => Type::I64,
+            "f32" => Type::F32,
+            "f64" => Type::F64,
+            "unknown" => Type::Unknown,
+            "string" => Type::Struct(StructKind::String),
+            "ptr" => {
+                self.expect_punctuation('<')?;
+                let constraint = self.parse_type()?;
+                let narrowed_to = if self.eat_punctuation(',') {
+                    self.parse_type()?
+                } else {
+                    constraint.clone()
+                };
+                self.expect_punctuation('>')?;
+                Type::Pointer {
+                    constraint: Box::new(constraint),
+                    narrowed_to: Box::new(narrowed_to),
+                }
+            }
+            "list" => {
+                self.expect_punctuation('<')?;
+                let item = self.parse_type()?;
+                self.expect_punctuation('>')?;
+                Type::Struct(StructKind::List(Box::new(item)))
+            }
+            "slice" => {
+                self.expect_punctuation('<')?;
+                let item = self.parse_type()?;
+                self.expect_punctuation('>')?;
+                Type::Struct(StructKind::Slice(Box::new(item)))
+            }
+            "array" => {
+                self.expect_punctuation('<')?;
+                let item_type = self.parse_type()?;
+                self.expect_punctuation(',')?;
+                let size = self.expect_usize()?;
+                self.expect_punctuation('>')?;
+                Type::Array {
+                    item_type: Box::new(item_type),
+                    size,
+                }
+            }
+            "buffer" => {
+                self.expect_punctuation('<')?;
+                let size = self.expect_usize()?;
+                self.expect_punctuation(',')?;
+                let alignment = self.expect_usize()?;
+                self.expect_punctuation('>')?;
+                Type::Buffer { size, alignment }
+            }
+            "fn" => {
+                let params = self.parse_params('(', ')')?;
+                self.expect_punctuation(':')?;
+                Type::Fn(FnType {
+                    params,
+                    return_type: Box::new(self.parse_type()?),
+                })
+            }
+            _ => {
+                return Err(IrParseError {
+                    line,
+                    kind: IrParseErrorKind::UnknownType(name),
+                })
+            }
+        };
+
+        Ok(ty)
+    }
+
+    fn parse_tag(&mut self) -> Result<TagType, IrParseError> {
+        self.expect_punctuation('#')?;
+        let name = self.expect_identifier()?;
+        let value_type = if self.eat_punctuation('(') {
+            let value_type = self.parse_type()?;
+            self.expect_punctuation(')')?;
+            Some(Box::new(value_type))
+        } else {
+            None
+        };
+
+        let name_id = self.program.string_interner.intern(&name);
+        Ok(TagType {
+            id: self.program.tag_interner.intern(&name_id),
+            value_type,
+            span: Span::default(),
+        })
+    }
+
+    /// Checks that every referenced value, function and constant is defined, then
+    /// moves the id counters past the parsed ids
+    fn finish(self) -> Result<ProgramBuilder, IrParseError> {
+        let program = self.program;
+
+        for (id, line) in self.value_uses {
+            if !program.value_types.contains_key(&id) {
+                return Err(IrParseError {
+                    line,
+                    kind: IrParseErrorKind::UndefinedValue(id.0),
+                });
+            }
+        }
+        for (id, line) in self.function_uses {
+            if !matches!(
+                program.declarations.get(&id),
+                Some(CheckedDeclaration::Function(_))
+            ) {
+                return Err(IrParseError {
+                    line,
+                    kind: IrParseErrorKind::UndefinedFunction(id.0),
+                });
+            }
+        }
+        for (id, line) in self.constant_uses {
+            if !program.constant_data.contains_key(&id) {
+                return Err(IrParseError {
+                    line,
+                    kind: IrParseErrorKind::UndefinedConstant(id.0),
+                });
+            }
+        }
+
+        let next =
+            |ids: &mut dyn Iterator<Item = usize>| ids.max().map_or(0, |max| max + 1);
+        program.value_id_counter.store(
+            next(&mut program.value_types.keys().map(|id| id.0)),
+            Ordering::SeqCst,
+        );
+        program.constant_id_counter.store(
+            next(&mut program.constant_data.keys().map(|id| id.0)),
+            Ordering::SeqCst,
+        );
+        program.decl_id_counter.store(
+            next(&mut program.declarations.keys().map(|id| id.0)),
+            Ordering::SeqCst,
+        );
+
+        Ok(program)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{
+        ast::DeclarationId,
+        compile::{
+            check_source,
+            interner::{SharedStringInterner, SharedTagInterner},
+        },
+        hir::{
+            parser::{parse_program, IrParseError, IrParseErrorKind},
+            printer::print_program,
+            ProgramBuilder,
+        },
+        interpret::{self, RuntimeValue},
+    };
+    use pretty_assertions::assert_eq;
+
+    fn parse(source: &str) -> Result<ProgramBuilder, IrParseError> {
+        parse_program(
+            source,
+            Arc::new(SharedStringInterner::default()),
+            Arc::new(SharedTagInterner::default()),
+        )
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let source = r#"
+fn pick(flag: bool): #A(i32) | #B {
+    if flag { #A(1i32) } else { #B }
+}
+
+fn main(): i32 {
+    let s = "tab\there \"quoted\"";
+    let mut i = 0i32;
+    let mut total = 0i32;
+    while i < 10i32 {
+        total = total + i;
+        i = i + 1i32;
+    }
+    let mut counts: [i32; 3] = [0i32; 3];
+    counts[1usize] = total;
+    let quote: [u8] = s[4usize..9usize];
+    let x = -1.5f64;
+    pick(true);
+    fn add_total(n: i32): i32 {
+        n + total
+    }
+    add_total(2i32)
+}
+"#;
+        let (program, _) = check_source("parser_round_trip", source);
+
+        let printed = print_program(&program);
+        let reparsed = parse(&printed).unwrap();
+
+        assert_eq!(print_program(&reparsed), printed);
+    }
+
+    #[test]
+    fn test_run_hand_written_ir() {
+        let source = r#"
+; Sums the numbers below the argument
+fn @sum.0(n: i32): i32 {
+bb0(v0: i32):
+    jump bb1(0i32, 0i32)
+bb1(v1: i32, v2: i32):
+    v3: bool = lt v1, v0
+    cond_jump v3, bb2, bb3
+bb2:
+    v4: i32 = add v2, v1
+    v5: i32 = add v1, 1i32
+    jump bb1(v5, v4)
+bb3:
+    return v2
+}
+
+fn @main.1(): i32 {
+bb0:
+    v6: i32 = call @sum.0(10i32)
+    return v6
+}
+"#;
+        let program = parse(source).unwrap();
+
+        assert_eq!(
+            interpret::run(&program, DeclarationId(1)),
+            Ok(RuntimeValue::Int(45))
+        );
+        // Values allocated after parsing must not collide with the parsed ones
+        assert_eq!(program.new_value_id().0, 7);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let undefined = "fn @main.0(): i32 {\nbb0:\n    return v3\n}\n";
+        assert_eq!(
+            parse(undefined).err(),
+            Some(IrParseError {
+                line: 3,
+                kind: IrParseErrorKind::UndefinedValue(3)
+            })
+        );
+
+        let unknown = "fn @main.0(): void {\nbb0:\n    v0: i32 = frobnicate 1i32\n}\n";
+        assert_eq!(
+            parse(unknown).err(),
+            Some(IrParseError {
+                line: 3,
+                kind: IrParseErrorKind::UnknownInstruction("frobnicate".to_string())
+            })
+        );
+
+        let missing_block = "fn @main.0(): void {\nbb0:\n    jump bb4\n}\n";
+        assert_eq!(
+            parse(missing_block).err(),
+            Some(IrParseError {
+                line: 3,
+                kind: IrParseErrorKind::UndefinedBlock(4)
+            })
+        );
+    }
+}
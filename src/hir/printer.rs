@@ -0,0 +1,633 @@
+use std::fmt::Write;
+
+use crate::{
+    ast::DeclarationId,
+    hir::{
+        cfg::{
+            BasicBlock, BasicBlockId, BinaryOperationKind, ControlFlowGraph, Instruction,
+            Terminator, UnaryOperationKind, Value, ValueId,
+        },
+        types::{
+            checked_declaration::{CheckedDeclaration, CheckedFnDecl, TagType},
+            checked_type::{StructKind, Type},
+        },
+        ProgramBuilder,
+    },
+    tokenize::NumberKind,
+};
+
+/// Renders the whole program in the textual HIR format: constant data first, then
+/// every function ordered by DeclarationId.
+///
+/// ```text
+/// const c0 = b"hello"
+///
+/// fn @main.3(): i32 {
+/// bb0:
+///     v1: ptr<i32> = stack_alloc 1
+///     store v1, 5i32
+///     v2: i32 = load v1
+///     return v2
+/// }
+/// ```
+pub fn print_program(program: &ProgramBuilder) -> String {
+    let printer = HirPrinter::new(program);
+    let mut out = String::new();
+
+    let mut constants: Vec<_> = program.constant_data.iter().collect();
+    constants.sort_by_key(|(id, _)| id.0);
+    for (id, bytes) in &constants {
+        let _ = writeln!(out, "const c{} = b\"{}\"", id.0, bytes.escape_ascii());
+    }
+    if !constants.is_empty() {
+        out.push('\n');
+    }
+
+    let mut functions: Vec<&CheckedFnDecl> = program
+        .declarations
+        .values()
+        .filter_map(|decl| match decl {
+            // Only the instances of generic functions are built
+            CheckedDeclaration::Function(f)
+                if !program.generic_templates.contains_key(&f.id) =>
+            {
+                Some(f)
+            }
+            _ => None,
+        })
+        .collect();
+    functions.sort_by_key(|f| f.id.0);
+
+    let functions: Vec<String> = functions
+        .into_iter()
+        .map(|f| printer.print_function(f))
+        .collect();
+    out.push_str(&functions.join("\n"));
+
+    out
+}
+
+pub struct HirPrinter<'a> {
+    program: &'a ProgramBuilder,
+}
+
+impl<'a> HirPrinter<'a> {
+    pub fn new(program: &'a ProgramBuilder) -> Self {
+        Self { program }
+    }
+
+    pub fn print_function(&self, f: &CheckedFnDecl) -> String {
+        let mut out = String::new();
+
+        let params: Vec<String> = f
+            .params
+            .iter()
+            .map(|p| {
+                format!(
+                    "{}: {}",
+                    self.program.string_interner.resolve(p.identifier.name),
+                    self.print_type(&p.ty)
+                )
+            })
+            .collect();
+
+        let _ = write!(
+            out,
+            "{}fn {}({}): {}",
+            if f.is_exported { "export " } else { "" },
+            self.function_name(f.id),
+            params.join(", "),
+            self.print_type(&f.return_type)
+        );
+        if let Some(environment) = f.environment {
+            let _ = write!(out, " env {}", self.value_name(environment));
+        }
+
+        match &f.body {
+            Some(cfg) => {
+                let _ = writeln!(out, " {{");
+                out.push_str(&self.print_cfg(cfg));
+                let _ = writeln!(out, "}}");
+            }
+            None => out.push('\n'),
+        }
+
+        out
+    }
+
+    /// Blocks are printed entry first, the rest by id
+    pub fn print_cfg(&self, cfg: &ControlFlowGraph) -> String {
+        let mut blocks: Vec<&BasicBlock> = cfg.blocks.values().collect();
+        blocks.sort_by_key(|b| (b.id != cfg.entry_block, b.id.0));
+
+        blocks.into_iter().map(|b| self.print_block(b)).collect()
+    }
+
+    pub fn print_block(&self, block: &BasicBlock) -> String {
+        let mut out = String::new();
+
+        if block.params.is_empty() {
+            let _ = writeln!(out, "{}:", self.block_name(block.id));
+        } else {
+            let params: Vec<String> = block
+                .params
+                .iter()
+                .map(|p| self.print_definition(*p))
+                .collect();
+            let _ =
+                writeln!(out, "{}({}):", self.block_name(block.id), params.join(", "));
+        }
+
+        for instruction in &block.instructions {
+            let _ = writeln!(out, "    {}", self.print_instruction(instruction));
+        }
+
+        if let Some(terminator) = &block.terminator {
+            let _ = writeln!(out, "    {}", self.print_terminator(terminator));
+        }
+
+        out
+    }
+
+    pub fn print_instruction(&self, instruction: &Instruction) -> String {
+        let assign = |destination: &ValueId, rhs: String| {
+            format!("{} = {}", self.print_definition(*destination), rhs)
+        };
+
+        match instruction {
+            Instruction::StackAlloc { destination, count } => {
+                assign(destination, format!("stack_alloc {}", count))
+            }
+            Instruction::HeapAlloc { destination, count } => assign(
+                destination,
+                format!("heap_alloc {}", self.print_value(count)),
+            ),
+            Instruction::HeapFree { ptr } => {
+                format!("heap_free {}", self.value_name(*ptr))
+            }
+            Instruction::Store { ptr, value } => format!(
+                "store {}, {}",
+                self.value_name(*ptr),
+                self.print_value(value)
+            ),
+            Instruction::Load { destination, ptr } => {
+                assign(destination, format!("load {}", self.value_name(*ptr)))
+            }
+            Instruction::LoadConstant {
+                destination,
+                constant_id,
+            } => assign(destination, format!("load_constant c{}", constant_id.0)),
+            Instruction::FileOpen {
+                destination_fd,
+                path,
+                mode,
+            } => assign(
+                destination_fd,
+                format!(
+                    "file_open {}, {}",
+                    self.print_value(path),
+                    self.print_value(mode)
+                ),
+            ),
+            Instruction::FileWrite { fd, data, len } => format!(
+                "file_write {}, {}, {}",
+                self.value_name(*fd),
+                self.print_value(data),
+                self.print_value(len)
+            ),
+            Instruction::FileRead {
+                fd,
+                buffer,
+                len,
+                value_destination,
+            } => assign(
+                value_destination,
+                format!(
+                    "file_read {}, {}, {}",
+                    self.value_name(*fd),
+                    self.value_name(*buffer),
+                    self.print_value(len)
+                ),
+            ),
+            Instruction::FileClose { fd } => {
+                format!("file_close {}", self.value_name(*fd))
+            }
+            Instruction::SocketConnect {
+                address,
+                port,
+                value_destination,
+            } => assign(
+                value_destination,
+                format!(
+                    "socket_connect {}, {}",
+                    self.print_value(address),
+                    self.print_value(port)
+                ),
+            ),
+            Instruction::SocketSend { socket, data } => format!(
+                "socket_send {}, {}",
+                self.value_name(*socket),
+                self.print_value(data)
+            ),
+            Instruction::SocketClose { socket } => {
+                format!("socket_close {}", self.value_name(*socket))
+            }
+            Instruction::UnaryOp {
+                op_kind,
+                destination,
+                operand,
+            } => assign(
+                destination,
+                format!(
+                    "{} {}",
+                    unary_operation_name(op_kind),
+                    self.print_value(operand)
+                ),
+            ),
+            Instruction::BinaryOp {
+                op_kind,
+                destination,
+                left,
+                right,
+            } => assign(
+                destination,
+                format!(
+                    "{} {}, {}",
+                    binary_operation_name(op_kind),
+                    self.print_value(left),
+                    self.print_value(right)
+                ),
+            ),
+            Instruction::TypeCast {
+                destination,
+                operand,
+                target_type,
+            } => assign(
+                destination,
+                format!(
+                    "cast {} to {}",
+                    self.print_value(operand),
+                    self.print_type(target_type)
+                ),
+            ),
+            Instruction::FunctionCall {
+                destination,
+                function_rvalue,
+                args,
+            } => {
+                let call = format!(
+                    "call {}({})",
+                    self.print_value(function_rvalue),
+                    self.print_values(args)
+                );
+                match destination {
+                    Some(destination) => assign(destination, call),
+                    None => call,
+                }
+            }
+            Instruction::MakeClosure {
+                destination,
+                function,
+                environment,
+            } => assign(
+                destination,
+                format!(
+                    "make_closure {}, {}",
+                    self.function_name(*function),
+                    self.value_name(*environment)
+                ),
+            ),
+            Instruction::GetFieldPtr {
+                destination,
+                base_ptr,
+                field_index,
+            } => assign(
+                destination,
+                format!(
+                    "get_field_ptr {}, {}",
+                    self.value_name(*base_ptr),
+                    field_index
+                ),
+            ),
+            Instruction::GetElementPtr {
+                destination,
+                base_ptr,
+                index,
+            } => assign(
+                destination,
+                format!(
+                    "get_element_ptr {}, {}",
+                    self.value_name(*base_ptr),
+                    self.print_value(index)
+                ),
+            ),
+            Instruction::Nop => "nop".to_string(),
+        }
+    }
+
+    pub fn print_terminator(&self, terminator: &Terminator) -> String {
+        match terminator {
+            Terminator::Jump { target, args } => {
+                format!("jump {}", self.print_target(*target, args))
+            }
+            Terminator::CondJump {
+                condition,
+                true_target,
+                true_args,
+                false_target,
+                false_args,
+            } => format!(
+                "cond_jump {}, {}, {}",
+                self.print_value(condition),
+                self.print_target(*true_target, true_args),
+                self.print_target(*false_target, false_args)
+            ),
+            Terminator::Return { value: Some(value) } => {
+                format!("return {}", self.print_value(value))
+            }
+            Terminator::Return { value: None } => "return".to_string(),
+            Terminator::Unreachable => "unreachable".to_string(),
+        }
+    }
+
+    pub fn print_value(&self, value: &Value) -> String {
+        match value {
+            Value::VoidLiteral => "void".to_string(),
+            Value::BoolLiteral(b) => b.to_string(),
+            Value::NumberLiteral(kind) => match kind {
+                NumberKind::F32(n) => format!("{:?}f32", n),
+                NumberKind::F64(n) => format!("{:?}f64", n),
+                kind => kind.to_string(),
+            },
+            Value::Function(id) => self.function_name(*id),
+            Value::Use(id) => self.value_name(*id),
+        }
+    }
+
+    pub fn print_type(&self, ty: &Type) -> String {
+        match ty {
+            Type::Void => "void".to_string(),
+            Type::Bool => "bool".to_string(),
+            Type::U8 => "u8".to_string(),
+            Type::U16 => "u16".to_string(),
+            Type::U32 => "u32".to_string(),
+            Type::U64 => "u64".to_string(),
+            Type::USize => "usize".to_string(),
+            Type::ISize => "isize".to_string(),
+            Type::I8 => "i8".to_string(),
+            Type::I16 => "i16".to_string(),
+            Type::I32 => "i32".to_string(),
+            Type::I64 => "i64".to_string(),
+            Type::F32 => "f32".to_string(),
+            Type::F64 => "f64".to_string(),
+            Type::Unknown => "unknown".to_string(),
+            Type::Pointer {
+                constraint,
+                narrowed_to,
+            } => {
+                if constraint == narrowed_to {
+                    format!("ptr<{}>", self.print_type(constraint))
+                } else {
+                    format!(
+                        "ptr<{}, {}>",
+                        self.print_type(constraint),
+                        self.print_type(narrowed_to)
+                    )
+                }
+            }
+            Type::Struct(kind) => match kind {
+                StructKind::UserDefined(fields) => {
+                    let fields: Vec<String> = fields
+                        .iter()
+                        .map(|f| {
+                            format!(
+                                "{}: {}",
+                                self.program.string_interner.resolve(f.identifier.name),
+                                self.print_type(&f.ty)
+                            )
+                        })
+                        .collect();
+                    format!("{{ {} }}", fields.join(", "))
+                }
+                StructKind::Tag(tag) => self.print_tag(tag),
+                StructKind::Union { variants } => {
+                    let variants: Vec<String> =
+                        variants.iter().map(|v| self.print_tag(v)).collect();
+                    format!("({})", variants.join(" | "))
+                }
+                StructKind::List(item) => format!("list<{}>", self.print_type(item)),
+                StructKind::Slice(item) => format!("slice<{}>", self.print_type(item)),
+                StructKind::String => "string".to_string(),
+            },
+            Type::Fn(fn_type) => {
+                let params: Vec<String> = fn_type
+                    .params
+                    .iter()
+                    .map(|p| {
+                        format!(
+                            "{}: {}",
+                            self.program.string_interner.resolve(p.identifier.name),
+                            self.print_type(&p.ty)
+                        )
+                    })
+                    .collect();
+                format!(
+                    "fn({}): {}",
+                    params.join(", "),
+                    self.print_type(&fn_type.return_type)
+                )
+            }
+            Type::Buffer { size, alignment } => {
+                format!("buffer<{}, {}>", size, alignment)
+            }
+            Type::Array { item_type, size } => {
+                format!("array<{}, {}>", self.print_type(item_type), size)
+            }
+        }
+    }
+
+    fn print_tag(&self, tag: &TagType) -> String {
+        let name = self
+            .program
+            .string_interner
+            .resolve(self.program.tag_interner.resolve(tag.id));
+
+        match &tag.value_type {
+            Some(value_type) => format!("#{}({})", name, self.print_type(value_type)),
+            None => format!("#{}", name),
+        }
+    }
+
+    fn print_definition(&self, id: ValueId) -> String {
+        let ty = self.program.get_value_id_type(&id);
+        format!("{}: {}", self.value_name(id), self.print_type(&ty))
+    }
+
+    fn print_values(&self, values: &[Value]) -> String {
+        values
+            .iter()
+            .map(|v| self.print_value(v))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn print_target(&self, target: BasicBlockId, args: &[Value]) -> String {
+        if args.is_empty() {
+            self.block_name(target)
+        } else {
+            format!("{}({})", self.block_name(target), self.print_values(args))
+        }
+    }
+
+    fn value_name(&self, id: ValueId) -> String {
+        format!("v{}", id.0)
+    }
+
+    fn block_name(&self, id: BasicBlockId) -> String {
+        format!("bb{}", id.0)
+    }
+
+    /// Functions are referred to by name and DeclarationId, since names are only
+    /// unique within a module
+    fn function_name(&self, id: DeclarationId) -> String {
+        match self.program.declarations.get(&id) {
+            Some(CheckedDeclaration::Function(f)) => format!(
+                "@{}.{}",
+                self.program.string_interner.resolve(f.identifier.name),
+                id.0
+            ),
+            _ => format!("@{}", id.0),
+        }
+    }
+}
+
+pub fn unary_operation_name(op_kind: &UnaryOperationKind) -> &'static str {
+    match op_kind {
+        UnaryOperationKind::Not => "not",
+        UnaryOperationKind::Neg => "neg",
+        UnaryOperationKind::BitNot => "bitnot",
+    }
+}
+
+pub fn binary_operation_name(op_kind: &BinaryOperationKind) -> &'static str {
+    match op_kind {
+        BinaryOperationKind::Add => "add",
+        BinaryOperationKind::Subtract => "sub",
+        BinaryOperationKind::Multiply => "mul",
+        BinaryOperationKind::Divide => "div",
+        BinaryOperationKind::Modulo => "mod",
+        BinaryOperationKind::LessThan => "lt",
+        BinaryOperationKind::LessThanOrEqual => "le",
+        BinaryOperationKind::GreaterThan => "gt",
+        BinaryOperationKind::GreaterThanOrEqual => "ge",
+        BinaryOperationKind::Equal => "eq",
+        BinaryOperationKind::NotEqual => "ne",
+        BinaryOperationKind::BitAnd => "and",
+        BinaryOperationKind::BitOr => "or",
+        BinaryOperationKind::BitXor => "xor",
+        BinaryOperationKind::ShiftLeft => "shl",
+        BinaryOperationKind::ShiftRight => "shr",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{compile::check_source, hir::printer::print_program};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_print_program() {
+        let source = r#"
+fn add(a: i32, b: i32): i32 {
+    a + b
+}
+
+fn main(): bool {
+    let s = "hi";
+    add(1i32, 2i32) > 2i32
+}
+"#;
+        let (program, _) = check_source("print_program", source);
+
+        let expected = r#"const c0 = b"hi"
+
+fn @add.0(a: i32, b: i32): i32 {
+bb0(v0: i32, v2: i32):
+    v1: ptr<i32> = stack_alloc 1
+    store v1, v0
+    v3: ptr<i32> = stack_alloc 1
+    store v3, v2
+    v4: i32 = load v1
+    v5: i32 = load v3
+    v6: i32 = add v4, v5
+    return v6
+}
+
+fn @main.2(): bool {
+bb0:
+    v7: ptr<string> = heap_alloc 1usize
+    v8: ptr<bool> = get_field_ptr v7, 0
+    store v8, false
+    v9: ptr<usize> = get_field_ptr v7, 1
+    store v9, 2usize
+    v10: ptr<ptr<u8>> = get_field_ptr v7, 2
+    v11: ptr<u8> = load_constant c0
+    store v10, v11
+    v12: ptr<ptr<string>> = stack_alloc 1
+    store v12, v7
+    v13: ptr<ptr<string>> = cast v12 to ptr<ptr<string>>
+    v14: i32 = call @add.0(1i32, 2i32)
+    v15: bool = gt v14, 2i32
+    return v15
+}
+"#;
+
+        assert_eq!(print_program(&program), expected);
+    }
+
+    #[test]
+    fn test_print_union_and_block_params() {
+        let source = r#"
+fn pick(flag: bool): #A(i32) | #B {
+    if flag { #A(1i32) } else { #B }
+}
+
+fn main(): i32 {
+    0i32
+}
+"#;
+        let (program, _) = check_source("print_union", source);
+
+        let expected = r#"fn @pick.0(flag: bool): (#A(i32) | #B) {
+bb0(v0: bool):
+    v1: ptr<bool> = stack_alloc 1
+    store v1, v0
+    v2: bool = load v1
+    cond_jump v2, bb2, bb3
+bb1(v10: (#A(i32) | #B)):
+    return v10
+bb2:
+    v3: ptr<#A(i32)> = stack_alloc 1
+    v4: ptr<u16> = get_field_ptr v3, 0
+    store v4, 0u16
+    v5: ptr<i32> = get_field_ptr v3, 1
+    store v5, 1i32
+    v6: #A(i32) = load v3
+    jump bb1(v6)
+bb3:
+    v7: ptr<#B> = stack_alloc 1
+    v8: ptr<u16> = get_field_ptr v7, 0
+    store v8, 1u16
+    v9: #B = load v7
+    jump bb1(v9)
+}
+
+fn @main.1(): i32 {
+bb0:
+    return 0i32
+}
+"#;
+
+        assert_eq!(print_program(&program), expected);
+    }
+}
@@ -1,9 +1,17 @@
 use crate::{
-    ast::expr::{Expr, ExprKind},
+    ast::{
+        expr::{Expr, ExprKind},
+        stmt::{CompoundOperator, IncrementOperator},
+        IdentifierNode,
+    },
     hir::{
-        cfg::{Value, ValueId},
+        cfg::{BinaryOperationKind, Value, ValueId},
         errors::{SemanticError, SemanticErrorKind},
-        types::{checked_declaration::CheckedDeclaration, checked_type::Type},
+        types::{
+            checked_declaration::CheckedDeclaration,
+            checked_type::{StructKind, Type},
+        },
+        utils::{check_is_assignable::check_is_assignable, numeric::one_of},
         FunctionBuilder, HIRContext,
     },
 };
@@ -16,7 +24,7 @@ impl FunctionBuilder {
     ) -> Result<(ValueId, ValueId), SemanticError> {
         match expr.kind {
             ExprKind::Identifier(identifier) => {
-                let id = ctx.module_builder.scope_lookup(identifier.name);
+                let id = ctx.module_builder.resolve_identifier(identifier);
                 let declaration = id.map(|id| ctx.program_builder.get_declaration(id));
 
                 let decl = match declaration {
@@ -50,24 +58,194 @@ impl FunctionBuilder {
         }
     }
 
+    /// The pointer an assignment writes through. Unlike `build_lvalue_expr` it also
+    /// reaches elements, which reading an element as a value does not go through.
+    fn build_assignment_target(
+        &mut self,
+        ctx: &mut HIRContext,
+        expr: Expr,
+    ) -> Result<(ValueId, ValueId), SemanticError> {
+        match expr.kind {
+            ExprKind::Index { left, index } => {
+                let element_ptr = self.build_element_ptr(ctx, *left, *index)?;
+
+                Ok((element_ptr, element_ptr))
+            }
+            ExprKind::Access { left, field } => {
+                let (base_ptr_id, _) = self.build_assignment_target(ctx, *left)?;
+
+                let field_ptr = self.emit_get_field_ptr(ctx, base_ptr_id, field)?;
+
+                Ok((field_ptr, field_ptr))
+            }
+            _ => self.build_lvalue_expr(ctx, expr),
+        }
+    }
+
+    /// The pointer to a variable or to a field of one, `None` for any other
+    /// expression. Unlike indexing, reaching it has no side effects, so callers can
+    /// evaluate the expression as a value instead when this fails.
+    pub fn build_place_ptr(
+        &mut self,
+        ctx: &mut HIRContext,
+        expr: &Expr,
+    ) -> Option<(ValueId, ValueId)> {
+        fn is_place(expr: &Expr) -> bool {
+            match &expr.kind {
+                ExprKind::Identifier(_) => true,
+                ExprKind::Access { left, .. } => is_place(left),
+                _ => false,
+            }
+        }
+
+        if !is_place(expr) {
+            return None;
+        }
+
+        self.build_lvalue_expr(ctx, expr.clone()).ok()
+    }
+
+    /// The pointer to an element of a list, slice or array, an index out of bounds
+    /// aborts the program
+    fn build_element_ptr(
+        &mut self,
+        ctx: &mut HIRContext,
+        left: Expr,
+        index: Expr,
+    ) -> Result<ValueId, SemanticError> {
+        let left_span = left.span;
+        let index_span = index.span;
+
+        // Arrays are changed in place, lists through the pointer they hold and slices
+        // through the buffer they borrow
+        let list_value = match left.kind {
+            ExprKind::Identifier(_)
+            | ExprKind::Access { .. }
+            | ExprKind::Index { .. } => {
+                let (slot, _) = self.build_assignment_target(ctx, left)?;
+                match ctx.program_builder.get_value_id_type(&slot) {
+                    Type::Pointer { narrowed_to, .. }
+                        if matches!(*narrowed_to, Type::Array { .. }) =>
+                    {
+                        let index_value = self.build_index_value(ctx, index)?;
+                        return self.build_array_item_ptr(
+                            ctx,
+                            slot,
+                            index_value,
+                            index_span,
+                        );
+                    }
+                    Type::Pointer { narrowed_to, .. }
+                        if matches!(*narrowed_to, Type::Struct(StructKind::Slice(_))) =>
+                    {
+                        Value::Use(slot)
+                    }
+                    _ => Value::Use(self.emit_load(ctx, slot)),
+                }
+            }
+            _ => self.build_expr(ctx, left),
+        };
+        let list_type = ctx.program_builder.get_value_type(&list_value);
+
+        let list_ptr = match (&list_value, &list_type) {
+            (Value::Use(id), Type::Pointer { narrowed_to, .. })
+                if matches!(
+                    &**narrowed_to,
+                    Type::Struct(StructKind::List(_) | StructKind::Slice(_))
+                ) =>
+            {
+                *id
+            }
+            _ => {
+                return Err(SemanticError {
+                    kind: SemanticErrorKind::CannotIndex(list_type),
+                    span: left_span,
+                })
+            }
+        };
+
+        let index_value = self.build_index_value(ctx, index)?;
+
+        let len_field = IdentifierNode {
+            name: ctx.program_builder.common_identifiers.len,
+            span: left_span,
+        };
+        let len_ptr = self.emit_get_field_ptr(ctx, list_ptr, len_field)?;
+        let len = Value::Use(self.emit_load(ctx, len_ptr));
+        let in_bounds = self.emit_binary_op(
+            ctx,
+            BinaryOperationKind::LessThan,
+            index_value.clone(),
+            index_span,
+            len,
+            left_span,
+        )?;
+
+        self.build_abort_unless(ctx, Value::Use(in_bounds));
+        let in_bounds_block = self.current_block_id;
+
+        let ptr_field = IdentifierNode {
+            name: ctx.program_builder.common_identifiers.ptr,
+            span: left_span,
+        };
+        let list_ptr = self.use_value_in_block(ctx, in_bounds_block, list_ptr);
+        let buffer_ptr_ptr = self.emit_get_field_ptr(ctx, list_ptr, ptr_field)?;
+        let buffer_ptr = self.emit_load(ctx, buffer_ptr_ptr);
+
+        let index_value = match index_value {
+            Value::Use(id) => {
+                Value::Use(self.use_value_in_block(ctx, in_bounds_block, id))
+            }
+            literal => literal,
+        };
+
+        self.emit_get_element_ptr(ctx, buffer_ptr, index_value)
+    }
+
+    fn build_index_value(
+        &mut self,
+        ctx: &mut HIRContext,
+        index: Expr,
+    ) -> Result<Value, SemanticError> {
+        let index_span = index.span;
+        let index_value = self.build_expr(ctx, index);
+        let index_type = ctx.program_builder.get_value_type(&index_value);
+        if !check_is_assignable(&index_type, &Type::USize) {
+            return Err(SemanticError {
+                kind: SemanticErrorKind::TypeMismatch {
+                    expected: Type::USize,
+                    received: index_type,
+                },
+                span: index_span,
+            });
+        }
+
+        Ok(index_value)
+    }
+
     pub fn build_assignment_stmt(
         &mut self,
         ctx: &mut HIRContext,
         target: Expr,
         value: Expr,
     ) {
+        if let Err(e) = check_target_is_mutable(ctx, &target) {
+            ctx.module_builder.errors.push(e);
+            return;
+        }
+
         let value_span = value.span;
         let source_val = self.build_expr(ctx, value);
         let source_type = ctx.program_builder.get_value_type(&source_val);
 
-        let (destination_ptr, root_id) = match self.build_lvalue_expr(ctx, target.clone())
-        {
-            Ok(ids) => ids,
-            Err(e) => {
-                ctx.module_builder.errors.push(e);
-                return;
-            }
-        };
+        let (destination_ptr, root_id) =
+            match self.build_assignment_target(ctx, target.clone()) {
+                Ok(ids) => ids,
+                Err(e) => {
+                    ctx.module_builder.errors.push(e);
+                    return;
+                }
+            };
 
         self.emit_store(ctx, destination_ptr, source_val, value_span);
 
@@ -89,4 +267,159 @@ impl FunctionBuilder {
             self.map_value(self.current_block_id, root_id, narrowed_ptr);
         }
     }
+
+    /// `target op= value`, the target is evaluated once and read before the value
+    pub fn build_compound_assignment_stmt(
+        &mut self,
+        ctx: &mut HIRContext,
+        target: Expr,
+        operator: CompoundOperator,
+        value: Expr,
+    ) {
+        if let Err(e) = check_target_is_mutable(ctx, &target) {
+            ctx.module_builder.errors.push(e);
+            return;
+        }
+
+        let target_span = target.span;
+        let value_span = value.span;
+
+        let (destination_ptr, _) = match self.build_assignment_target(ctx, target) {
+            Ok(ids) => ids,
+            Err(e) => {
+                ctx.module_builder.errors.push(e);
+                return;
+            }
+        };
+
+        let current_value = Value::Use(self.emit_load(ctx, destination_ptr));
+        let value = self.build_expr(ctx, value);
+
+        let op_kind = match operator {
+            CompoundOperator::Add => BinaryOperationKind::Add,
+            CompoundOperator::Subtract => BinaryOperationKind::Subtract,
+            CompoundOperator::Multiply => BinaryOperationKind::Multiply,
+            CompoundOperator::Divide => BinaryOperationKind::Divide,
+            CompoundOperator::Modulo => BinaryOperationKind::Modulo,
+        };
+
+        let result = match self.emit_binary_op(
+            ctx,
+            op_kind,
+            current_value,
+            target_span,
+            value,
+            value_span,
+        ) {
+            Ok(id) => id,
+            Err(e) => {
+                ctx.module_builder.errors.push(e);
+                return;
+            }
+        };
+
+        // The value may have been built in another block, e.g. an `if` expression
+        let destination_ptr =
+            self.use_value_in_block(ctx, self.current_block_id, destination_ptr);
+        self.emit_store(ctx, destination_ptr, Value::Use(result), value_span);
+    }
+
+    /// `target++` or `target--`, changes the target by the `1` of its own type
+    pub fn build_increment_stmt(
+        &mut self,
+        ctx: &mut HIRContext,
+        target: Expr,
+        operator: IncrementOperator,
+    ) {
+        if let Err(e) = check_target_is_mutable(ctx, &target) {
+            ctx.module_builder.errors.push(e);
+            return;
+        }
+
+        let target_span = target.span;
+
+        let (destination_ptr, _) = match self.build_assignment_target(ctx, target) {
+            Ok(ids) => ids,
+            Err(e) => {
+                ctx.module_builder.errors.push(e);
+                return;
+            }
+        };
+
+        let current_value = Value::Use(self.emit_load(ctx, destination_ptr));
+        let current_type = ctx.program_builder.get_value_type(&current_value);
+        let Some(one) = one_of(&current_type) else {
+            if current_type != Type::Unknown {
+                ctx.module_builder.errors.push(SemanticError {
+                    kind: SemanticErrorKind::ExpectedANumericOperand,
+                    span: target_span,
+                });
+            }
+            return;
+        };
+
+        let op_kind = match operator {
+            IncrementOperator::Increment => BinaryOperationKind::Add,
+            IncrementOperator::Decrement => BinaryOperationKind::Subtract,
+        };
+
+        match self.emit_binary_op(
+            ctx,
+            op_kind,
+            current_value,
+            target_span,
+            Value::NumberLiteral(one),
+            target_span,
+        ) {
+            Ok(result) => {
+                self.emit_store(ctx, destination_ptr, Value::Use(result), target_span)
+            }
+            Err(e) => ctx.module_builder.errors.push(e),
+        }
+    }
+}
+
+/// Assignments go through a mutable variable, also when they change a field or an
+/// element reached through it. `self` itself is a copy of the receiver, replacing it
+/// would not change the caller's value.
+fn check_target_is_mutable(ctx: &HIRContext, target: &Expr) -> Result<(), SemanticError> {
+    if let ExprKind::Identifier(identifier) = target.kind {
+        if identifier.name == ctx.program_builder.common_identifiers.self_ {
+            return Err(SemanticError {
+                kind: SemanticErrorKind::AssignmentToReceiver,
+                span: target.span,
+            });
+        }
+    }
+
+    match immutable_root(ctx, target) {
+        Some(identifier) => Err(SemanticError {
+            kind: SemanticErrorKind::AssignmentToImmutable(identifier),
+            span: target.span,
+        }),
+        None => Ok(()),
+    }
+}
+
+/// The immutable variable or constant `expr` reaches its place through, `None` for
+/// mutable variables and for temporary values
+pub fn immutable_root(ctx: &HIRContext, expr: &Expr) -> Option<IdentifierNode> {
+    let mut root = expr;
+    while let ExprKind::Access { left, .. } | ExprKind::Index { left, .. } = &root.kind {
+        root = left;
+    }
+
+    let ExprKind::Identifier(identifier) = root.kind else {
+        return None;
+    };
+
+    match ctx
+        .module_builder
+        .scope_lookup(identifier.name)
+        .map(|id| ctx.program_builder.get_declaration(id))
+    {
+        Some(CheckedDeclaration::Var(var)) if !var.is_mutable => Some(identifier),
+        Some(CheckedDeclaration::Const(_)) => Some(identifier),
+        _ => None,
+    }
 }
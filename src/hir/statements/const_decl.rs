@@ -0,0 +1,166 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    ast::{
+        decl::{ConstDecl, Declaration},
+        visitor::ASTVisitor,
+        DeclarationId, IdentifierNode,
+    },
+    compile::interner::StringId,
+    hir::{
+        errors::{SemanticError, SemanticErrorKind},
+        types::checked_declaration::{CheckedConstDecl, CheckedDeclaration},
+        utils::{
+            check_is_assignable::check_is_assignable, check_type::check_type_annotation,
+            const_eval::eval_const_expr,
+        },
+        HIRContext,
+    },
+};
+
+/// Evaluates the initializer of a constant and declares it in the current scope.
+/// Constants of a block can only refer to constants declared before them, the file
+/// scope orders its constants with [`FileConstants`].
+pub fn build_const_decl(ctx: &mut HIRContext, const_decl: ConstDecl) {
+    let value_span = const_decl.value.span;
+    let value = match eval_const_expr(ctx, &const_decl.value) {
+        Ok(value) => value,
+        Err(e) => {
+            ctx.module_builder.errors.push(e);
+            return;
+        }
+    };
+
+    if let Some(constraint) = &const_decl.constraint {
+        let expected = check_type_annotation(ctx, constraint);
+        let received = value.get_type();
+
+        if !check_is_assignable(&received, &expected) {
+            ctx.module_builder.errors.push(SemanticError {
+                kind: SemanticErrorKind::TypeMismatch { expected, received },
+                span: value_span,
+            });
+            return;
+        }
+    }
+
+    let checked_const_decl = CheckedConstDecl {
+        id: const_decl.id,
+        identifier: const_decl.identifier,
+        documentation: const_decl.documentation,
+        value,
+        is_exported: const_decl.is_exported,
+    };
+
+    ctx.module_builder.scope_insert(
+        ctx.program_builder,
+        const_decl.identifier,
+        CheckedDeclaration::Const(checked_const_decl),
+    );
+}
+
+/// Builds the constants of a file scope after the constants their initializers refer
+/// to, so a constant can refer to one declared later in the file. Constants built from
+/// imported names wait until the imports of the file are resolved.
+pub struct FileConstants<'a> {
+    declarations: HashMap<StringId, &'a ConstDecl>,
+    /// Names imported by the file, while its imports are not resolved yet
+    imported: HashSet<StringId>,
+    /// Constants which refer to an imported name, directly or through other constants
+    deferred: Vec<&'a ConstDecl>,
+    /// Constants whose initializers are waiting on their references, innermost last
+    building: Vec<&'a ConstDecl>,
+    visited: HashSet<DeclarationId>,
+    /// Constants of a cycle and the constants referring to them, which are reported
+    /// once with the cycle
+    failed: HashSet<DeclarationId>,
+}
+
+impl<'a> FileConstants<'a> {
+    pub fn new(declarations: &'a [Declaration], imported: HashSet<StringId>) -> Self {
+        let mut constants = HashMap::new();
+        for decl in declarations {
+            if let Declaration::Const(d) = decl {
+                constants.entry(d.identifier.name).or_insert(d);
+            }
+        }
+
+        Self {
+            declarations: constants,
+            imported,
+            deferred: vec![],
+            building: vec![],
+            visited: HashSet::new(),
+            failed: HashSet::new(),
+        }
+    }
+
+    pub fn build(&mut self, ctx: &mut HIRContext, const_decl: &'a ConstDecl) {
+        if !self.visited.insert(const_decl.id) {
+            return;
+        }
+
+        let mut references = ConstReferences(vec![]);
+        references.visit_expr(&const_decl.value);
+
+        let mut is_deferred = false;
+        self.building.push(const_decl);
+        for reference in references.0 {
+            let Some(&dependency) = self.declarations.get(&reference.name) else {
+                is_deferred |= self.imported.contains(&reference.name);
+                continue;
+            };
+
+            if let Some(start) = self.building.iter().position(|c| c.id == dependency.id)
+            {
+                let cycle = &self.building[start..];
+                self.failed.extend(cycle.iter().map(|c| c.id));
+                ctx.module_builder.errors.push(SemanticError {
+                    kind: SemanticErrorKind::RecursiveConstant(
+                        cycle.iter().map(|c| c.identifier).collect(),
+                    ),
+                    span: reference.span,
+                });
+                continue;
+            }
+
+            self.build(ctx, dependency);
+            if self.failed.contains(&dependency.id) {
+                self.failed.insert(const_decl.id);
+            }
+            is_deferred |= self.deferred.iter().any(|d| d.id == dependency.id);
+        }
+        self.building.pop();
+
+        if self.failed.contains(&const_decl.id) {
+            return;
+        }
+
+        if is_deferred {
+            self.deferred.push(const_decl);
+        } else {
+            build_const_decl(ctx, const_decl.clone());
+        }
+    }
+
+    /// Builds the constants which waited on imported names, once the imports of the
+    /// file are resolved
+    pub fn build_imported(mut self, ctx: &mut HIRContext) {
+        self.imported.clear();
+        let deferred = std::mem::take(&mut self.deferred);
+        for const_decl in &deferred {
+            self.visited.remove(&const_decl.id);
+        }
+        for const_decl in deferred {
+            self.build(ctx, const_decl);
+        }
+    }
+}
+
+struct ConstReferences(Vec<IdentifierNode>);
+
+impl<'ast> ASTVisitor<'ast> for ConstReferences {
+    fn visit_ident_usage(&mut self, id: IdentifierNode) {
+        self.0.push(id);
+    }
+}
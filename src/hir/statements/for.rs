@@ -0,0 +1,366 @@
+// src/hir/statements/for.rs
+
+use std::collections::HashSet;
+
+use crate::{
+    ast::{
+        expr::{BlockContents, Expr, ExprKind},
+        IdentifierNode, Span,
+    },
+    hir::{
+        cfg::{BinaryOperationKind, Terminator, Value, ValueId},
+        errors::{SemanticError, SemanticErrorKind},
+        types::{
+            checked_declaration::CheckedParam,
+            checked_type::{StructKind, Type},
+        },
+        utils::{
+            check_binary_numeric_op::check_binary_numeric_operation,
+            numeric::{is_integer, one_of},
+            scope::ScopeKind,
+        },
+        FunctionBuilder, HIRContext,
+    },
+    tokenize::NumberKind,
+};
+
+impl FunctionBuilder {
+    pub fn build_for_stmt(
+        &mut self,
+        ctx: &mut HIRContext,
+        item: IdentifierNode,
+        iterable: Box<Expr>,
+        body: BlockContents,
+    ) {
+        match iterable.kind {
+            ExprKind::Range { start, end } => {
+                self.build_range_loop(ctx, item, *start, *end, body)
+            }
+            _ => self.build_list_loop(ctx, item, *iterable, body),
+        }
+    }
+
+    /// `for i in start..end`, the bounds are evaluated once
+    fn build_range_loop(
+        &mut self,
+        ctx: &mut HIRContext,
+        item: IdentifierNode,
+        start: Expr,
+        end: Expr,
+        body: BlockContents,
+    ) {
+        let start_span = start.span;
+        let end_span = end.span;
+        let start_value = self.build_expr(ctx, start);
+        let end_value = self.build_expr(ctx, end);
+
+        let start_type = ctx.program_builder.get_value_type(&start_value);
+        let end_type = ctx.program_builder.get_value_type(&end_value);
+
+        if start_type == Type::Unknown || end_type == Type::Unknown {
+            return;
+        }
+
+        for (ty, span) in [(&start_type, start_span), (&end_type, end_span)] {
+            if !is_integer(ty) {
+                ctx.module_builder.errors.push(SemanticError {
+                    kind: SemanticErrorKind::TypeMismatchExpectedOneOf {
+                        expected: integer_types(),
+                        received: ty.clone(),
+                    },
+                    span,
+                });
+                return;
+            }
+        }
+
+        let counter_type = match check_binary_numeric_operation(
+            &start_type,
+            start_span,
+            &end_type,
+            end_span,
+        ) {
+            Ok(ty) => ty,
+            Err(e) => {
+                ctx.module_builder.errors.push(e);
+                return;
+            }
+        };
+
+        let start_value = if start_type == counter_type {
+            start_value
+        } else {
+            Value::Use(self.emit_type_cast(
+                ctx,
+                start_value,
+                start_span,
+                counter_type.clone(),
+            ))
+        };
+        let end_value = if end_type == counter_type {
+            end_value
+        } else {
+            Value::Use(self.emit_type_cast(ctx, end_value, end_span, counter_type))
+        };
+
+        self.build_counting_loop(
+            ctx,
+            item,
+            start_value,
+            body,
+            |this, ctx| match &end_value {
+                Value::Use(id) => {
+                    Value::Use(this.use_value_in_block(ctx, this.current_block_id, *id))
+                }
+                literal => literal.clone(),
+            },
+            |_, _, index| index,
+        );
+    }
+
+    /// `for x in list` or `for x in slice`, the length is read before every
+    /// iteration
+    fn build_list_loop(
+        &mut self,
+        ctx: &mut HIRContext,
+        item: IdentifierNode,
+        iterable: Expr,
+        body: BlockContents,
+    ) {
+        let iterable_span = iterable.span;
+        let list_value = self.build_expr(ctx, iterable);
+        let list_type = ctx.program_builder.get_value_type(&list_value);
+
+        let list_ptr = match (&list_value, &list_type) {
+            (_, Type::Unknown) => return,
+            (_, Type::Array { size, .. }) => {
+                let size = *size;
+                return self.build_array_loop(ctx, item, list_value, size, body);
+            }
+            (Value::Use(id), Type::Pointer { narrowed_to, .. })
+                if matches!(&**narrowed_to, Type::Struct(StructKind::List(_))) =>
+            {
+                *id
+            }
+            // Slices have the same len and ptr fields as lists once they are spilled
+            (_, Type::Struct(StructKind::Slice(_))) => {
+                let slice_ptr = self.emit_stack_alloc(ctx, list_type.clone(), 1);
+                self.emit_store(ctx, slice_ptr, list_value.clone(), iterable_span);
+                slice_ptr
+            }
+            _ => {
+                ctx.module_builder.errors.push(SemanticError {
+                    kind: SemanticErrorKind::CannotIterate(list_type),
+                    span: iterable_span,
+                });
+                return;
+            }
+        };
+
+        let len_field = IdentifierNode {
+            name: ctx.program_builder.common_identifiers.len,
+            span: iterable_span,
+        };
+        let ptr_field = IdentifierNode {
+            name: ctx.program_builder.common_identifiers.ptr,
+            span: iterable_span,
+        };
+
+        self.build_counting_loop(
+            ctx,
+            item,
+            Value::NumberLiteral(NumberKind::USize(0)),
+            body,
+            |this, ctx| {
+                let list_ptr =
+                    this.use_value_in_block(ctx, this.current_block_id, list_ptr);
+                let len_ptr = this
+                    .emit_get_field_ptr(ctx, list_ptr, len_field)
+                    .expect("INTERNAL COMPILER ERROR: Expected list to have a len field");
+                Value::Use(this.emit_load(ctx, len_ptr))
+            },
+            |this, ctx, index| {
+                let list_ptr =
+                    this.use_value_in_block(ctx, this.current_block_id, list_ptr);
+                let buffer_ptr_ptr = this
+                    .emit_get_field_ptr(ctx, list_ptr, ptr_field)
+                    .expect("INTERNAL COMPILER ERROR: Expected list to have a ptr field");
+                let buffer_ptr = this.emit_load(ctx, buffer_ptr_ptr);
+                let element_ptr = this
+                    .emit_get_element_ptr(ctx, buffer_ptr, index)
+                    .expect("INTERNAL COMPILER ERROR: Expected a usize list index");
+                Value::Use(this.emit_load(ctx, element_ptr))
+            },
+        );
+    }
+
+    /// `for x in array`, the loop iterates over a copy of the array
+    fn build_array_loop(
+        &mut self,
+        ctx: &mut HIRContext,
+        item: IdentifierNode,
+        array_value: Value,
+        size: usize,
+        body: BlockContents,
+    ) {
+        let array_type = ctx.program_builder.get_value_type(&array_value);
+        let array_ptr = self.emit_stack_alloc(ctx, array_type, 1);
+        self.emit_store(ctx, array_ptr, array_value, item.span);
+        let items_ptr = self.emit_array_items_ptr(ctx, array_ptr);
+
+        self.build_counting_loop(
+            ctx,
+            item,
+            Value::NumberLiteral(NumberKind::USize(0)),
+            body,
+            |_, _| Value::NumberLiteral(NumberKind::USize(size)),
+            |this, ctx, index| {
+                let items_ptr =
+                    this.use_value_in_block(ctx, this.current_block_id, items_ptr);
+                let item_ptr = this
+                    .emit_get_element_ptr(ctx, items_ptr, index)
+                    .expect("INTERNAL COMPILER ERROR: Expected a usize array index");
+                Value::Use(this.emit_load(ctx, item_ptr))
+            },
+        );
+    }
+
+    /// Lowers a loop over a counter which starts at `start` and is incremented while
+    /// it is less than `bound`. `item_value` gets the counter and returns the value
+    /// the item is bound to in the body.
+    ///
+    /// ```text
+    /// header: if counter < bound { body } else { exit }
+    /// body:   item = item_value(counter); ...; latch
+    /// latch:  counter = counter + 1; header
+    /// ```
+    fn build_counting_loop(
+        &mut self,
+        ctx: &mut HIRContext,
+        item: IdentifierNode,
+        start: Value,
+        body: BlockContents,
+        bound: impl Fn(&mut Self, &mut HIRContext) -> Value,
+        item_value: impl Fn(&mut Self, &mut HIRContext, Value) -> Value,
+    ) {
+        let counter_type = ctx.program_builder.get_value_type(&start);
+        let one = one_of(&counter_type).unwrap_or_else(|| {
+            panic!(
+                "INTERNAL COMPILER ERROR: Expected loop counter to be an integer, found \
+                 {:?}",
+                counter_type
+            )
+        });
+
+        let counter_ptr = self.emit_stack_alloc(ctx, counter_type, 1);
+        self.emit_store(ctx, counter_ptr, start, item.span);
+
+        let header_block = self.new_basic_block();
+        let body_block = self.new_basic_block();
+        let latch_block = self.new_basic_block();
+        let exit_block = self.new_basic_block();
+
+        self.set_basic_block_terminator(Terminator::Jump {
+            target: header_block,
+            args: vec![],
+        });
+
+        self.use_basic_block(header_block);
+
+        let counter = self.load_counter(ctx, counter_ptr);
+        let bound_value = bound(self, ctx);
+        let condition = match self.emit_binary_op(
+            ctx,
+            BinaryOperationKind::LessThan,
+            counter,
+            item.span,
+            bound_value,
+            item.span,
+        ) {
+            Ok(id) => Value::Use(id),
+            Err(e) => Value::Use(self.report_error_and_get_poison(ctx, e)),
+        };
+
+        self.set_basic_block_terminator(Terminator::CondJump {
+            condition,
+            true_target: body_block,
+            true_args: vec![],
+            false_target: exit_block,
+            false_args: vec![],
+        });
+
+        self.seal_block(ctx, body_block);
+        self.use_basic_block(body_block);
+
+        ctx.module_builder.enter_scope(ScopeKind::While {
+            break_target: exit_block,
+            continue_target: latch_block,
+        });
+
+        let counter = self.load_counter(ctx, counter_ptr);
+        let value = item_value(self, ctx, counter);
+        let param = CheckedParam {
+            identifier: item,
+            ty: ctx.program_builder.get_value_type(&value),
+        };
+        self.bind_local(ctx, &param, value, false);
+
+        let _ = self.build_codeblock_expr(ctx, body);
+
+        ctx.module_builder.exit_scope();
+
+        if self.get_current_basic_block().terminator.is_none() {
+            self.set_basic_block_terminator(Terminator::Jump {
+                target: latch_block,
+                args: vec![],
+            });
+        }
+
+        self.seal_block(ctx, latch_block);
+        self.use_basic_block(latch_block);
+
+        let counter = self.load_counter(ctx, counter_ptr);
+        let next = self
+            .emit_binary_op(
+                ctx,
+                BinaryOperationKind::Add,
+                counter,
+                item.span,
+                Value::NumberLiteral(one),
+                item.span,
+            )
+            .expect("INTERNAL COMPILER ERROR: Expected loop counter to be numeric");
+        let counter_ptr = self.use_value_in_block(ctx, latch_block, counter_ptr);
+        self.emit_store(ctx, counter_ptr, Value::Use(next), Span::default());
+
+        self.set_basic_block_terminator(Terminator::Jump {
+            target: header_block,
+            args: vec![],
+        });
+
+        self.seal_block(ctx, header_block);
+        self.use_basic_block(exit_block);
+        self.seal_block(ctx, exit_block);
+    }
+
+    fn load_counter(&mut self, ctx: &mut HIRContext, counter_ptr: ValueId) -> Value {
+        let counter_ptr =
+            self.use_value_in_block(ctx, self.current_block_id, counter_ptr);
+        Value::Use(self.emit_load(ctx, counter_ptr))
+    }
+}
+
+fn integer_types() -> HashSet<Type> {
+    HashSet::from([
+        Type::I8,
+        Type::I16,
+        Type::I32,
+        Type::I64,
+        Type::ISize,
+        Type::U8,
+        Type::U16,
+        Type::U32,
+        Type::U64,
+        Type::USize,
+    ])
+}
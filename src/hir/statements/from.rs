@@ -1,15 +1,27 @@
+use std::path::{Path, PathBuf};
+
 use crate::{
     ast::{IdentifierNode, Span, StringNode},
     hir::{
         errors::{SemanticError, SemanticErrorKind},
-        HIRContext,
+        types::checked_declaration::{CheckedDeclaration, CheckedModuleDecl},
+        HIRContext, SymbolReference,
     },
 };
 
+/// The file a `from` statement of the module at `module_path` refers to
+pub fn import_path(module_path: &Path, path: &str) -> PathBuf {
+    let mut target_path = module_path.to_path_buf();
+    target_path.pop();
+    target_path.push(path);
+    target_path
+}
+
 pub fn build_from_stmt(
     ctx: &mut HIRContext,
     path: StringNode,
     identifiers: Vec<(IdentifierNode, Option<IdentifierNode>)>,
+    namespace: Option<IdentifierNode>,
     span: Span,
 ) {
     if !ctx.module_builder.is_file_scope() {
@@ -20,9 +32,7 @@ pub fn build_from_stmt(
         return;
     }
 
-    let mut target_path = ctx.module_builder.module.path.clone();
-    target_path.pop();
-    target_path.push(path.value);
+    let target_path = import_path(&ctx.module_builder.module.path, &path.value);
 
     let canonical_path = match target_path.canonicalize() {
         Ok(p) => p,
@@ -35,12 +45,40 @@ pub fn build_from_stmt(
         }
     };
 
+    if let Some(namespace) = namespace {
+        if !ctx.program_builder.modules.contains_key(&canonical_path) {
+            ctx.module_builder.errors.push(SemanticError {
+                kind: SemanticErrorKind::ModuleNotFound(canonical_path),
+                span: path.span,
+            });
+            return;
+        }
+
+        let module_decl = CheckedModuleDecl {
+            id: ctx.program_builder.new_declaration_id(),
+            identifier: namespace,
+            path: canonical_path,
+        };
+        ctx.module_builder.scope_insert(
+            ctx.program_builder,
+            namespace,
+            CheckedDeclaration::Module(module_decl),
+        );
+        return;
+    }
+
     let target_module = ctx.program_builder.modules.get(&canonical_path);
 
     match target_module {
         Some(m) => {
             for (imported_ident, alias) in identifiers {
                 if let Some(decl_id) = m.resolve_export(imported_ident.name) {
+                    if alias.is_some() {
+                        ctx.module_builder.references.push(SymbolReference {
+                            span: imported_ident.span,
+                            decl_id,
+                        });
+                    }
                     let name_in_current_scope = alias.unwrap_or(imported_ident);
 
                     ctx.module_builder.scope_map(name_in_current_scope, decl_id);
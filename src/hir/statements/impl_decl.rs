@@ -0,0 +1,102 @@
+use crate::{
+    ast::decl::ImplDecl,
+    hir::{
+        errors::{SemanticError, SemanticErrorKind},
+        types::{
+            checked_declaration::{CheckedDeclaration, CheckedFnDecl},
+            checked_type::Type,
+        },
+        utils::check_type::{
+            check_params, check_type_annotation, check_type_identifier_annotation,
+        },
+        HIRContext, Method, SymbolReference,
+    },
+};
+
+/// Checks the signatures of the functions of an impl block and attaches them to the
+/// type of its target. Their bodies are built with the other functions.
+pub fn build_impl_decl(ctx: &mut HIRContext, decl: &ImplDecl) {
+    let target_type =
+        match check_type_identifier_annotation(ctx, decl.target, decl.target.span) {
+            Ok(Type::Unknown) => return,
+            Ok(ty) => ty,
+            Err(e) => {
+                ctx.module_builder.errors.push(e);
+                return;
+            }
+        };
+
+    let self_name = ctx.program_builder.common_identifiers.self_;
+
+    for method in &decl.methods {
+        if !method.generic_params.is_empty() {
+            ctx.module_builder.errors.push(SemanticError {
+                kind: SemanticErrorKind::MethodCannotBeGeneric,
+                span: method.identifier.span,
+            });
+            continue;
+        }
+
+        let params = check_params(ctx, &method.params);
+        let return_type = check_type_annotation(ctx, &method.return_type);
+        let has_receiver = params
+            .first()
+            .is_some_and(|p| p.identifier.name == self_name);
+        let mutates_receiver =
+            has_receiver && method.params.first().is_some_and(|p| p.is_mutable);
+
+        let previous = ctx
+            .program_builder
+            .find_method(&target_type, method.identifier.name);
+
+        if let Some(previous) = previous {
+            let previous = ctx
+                .program_builder
+                .get_declaration(previous.decl_id)
+                .identifier()
+                .span;
+
+            ctx.module_builder.errors.push(SemanticError {
+                kind: SemanticErrorKind::DuplicateIdentifier {
+                    identifier: method.identifier,
+                    previous: Some(previous),
+                },
+                span: method.identifier.span,
+            });
+            continue;
+        }
+
+        ctx.program_builder
+            .methods
+            .entry(target_type.clone())
+            .or_default()
+            .insert(
+                method.identifier.name,
+                Method {
+                    decl_id: method.id,
+                    has_receiver,
+                    mutates_receiver,
+                },
+            );
+
+        ctx.program_builder.declarations.insert(
+            method.id,
+            CheckedDeclaration::Function(CheckedFnDecl {
+                id: method.id,
+                identifier: method.identifier,
+                params,
+                return_type,
+                body: None,
+                is_exported: false,
+                environment: None,
+            }),
+        );
+        ctx.module_builder
+            .definitions
+            .insert(method.id, method.identifier.span);
+        ctx.module_builder.references.push(SymbolReference {
+            span: method.identifier.span,
+            decl_id: method.id,
+        });
+    }
+}
@@ -1,5 +1,8 @@
 pub mod assignment;
+pub mod const_decl;
+pub mod r#for;
 pub mod from;
+pub mod impl_decl;
 pub mod r#return;
 pub mod type_alias_decl;
 pub mod var_decl;
@@ -14,7 +17,10 @@ use crate::{
         cfg::Terminator,
         errors::{SemanticError, SemanticErrorKind},
         expressions::r#if::IfContext,
-        statements::{from::build_from_stmt, type_alias_decl::build_type_alias_decl},
+        statements::{
+            const_decl::build_const_decl, from::build_from_stmt,
+            type_alias_decl::build_type_alias_decl,
+        },
         FunctionBuilder, HIRContext,
     },
 };
@@ -45,21 +51,45 @@ impl FunctionBuilder {
                 StmtKind::TypeAliasDecl(type_alias_decl) => {
                     build_type_alias_decl(ctx, type_alias_decl, statement.span);
                 }
+                StmtKind::ImplDecl(_) => {
+                    ctx.module_builder.errors.push(SemanticError {
+                        kind: SemanticErrorKind::ImplMustBeDeclaredAtTopLevel,
+                        span: statement.span,
+                    });
+                }
                 StmtKind::VarDecl(var_decl) => {
                     self.build_var_decl(ctx, var_decl, statement.span)
                 }
+                StmtKind::ConstDecl(const_decl) => build_const_decl(ctx, const_decl),
                 StmtKind::Return { value } => {
                     self.build_return_stmt(ctx, value, statement.span)
                 }
                 StmtKind::Assignment { target, value } => {
                     self.build_assignment_stmt(ctx, target, value)
                 }
-                StmtKind::From { path, identifiers } => {
-                    build_from_stmt(ctx, path, identifiers, statement.span)
+                StmtKind::CompoundAssignment {
+                    target,
+                    operator,
+                    value,
+                } => self.build_compound_assignment_stmt(ctx, target, operator, value),
+                StmtKind::Increment { target, operator } => {
+                    self.build_increment_stmt(ctx, target, operator)
                 }
+                StmtKind::From {
+                    path,
+                    identifiers,
+                    namespace,
+                } => build_from_stmt(ctx, path, identifiers, namespace, statement.span),
                 StmtKind::While { condition, body } => {
                     self.build_while_stmt(ctx, condition, body);
                 }
+                StmtKind::For {
+                    item,
+                    iterable,
+                    body,
+                } => {
+                    self.build_for_stmt(ctx, item, iterable, body);
+                }
                 StmtKind::Break => {
                     if let Some((_, break_target)) =
                         ctx.module_builder.within_loop_scope()
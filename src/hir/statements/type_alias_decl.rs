@@ -3,7 +3,9 @@ use crate::{
     hir::{
         errors::{SemanticError, SemanticErrorKind},
         types::checked_declaration::{CheckedDeclaration, CheckedTypeAliasDecl},
-        utils::check_type::check_type_annotation,
+        utils::{
+            check_type::check_type_annotation, generics::declare_generic_type_alias,
+        },
         HIRContext,
     },
 };
@@ -21,6 +23,11 @@ pub fn build_type_alias_decl(
         return;
     }
 
+    if !type_alias_decl.generic_params.is_empty() {
+        declare_generic_type_alias(ctx, type_alias_decl, span);
+        return;
+    }
+
     let alias_value = Box::new(check_type_annotation(ctx, &type_alias_decl.value));
 
     let checked_type_alias_decl = CheckedTypeAliasDecl {
@@ -32,13 +32,14 @@ impl FunctionBuilder {
         let initial_value_span = var_decl.value.span;
         let (initial_value, initial_constraint) = match var_decl.constraint {
             Some(constraint_annotation) => {
-                let initial_value = self.build_expr(ctx, var_decl.value);
-                let initial_value_type =
-                    ctx.program_builder.get_value_type(&initial_value);
-
                 let expected_constraint =
                     check_type_annotation(ctx, &constraint_annotation);
 
+                let initial_value =
+                    self.build_expr_for_type(ctx, var_decl.value, &expected_constraint);
+                let initial_value_type =
+                    ctx.program_builder.get_value_type(&initial_value);
+
                 if !check_is_assignable(&initial_value_type, &expected_constraint) {
                     ctx.module_builder.errors.push(SemanticError {
                         span: initial_value_span,
@@ -93,6 +94,7 @@ impl FunctionBuilder {
             identifier: var_decl.identifier,
             documentation: var_decl.documentation,
             constraint: initial_constraint,
+            is_mutable: var_decl.is_mutable,
         };
 
         ctx.module_builder.scope_insert(
@@ -1,13 +1,17 @@
-use std::hash::{Hash, Hasher};
+use std::{
+    hash::{Hash, Hasher},
+    path::PathBuf,
+};
 
 use crate::{
     ast::{DeclarationId, IdentifierNode, Span},
     compile::interner::TagId,
     hir::{
-        cfg::{ControlFlowGraph, ValueId},
-        types::checked_type::Type,
+        cfg::{ConstantId, ControlFlowGraph, ValueId},
+        types::checked_type::{StructKind, Type},
     },
     parse::DocAnnotation,
+    tokenize::NumberKind,
 };
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
@@ -74,6 +78,9 @@ pub struct CheckedFnDecl {
     pub return_type: Type,
     pub body: Option<ControlFlowGraph>,
     pub is_exported: bool,
+    /// For closures, the last parameter of the entry block, which receives the pointer
+    /// to the captured environment
+    pub environment: Option<ValueId>,
 }
 
 #[derive(Clone, Debug)]
@@ -84,6 +91,67 @@ pub struct CheckedVarDecl {
     pub identifier: IdentifierNode,
     pub documentation: Option<DocAnnotation>,
     pub constraint: Type,
+    /// Whether the variable or anything reached through it can be assigned to
+    pub is_mutable: bool,
+}
+
+/// The value of a constant, known at compile time
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConstValue {
+    Number(NumberKind),
+    Bool(bool),
+    /// The bytes are stored in the program's constant data
+    String {
+        constant_id: ConstantId,
+        len: usize,
+    },
+}
+
+impl ConstValue {
+    pub fn get_type(&self) -> Type {
+        match self {
+            ConstValue::Number(kind) => match kind {
+                NumberKind::I64(_) => Type::I64,
+                NumberKind::I32(_) => Type::I32,
+                NumberKind::I16(_) => Type::I16,
+                NumberKind::I8(_) => Type::I8,
+                NumberKind::F32(_) => Type::F32,
+                NumberKind::F64(_) => Type::F64,
+                NumberKind::U64(_) => Type::U64,
+                NumberKind::U32(_) => Type::U32,
+                NumberKind::U16(_) => Type::U16,
+                NumberKind::U8(_) => Type::U8,
+                NumberKind::USize(_) => Type::USize,
+                NumberKind::ISize(_) => Type::ISize,
+            },
+            ConstValue::Bool(_) => Type::Bool,
+            ConstValue::String { .. } => {
+                let inner = Box::new(Type::Struct(StructKind::String));
+                Type::Pointer {
+                    constraint: inner.clone(),
+                    narrowed_to: inner,
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct CheckedConstDecl {
+    pub id: DeclarationId,
+    pub identifier: IdentifierNode,
+    pub documentation: Option<DocAnnotation>,
+    pub value: ConstValue,
+    pub is_exported: bool,
+}
+
+/// A module imported under a name with `from "./module.wl" as name`
+#[derive(Clone, Debug)]
+pub struct CheckedModuleDecl {
+    pub id: DeclarationId,
+    pub identifier: IdentifierNode,
+    /// Canonical path of the imported module
+    pub path: PathBuf,
 }
 
 #[derive(Clone, Debug)]
@@ -91,9 +159,24 @@ pub enum CheckedDeclaration {
     TypeAlias(CheckedTypeAliasDecl),
     Function(CheckedFnDecl),
     Var(CheckedVarDecl),
+    Const(CheckedConstDecl),
+    Module(CheckedModuleDecl),
     // This is for detecting the Temporal Dead Zone
     UninitializedVar {
         id: DeclarationId,
         identifier: IdentifierNode,
     },
 }
+
+impl CheckedDeclaration {
+    pub fn identifier(&self) -> &IdentifierNode {
+        match self {
+            CheckedDeclaration::TypeAlias(decl) => &decl.identifier,
+            CheckedDeclaration::Function(decl) => &decl.identifier,
+            CheckedDeclaration::Var(decl) => &decl.identifier,
+            CheckedDeclaration::Const(decl) => &decl.identifier,
+            CheckedDeclaration::Module(decl) => &decl.identifier,
+            CheckedDeclaration::UninitializedVar { identifier, .. } => identifier,
+        }
+    }
+}
@@ -25,6 +25,9 @@ pub enum StructKind {
 
     /// { is_heap_allocated: bool, len: usize, ptr: ptr<u8> }
     String,
+
+    /// { len: usize, ptr: ptr<T> }, borrows the buffer of a list or string
+    Slice(Box<Type>),
 }
 
 impl StructKind {
@@ -47,6 +50,17 @@ impl StructKind {
                 ),
             ],
 
+            StructKind::Slice(elem_ty) => vec![
+                (ctx.common_identifiers.len, Type::USize),
+                (
+                    ctx.common_identifiers.ptr,
+                    Type::Pointer {
+                        constraint: elem_ty.clone(),
+                        narrowed_to: elem_ty.clone(),
+                    },
+                ),
+            ],
+
             StructKind::String => vec![
                 (ctx.common_identifiers.is_heap_allocated, Type::Bool),
                 (ctx.common_identifiers.len, Type::USize),
@@ -132,6 +146,13 @@ pub enum Type {
     /// Represents any block of memory with named fields
     Struct(StructKind),
 
+    /// `size` items stored inline, arrays are values like numbers and are copied
+    /// on assignment
+    Array {
+        item_type: Box<Type>,
+        size: usize,
+    },
+
     /// Represents a function pointer signature
     Fn(FnType),
 
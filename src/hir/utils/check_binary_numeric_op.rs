@@ -1,71 +1,160 @@
 use crate::{
     ast::Span,
     hir::{
+        cfg::BinaryOperationKind,
         errors::{SemanticError, SemanticErrorKind},
         types::checked_type::Type,
-        utils::numeric::{get_numeric_type_rank, is_float, is_integer, is_signed},
-        FunctionBuilder,
+        utils::{
+            check_is_equatable::check_is_equatable,
+            numeric::{get_numeric_type_rank, is_float, is_integer, is_signed},
+        },
     },
 };
 
-impl FunctionBuilder {
-    pub fn check_binary_numeric_operation(
-        &mut self,
-        left: &Type,
-        left_span: Span,
-        right: &Type,
-        right_span: Span,
-    ) -> Result<Type, SemanticError> {
-        let span = Span {
-            start: left_span.start,
-            end: right_span.end,
-        };
-
-        let left_type = if is_float(left) || is_integer(left) {
-            left
-        } else {
-            return Err(SemanticError {
-                kind: SemanticErrorKind::ExpectedANumericOperand,
-                span: left_span,
-            });
-        };
+pub fn check_binary_numeric_operation(
+    left: &Type,
+    left_span: Span,
+    right: &Type,
+    right_span: Span,
+) -> Result<Type, SemanticError> {
+    let span = Span {
+        start: left_span.start,
+        end: right_span.end,
+    };
 
-        let right_type = if is_float(right) || is_integer(right) {
-            right
-        } else {
-            return Err(SemanticError {
-                kind: SemanticErrorKind::ExpectedANumericOperand,
-                span: right_span,
-            });
-        };
+    let left_type = if is_float(left) || is_integer(left) {
+        left
+    } else {
+        return Err(SemanticError {
+            kind: SemanticErrorKind::ExpectedANumericOperand,
+            span: left_span,
+        });
+    };
+
+    let right_type = if is_float(right) || is_integer(right) {
+        right
+    } else {
+        return Err(SemanticError {
+            kind: SemanticErrorKind::ExpectedANumericOperand,
+            span: right_span,
+        });
+    };
+
+    if (is_float(left_type) && is_integer(right_type))
+        || (is_integer(left_type) && is_float(right_type))
+    {
+        return Err(SemanticError {
+            kind: SemanticErrorKind::MixedFloatAndInteger,
+            span,
+        });
+    }
+
+    if is_signed(left_type) != is_signed(right_type) {
+        return Err(SemanticError {
+            kind: SemanticErrorKind::MixedSignedAndUnsigned,
+            span,
+        });
+    }
+
+    if right_type == left_type {
+        return Ok(left_type.clone());
+    }
+
+    let left_rank = get_numeric_type_rank(left_type);
+    let right_rank = get_numeric_type_rank(right_type);
+
+    if left_rank > right_rank {
+        Ok(left_type.clone())
+    } else {
+        Ok(right_type.clone())
+    }
+}
 
-        if (is_float(left_type) && is_integer(right_type))
-            || (is_integer(left_type) && is_float(right_type))
-        {
+/// Like `check_binary_numeric_operation`, for operators which only apply to
+/// integers
+pub fn check_binary_integer_operation(
+    left: &Type,
+    left_span: Span,
+    right: &Type,
+    right_span: Span,
+) -> Result<Type, SemanticError> {
+    for (ty, span) in [(left, left_span), (right, right_span)] {
+        if !is_integer(ty) {
             return Err(SemanticError {
-                kind: SemanticErrorKind::MixedFloatAndInteger,
+                kind: SemanticErrorKind::ExpectedAnIntegerOperand,
                 span,
             });
         }
+    }
 
-        if is_signed(left_type) != is_signed(right_type) {
-            return Err(SemanticError {
-                kind: SemanticErrorKind::MixedSignedAndUnsigned,
-                span,
-            });
+    check_binary_numeric_operation(left, left_span, right, right_span)
+}
+
+/// The type of the result of a binary operation
+pub fn check_binary_operation(
+    op_kind: &BinaryOperationKind,
+    left: &Type,
+    left_span: Span,
+    right: &Type,
+    right_span: Span,
+) -> Result<Type, SemanticError> {
+    let combined_span = Span {
+        start: left_span.start,
+        end: right_span.end,
+    };
+
+    let result_type = match op_kind {
+        BinaryOperationKind::Add
+        | BinaryOperationKind::Subtract
+        | BinaryOperationKind::Multiply
+        | BinaryOperationKind::Divide
+        | BinaryOperationKind::Modulo => {
+            check_binary_numeric_operation(left, left_span, right, right_span)?
         }
 
-        if right_type == left_type {
-            return Ok(left_type.clone());
+        BinaryOperationKind::BitAnd
+        | BinaryOperationKind::BitOr
+        | BinaryOperationKind::BitXor => {
+            check_binary_integer_operation(left, left_span, right, right_span)?
         }
 
-        let left_rank = get_numeric_type_rank(left_type);
-        let right_rank = get_numeric_type_rank(right_type);
+        // The amount may be any integer, the result has the type of the left side
+        BinaryOperationKind::ShiftLeft | BinaryOperationKind::ShiftRight => {
+            for (ty, span) in [(left, left_span), (right, right_span)] {
+                if !is_integer(ty) {
+                    return Err(SemanticError {
+                        kind: SemanticErrorKind::ExpectedAnIntegerOperand,
+                        span,
+                    });
+                }
+            }
 
-        if left_rank > right_rank {
-            Ok(left_type.clone())
-        } else {
-            Ok(right_type.clone())
+            left.clone()
         }
-    }
+
+        BinaryOperationKind::LessThan
+        | BinaryOperationKind::LessThanOrEqual
+        | BinaryOperationKind::GreaterThan
+        | BinaryOperationKind::GreaterThanOrEqual => {
+            check_binary_numeric_operation(left, left_span, right, right_span)?;
+
+            Type::Bool
+        }
+
+        BinaryOperationKind::Equal | BinaryOperationKind::NotEqual => {
+            if !check_is_equatable(left, right) {
+                return Err(SemanticError {
+                    span: combined_span,
+                    kind: SemanticErrorKind::CannotCompareType {
+                        of: left.clone(),
+                        to: right.clone(),
+                    },
+                });
+            }
+
+            Type::Bool
+        }
+    };
+
+    Ok(result_type)
 }
@@ -101,13 +101,28 @@ fn check_is_assignable_recursive<'a>(
 
                 is_assignable
             }
-            (StructKind::List(s_inner), StructKind::List(t_inner)) => {
+            (StructKind::List(s_inner), StructKind::List(t_inner))
+            | (StructKind::Slice(s_inner), StructKind::Slice(t_inner)) => {
                 check_is_assignable_recursive(s_inner, t_inner, visited)
                     && check_is_assignable_recursive(t_inner, s_inner, visited)
             }
             (StructKind::String, StructKind::String) => true,
             _ => false,
         },
+        (
+            Array {
+                item_type: source_item,
+                size: source_size,
+            },
+            Array {
+                item_type: target_item,
+                size: target_size,
+            },
+        ) => {
+            source_size == target_size
+                && check_is_assignable_recursive(source_item, target_item, visited)
+                && check_is_assignable_recursive(target_item, source_item, visited)
+        }
         (
             Fn(FnType {
                 params: source_params,
@@ -13,7 +13,12 @@ pub fn check_is_equatable(left: &Type, right: &Type) -> bool {
         // Filter out types that shouldn't be compared even if they match
         return !matches!(
             left,
-            Type::Void | Type::Fn(_) | Type::Unknown | Type::Buffer { .. }
+            Type::Void
+                | Type::Fn(_)
+                | Type::Unknown
+                | Type::Buffer { .. }
+                | Type::Array { .. }
+                | Type::Struct(StructKind::Slice(_))
         );
     }
 
@@ -13,11 +13,41 @@ use crate::{
             checked_declaration::{CheckedDeclaration, CheckedParam, FnType, TagType},
             checked_type::{StructKind, Type},
         },
-        utils::layout::pack_struct,
+        utils::{
+            const_eval::eval_const_usize,
+            generics::{check_generic_instance_annotation, is_builtin_list},
+            layout::{get_layout_of, pack_struct, MAX_ARRAY_SIZE},
+        },
         HIRContext,
     },
 };
 
+/// The type of `size` items of `item_type`, arrays which would not fit on the stack
+/// are rejected
+pub fn check_array_type(
+    ctx: &HIRContext,
+    item_type: Type,
+    size: usize,
+    span: Span,
+) -> Result<Type, SemanticError> {
+    let fits = get_layout_of(&item_type, ctx.program_builder)
+        .size
+        .checked_mul(size)
+        .is_some_and(|bytes| bytes <= MAX_ARRAY_SIZE);
+
+    if !fits {
+        return Err(SemanticError {
+            kind: SemanticErrorKind::ArrayTooLarge { item_type, size },
+            span,
+        });
+    }
+
+    Ok(Type::Array {
+        item_type: Box::new(item_type),
+        size,
+    })
+}
+
 pub fn check_params(ctx: &mut HIRContext, params: &[Param]) -> Vec<CheckedParam> {
     params
         .iter()
@@ -34,25 +64,52 @@ pub fn check_type_identifier_annotation(
     span: Span,
 ) -> Result<Type, SemanticError> {
     ctx.module_builder
-        .scope_lookup(id.name)
+        .resolve_identifier(id)
         .map(|entry| match ctx.program_builder.get_declaration(entry) {
+            CheckedDeclaration::TypeAlias(_)
+                if ctx.program_builder.generic_templates.contains_key(&entry) =>
+            {
+                let expected = ctx
+                    .program_builder
+                    .get_generic_template(entry)
+                    .declaration
+                    .generic_params()
+                    .len();
+                Err(SemanticError {
+                    kind: SemanticErrorKind::GenericArgumentCountMismatch {
+                        expected,
+                        received: 0,
+                    },
+                    span,
+                })
+            }
             CheckedDeclaration::TypeAlias(decl) => Ok((*decl.value).clone()),
             CheckedDeclaration::Function(_) => Err(SemanticError {
                 kind: SemanticErrorKind::CannotUseFunctionDeclarationAsType,
                 span,
             }),
-            CheckedDeclaration::Var(_) | CheckedDeclaration::UninitializedVar { .. } => {
-                Err(SemanticError {
-                    kind: SemanticErrorKind::CannotUseVariableDeclarationAsType,
-                    span,
-                })
-            }
+            CheckedDeclaration::Var(_)
+            | CheckedDeclaration::Const(_)
+            | CheckedDeclaration::UninitializedVar { .. } => Err(SemanticError {
+                kind: SemanticErrorKind::CannotUseVariableDeclarationAsType,
+                span,
+            }),
+            CheckedDeclaration::Module(_) => Err(SemanticError {
+                kind: SemanticErrorKind::CannotUseModuleAsType,
+                span,
+            }),
         })
         .unwrap_or_else(|| {
-            Err(SemanticError {
-                kind: SemanticErrorKind::UndeclaredType(id),
-                span,
-            })
+            let kind = if is_builtin_list(ctx, id) {
+                SemanticErrorKind::GenericArgumentCountMismatch {
+                    expected: 1,
+                    received: 0,
+                }
+            } else {
+                SemanticErrorKind::UndeclaredType(id)
+            };
+
+            Err(SemanticError { kind, span })
         })
 }
 
@@ -75,13 +132,15 @@ pub fn check_tag_annotation(
 }
 
 pub fn check_type_annotation(ctx: &mut HIRContext, annotation: &TypeAnnotation) -> Type {
-    let kind = match &annotation.kind {
+    match &annotation.kind {
         TypeAnnotationKind::Void => Type::Void,
         TypeAnnotationKind::Bool => Type::Bool,
         TypeAnnotationKind::U8 => Type::U8,
         TypeAnnotationKind::U16 => Type::U16,
         TypeAnnotationKind::U32 => Type::U32,
         TypeAnnotationKind::U64 => Type::U64,
+        TypeAnnotationKind::USize => Type::USize,
+        TypeAnnotationKind::ISize => Type::ISize,
         TypeAnnotationKind::I8 => Type::I8,
         TypeAnnotationKind::I16 => Type::I16,
         TypeAnnotationKind::I32 => Type::I32,
@@ -90,9 +149,21 @@ pub fn check_type_annotation(ctx: &mut HIRContext, annotation: &TypeAnnotation)
         TypeAnnotationKind::F64 => Type::F64,
         TypeAnnotationKind::Identifier(id) => {
             match check_type_identifier_annotation(ctx, *id, annotation.span) {
-                Ok(resolved_type) => {
-                    return resolved_type;
+                Ok(resolved_type) => resolved_type,
+                Err(error) => {
+                    ctx.module_builder.errors.push(error);
+                    Type::Unknown
                 }
+            }
+        }
+        TypeAnnotationKind::GenericInstance { identifier, args } => {
+            match check_generic_instance_annotation(
+                ctx,
+                *identifier,
+                args,
+                annotation.span,
+            ) {
+                Ok(resolved_type) => resolved_type,
                 Err(error) => {
                     ctx.module_builder.errors.push(error);
                     Type::Unknown
@@ -132,7 +203,7 @@ pub fn check_type_annotation(ctx: &mut HIRContext, annotation: &TypeAnnotation)
                 }
             }
 
-            checked_variants.sort_by(|a, b| a.id.0.cmp(&b.id.0));
+            checked_variants.sort_by_key(|a| a.id.0);
 
             Type::Struct(StructKind::Union {
                 variants: checked_variants,
@@ -156,6 +227,23 @@ pub fn check_type_annotation(ctx: &mut HIRContext, annotation: &TypeAnnotation)
                 narrowed_to: inner,
             }
         }
+        // Passed by value, the items stay in the buffer they were sliced from
+        TypeAnnotationKind::Slice(item_type) => {
+            let checked_item_type = check_type_annotation(ctx, item_type);
+            Type::Struct(StructKind::Slice(Box::new(checked_item_type)))
+        }
+        TypeAnnotationKind::Array { item_type, size } => {
+            let item_type = check_type_annotation(ctx, item_type);
+            match eval_const_usize(ctx, size)
+                .and_then(|size| check_array_type(ctx, item_type, size, annotation.span))
+            {
+                Ok(array_type) => array_type,
+                Err(error) => {
+                    ctx.module_builder.errors.push(error);
+                    Type::Unknown
+                }
+            }
+        }
         TypeAnnotationKind::Struct(items) => {
             let checked_field_types = check_params(ctx, items);
 
@@ -171,7 +259,5 @@ pub fn check_type_annotation(ctx: &mut HIRContext, annotation: &TypeAnnotation)
                 narrowed_to: inner,
             }
         }
-    };
-
-    kind
+    }
 }
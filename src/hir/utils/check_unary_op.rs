@@ -0,0 +1,68 @@
+use std::collections::HashSet;
+
+use crate::{
+    ast::Span,
+    hir::{
+        cfg::UnaryOperationKind,
+        errors::{SemanticError, SemanticErrorKind},
+        types::checked_type::Type,
+        utils::{
+            check_is_assignable::check_is_assignable,
+            numeric::{is_integer, is_signed},
+        },
+    },
+};
+
+/// Unary operations keep the type of their operand
+pub fn check_unary_operation(
+    op_kind: &UnaryOperationKind,
+    value_type: &Type,
+    span: Span,
+) -> Result<(), SemanticError> {
+    match op_kind {
+        UnaryOperationKind::Neg => {
+            if !is_signed(value_type) {
+                let expected = HashSet::from([
+                    Type::I8,
+                    Type::I16,
+                    Type::I32,
+                    Type::I64,
+                    Type::ISize,
+                    Type::F32,
+                    Type::F64,
+                ]);
+
+                return Err(SemanticError {
+                    kind: SemanticErrorKind::TypeMismatchExpectedOneOf {
+                        expected,
+                        received: value_type.clone(),
+                    },
+                    span,
+                });
+            }
+        }
+        UnaryOperationKind::BitNot => {
+            if !is_integer(value_type) {
+                return Err(SemanticError {
+                    kind: SemanticErrorKind::ExpectedAnIntegerOperand,
+                    span,
+                });
+            }
+        }
+        UnaryOperationKind::Not => {
+            let bool_type = Type::Bool;
+
+            if !check_is_assignable(value_type, &bool_type) {
+                return Err(SemanticError {
+                    kind: SemanticErrorKind::TypeMismatch {
+                        expected: bool_type.clone(),
+                        received: value_type.clone(),
+                    },
+                    span,
+                });
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,458 @@
+use crate::{
+    ast::{
+        expr::{Expr, ExprKind},
+        Span,
+    },
+    hir::{
+        cfg::{BinaryOperationKind, UnaryOperationKind},
+        errors::{SemanticError, SemanticErrorKind},
+        types::{
+            checked_declaration::{CheckedDeclaration, ConstValue},
+            checked_type::Type,
+        },
+        utils::{
+            check_binary_numeric_op::check_binary_operation,
+            check_is_casting_allowed::check_is_casting_allowed,
+            check_type::check_type_annotation,
+            check_unary_op::check_unary_operation,
+            numeric::{get_integer_bit_width, is_float},
+        },
+        HIRContext,
+    },
+    tokenize::NumberKind,
+};
+
+/// Computes the value of an expression at compile time. Literals, other constants,
+/// operators and numeric casts can be used, string values are added to the constant
+/// data. Integer arithmetic which does not fit the type of its result is an error,
+/// unlike at runtime where it wraps around.
+pub fn eval_const_expr(
+    ctx: &mut HIRContext,
+    expr: &Expr,
+) -> Result<ConstValue, SemanticError> {
+    if let Some((op_kind, left, right)) = binary_operation(&expr.kind) {
+        let left_value = eval_const_expr(ctx, left)?;
+        let right_value = eval_const_expr(ctx, right)?;
+        return eval_binary_op(ctx, op_kind, (left_value, left), (right_value, right));
+    }
+
+    if let Some((op_kind, right)) = unary_operation(&expr.kind) {
+        let value = eval_const_expr(ctx, right)?;
+        return eval_unary_op(op_kind, value, expr);
+    }
+
+    match &expr.kind {
+        ExprKind::Number(kind) => Ok(ConstValue::Number(*kind)),
+        ExprKind::BoolLiteral(value) => Ok(ConstValue::Bool(*value)),
+        ExprKind::String(node) => {
+            let constant_id = ctx.program_builder.new_constant_id();
+            ctx.program_builder
+                .constant_data
+                .insert(constant_id, node.value.as_bytes().to_vec());
+
+            Ok(ConstValue::String {
+                constant_id,
+                len: node.len,
+            })
+        }
+        ExprKind::Identifier(identifier) => {
+            match ctx
+                .module_builder
+                .resolve_identifier(*identifier)
+                .map(|id| ctx.program_builder.get_declaration(id))
+            {
+                Some(CheckedDeclaration::Const(decl)) => Ok(decl.value.clone()),
+                Some(_) => Err(SemanticError {
+                    kind: SemanticErrorKind::ExpectedConstantExpression,
+                    span: expr.span,
+                }),
+                None => Err(SemanticError {
+                    kind: SemanticErrorKind::UndeclaredIdentifier(*identifier),
+                    span: expr.span,
+                }),
+            }
+        }
+        ExprKind::And { left, right } | ExprKind::Or { left, right } => {
+            let is_and = matches!(expr.kind, ExprKind::And { .. });
+            let left = expect_bool(eval_const_expr(ctx, left)?, left)?;
+            let right = expect_bool(eval_const_expr(ctx, right)?, right)?;
+
+            Ok(ConstValue::Bool(if is_and {
+                left && right
+            } else {
+                left || right
+            }))
+        }
+        ExprKind::TypeCast { left, target } => {
+            let value = eval_const_expr(ctx, left)?;
+            let target_type = check_type_annotation(ctx, target);
+            let source_type = value.get_type();
+
+            let ConstValue::Number(kind) = value else {
+                return Err(SemanticError {
+                    kind: SemanticErrorKind::ExpectedConstantExpression,
+                    span: expr.span,
+                });
+            };
+
+            if !check_is_casting_allowed(&source_type, &target_type) {
+                return Err(SemanticError {
+                    kind: SemanticErrorKind::CannotCastType {
+                        source_type,
+                        target_type,
+                    },
+                    span: left.span,
+                });
+            }
+
+            // Only widening casts are allowed, so the value always fits
+            let number = match int_value(&kind) {
+                Some(value) if is_float(&target_type) => {
+                    float_of_type(value as f64, &target_type)
+                }
+                Some(value) => wrap_int(value, &target_type),
+                None => float_of_type(float_value(&kind), &target_type),
+            };
+
+            Ok(ConstValue::Number(number))
+        }
+        _ => Err(SemanticError {
+            kind: SemanticErrorKind::ExpectedConstantExpression,
+            span: expr.span,
+        }),
+    }
+}
+
+/// Whether `eval_const_expr` applies to the expression. Nothing is reported or
+/// resolved, the expression may still have type errors.
+pub fn is_const_expr(ctx: &HIRContext, expr: &Expr) -> bool {
+    if let Some((_, left, right)) = binary_operation(&expr.kind) {
+        return is_const_expr(ctx, left) && is_const_expr(ctx, right);
+    }
+
+    if let Some((_, right)) = unary_operation(&expr.kind) {
+        return is_const_expr(ctx, right);
+    }
+
+    match &expr.kind {
+        ExprKind::Number(_) | ExprKind::BoolLiteral(_) | ExprKind::String(_) => true,
+        ExprKind::Identifier(identifier) => ctx
+            .module_builder
+            .scope_lookup(identifier.name)
+            .is_some_and(|id| {
+                matches!(
+                    ctx.program_builder.get_declaration(id),
+                    CheckedDeclaration::Const(_)
+                )
+            }),
+        ExprKind::And { left, right } | ExprKind::Or { left, right } => {
+            is_const_expr(ctx, left) && is_const_expr(ctx, right)
+        }
+        ExprKind::TypeCast { left, .. } => is_const_expr(ctx, left),
+        _ => false,
+    }
+}
+
+/// Evaluates a size, e.g. the length of an array type. Any integer type is accepted
+/// so sizes like `[i32; 4]` need no suffix.
+pub fn eval_const_usize(
+    ctx: &mut HIRContext,
+    expr: &Expr,
+) -> Result<usize, SemanticError> {
+    let value = eval_const_expr(ctx, expr)?;
+
+    let ConstValue::Number(kind) = &value else {
+        return Err(SemanticError {
+            kind: SemanticErrorKind::TypeMismatch {
+                expected: Type::USize,
+                received: value.get_type(),
+            },
+            span: expr.span,
+        });
+    };
+
+    match int_value(kind) {
+        Some(size) => usize::try_from(size).map_err(|_| SemanticError {
+            kind: SemanticErrorKind::ConstantOverflow(Type::USize),
+            span: expr.span,
+        }),
+        None => Err(SemanticError {
+            kind: SemanticErrorKind::TypeMismatch {
+                expected: Type::USize,
+                received: value.get_type(),
+            },
+            span: expr.span,
+        }),
+    }
+}
+
+fn binary_operation(kind: &ExprKind) -> Option<(BinaryOperationKind, &Expr, &Expr)> {
+    let (op_kind, left, right) = match kind {
+        ExprKind::Add { left, right } => (BinaryOperationKind::Add, left, right),
+        ExprKind::Subtract { left, right } => {
+            (BinaryOperationKind::Subtract, left, right)
+        }
+        ExprKind::Multiply { left, right } => {
+            (BinaryOperationKind::Multiply, left, right)
+        }
+        ExprKind::Divide { left, right } => (BinaryOperationKind::Divide, left, right),
+        ExprKind::Modulo { left, right } => (BinaryOperationKind::Modulo, left, right),
+        ExprKind::LessThan { left, right } => {
+            (BinaryOperationKind::LessThan, left, right)
+        }
+        ExprKind::LessThanOrEqual { left, right } => {
+            (BinaryOperationKind::LessThanOrEqual, left, right)
+        }
+        ExprKind::GreaterThan { left, right } => {
+            (BinaryOperationKind::GreaterThan, left, right)
+        }
+        ExprKind::GreaterThanOrEqual { left, right } => {
+            (BinaryOperationKind::GreaterThanOrEqual, left, right)
+        }
+        ExprKind::Equal { left, right } => (BinaryOperationKind::Equal, left, right),
+        ExprKind::NotEqual { left, right } => {
+            (BinaryOperationKind::NotEqual, left, right)
+        }
+        ExprKind::BitAnd { left, right } => (BinaryOperationKind::BitAnd, left, right),
+        ExprKind::BitOr { left, right } => (BinaryOperationKind::BitOr, left, right),
+        ExprKind::BitXor { left, right } => (BinaryOperationKind::BitXor, left, right),
+        ExprKind::ShiftLeft { left, right } => {
+            (BinaryOperationKind::ShiftLeft, left, right)
+        }
+        ExprKind::ShiftRight { left, right } => {
+            (BinaryOperationKind::ShiftRight, left, right)
+        }
+        _ => return None,
+    };
+
+    Some((op_kind, left, right))
+}
+
+fn unary_operation(kind: &ExprKind) -> Option<(UnaryOperationKind, &Expr)> {
+    match kind {
+        ExprKind::Neg { right } => Some((UnaryOperationKind::Neg, right)),
+        ExprKind::Not { right } => Some((UnaryOperationKind::Not, right)),
+        ExprKind::BitNot { right } => Some((UnaryOperationKind::BitNot, right)),
+        _ => None,
+    }
+}
+
+fn eval_unary_op(
+    op_kind: UnaryOperationKind,
+    value: ConstValue,
+    expr: &Expr,
+) -> Result<ConstValue, SemanticError> {
+    let ty = value.get_type();
+    check_unary_operation(&op_kind, &ty, expr.span)?;
+
+    let result = match (op_kind, value) {
+        (UnaryOperationKind::Not, ConstValue::Bool(value)) => ConstValue::Bool(!value),
+        (UnaryOperationKind::Neg, ConstValue::Number(kind)) => match int_value(&kind) {
+            Some(value) => ConstValue::Number(checked_int(-value, &ty, expr.span)?),
+            None => ConstValue::Number(float_of_type(-float_value(&kind), &ty)),
+        },
+        (UnaryOperationKind::BitNot, ConstValue::Number(kind)) => {
+            let value = int_value(&kind)
+                .expect("INTERNAL COMPILER ERROR: Expected an integer operand");
+            ConstValue::Number(wrap_int(!value, &ty))
+        }
+        _ => unreachable!("INTERNAL COMPILER ERROR: Unary operand was not checked"),
+    };
+
+    Ok(result)
+}
+
+fn eval_binary_op(
+    ctx: &HIRContext,
+    op_kind: BinaryOperationKind,
+    (left, left_expr): (ConstValue, &Expr),
+    (right, right_expr): (ConstValue, &Expr),
+) -> Result<ConstValue, SemanticError> {
+    let result_type = check_binary_operation(
+        &op_kind,
+        &left.get_type(),
+        left_expr.span,
+        &right.get_type(),
+        right_expr.span,
+    )?;
+
+    if let BinaryOperationKind::Equal | BinaryOperationKind::NotEqual = op_kind {
+        let is_equal = match (&left, &right) {
+            (ConstValue::Bool(l), ConstValue::Bool(r)) => l == r,
+            (
+                ConstValue::String { constant_id: l, .. },
+                ConstValue::String { constant_id: r, .. },
+            ) => {
+                ctx.program_builder.constant_data[l]
+                    == ctx.program_builder.constant_data[r]
+            }
+            (ConstValue::Number(l), ConstValue::Number(r)) => {
+                match (int_value(l), int_value(r)) {
+                    (Some(l), Some(r)) => l == r,
+                    _ => float_value(l) == float_value(r),
+                }
+            }
+            _ => unreachable!("INTERNAL COMPILER ERROR: Operands were not checked"),
+        };
+
+        return Ok(ConstValue::Bool(
+            is_equal == matches!(op_kind, BinaryOperationKind::Equal),
+        ));
+    }
+
+    let (ConstValue::Number(left), ConstValue::Number(right)) = (left, right) else {
+        unreachable!("INTERNAL COMPILER ERROR: Operands were not checked");
+    };
+
+    let span = Span {
+        start: left_expr.span.start,
+        end: right_expr.span.end,
+    };
+
+    let (Some(l), Some(r)) = (int_value(&left), int_value(&right)) else {
+        let (l, r) = (float_value(&left), float_value(&right));
+        let result = match op_kind {
+            BinaryOperationKind::Add => l + r,
+            BinaryOperationKind::Subtract => l - r,
+            BinaryOperationKind::Multiply => l * r,
+            BinaryOperationKind::Divide => l / r,
+            BinaryOperationKind::Modulo => l % r,
+            BinaryOperationKind::LessThan => return Ok(ConstValue::Bool(l < r)),
+            BinaryOperationKind::LessThanOrEqual => return Ok(ConstValue::Bool(l <= r)),
+            BinaryOperationKind::GreaterThan => return Ok(ConstValue::Bool(l > r)),
+            BinaryOperationKind::GreaterThanOrEqual => {
+                return Ok(ConstValue::Bool(l >= r))
+            }
+            _ => unreachable!("INTERNAL COMPILER ERROR: Operands were not checked"),
+        };
+
+        return Ok(ConstValue::Number(float_of_type(result, &result_type)));
+    };
+
+    let result = match op_kind {
+        BinaryOperationKind::Add => l + r,
+        BinaryOperationKind::Subtract => l - r,
+        // Products of 64 bit integers may not fit either
+        BinaryOperationKind::Multiply => l.checked_mul(r).unwrap_or(i128::MAX),
+        BinaryOperationKind::Divide | BinaryOperationKind::Modulo if r == 0 => {
+            return Err(SemanticError {
+                kind: SemanticErrorKind::DivisionByZero,
+                span: right_expr.span,
+            })
+        }
+        BinaryOperationKind::Divide => l / r,
+        BinaryOperationKind::Modulo => l % r,
+        BinaryOperationKind::LessThan => return Ok(ConstValue::Bool(l < r)),
+        BinaryOperationKind::LessThanOrEqual => return Ok(ConstValue::Bool(l <= r)),
+        BinaryOperationKind::GreaterThan => return Ok(ConstValue::Bool(l > r)),
+        BinaryOperationKind::GreaterThanOrEqual => return Ok(ConstValue::Bool(l >= r)),
+        // Bits which do not fit are dropped, like at runtime
+        BinaryOperationKind::BitAnd => {
+            return Ok(ConstValue::Number(wrap_int(l & r, &result_type)))
+        }
+        BinaryOperationKind::BitOr => {
+            return Ok(ConstValue::Number(wrap_int(l | r, &result_type)))
+        }
+        BinaryOperationKind::BitXor => {
+            return Ok(ConstValue::Number(wrap_int(l ^ r, &result_type)))
+        }
+        BinaryOperationKind::ShiftLeft | BinaryOperationKind::ShiftRight => {
+            let amount = r as u32 & (get_integer_bit_width(&result_type) - 1);
+            let result = if matches!(op_kind, BinaryOperationKind::ShiftLeft) {
+                l.wrapping_shl(amount)
+            } else {
+                l >> amount
+            };
+            return Ok(ConstValue::Number(wrap_int(result, &result_type)));
+        }
+        BinaryOperationKind::Equal | BinaryOperationKind::NotEqual => unreachable!(),
+    };
+
+    checked_int(result, &result_type, span).map(ConstValue::Number)
+}
+
+fn expect_bool(value: ConstValue, expr: &Expr) -> Result<bool, SemanticError> {
+    match value {
+        ConstValue::Bool(value) => Ok(value),
+        value => Err(SemanticError {
+            kind: SemanticErrorKind::TypeMismatch {
+                expected: Type::Bool,
+                received: value.get_type(),
+            },
+            span: expr.span,
+        }),
+    }
+}
+
+/// The value of an integer literal, `None` for floats
+pub fn int_value(kind: &NumberKind) -> Option<i128> {
+    Some(match *kind {
+        NumberKind::I8(v) => v as i128,
+        NumberKind::I16(v) => v as i128,
+        NumberKind::I32(v) => v as i128,
+        NumberKind::I64(v) => v as i128,
+        NumberKind::ISize(v) => v as i128,
+        NumberKind::U8(v) => v as i128,
+        NumberKind::U16(v) => v as i128,
+        NumberKind::U32(v) => v as i128,
+        NumberKind::U64(v) => v as i128,
+        NumberKind::USize(v) => v as i128,
+        NumberKind::F32(_) | NumberKind::F64(_) => return None,
+    })
+}
+
+fn float_value(kind: &NumberKind) -> f64 {
+    match *kind {
+        NumberKind::F32(v) => v as f64,
+        NumberKind::F64(v) => v,
+        _ => panic!(
+            "INTERNAL COMPILER ERROR: Expected a float, found {:?}",
+            kind
+        ),
+    }
+}
+
+fn float_of_type(value: f64, ty: &Type) -> NumberKind {
+    match ty {
+        Type::F32 => NumberKind::F32(value as f32),
+        Type::F64 => NumberKind::F64(value),
+        _ => panic!(
+            "INTERNAL COMPILER ERROR: Expected a float type, found {:?}",
+            ty
+        ),
+    }
+}
+
+/// The value truncated to the bits of an integer type
+fn wrap_int(value: i128, ty: &Type) -> NumberKind {
+    match ty {
+        Type::I8 => NumberKind::I8(value as i8),
+        Type::I16 => NumberKind::I16(value as i16),
+        Type::I32 => NumberKind::I32(value as i32),
+        Type::I64 => NumberKind::I64(value as i64),
+        Type::ISize => NumberKind::ISize(value as isize),
+        Type::U8 => NumberKind::U8(value as u8),
+        Type::U16 => NumberKind::U16(value as u16),
+        Type::U32 => NumberKind::U32(value as u32),
+        Type::U64 => NumberKind::U64(value as u64),
+        Type::USize => NumberKind::USize(value as usize),
+        _ => panic!(
+            "INTERNAL COMPILER ERROR: Expected an integer type, found {:?}",
+            ty
+        ),
+    }
+}
+
+/// The value as a number of an integer type, if it fits
+fn checked_int(value: i128, ty: &Type, span: Span) -> Result<NumberKind, SemanticError> {
+    let number = wrap_int(value, ty);
+
+    if int_value(&number) == Some(value) {
+        Ok(number)
+    } else {
+        Err(SemanticError {
+            kind: SemanticErrorKind::ConstantOverflow(ty.clone()),
+            span,
+        })
+    }
+}
@@ -0,0 +1,518 @@
+use std::{collections::HashMap, path::Path};
+
+use crate::{
+    ast::{
+        decl::{Declaration, FnDecl, TypeAliasDecl},
+        type_annotation::{TagAnnotation, TypeAnnotation, TypeAnnotationKind},
+        DeclarationId, IdentifierNode, Span,
+    },
+    compile::interner::StringId,
+    hir::{
+        errors::{SemanticError, SemanticErrorKind},
+        types::{
+            checked_declaration::{
+                CheckedDeclaration, CheckedFnDecl, CheckedTypeAliasDecl,
+            },
+            checked_type::{StructKind, Type},
+        },
+        utils::{
+            check_type::{check_params, check_type_annotation},
+            scope::ScopeKind,
+        },
+        GenericTemplate, HIRContext,
+    },
+};
+
+/// How deeply generic instances may request further instances. Calling a generic
+/// function with ever growing type arguments would create new instances forever,
+/// and their types grow exponentially, so the limit is kept small
+pub const MAX_GENERIC_INSTANCE_DEPTH: usize = 8;
+
+/// Declares a generic function in the file scope. Its signature and body are only
+/// checked once the function is called and its type arguments are known.
+pub fn declare_generic_fn(ctx: &mut HIRContext, fn_decl: &FnDecl) {
+    register_template(ctx, fn_decl.id, Declaration::Fn(fn_decl.clone()));
+
+    let checked_fn = CheckedFnDecl {
+        id: fn_decl.id,
+        identifier: fn_decl.identifier,
+        params: vec![],
+        return_type: Type::Unknown,
+        body: None,
+        is_exported: fn_decl.is_exported,
+        environment: None,
+    };
+
+    ctx.module_builder.scope_insert(
+        ctx.program_builder,
+        fn_decl.identifier,
+        CheckedDeclaration::Function(checked_fn),
+    );
+}
+
+/// Declares a generic type alias in the file scope, its value is checked every time
+/// the alias is applied to type arguments
+pub fn declare_generic_type_alias(
+    ctx: &mut HIRContext,
+    type_alias_decl: TypeAliasDecl,
+    span: Span,
+) {
+    let checked_type_alias_decl = CheckedTypeAliasDecl {
+        id: type_alias_decl.id,
+        documentation: type_alias_decl.documentation.clone(),
+        identifier: type_alias_decl.identifier,
+        span,
+        value: Box::new(Type::Unknown),
+        is_exported: type_alias_decl.is_exported,
+    };
+
+    ctx.module_builder.scope_insert(
+        ctx.program_builder,
+        type_alias_decl.identifier,
+        CheckedDeclaration::TypeAlias(checked_type_alias_decl),
+    );
+
+    register_template(
+        ctx,
+        type_alias_decl.id,
+        Declaration::TypeAlias(type_alias_decl),
+    );
+}
+
+fn register_template(ctx: &mut HIRContext, id: DeclarationId, declaration: Declaration) {
+    let template = GenericTemplate {
+        module: ctx.module_builder.module.path.clone(),
+        declaration,
+    };
+    ctx.program_builder.generic_templates.insert(id, template);
+}
+
+/// Runs `f` in the file scope of the module declaring the generic `template`, with
+/// its type parameters bound to `args`
+pub fn with_generic_scope<R>(
+    ctx: &mut HIRContext,
+    template: DeclarationId,
+    args: &[Type],
+    f: impl FnOnce(&mut HIRContext) -> R,
+) -> R {
+    let GenericTemplate {
+        module,
+        declaration,
+    } = ctx.program_builder.get_generic_template(template);
+
+    within_module(ctx, &module, |ctx| {
+        let file_scope = ctx.module_builder.scopes[0].clone();
+        let scopes = std::mem::replace(&mut ctx.module_builder.scopes, vec![file_scope]);
+
+        ctx.module_builder.enter_scope(ScopeKind::GenericParams);
+        for (param, arg) in declaration.generic_params().iter().zip(args) {
+            let binding = CheckedTypeAliasDecl {
+                id: ctx.program_builder.new_declaration_id(),
+                identifier: *param,
+                documentation: None,
+                value: Box::new(arg.clone()),
+                is_exported: false,
+                span: param.span,
+            };
+            ctx.module_builder.scope_insert(
+                ctx.program_builder,
+                *param,
+                CheckedDeclaration::TypeAlias(binding),
+            );
+        }
+
+        let result = f(ctx);
+        ctx.module_builder.scopes = scopes;
+        result
+    })
+}
+
+/// Runs `f` with the builder of the module at `path` as the current one. The current
+/// builder is parked among the program's modules meanwhile, so that it can be reached
+/// the same way as the others.
+fn within_module<R>(
+    ctx: &mut HIRContext,
+    path: &Path,
+    f: impl FnOnce(&mut HIRContext) -> R,
+) -> R {
+    if ctx.module_builder.module.path == path {
+        return f(ctx);
+    }
+
+    let target = ctx
+        .program_builder
+        .modules
+        .remove(path)
+        .expect("INTERNAL COMPILER ERROR: Module of a generic declaration not found");
+    let current = std::mem::replace(ctx.module_builder, target);
+    let current_path = current.module.path.clone();
+    ctx.program_builder
+        .modules
+        .insert(current_path.clone(), current);
+
+    let result = f(ctx);
+
+    let current = ctx
+        .program_builder
+        .modules
+        .remove(&current_path)
+        .expect("INTERNAL COMPILER ERROR: Parked module builder not found");
+    let target = std::mem::replace(ctx.module_builder, current);
+    ctx.program_builder
+        .modules
+        .insert(path.to_path_buf(), target);
+
+    result
+}
+
+/// Checks a generic type alias applied to type arguments, e.g. `Option<i32>`
+pub fn check_generic_instance_annotation(
+    ctx: &mut HIRContext,
+    identifier: IdentifierNode,
+    args: &[TypeAnnotation],
+    span: Span,
+) -> Result<Type, SemanticError> {
+    let Some(alias_id) = ctx.module_builder.resolve_identifier(identifier) else {
+        if is_builtin_list(ctx, identifier) {
+            return check_builtin_list_annotation(ctx, args, span);
+        }
+
+        return Err(SemanticError {
+            kind: SemanticErrorKind::UndeclaredType(identifier),
+            span,
+        });
+    };
+
+    let checked_args: Vec<Type> = args
+        .iter()
+        .map(|arg| check_type_annotation(ctx, arg))
+        .collect();
+
+    let template = match ctx.program_builder.generic_templates.get(&alias_id) {
+        Some(GenericTemplate {
+            declaration: Declaration::TypeAlias(decl),
+            ..
+        }) => decl.clone(),
+        _ => {
+            let kind = match ctx.program_builder.get_declaration(alias_id) {
+                CheckedDeclaration::TypeAlias(_) => {
+                    SemanticErrorKind::GenericArgumentCountMismatch {
+                        expected: 0,
+                        received: args.len(),
+                    }
+                }
+                CheckedDeclaration::Function(_) => {
+                    SemanticErrorKind::CannotUseFunctionDeclarationAsType
+                }
+                CheckedDeclaration::Var(_)
+                | CheckedDeclaration::Const(_)
+                | CheckedDeclaration::UninitializedVar { .. } => {
+                    SemanticErrorKind::CannotUseVariableDeclarationAsType
+                }
+                CheckedDeclaration::Module(_) => SemanticErrorKind::CannotUseModuleAsType,
+            };
+            return Err(SemanticError { kind, span });
+        }
+    };
+
+    if template.generic_params.len() != args.len() {
+        return Err(SemanticError {
+            kind: SemanticErrorKind::GenericArgumentCountMismatch {
+                expected: template.generic_params.len(),
+                received: args.len(),
+            },
+            span,
+        });
+    }
+
+    if ctx.program_builder.expanding_aliases.contains(&alias_id) {
+        return Err(SemanticError {
+            kind: SemanticErrorKind::RecursiveGenericTypeAlias(identifier),
+            span,
+        });
+    }
+
+    ctx.program_builder.expanding_aliases.push(alias_id);
+    let value = with_generic_scope(ctx, alias_id, &checked_args, |ctx| {
+        check_type_annotation(ctx, &template.value)
+    });
+    ctx.program_builder.expanding_aliases.pop();
+
+    Ok(value)
+}
+
+/// `List` is a built-in generic type, `List<T>` is the same type as `T[]`. It only
+/// applies when no declaration in scope shadows the name.
+pub fn is_builtin_list(ctx: &HIRContext, identifier: IdentifierNode) -> bool {
+    ctx.program_builder.string_interner.resolve(identifier.name) == "List"
+}
+
+fn check_builtin_list_annotation(
+    ctx: &mut HIRContext,
+    args: &[TypeAnnotation],
+    span: Span,
+) -> Result<Type, SemanticError> {
+    let [item_type] = args else {
+        return Err(SemanticError {
+            kind: SemanticErrorKind::GenericArgumentCountMismatch {
+                expected: 1,
+                received: args.len(),
+            },
+            span,
+        });
+    };
+
+    let inner = Box::new(Type::Struct(StructKind::List(Box::new(
+        check_type_annotation(ctx, item_type),
+    ))));
+
+    Ok(Type::Pointer {
+        constraint: inner.clone(),
+        narrowed_to: inner,
+    })
+}
+
+/// Returns the instance of the generic function `template` for the types of the
+/// arguments it is called with. New instances have their signature checked right
+/// away, their body is built after the bodies of the other functions.
+pub fn instantiate_generic_fn(
+    ctx: &mut HIRContext,
+    template: DeclarationId,
+    arg_types: &[Type],
+    span: Span,
+) -> Result<DeclarationId, SemanticError> {
+    let GenericTemplate {
+        module,
+        declaration: Declaration::Fn(fn_decl),
+    } = ctx.program_builder.get_generic_template(template)
+    else {
+        panic!("INTERNAL COMPILER ERROR: Expected a generic function template");
+    };
+
+    let mut bindings = HashMap::new();
+    within_module(ctx, &module, |ctx| {
+        for (param, arg_type) in fn_decl.params.iter().zip(arg_types) {
+            infer_generic_args(
+                ctx,
+                &param.constraint,
+                arg_type,
+                &fn_decl.generic_params,
+                &mut bindings,
+            );
+        }
+    });
+
+    let args = fn_decl
+        .generic_params
+        .iter()
+        .map(|param| {
+            bindings.remove(&param.name).ok_or(SemanticError {
+                kind: SemanticErrorKind::CannotInferGenericArgument(*param),
+                span,
+            })
+        })
+        .collect::<Result<Vec<Type>, SemanticError>>()?;
+
+    let key = (template, args);
+    if let Some(instance_id) = ctx.program_builder.generic_instances.get(&key) {
+        return Ok(*instance_id);
+    }
+
+    let depth = ctx.program_builder.instance_depth + 1;
+    if depth > MAX_GENERIC_INSTANCE_DEPTH {
+        return Err(SemanticError {
+            kind: SemanticErrorKind::GenericInstancesTooDeep(fn_decl.identifier),
+            span,
+        });
+    }
+
+    let instance_id = ctx.program_builder.new_declaration_id();
+    let (params, return_type) = with_generic_scope(ctx, template, &key.1, |ctx| {
+        (
+            check_params(ctx, &fn_decl.params),
+            check_type_annotation(ctx, &fn_decl.return_type),
+        )
+    });
+
+    let instance = CheckedFnDecl {
+        id: instance_id,
+        identifier: fn_decl.identifier,
+        params,
+        return_type,
+        body: None,
+        is_exported: false,
+        environment: None,
+    };
+    ctx.program_builder
+        .declarations
+        .insert(instance_id, CheckedDeclaration::Function(instance));
+    ctx.program_builder.pending_instances.push((
+        instance_id,
+        template,
+        key.1.clone(),
+        depth,
+    ));
+    ctx.program_builder
+        .generic_instances
+        .insert(key, instance_id);
+
+    Ok(instance_id)
+}
+
+/// Binds the type parameters appearing in `annotation` by matching it against the
+/// structure of `ty`. The first binding of a parameter wins, the call is type-checked
+/// against the resulting signature afterwards.
+fn infer_generic_args(
+    ctx: &mut HIRContext,
+    annotation: &TypeAnnotation,
+    ty: &Type,
+    params: &[IdentifierNode],
+    bindings: &mut HashMap<StringId, Type>,
+) {
+    let pointee = match ty {
+        Type::Pointer { narrowed_to, .. } => Some(&**narrowed_to),
+        _ => None,
+    };
+
+    match (&annotation.kind, ty) {
+        (TypeAnnotationKind::Identifier(id), _)
+            if params.iter().any(|p| p.name == id.name) =>
+        {
+            bindings.entry(id.name).or_insert_with(|| ty.clone());
+        }
+        (TypeAnnotationKind::List(item), _) => {
+            if let Some(Type::Struct(StructKind::List(item_type))) = pointee {
+                infer_generic_args(ctx, item, item_type, params, bindings);
+            }
+        }
+        (TypeAnnotationKind::Slice(item), Type::Struct(StructKind::Slice(item_type))) => {
+            infer_generic_args(ctx, item, item_type, params, bindings);
+        }
+        (
+            TypeAnnotationKind::Array { item_type, .. },
+            Type::Array {
+                item_type: checked_item,
+                ..
+            },
+        ) => {
+            infer_generic_args(ctx, item_type, checked_item, params, bindings);
+        }
+        (TypeAnnotationKind::Struct(fields), _) => {
+            if let Some(Type::Struct(StructKind::UserDefined(checked_fields))) = pointee {
+                for field in fields {
+                    if let Some(checked_field) = checked_fields
+                        .iter()
+                        .find(|f| f.identifier.name == field.identifier.name)
+                    {
+                        infer_generic_args(
+                            ctx,
+                            &field.constraint,
+                            &checked_field.ty,
+                            params,
+                            bindings,
+                        );
+                    }
+                }
+            }
+        }
+        (
+            TypeAnnotationKind::FnType {
+                params: fn_params,
+                return_type,
+            },
+            Type::Fn(fn_type),
+        ) => {
+            for (param, checked_param) in fn_params.iter().zip(&fn_type.params) {
+                infer_generic_args(
+                    ctx,
+                    &param.constraint,
+                    &checked_param.ty,
+                    params,
+                    bindings,
+                );
+            }
+            infer_generic_args(ctx, return_type, &fn_type.return_type, params, bindings);
+        }
+        (TypeAnnotationKind::Tag(tag), _) => {
+            infer_from_tags(ctx, std::slice::from_ref(tag), ty, params, bindings);
+        }
+        (TypeAnnotationKind::Union(tags), _) => {
+            infer_from_tags(ctx, tags, ty, params, bindings);
+        }
+        (TypeAnnotationKind::GenericInstance { identifier, args }, _)
+            if ctx.module_builder.scope_lookup(identifier.name).is_none()
+                && is_builtin_list(ctx, *identifier) =>
+        {
+            if let (Some(Type::Struct(StructKind::List(item_type))), [item]) =
+                (pointee, args.as_slice())
+            {
+                infer_generic_args(ctx, item, item_type, params, bindings);
+            }
+        }
+        (TypeAnnotationKind::GenericInstance { identifier, args }, _) => {
+            let Some(alias) = ctx
+                .module_builder
+                .scope_lookup(identifier.name)
+                .and_then(|id| ctx.program_builder.generic_templates.get(&id))
+                .cloned()
+            else {
+                return;
+            };
+            let Declaration::TypeAlias(alias_decl) = &alias.declaration else {
+                return;
+            };
+
+            // Bind the parameters of the alias first, then match its arguments
+            // against what they were bound to
+            let mut alias_bindings = HashMap::new();
+            within_module(ctx, &alias.module, |ctx| {
+                infer_generic_args(
+                    ctx,
+                    &alias_decl.value,
+                    ty,
+                    &alias_decl.generic_params,
+                    &mut alias_bindings,
+                );
+            });
+
+            for (arg, alias_param) in args.iter().zip(&alias_decl.generic_params) {
+                if let Some(bound) = alias_bindings.get(&alias_param.name) {
+                    infer_generic_args(ctx, arg, bound, params, bindings);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Matches the annotated tags against a tag or union type by their names
+fn infer_from_tags(
+    ctx: &mut HIRContext,
+    tags: &[TagAnnotation],
+    ty: &Type,
+    params: &[IdentifierNode],
+    bindings: &mut HashMap<StringId, Type>,
+) {
+    let variants = match ty {
+        Type::Struct(StructKind::Tag(tag)) => std::slice::from_ref(tag),
+        Type::Struct(StructKind::Union { variants }) => variants.as_slice(),
+        _ => return,
+    };
+
+    for tag in tags {
+        let tag_id = ctx
+            .program_builder
+            .tag_interner
+            .intern(&tag.identifier.name);
+        let (Some(annotation), Some(variant)) = (
+            &tag.value_type,
+            variants.iter().find(|variant| variant.id == tag_id),
+        ) else {
+            continue;
+        };
+
+        if let Some(value_type) = &variant.value_type {
+            infer_generic_args(ctx, annotation, value_type, params, bindings);
+        }
+    }
+}
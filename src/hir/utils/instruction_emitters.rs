@@ -1,17 +1,20 @@
-use std::collections::HashSet;
-
 use crate::{
-    ast::{IdentifierNode, Span},
+    ast::{DeclarationId, IdentifierNode, Span},
     hir::{
         cfg::{
             BinaryOperationKind, ConstantId, Instruction, UnaryOperationKind, Value,
             ValueId,
         },
         errors::{SemanticError, SemanticErrorKind},
-        types::{checked_declaration::CheckedDeclaration, checked_type::Type},
+        types::{
+            checked_declaration::{CheckedDeclaration, FnType},
+            checked_type::Type,
+        },
         utils::{
+            check_binary_numeric_op::check_binary_operation,
             check_is_assignable::check_is_assignable,
-            check_is_equatable::check_is_equatable, numeric::is_signed,
+            check_is_casting_allowed::check_is_casting_allowed,
+            check_unary_op::check_unary_operation, const_eval::int_value,
         },
         FunctionBuilder, HIRContext, ModuleBuilder,
     },
@@ -213,7 +216,7 @@ impl FunctionBuilder {
         let destination = self.alloc_value(ctx, result_ptr_ty);
         self.push_instruction(Instruction::GetFieldPtr {
             destination,
-            base_ptr,
+            base_ptr: current_ptr,
             field_index,
         });
 
@@ -256,6 +259,53 @@ impl FunctionBuilder {
         Ok(destination)
     }
 
+    /// Casts a pointer to an array to a pointer to its first item, the items of an
+    /// array are reached with `emit_get_element_ptr` like the buffer of a list
+    pub fn emit_array_items_ptr(
+        &mut self,
+        ctx: &mut HIRContext,
+        array_ptr: ValueId,
+    ) -> ValueId {
+        let item_ptr_type = match ctx.program_builder.get_value_id_type(&array_ptr) {
+            Type::Pointer {
+                constraint,
+                narrowed_to,
+            } => match (*constraint, *narrowed_to) {
+                (
+                    Type::Array {
+                        item_type: constraint,
+                        ..
+                    },
+                    Type::Array {
+                        item_type: narrowed_to,
+                        ..
+                    },
+                ) => Type::Pointer {
+                    constraint,
+                    narrowed_to,
+                },
+                other => panic!(
+                    "INTERNAL COMPILER ERROR: emit_array_items_ptr expects a pointer to \
+                     an array, found {:?}",
+                    other
+                ),
+            },
+            other => panic!(
+                "INTERNAL COMPILER ERROR: emit_array_items_ptr expects a pointer, found {:?}",
+                other
+            ),
+        };
+
+        let destination = self.alloc_value(ctx, item_ptr_type.clone());
+        self.push_instruction(Instruction::TypeCast {
+            destination,
+            operand: Value::Use(array_ptr),
+            target_type: item_ptr_type,
+        });
+
+        destination
+    }
+
     pub fn emit_unary_op(
         &mut self,
         ctx: &mut HIRContext,
@@ -265,42 +315,7 @@ impl FunctionBuilder {
         let value_type = ctx.program_builder.get_value_type(&value);
         let span = Span::default(); // TODO: Fix span
 
-        match op_kind {
-            UnaryOperationKind::Neg => {
-                if !is_signed(&value_type) {
-                    let expected = HashSet::from([
-                        Type::I8,
-                        Type::I16,
-                        Type::I32,
-                        Type::I64,
-                        Type::ISize,
-                        Type::F32,
-                        Type::F64,
-                    ]);
-
-                    return Err(SemanticError {
-                        kind: SemanticErrorKind::TypeMismatchExpectedOneOf {
-                            expected,
-                            received: value_type.clone(),
-                        },
-                        span,
-                    });
-                }
-            }
-            UnaryOperationKind::Not => {
-                let bool_type = Type::Bool;
-
-                if !check_is_assignable(&value_type, &bool_type) {
-                    return Err(SemanticError {
-                        kind: SemanticErrorKind::TypeMismatch {
-                            expected: bool_type.clone(),
-                            received: value_type,
-                        },
-                        span,
-                    });
-                }
-            }
-        };
+        check_unary_operation(&op_kind, &value_type, span)?;
 
         let destination = self.alloc_value(ctx, value_type);
         self.push_instruction(Instruction::UnaryOp {
@@ -323,51 +338,26 @@ impl FunctionBuilder {
     ) -> Result<ValueId, SemanticError> {
         let left_type = ctx.program_builder.get_value_type(&left);
         let right_type = ctx.program_builder.get_value_type(&right);
-        let combined_span = Span {
-            start: left_span.start,
-            end: right_span.end,
-        };
-
-        let destination_type = match op_kind {
-            BinaryOperationKind::Add
-            | BinaryOperationKind::Subtract
-            | BinaryOperationKind::Multiply
-            | BinaryOperationKind::Divide
-            | BinaryOperationKind::Modulo => self.check_binary_numeric_operation(
-                &left_type,
-                left_span,
-                &right_type,
-                right_span,
-            )?,
-
-            BinaryOperationKind::LessThan
-            | BinaryOperationKind::LessThanOrEqual
-            | BinaryOperationKind::GreaterThan
-            | BinaryOperationKind::GreaterThanOrEqual => {
-                self.check_binary_numeric_operation(
-                    &left_type,
-                    left_span,
-                    &right_type,
-                    right_span,
-                )?;
-
-                Type::Bool
-            }
-
-            BinaryOperationKind::Equal | BinaryOperationKind::NotEqual => {
-                if !check_is_equatable(&left_type, &right_type) {
-                    return Err(SemanticError {
-                        span: combined_span,
-                        kind: SemanticErrorKind::CannotCompareType {
-                            of: left_type,
-                            to: right_type,
-                        },
-                    });
-                }
-
-                Type::Bool
-            }
-        };
+        let destination_type = check_binary_operation(
+            &op_kind,
+            &left_type,
+            left_span,
+            &right_type,
+            right_span,
+        )?;
+
+        let is_division = matches!(
+            op_kind,
+            BinaryOperationKind::Divide | BinaryOperationKind::Modulo
+        );
+        if is_division
+            && matches!(&right, Value::NumberLiteral(kind) if int_value(kind) == Some(0))
+        {
+            return Err(SemanticError {
+                kind: SemanticErrorKind::DivisionByZero,
+                span: right_span,
+            });
+        }
 
         let destination = self.alloc_value(ctx, destination_type);
         self.push_instruction(Instruction::BinaryOp {
@@ -438,7 +428,7 @@ impl FunctionBuilder {
 
         for (arg, param) in args.iter().zip(params.iter()) {
             let arg_ty = ctx.program_builder.get_value_type(arg);
-            if !check_is_assignable(&param.ty, &arg_ty) {
+            if !check_is_assignable(&arg_ty, &param.ty) {
                 return Err(SemanticError {
                     kind: SemanticErrorKind::TypeMismatch {
                         expected: param.ty.clone(),
@@ -464,6 +454,33 @@ impl FunctionBuilder {
         Ok(destination_id)
     }
 
+    /// Pairs the closure `function` with its environment, returns a function value
+    pub fn emit_make_closure(
+        &mut self,
+        ctx: &mut HIRContext,
+        function: DeclarationId,
+        environment: ValueId,
+    ) -> ValueId {
+        let fn_type = match ctx.program_builder.get_declaration(function) {
+            CheckedDeclaration::Function(f) => Type::Fn(FnType {
+                params: f.params.clone(),
+                return_type: Box::new(f.return_type.clone()),
+            }),
+            _ => panic!(
+                "INTERNAL COMPILER ERROR: emit_make_closure expected a function declaration"
+            ),
+        };
+
+        let destination = self.alloc_value(ctx, fn_type);
+        self.push_instruction(Instruction::MakeClosure {
+            destination,
+            function,
+            environment,
+        });
+
+        destination
+    }
+
     pub fn emit_type_cast(
         &mut self,
         ctx: &mut HIRContext,
@@ -473,7 +490,7 @@ impl FunctionBuilder {
     ) -> ValueId {
         let value_type = ctx.program_builder.get_value_type(&value);
 
-        if !self.check_is_casting_allowed(&value_type, &target_type) {
+        if !check_is_casting_allowed(&value_type, &target_type) {
             return self.report_error_and_get_poison(
                 ctx,
                 SemanticError {
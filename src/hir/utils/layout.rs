@@ -21,6 +21,9 @@ const PTR_ALIGN: usize = size_of::<usize>();
 const USIZE_SIZE: usize = size_of::<usize>();
 const USIZE_ALIGN: usize = size_of::<usize>();
 
+/// The largest array in bytes, arrays live on the stack
+pub const MAX_ARRAY_SIZE: usize = 1 << 20;
+
 /// IMPORTANT: Make sure user-defined and closure-environment structs are packed first before calling this function
 pub fn get_layout_of(ty: &Type, ctx: &ProgramBuilder) -> Layout {
     match ty {
@@ -30,12 +33,20 @@ pub fn get_layout_of(ty: &Type, ctx: &ProgramBuilder) -> Layout {
         Type::U32 | Type::I32 | Type::F32 => Layout::new(4, 4),
         Type::U64 | Type::I64 | Type::F64 => Layout::new(8, 8),
 
-        Type::Pointer { .. } | Type::Fn(_) | Type::USize | Type::ISize => {
-            Layout::new(USIZE_SIZE, USIZE_ALIGN)
-        }
+        Type::Pointer { .. } => Layout::new(PTR_SIZE, PTR_ALIGN),
+        // { fn: ptr, env: ptr }, env is null for functions which capture nothing
+        Type::Fn(_) => Layout::new(PTR_SIZE * 2, PTR_ALIGN),
+        Type::USize | Type::ISize => Layout::new(USIZE_SIZE, USIZE_ALIGN),
 
         Type::Buffer { size, alignment } => Layout::new(*size, *alignment),
 
+        // The size of a type is a multiple of its alignment, so the items need no
+        // padding between them
+        Type::Array { item_type, size } => {
+            let item_layout = get_layout_of(item_type, ctx);
+            Layout::new(item_layout.size * size, item_layout.alignment)
+        }
+
         Type::Unknown => Layout::new(0, 1),
 
         Type::Struct(s) => {
@@ -51,8 +62,40 @@ pub fn get_alignment_of(ty: &Type, ctx: &ProgramBuilder) -> usize {
     get_layout_of(ty, ctx).alignment
 }
 
+/// Returns the byte offset of the field at `field_index` within the given struct
+pub fn get_field_offset(
+    struct_kind: &StructKind,
+    field_index: usize,
+    ctx: &ProgramBuilder,
+) -> usize {
+    let fields = struct_kind.fields(ctx);
+    let types: Vec<&Type> = fields.iter().map(|(_, ty)| ty).collect();
+
+    calculate_field_offsets(&types, ctx)
+        .0
+        .get(field_index)
+        .copied()
+        .unwrap_or_else(|| {
+            panic!(
+                "INTERNAL COMPILER ERROR: Field index {} is out of bounds for a struct \
+                 with {} fields",
+                field_index,
+                types.len()
+            )
+        })
+}
+
 /// Helper to calculate layout of fields placed sequentially in memory
 fn calculate_fields_layout(field_types: &[&Type], ctx: &ProgramBuilder) -> Layout {
+    calculate_field_offsets(field_types, ctx).1
+}
+
+/// Returns the offset of each field together with the layout of the whole struct
+fn calculate_field_offsets(
+    field_types: &[&Type],
+    ctx: &ProgramBuilder,
+) -> (Vec<usize>, Layout) {
+    let mut offsets = Vec::with_capacity(field_types.len());
     let mut current_offset = 0;
     let mut max_alignment = 1;
 
@@ -66,13 +109,14 @@ fn calculate_fields_layout(field_types: &[&Type], ctx: &ProgramBuilder) -> Layou
             % field_layout.alignment;
 
         current_offset += padding;
+        offsets.push(current_offset);
         current_offset += field_layout.size;
     }
 
     let padding_end = (max_alignment - (current_offset % max_alignment)) % max_alignment;
     let total_size = current_offset + padding_end;
 
-    Layout::new(total_size, max_alignment)
+    (offsets, Layout::new(total_size, max_alignment))
 }
 
 pub fn pack_struct(
@@ -3,6 +3,9 @@ pub mod check_is_assignable;
 pub mod check_is_casting_allowed;
 pub mod check_is_equatable;
 pub mod check_type;
+pub mod check_unary_op;
+pub mod const_eval;
+pub mod generics;
 pub mod instruction_emitters;
 pub mod layout;
 pub mod new_basic_block;
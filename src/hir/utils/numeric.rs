@@ -1,4 +1,4 @@
-use crate::hir::types::checked_type::Type;
+use crate::{hir::types::checked_type::Type, tokenize::NumberKind};
 
 pub fn get_numeric_type_rank(ty: &Type) -> i32 {
     use Type::*;
@@ -13,6 +13,21 @@ pub fn get_numeric_type_rank(ty: &Type) -> i32 {
     }
 }
 
+/// The number of bits of an integer type, shift amounts wrap around it
+pub fn get_integer_bit_width(ty: &Type) -> u32 {
+    use Type::*;
+    match &ty {
+        I8 | U8 => 8,
+        I16 | U16 => 16,
+        I32 | U32 => 32,
+        I64 | U64 | ISize | USize => 64,
+        _ => panic!(
+            "INTERNAL COMPILER ERROR: Expected an integer type, found {:?}",
+            ty
+        ),
+    }
+}
+
 pub fn is_float(ty: &Type) -> bool {
     use Type::*;
     matches!(ty, F32 | F64)
@@ -26,6 +41,25 @@ pub fn is_integer(ty: &Type) -> bool {
     )
 }
 
+/// The literal `1` of a numeric type
+pub fn one_of(ty: &Type) -> Option<NumberKind> {
+    Some(match ty {
+        Type::I8 => NumberKind::I8(1),
+        Type::I16 => NumberKind::I16(1),
+        Type::I32 => NumberKind::I32(1),
+        Type::I64 => NumberKind::I64(1),
+        Type::ISize => NumberKind::ISize(1),
+        Type::U8 => NumberKind::U8(1),
+        Type::U16 => NumberKind::U16(1),
+        Type::U32 => NumberKind::U32(1),
+        Type::U64 => NumberKind::U64(1),
+        Type::USize => NumberKind::USize(1),
+        Type::F32 => NumberKind::F32(1.0),
+        Type::F64 => NumberKind::F64(1.0),
+        _ => return None,
+    })
+}
+
 pub fn is_signed(ty: &Type) -> bool {
     use Type::*;
     matches!(ty, I8 | I16 | I32 | I64 | ISize | F32 | F64)
@@ -10,11 +10,11 @@ use crate::{
         cfg::BasicBlockId,
         errors::{SemanticError, SemanticErrorKind},
         types::checked_declaration::CheckedDeclaration,
-        ModuleBuilder, ProgramBuilder,
+        ModuleBuilder, ProgramBuilder, SymbolReference,
     },
 };
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum ScopeKind {
     Function,
     While {
@@ -23,10 +23,11 @@ pub enum ScopeKind {
     },
     CodeBlock,
     File,
-    GenericParams, // Not used for now
+    /// Binds the type parameters of a generic declaration to its type arguments
+    GenericParams,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Scope {
     pub kind: ScopeKind,
     symbols: HashMap<StringId, DeclarationId>,
@@ -72,21 +73,39 @@ impl ModuleBuilder {
     ) {
         let decl_id = match &declaration {
             CheckedDeclaration::Var(decl) => decl.id,
+            CheckedDeclaration::Const(decl) => decl.id,
             CheckedDeclaration::TypeAlias(decl) => decl.id,
             CheckedDeclaration::Function(decl) => decl.id,
+            CheckedDeclaration::Module(decl) => decl.id,
             CheckedDeclaration::UninitializedVar { id, .. } => *id,
         };
 
         program_builder.declarations.insert(decl_id, declaration);
 
         let last_scope = self.last_scope_mut();
-        if let Entry::Vacant(e) = last_scope.symbols.entry(id.name) {
-            e.insert(decl_id);
-        } else {
-            self.errors.push(SemanticError {
-                kind: SemanticErrorKind::DuplicateIdentifier(id),
-                span: id.span,
-            });
+        match last_scope.symbols.entry(id.name) {
+            Entry::Vacant(e) => {
+                e.insert(decl_id);
+                self.definitions.insert(decl_id, id.span);
+                self.references.push(SymbolReference {
+                    span: id.span,
+                    decl_id,
+                });
+            }
+            Entry::Occupied(e) => {
+                let previous = program_builder
+                    .declarations
+                    .get(e.get())
+                    .map(|decl| decl.identifier().span);
+
+                self.errors.push(SemanticError {
+                    kind: SemanticErrorKind::DuplicateIdentifier {
+                        identifier: id,
+                        previous,
+                    },
+                    span: id.span,
+                });
+            }
         }
     }
 
@@ -133,6 +152,17 @@ impl ModuleBuilder {
         *existing_decl = new_declaration;
     }
 
+    /// Looks up the identifier and records the reference when it resolves
+    pub fn resolve_identifier(&mut self, id: IdentifierNode) -> Option<DeclarationId> {
+        let decl_id = self.scope_lookup(id.name)?;
+        self.references.push(SymbolReference {
+            span: id.span,
+            decl_id,
+        });
+
+        Some(decl_id)
+    }
+
     pub fn scope_lookup(&self, key: StringId) -> Option<DeclarationId> {
         for scope in self.scopes.iter().rev() {
             if let Some(id) = scope.symbols.get(&key) {
@@ -176,9 +206,16 @@ impl ModuleBuilder {
 
         if let Entry::Vacant(e) = last_scope.symbols.entry(id.name) {
             e.insert(decl_id);
+            self.references.push(SymbolReference {
+                span: id.span,
+                decl_id,
+            });
         } else {
             self.errors.push(SemanticError {
-                kind: SemanticErrorKind::DuplicateIdentifier(id),
+                kind: SemanticErrorKind::DuplicateIdentifier {
+                    identifier: id,
+                    previous: None,
+                },
                 span: id.span,
             });
         }
@@ -89,16 +89,18 @@ impl FunctionBuilder {
         block_id: BasicBlockId,
         original_value_id: ValueId,
     ) -> ValueId {
+        // A mapping (e.g. a narrowed pointer) takes precedence even in the block
+        // which defines the original value
+        if let Some(local_id) = self.get_mapped_value(block_id, original_value_id) {
+            return local_id;
+        }
+
         if let Some(def_block) = self.value_definitions.get(&original_value_id) {
             if *def_block == block_id {
                 return original_value_id;
             }
         }
 
-        if let Some(local_id) = self.get_mapped_value(block_id, original_value_id) {
-            return local_id;
-        }
-
         if !self.sealed_blocks.contains(&block_id) {
             // We don't know all predecessors yet, so we MUST create a placeholder parameter.
             // We will fill in the terminator arguments later when we seal.
@@ -242,40 +244,4 @@ impl FunctionBuilder {
                 )
             })
     }
-
-    /// Helper to find which ValueId is passed to a specific block parameter index
-    fn get_terminator_arg_for_param(
-        &self,
-        from_block: BasicBlockId,
-        to_block: BasicBlockId,
-        param_index: usize,
-    ) -> Value {
-        let terminator = self.cfg.blocks[&from_block]
-            .terminator
-            .as_ref()
-            .expect("Block must have terminator");
-
-        match terminator {
-            Terminator::Jump { target, args } => {
-                assert_eq!(target, &to_block);
-                args[param_index].clone()
-            }
-            Terminator::CondJump {
-                true_target,
-                true_args,
-                false_target,
-                false_args,
-                ..
-            } => {
-                if true_target == &to_block {
-                    true_args[param_index].clone()
-                } else if false_target == &to_block {
-                    false_args[param_index].clone()
-                } else {
-                    panic!("Inconsistent CFG: target block not found in CondJump")
-                }
-            }
-            _ => panic!("Terminator type does not support block arguments"),
-        }
-    }
 }
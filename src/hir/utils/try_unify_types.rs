@@ -130,7 +130,7 @@ fn wrap_variants(mut variants: Vec<TagType>) -> Type {
         return Type::Struct(StructKind::Tag(variants.pop().unwrap()));
     }
 
-    variants.sort_by(|a, b| a.id.0.cmp(&b.id.0));
+    variants.sort_by_key(|a| a.id.0);
 
     Type::Struct(StructKind::Union { variants })
 }
@@ -79,6 +79,10 @@ pub fn type_to_string_recursive(
         Type::Buffer { size, alignment } => {
             format!("Buffer(size={}, align={})", size, alignment)
         }
+        Type::Array { item_type, size } => {
+            let item = type_to_string_recursive(item_type, interners, visited_set);
+            format!("[{}; {}]", item, size)
+        }
     };
 
     visited_set.remove(ty);
@@ -149,6 +153,12 @@ fn struct_to_string(
 
             format!("{}[]", elem_type_str)
         }
+        StructKind::Slice(item_type) => {
+            let elem_type_str =
+                type_to_string_recursive(item_type, interners, visited_set);
+
+            format!("[{}]", elem_type_str)
+        }
         StructKind::String => String::from("string"),
     }
 }
@@ -52,7 +52,10 @@ impl<'a, 'b, 'ast> ASTVisitor<'ast> for CaptureAnalyzerVisitor<'a, 'b> {
     }
 
     fn visit_fn_expr(&mut self, decl: &'ast FnDecl) {
+        self.local_scope.insert(decl.identifier);
+
         let mut nested_visitor = CaptureAnalyzerVisitor::new(self.ctx);
+        nested_visitor.local_scope.insert(decl.identifier);
 
         for param in &decl.params {
             nested_visitor.local_scope.insert(param.identifier);
@@ -68,12 +71,16 @@ impl<'a, 'b, 'ast> ASTVisitor<'ast> for CaptureAnalyzerVisitor<'a, 'b> {
     }
 }
 
+/// The variables of enclosing functions which the function `identifier` uses. Its
+/// own name refers to the function itself and is never captured.
 pub fn analyze_captures(
     ctx: &mut HIRContext,
+    identifier: IdentifierNode,
     params: &[CheckedParam],
     body: &BlockContents,
 ) -> HashMap<IdentifierNode, Type> {
     let mut visitor = CaptureAnalyzerVisitor::new(ctx);
+    visitor.local_scope.insert(identifier);
 
     for p in params {
         visitor.local_scope.insert(p.identifier);
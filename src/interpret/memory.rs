@@ -0,0 +1,156 @@
+use crate::interpret::RuntimeError;
+
+/// Pointers are encoded as `(allocation index + 1) << 32 | offset`, so the null pointer
+/// stays 0 and any arithmetic on a pointer keeps it inside its allocation's address
+/// range where bounds can be checked.
+const OFFSET_BITS: u32 = 32;
+const OFFSET_MASK: u64 = (1 << OFFSET_BITS) - 1;
+
+/// Function pointers live outside of the allocation address space, the low bits hold
+/// the DeclarationId of the function.
+pub const FUNCTION_POINTER_TAG: u64 = 1 << 63;
+
+/// Function values are `{ fn: ptr, env: ptr }`, this is the offset of the environment
+pub const FUNCTION_ENV_OFFSET: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AllocationKind {
+    Stack,
+    Heap,
+    Constant,
+}
+
+#[derive(Debug)]
+struct Allocation {
+    kind: AllocationKind,
+    bytes: Vec<u8>,
+    is_freed: bool,
+}
+
+/// Byte-addressed memory shared by every frame of the interpreter. Allocations are
+/// never reused, so dangling pointers are always detected.
+#[derive(Debug, Default)]
+pub struct Memory {
+    allocations: Vec<Allocation>,
+}
+
+impl Memory {
+    /// A zeroed allocation of `size` bytes, a request the host cannot provide is an
+    /// error of the program instead of aborting the interpreter
+    pub fn allocate(
+        &mut self,
+        kind: AllocationKind,
+        size: usize,
+    ) -> Result<u64, RuntimeError> {
+        // Offsets inside an allocation have to fit in the low bits of a pointer
+        if size as u64 > OFFSET_MASK {
+            return Err(RuntimeError::OutOfMemory(size));
+        }
+
+        let mut bytes = Vec::new();
+        bytes
+            .try_reserve_exact(size)
+            .map_err(|_| RuntimeError::OutOfMemory(size))?;
+        bytes.resize(size, 0);
+
+        Ok(self.allocate_with(kind, bytes))
+    }
+
+    pub fn allocate_with(&mut self, kind: AllocationKind, bytes: Vec<u8>) -> u64 {
+        self.allocations.push(Allocation {
+            kind,
+            bytes,
+            is_freed: false,
+        });
+
+        (self.allocations.len() as u64) << OFFSET_BITS
+    }
+
+    pub fn free(
+        &mut self,
+        ptr: u64,
+        expected: AllocationKind,
+    ) -> Result<(), RuntimeError> {
+        let (index, offset) = decode(ptr).ok_or(RuntimeError::NullPointer)?;
+        let allocation = self
+            .allocations
+            .get_mut(index)
+            .ok_or(RuntimeError::InvalidPointer(ptr))?;
+
+        if allocation.is_freed {
+            return Err(RuntimeError::DoubleFree(ptr));
+        }
+        if offset != 0 || allocation.kind != expected {
+            return Err(RuntimeError::InvalidFree(ptr));
+        }
+
+        allocation.is_freed = true;
+        allocation.bytes = Vec::new();
+
+        Ok(())
+    }
+
+    pub fn read(&self, ptr: u64, size: usize) -> Result<&[u8], RuntimeError> {
+        if size == 0 {
+            return Ok(&[]);
+        }
+
+        let (allocation, offset) = self.resolve(ptr, size)?;
+        Ok(&allocation.bytes[offset..offset + size])
+    }
+
+    pub fn write(&mut self, ptr: u64, bytes: &[u8]) -> Result<(), RuntimeError> {
+        if bytes.is_empty() {
+            return Ok(());
+        }
+
+        let (index, offset) = self.locate(ptr, bytes.len())?;
+        let allocation = &mut self.allocations[index];
+        if allocation.kind == AllocationKind::Constant {
+            return Err(RuntimeError::WriteToConstant(ptr));
+        }
+
+        allocation.bytes[offset..offset + bytes.len()].copy_from_slice(bytes);
+        Ok(())
+    }
+
+    fn resolve(
+        &self,
+        ptr: u64,
+        size: usize,
+    ) -> Result<(&Allocation, usize), RuntimeError> {
+        let (index, offset) = self.locate(ptr, size)?;
+        Ok((&self.allocations[index], offset))
+    }
+
+    fn locate(&self, ptr: u64, size: usize) -> Result<(usize, usize), RuntimeError> {
+        let (index, offset) = decode(ptr).ok_or(RuntimeError::NullPointer)?;
+        let allocation = self
+            .allocations
+            .get(index)
+            .ok_or(RuntimeError::InvalidPointer(ptr))?;
+
+        if allocation.is_freed {
+            return Err(RuntimeError::UseAfterFree(ptr));
+        }
+        if offset + size > allocation.bytes.len() {
+            return Err(RuntimeError::OutOfBounds {
+                ptr,
+                size,
+                allocation_size: allocation.bytes.len(),
+            });
+        }
+
+        Ok((index, offset))
+    }
+}
+
+/// Splits a pointer into its allocation index and offset, None for null
+fn decode(ptr: u64) -> Option<(usize, usize)> {
+    let allocation = ptr >> OFFSET_BITS;
+    if allocation == 0 {
+        return None;
+    }
+
+    Some(((allocation - 1) as usize, (ptr & OFFSET_MASK) as usize))
+}
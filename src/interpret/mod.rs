@@ -0,0 +1,1563 @@
+use std::collections::HashMap;
+
+use crate::{
+    ast::DeclarationId,
+    codegen::tagged_payload,
+    hir::{
+        cfg::{
+            BasicBlock, BasicBlockId, BinaryOperationKind, ConstantId, Instruction,
+            Terminator, UnaryOperationKind, Value, ValueId,
+        },
+        types::{
+            checked_declaration::{CheckedDeclaration, CheckedFnDecl},
+            checked_type::{StructKind, Type},
+        },
+        utils::{
+            layout::{get_field_offset, get_layout_of},
+            numeric::{
+                get_integer_bit_width, get_numeric_type_rank, is_float, is_integer,
+            },
+        },
+        ProgramBuilder,
+    },
+    interpret::{
+        memory::{AllocationKind, Memory, FUNCTION_ENV_OFFSET, FUNCTION_POINTER_TAG},
+        value::{
+            decode_value, read_float, read_int, read_pointer, read_string, write_float,
+            write_int,
+        },
+    },
+    tokenize::NumberKind,
+};
+
+pub mod memory;
+pub mod value;
+
+pub use value::RuntimeValue;
+
+/// Upper bound on nested calls before the program is considered to recurse forever
+const MAX_CALL_DEPTH: usize = 100_000;
+
+/// The bytes of a function value, `env` is 0 for functions which capture nothing
+fn function_value(id: DeclarationId, env: u64) -> Vec<u8> {
+    let mut bytes = (FUNCTION_POINTER_TAG | id.0 as u64).to_le_bytes().to_vec();
+    bytes.extend(env.to_le_bytes());
+    bytes
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RuntimeError {
+    /// The entry function declares parameters, which the interpreter cannot provide
+    InvalidEntryPoint,
+    NullPointer,
+    InvalidPointer(u64),
+    UseAfterFree(u64),
+    DoubleFree(u64),
+    /// Freeing a pointer which does not point to the start of a heap allocation
+    InvalidFree(u64),
+    WriteToConstant(u64),
+    OutOfBounds {
+        ptr: u64,
+        size: usize,
+        allocation_size: usize,
+    },
+    DivisionByZero,
+    /// A tag or union holds an id which is not one of its variants
+    InvalidTagId(u16),
+    /// The called function has no body to execute
+    MissingFunctionBody(String),
+    StackOverflow,
+    /// An allocation of this many bytes could not be made
+    OutOfMemory(usize),
+    Unreachable,
+    /// The instruction has no implementation in the interpreter yet
+    UnsupportedInstruction(&'static str),
+}
+
+/// Runs `entry` to completion and returns its result
+pub fn run(
+    program: &ProgramBuilder,
+    entry: DeclarationId,
+) -> Result<RuntimeValue, RuntimeError> {
+    let entry_fn = match program.get_declaration(entry) {
+        CheckedDeclaration::Function(f) => f,
+        _ => return Err(RuntimeError::InvalidEntryPoint),
+    };
+    if !entry_fn.params.is_empty() {
+        return Err(RuntimeError::InvalidEntryPoint);
+    }
+
+    let mut interpreter = Interpreter::new(program);
+    let bytes = interpreter.call_entry(entry_fn, Vec::new())?;
+
+    decode_value(&bytes, &entry_fn.return_type, &interpreter.memory, program)
+}
+
+/// Memory and constants which outlive a single run, so values made by one entry
+/// function can be used by the next one
+#[derive(Debug, Default)]
+pub struct Session {
+    memory: Memory,
+    constants: HashMap<ConstantId, u64>,
+}
+
+/// Runs `entry` in `session`, passing it the pointers `args`. The stack slots the
+/// values `kept` of the entry point to are not freed when it returns, their addresses
+/// are returned with its result.
+pub fn run_in_session(
+    program: &ProgramBuilder,
+    entry: DeclarationId,
+    args: &[u64],
+    kept: &[ValueId],
+    session: &mut Session,
+) -> Result<(RuntimeValue, HashMap<ValueId, u64>), RuntimeError> {
+    let entry_fn = match program.get_declaration(entry) {
+        CheckedDeclaration::Function(f) => f,
+        _ => return Err(RuntimeError::InvalidEntryPoint),
+    };
+    if entry_fn.params.len() != args.len() {
+        return Err(RuntimeError::InvalidEntryPoint);
+    }
+
+    let mut interpreter = Interpreter::resume(program, std::mem::take(session));
+    interpreter.kept_slots = kept.to_vec();
+    let args = args
+        .iter()
+        .zip(&entry_fn.params)
+        .map(|(ptr, param)| (ptr.to_le_bytes().to_vec(), param.ty.clone()))
+        .collect();
+
+    let result = interpreter.call_entry(entry_fn, args).and_then(|bytes| {
+        decode_value(&bytes, &entry_fn.return_type, &interpreter.memory, program)
+    });
+
+    let kept_addresses = std::mem::take(&mut interpreter.kept_addresses);
+    *session = Session {
+        memory: interpreter.memory,
+        constants: interpreter.constants,
+    };
+
+    result.map(|value| (value, kept_addresses))
+}
+
+struct Frame<'a> {
+    function: &'a CheckedFnDecl,
+    block: &'a BasicBlock,
+    next_instruction: usize,
+    values: HashMap<ValueId, Vec<u8>>,
+    stack_allocations: Vec<u64>,
+    /// Where the caller expects the returned value
+    return_destination: Option<ValueId>,
+}
+
+/// Executes the control flow graphs of a checked program directly. Every value is kept
+/// in its in-memory representation, so layouts, field offsets and tag conversions
+/// behave exactly like in compiled code.
+pub struct Interpreter<'a> {
+    program: &'a ProgramBuilder,
+    memory: Memory,
+    constants: HashMap<ConstantId, u64>,
+    frames: Vec<Frame<'a>>,
+    /// Values of the entry function pointing to stack slots which outlive it
+    kept_slots: Vec<ValueId>,
+    kept_addresses: HashMap<ValueId, u64>,
+}
+
+impl<'a> Interpreter<'a> {
+    pub fn new(program: &'a ProgramBuilder) -> Self {
+        Self::resume(program, Session::default())
+    }
+
+    /// Continues with the memory of `session`, only the constants added to the
+    /// program since are allocated
+    pub fn resume(program: &'a ProgramBuilder, session: Session) -> Self {
+        let Session {
+            mut memory,
+            mut constants,
+        } = session;
+        let mut constant_ids: Vec<&ConstantId> = program
+            .constant_data
+            .keys()
+            .filter(|id| !constants.contains_key(*id))
+            .collect();
+        constant_ids.sort_by_key(|id| id.0);
+
+        for id in constant_ids {
+            let bytes = program.constant_data[id].clone();
+            constants.insert(*id, memory.allocate_with(AllocationKind::Constant, bytes));
+        }
+
+        Self {
+            program,
+            memory,
+            constants,
+            frames: Vec::new(),
+            kept_slots: Vec::new(),
+            kept_addresses: HashMap::new(),
+        }
+    }
+
+    fn call_entry(
+        &mut self,
+        entry_fn: &'a CheckedFnDecl,
+        args: Vec<(Vec<u8>, Type)>,
+    ) -> Result<Vec<u8>, RuntimeError> {
+        self.push_frame(entry_fn, args, None)?;
+
+        loop {
+            let frame = self.frames.last_mut().unwrap();
+            let block = frame.block;
+
+            if let Some(instruction) = block.instructions.get(frame.next_instruction) {
+                frame.next_instruction += 1;
+                self.execute_instruction(instruction)?;
+                continue;
+            }
+
+            let terminator =
+                block.terminator.as_ref().ok_or(RuntimeError::Unreachable)?;
+            if let Some(result) = self.execute_terminator(terminator)? {
+                return Ok(result);
+            }
+        }
+    }
+
+    fn push_frame(
+        &mut self,
+        function: &'a CheckedFnDecl,
+        args: Vec<(Vec<u8>, Type)>,
+        return_destination: Option<ValueId>,
+    ) -> Result<(), RuntimeError> {
+        if self.frames.len() >= MAX_CALL_DEPTH {
+            return Err(RuntimeError::StackOverflow);
+        }
+
+        let cfg = function.body.as_ref().ok_or_else(|| {
+            RuntimeError::MissingFunctionBody(
+                self.program
+                    .string_interner
+                    .resolve(function.identifier.name),
+            )
+        })?;
+        let block = &cfg.blocks[&cfg.entry_block];
+
+        let mut values = HashMap::new();
+        for (param, (bytes, ty)) in block.params.iter().zip(args) {
+            let param_type = self.program.get_value_id_type(param);
+            values.insert(*param, self.convert(&bytes, &ty, &param_type));
+        }
+
+        self.frames.push(Frame {
+            function,
+            block,
+            next_instruction: 0,
+            values,
+            stack_allocations: Vec::new(),
+            return_destination,
+        });
+
+        Ok(())
+    }
+
+    fn frame(&mut self) -> &mut Frame<'a> {
+        self.frames
+            .last_mut()
+            .expect("INTERNAL COMPILER ERROR: Interpreter has no active frame")
+    }
+
+    fn type_of(&self, id: ValueId) -> Type {
+        self.program.get_value_id_type(&id)
+    }
+
+    fn size_of(&self, ty: &Type) -> usize {
+        get_layout_of(ty, self.program).size
+    }
+
+    fn pointee_of(&self, ptr: ValueId) -> Type {
+        match self.type_of(ptr) {
+            Type::Pointer { constraint, .. } => *constraint,
+            other => panic!(
+                "INTERNAL COMPILER ERROR: Expected value {} to be a pointer, found {:?}",
+                ptr.0, other
+            ),
+        }
+    }
+
+    fn evaluate(&self, value: &Value) -> Vec<u8> {
+        match value {
+            Value::VoidLiteral => Vec::new(),
+            Value::BoolLiteral(b) => vec![*b as u8],
+            Value::NumberLiteral(kind) => match kind {
+                NumberKind::I64(n) => n.to_le_bytes().to_vec(),
+                NumberKind::I32(n) => n.to_le_bytes().to_vec(),
+                NumberKind::I16(n) => n.to_le_bytes().to_vec(),
+                NumberKind::I8(n) => n.to_le_bytes().to_vec(),
+                NumberKind::ISize(n) => n.to_le_bytes().to_vec(),
+                NumberKind::U64(n) => n.to_le_bytes().to_vec(),
+                NumberKind::U32(n) => n.to_le_bytes().to_vec(),
+                NumberKind::U16(n) => n.to_le_bytes().to_vec(),
+                NumberKind::U8(n) => n.to_le_bytes().to_vec(),
+                NumberKind::USize(n) => n.to_le_bytes().to_vec(),
+                NumberKind::F32(n) => n.to_le_bytes().to_vec(),
+                NumberKind::F64(n) => n.to_le_bytes().to_vec(),
+            },
+            Value::Function(id) => function_value(*id, 0),
+            Value::Use(id) => self
+                .frames
+                .last()
+                .and_then(|frame| frame.values.get(id))
+                .cloned()
+                .unwrap_or_else(|| {
+                    panic!(
+                        "INTERNAL COMPILER ERROR: Value {} used before it was defined",
+                        id.0
+                    )
+                }),
+        }
+    }
+
+    fn evaluate_as(&self, value: &Value, target: &Type) -> Vec<u8> {
+        let source = self.program.get_value_type(value);
+        self.convert(&self.evaluate(value), &source, target)
+    }
+
+    fn define(&mut self, id: ValueId, bytes: Vec<u8>) {
+        self.frame().values.insert(id, bytes);
+    }
+
+    fn pointer_value(&self, id: ValueId) -> u64 {
+        read_pointer(&self.evaluate(&Value::Use(id)))
+    }
+
+    fn int_value(&self, value: &Value) -> i128 {
+        let ty = self.program.get_value_type(value);
+        read_int(&self.evaluate(value), &ty)
+    }
+
+    /// Moves a value between two representations, mirroring the coercions the native
+    /// backends perform on every store, load, call and block argument
+    fn convert(&self, bytes: &[u8], from: &Type, to: &Type) -> Vec<u8> {
+        let to_size = self.size_of(to);
+        if to_size == 0 {
+            return Vec::new();
+        }
+        if from == to {
+            return bytes.to_vec();
+        }
+
+        let is_scalar = |ty: &Type| {
+            is_integer(ty)
+                || is_float(ty)
+                || matches!(ty, Type::Bool | Type::Pointer { .. })
+        };
+
+        if is_scalar(from) && is_scalar(to) {
+            return if is_float(to) {
+                let value = if is_float(from) {
+                    read_float(bytes, from)
+                } else {
+                    read_int(bytes, from) as f64
+                };
+                write_float(value, to)
+            } else {
+                let value = if is_float(from) {
+                    read_float(bytes, from) as i128
+                } else {
+                    read_int(bytes, from)
+                };
+                write_int(value, to, self.program)
+            };
+        }
+
+        let mut result = vec![0; to_size];
+        match (
+            tagged_payload(from, self.program),
+            tagged_payload(to, self.program),
+        ) {
+            (Some((from_offset, from_size)), Some((to_offset, to_size))) => {
+                result[..2].copy_from_slice(&bytes[..2]);
+                let size = from_size.min(to_size);
+                result[to_offset..to_offset + size]
+                    .copy_from_slice(&bytes[from_offset..from_offset + size]);
+            }
+            _ => {
+                let size = bytes.len().min(to_size);
+                result[..size].copy_from_slice(&bytes[..size]);
+            }
+        }
+
+        result
+    }
+
+    fn execute_instruction(
+        &mut self,
+        instruction: &'a Instruction,
+    ) -> Result<(), RuntimeError> {
+        match instruction {
+            Instruction::StackAlloc { destination, count } => {
+                let size = self
+                    .size_of(&self.pointee_of(*destination))
+                    .saturating_mul(*count);
+                let ptr = self.memory.allocate(AllocationKind::Stack, size)?;
+                self.frame().stack_allocations.push(ptr);
+                self.define(*destination, ptr.to_le_bytes().to_vec());
+            }
+            Instruction::HeapAlloc { destination, count } => {
+                let count = usize::try_from(self.int_value(count)).unwrap_or(0);
+                let size = self
+                    .size_of(&self.pointee_of(*destination))
+                    .saturating_mul(count);
+                let ptr = self.memory.allocate(AllocationKind::Heap, size)?;
+                self.define(*destination, ptr.to_le_bytes().to_vec());
+            }
+            Instruction::HeapFree { ptr } => {
+                let ptr = self.pointer_value(*ptr);
+                self.memory.free(ptr, AllocationKind::Heap)?;
+            }
+            Instruction::Store { ptr, value } => {
+                let target = self.pointee_of(*ptr);
+                let bytes = self.evaluate_as(value, &target);
+                let ptr = self.pointer_value(*ptr);
+                self.memory.write(ptr, &bytes)?;
+            }
+            Instruction::Load { destination, ptr } => {
+                let source = self.pointee_of(*ptr);
+                let address = self.pointer_value(*ptr);
+                let bytes = self.memory.read(address, self.size_of(&source))?.to_vec();
+                let bytes = self.convert(&bytes, &source, &self.type_of(*destination));
+                self.define(*destination, bytes);
+            }
+            Instruction::LoadConstant {
+                destination,
+                constant_id,
+            } => {
+                let ptr = self.constants[constant_id];
+                self.define(*destination, ptr.to_le_bytes().to_vec());
+            }
+            Instruction::UnaryOp {
+                op_kind,
+                destination,
+                operand,
+            } => {
+                let ty = self.program.get_value_type(operand);
+                let bytes = self.evaluate(operand);
+                let result = match op_kind {
+                    UnaryOperationKind::Not => vec![(bytes[0] == 0) as u8],
+                    UnaryOperationKind::Neg if is_float(&ty) => {
+                        write_float(-read_float(&bytes, &ty), &ty)
+                    }
+                    UnaryOperationKind::Neg => {
+                        write_int(read_int(&bytes, &ty).wrapping_neg(), &ty, self.program)
+                    }
+                    UnaryOperationKind::BitNot => {
+                        write_int(!read_int(&bytes, &ty), &ty, self.program)
+                    }
+                };
+                self.define(*destination, result);
+            }
+            Instruction::BinaryOp {
+                op_kind,
+                destination,
+                left,
+                right,
+            } => {
+                let result = self.binary_op(op_kind, *destination, left, right)?;
+                self.define(*destination, result);
+            }
+            Instruction::TypeCast {
+                destination,
+                operand,
+                target_type,
+            } => {
+                let bytes = self.evaluate_as(operand, target_type);
+                let bytes =
+                    self.convert(&bytes, target_type, &self.type_of(*destination));
+                self.define(*destination, bytes);
+            }
+            Instruction::FunctionCall {
+                destination,
+                function_rvalue,
+                args,
+            } => {
+                let function = self.resolve_function(function_rvalue)?;
+                let mut args: Vec<(Vec<u8>, Type)> = args
+                    .iter()
+                    .map(|arg| (self.evaluate(arg), self.program.get_value_type(arg)))
+                    .collect();
+
+                // Calls through a function value pass its environment along
+                if let (Value::Use(_), Some(env_param)) =
+                    (function_rvalue, function.environment)
+                {
+                    let env =
+                        self.evaluate(function_rvalue)[FUNCTION_ENV_OFFSET..].to_vec();
+                    args.push((env, self.type_of(env_param)));
+                }
+
+                self.push_frame(function, args, *destination)?;
+            }
+            Instruction::MakeClosure {
+                destination,
+                function,
+                environment,
+            } => {
+                let env = self.pointer_value(*environment);
+                self.define(*destination, function_value(*function, env));
+            }
+            Instruction::GetFieldPtr {
+                destination,
+                base_ptr,
+                field_index,
+            } => {
+                let offset = match self.pointee_of(*base_ptr) {
+                    Type::Struct(kind) => {
+                        get_field_offset(&kind, *field_index, self.program)
+                    }
+                    other => panic!(
+                        "INTERNAL COMPILER ERROR: GetFieldPtr on a pointer to non-struct \
+                         type {:?}",
+                        other
+                    ),
+                };
+                let ptr = self.pointer_value(*base_ptr).wrapping_add(offset as u64);
+                self.define(*destination, ptr.to_le_bytes().to_vec());
+            }
+            Instruction::GetElementPtr {
+                destination,
+                base_ptr,
+                index,
+            } => {
+                let stride = self.size_of(&self.pointee_of(*base_ptr)) as i128;
+                let offset = self.int_value(index) * stride;
+                let ptr = (self.pointer_value(*base_ptr) as i128).wrapping_add(offset);
+                self.define(*destination, (ptr as u64).to_le_bytes().to_vec());
+            }
+            Instruction::Nop => {}
+            Instruction::FileOpen { .. } => {
+                return Err(RuntimeError::UnsupportedInstruction("FileOpen"))
+            }
+            Instruction::FileWrite { .. } => {
+                return Err(RuntimeError::UnsupportedInstruction("FileWrite"))
+            }
+            Instruction::FileRead { .. } => {
+                return Err(RuntimeError::UnsupportedInstruction("FileRead"))
+            }
+            Instruction::FileClose { .. } => {
+                return Err(RuntimeError::UnsupportedInstruction("FileClose"))
+            }
+            Instruction::SocketConnect { .. } => {
+                return Err(RuntimeError::UnsupportedInstruction("SocketConnect"))
+            }
+            Instruction::SocketSend { .. } => {
+                return Err(RuntimeError::UnsupportedInstruction("SocketSend"))
+            }
+            Instruction::SocketClose { .. } => {
+                return Err(RuntimeError::UnsupportedInstruction("SocketClose"))
+            }
+        };
+
+        Ok(())
+    }
+
+    fn resolve_function(&self, value: &Value) -> Result<&'a CheckedFnDecl, RuntimeError> {
+        let id = match value {
+            Value::Function(id) => *id,
+            other => {
+                let ptr = read_pointer(&self.evaluate(other)[..FUNCTION_ENV_OFFSET]);
+                if ptr & FUNCTION_POINTER_TAG == 0 {
+                    return Err(RuntimeError::InvalidPointer(ptr));
+                }
+                DeclarationId((ptr & !FUNCTION_POINTER_TAG) as usize)
+            }
+        };
+
+        match self.program.declarations.get(&id) {
+            Some(CheckedDeclaration::Function(f)) => Ok(f),
+            _ => Err(RuntimeError::InvalidPointer(
+                FUNCTION_POINTER_TAG | id.0 as u64,
+            )),
+        }
+    }
+
+    fn binary_op(
+        &self,
+        op_kind: &BinaryOperationKind,
+        destination: ValueId,
+        left: &Value,
+        right: &Value,
+    ) -> Result<Vec<u8>, RuntimeError> {
+        let left_type = self.program.get_value_type(left);
+        let right_type = self.program.get_value_type(right);
+
+        match op_kind {
+            BinaryOperationKind::Equal => {
+                return Ok(vec![self.values_equal(left, right)? as u8]);
+            }
+            BinaryOperationKind::NotEqual => {
+                return Ok(vec![!self.values_equal(left, right)? as u8]);
+            }
+            // The amount wraps around the width of the left side, `>>` is arithmetic
+            // for signed integers and logical for unsigned ones
+            BinaryOperationKind::ShiftLeft | BinaryOperationKind::ShiftRight => {
+                let l = read_int(&self.evaluate(left), &left_type);
+                let amount = read_int(&self.evaluate(right), &right_type) as u32
+                    & (get_integer_bit_width(&left_type) - 1);
+                let result = if matches!(op_kind, BinaryOperationKind::ShiftLeft) {
+                    l.wrapping_shl(amount)
+                } else {
+                    l >> amount
+                };
+                return Ok(write_int(result, &left_type, self.program));
+            }
+            _ => {}
+        }
+
+        let common_type =
+            if get_numeric_type_rank(&left_type) >= get_numeric_type_rank(&right_type) {
+                left_type.clone()
+            } else {
+                right_type.clone()
+            };
+        let destination_type = self.type_of(destination);
+        let l = self.evaluate_as(left, &common_type);
+        let r = self.evaluate_as(right, &common_type);
+
+        let compare = |ordering: Option<std::cmp::Ordering>| -> Vec<u8> {
+            use std::cmp::Ordering::*;
+            let result = match op_kind {
+                BinaryOperationKind::LessThan => ordering == Some(Less),
+                BinaryOperationKind::LessThanOrEqual => {
+                    matches!(ordering, Some(Less | Equal))
+                }
+                BinaryOperationKind::GreaterThan => ordering == Some(Greater),
+                BinaryOperationKind::GreaterThanOrEqual => {
+                    matches!(ordering, Some(Greater | Equal))
+                }
+                _ => unreachable!(),
+            };
+            vec![result as u8]
+        };
+
+        if is_float(&common_type) {
+            let l = read_float(&l, &common_type);
+            let r = read_float(&r, &common_type);
+            let result = match op_kind {
+                BinaryOperationKind::Add => l + r,
+                BinaryOperationKind::Subtract => l - r,
+                BinaryOperationKind::Multiply => l * r,
+                BinaryOperationKind::Divide => l / r,
+                BinaryOperationKind::Modulo => l % r,
+                _ => return Ok(compare(l.partial_cmp(&r))),
+            };
+            return Ok(write_float(result, &destination_type));
+        }
+
+        let l = read_int(&l, &common_type);
+        let r = read_int(&r, &common_type);
+        let result = match op_kind {
+            BinaryOperationKind::Add => l.wrapping_add(r),
+            BinaryOperationKind::Subtract => l.wrapping_sub(r),
+            BinaryOperationKind::Multiply => l.wrapping_mul(r),
+            BinaryOperationKind::Divide | BinaryOperationKind::Modulo if r == 0 => {
+                return Err(RuntimeError::DivisionByZero);
+            }
+            BinaryOperationKind::Divide => l.wrapping_div(r),
+            BinaryOperationKind::Modulo => l.wrapping_rem(r),
+            BinaryOperationKind::BitAnd => l & r,
+            BinaryOperationKind::BitOr => l | r,
+            BinaryOperationKind::BitXor => l ^ r,
+            _ => return Ok(compare(Some(l.cmp(&r)))),
+        };
+
+        Ok(write_int(result, &destination_type, self.program))
+    }
+
+    fn values_equal(&self, left: &Value, right: &Value) -> Result<bool, RuntimeError> {
+        let left_type = self.program.get_value_type(left);
+        let right_type = self.program.get_value_type(right);
+        let l = self.evaluate(left);
+        let r = self.evaluate(right);
+
+        if is_integer(&left_type) && is_integer(&right_type) {
+            return Ok(read_int(&l, &left_type) == read_int(&r, &right_type));
+        }
+
+        self.bytes_equal(&l, &left_type, &r, &right_type)
+    }
+
+    /// Structural equality, the same rules the backends' generated comparisons follow
+    fn bytes_equal(
+        &self,
+        left: &[u8],
+        left_type: &Type,
+        right: &[u8],
+        right_type: &Type,
+    ) -> Result<bool, RuntimeError> {
+        if let (Some((left_payload, _)), Some((right_payload, _))) = (
+            tagged_payload(left_type, self.program),
+            tagged_payload(right_type, self.program),
+        ) {
+            if left[..2] != right[..2] {
+                return Ok(false);
+            }
+
+            let id = read_int(&left[..2], &Type::U16) as u16;
+            let variants = match left_type {
+                Type::Struct(StructKind::Tag(tag)) => vec![tag.clone()],
+                Type::Struct(StructKind::Union { variants }) => variants.clone(),
+                _ => unreachable!(),
+            };
+            let payload_type = variants
+                .into_iter()
+                .find(|v| v.id.0 == id)
+                .and_then(|v| v.value_type);
+
+            return match payload_type {
+                Some(payload_type) => {
+                    let size = self.size_of(&payload_type);
+                    self.bytes_equal(
+                        &left[left_payload..left_payload + size],
+                        &payload_type,
+                        &right[right_payload..right_payload + size],
+                        &payload_type,
+                    )
+                }
+                None => Ok(true),
+            };
+        }
+
+        match left_type {
+            Type::F32 | Type::F64 => {
+                Ok(read_float(left, left_type) == read_float(right, right_type))
+            }
+            Type::Pointer { constraint, .. }
+                if matches!(**constraint, Type::Struct(StructKind::String)) =>
+            {
+                let l = read_string(read_pointer(left), &self.memory, self.program)?;
+                let r = read_string(read_pointer(right), &self.memory, self.program)?;
+                Ok(l == r)
+            }
+            _ => {
+                let size = left.len().min(right.len());
+                Ok(left[..size] == right[..size])
+            }
+        }
+    }
+
+    /// Returns the result of the program once the entry function returns
+    fn execute_terminator(
+        &mut self,
+        terminator: &'a Terminator,
+    ) -> Result<Option<Vec<u8>>, RuntimeError> {
+        match terminator {
+            Terminator::Jump { target, args } => self.jump(*target, args),
+            Terminator::CondJump {
+                condition,
+                true_target,
+                true_args,
+                false_target,
+                false_args,
+            } => {
+                if self.evaluate(condition)[0] != 0 {
+                    self.jump(*true_target, true_args)
+                } else {
+                    self.jump(*false_target, false_args)
+                }
+            }
+            Terminator::Return { value } => {
+                let frame = self.frames.last().unwrap();
+                let return_type = frame.function.return_type.clone();
+                let result = match value {
+                    Some(value) => self.evaluate_as(value, &return_type),
+                    None => Vec::new(),
+                };
+
+                let frame = self.frames.pop().unwrap();
+                if self.frames.is_empty() {
+                    self.kept_addresses = self
+                        .kept_slots
+                        .iter()
+                        .filter_map(|id| Some((*id, read_pointer(frame.values.get(id)?))))
+                        .collect();
+                }
+                for ptr in frame.stack_allocations {
+                    if !self.kept_addresses.values().any(|kept| *kept == ptr) {
+                        self.memory.free(ptr, AllocationKind::Stack)?;
+                    }
+                }
+
+                if self.frames.is_empty() {
+                    return Ok(Some(result));
+                }
+
+                if let Some(destination) = frame.return_destination {
+                    let result =
+                        self.convert(&result, &return_type, &self.type_of(destination));
+                    self.define(destination, result);
+                }
+
+                Ok(None)
+            }
+            Terminator::Unreachable => Err(RuntimeError::Unreachable),
+        }
+    }
+
+    fn jump(
+        &mut self,
+        target: BasicBlockId,
+        args: &[Value],
+    ) -> Result<Option<Vec<u8>>, RuntimeError> {
+        let function: &'a CheckedFnDecl = self.frames.last().unwrap().function;
+        let block = &function.body.as_ref().unwrap().blocks[&target];
+
+        // All arguments are evaluated before any parameter is assigned, a block may
+        // receive its own parameters in a different order
+        let values: Vec<(ValueId, Vec<u8>)> = block
+            .params
+            .iter()
+            .zip(args)
+            .map(|(param, arg)| (*param, self.evaluate_as(arg, &self.type_of(*param))))
+            .collect();
+
+        let frame = self.frame();
+        for (param, bytes) in values {
+            frame.values.insert(param, bytes);
+        }
+        frame.block = block;
+        frame.next_instruction = 0;
+
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        compile::check_source,
+        interpret::{run, RuntimeError, RuntimeValue},
+    };
+    use pretty_assertions::assert_eq;
+
+    fn interpret(name: &str, source: &str) -> Result<RuntimeValue, RuntimeError> {
+        let (program, entry) = check_source(name, source);
+        run(&program, entry)
+    }
+
+    #[test]
+    fn test_recursion_and_while_loop() {
+        let source = r#"
+            fn fib(n: i32): i32 {
+                if n < 2i32 {
+                    n
+                } else {
+                    fib(n - 1i32) + fib(n - 2i32)
+                }
+            }
+
+            fn main(): i32 {
+                let mut i: i32 = 0i32;
+                let mut acc: i32 = 0i32;
+                while i < 5i32 {
+                    acc = acc + i;
+                    i = i + 1i32;
+                }
+                fib(10i32) - acc
+            }
+        "#;
+
+        assert_eq!(interpret("fib", source), Ok(RuntimeValue::Int(45)));
+    }
+
+    #[test]
+    fn test_struct_fields_match_and_function_pointers() {
+        let source = r#"
+            type Shape = #Circle(f64) | #Square(f64) | #Empty;
+            type Point = { x: i32, y: i64, tag: u8 };
+
+            fn area(s: Shape): f64 {
+                match s {
+                    Circle(r) => r * r * 3f64,
+                    Square(x) => x * x,
+                    Empty => 0f64,
+                }
+            }
+
+            fn apply(f: fn(a: i32): i32, v: i32): i32 {
+                f(v)
+            }
+
+            fn double(a: i32): i32 {
+                a * 2i32
+            }
+
+            fn main(): i32 {
+                let p: Point = { x: 3i32, y: 40i64, tag: 7u8 };
+                let total: f64 = area(#Square(4f64)) + area(#Circle(1f64)) + area(#Empty);
+                let mut r: i32 = 0i32;
+                if "hello" == "hello" { r = r + 1i32; }
+                if "hello" == "world" { r = r + 100i32; }
+                if total > 18.5f64 { r = r + 10i32; }
+                r + p.x + apply(double, 5i32)
+            }
+        "#;
+
+        assert_eq!(interpret("shapes", source), Ok(RuntimeValue::Int(24)));
+    }
+
+    #[test]
+    fn test_match_on_narrowed_variable() {
+        let source = r#"
+            type Shape = #Circle(i64) | #Square(i64) | #Empty;
+
+            fn get(): Shape {
+                #Square(3)
+            }
+
+            fn main(): i64 {
+                let mut shape: Shape = #Empty;
+                let empty = match shape {
+                    Empty => 1,
+                    Circle(r) => r,
+                    Square(q) => q,
+                };
+                shape = #Circle(40);
+                let circle = match shape { Circle(r) => r };
+                let square = match get() { Circle(r) => r, Square(q) => q, Empty => 0 };
+                empty + circle + square
+            }
+        "#;
+
+        assert_eq!(
+            interpret("narrowed_match", source),
+            Ok(RuntimeValue::Int(44))
+        );
+    }
+
+    #[test]
+    fn test_closures_capture_values() {
+        let source = r#"
+            fn make_adder(n: i32): fn(x: i32): i32 {
+                fn adder(x: i32): i32 {
+                    fn inner(y: i32): i32 {
+                        y + n
+                    }
+                    inner(x) + n
+                }
+                adder
+            }
+
+            fn main(): i32 {
+                let mut base: i32 = 10i32;
+                fn scaled(x: i32): i32 {
+                    x * base
+                }
+                base = 100i32;
+                let add5: fn(x: i32): i32 = make_adder(5i32);
+                add5(1i32) + scaled(2i32)
+            }
+        "#;
+
+        assert_eq!(interpret("closures", source), Ok(RuntimeValue::Int(31)));
+    }
+
+    #[test]
+    fn test_nested_functions_call_themselves() {
+        let source = r#"
+            fn main(): i32 {
+                let base: i32 = 100i32;
+                fn factorial(n: i32): i32 {
+                    if n <= 1i32 { 1i32 } else { n * factorial(n - 1i32) }
+                }
+                fn count_down(n: i32): i32 {
+                    if n == 0i32 { base } else { count_down(n - 1i32) + 1i32 }
+                }
+                factorial(5i32) + count_down(3i32)
+            }
+        "#;
+
+        assert_eq!(
+            interpret("recursive_closures", source),
+            Ok(RuntimeValue::Int(223))
+        );
+    }
+
+    #[test]
+    fn test_generic_functions_are_monomorphized() {
+        let source = r#"
+            type Option<T> = #some(T) | #none;
+            type Pair<A, B> = { first: A, second: B };
+
+            fn unwrap_or<T>(opt: Option<T>, default: T): T {
+                match opt {
+                    some(v) => v,
+                    none => default,
+                }
+            }
+
+            fn apply<T, U>(f: fn(x: T): U, value: T): U {
+                f(value)
+            }
+
+            fn swap<A, B>(p: Pair<A, B>): Pair<B, A> {
+                { first: p.second, second: p.first }
+            }
+
+            fn widen(x: i32): i64 {
+                x::as(i64) * 2
+            }
+
+            fn main(): i64 {
+                let some: Option<i32> = #some(3i32);
+                let none: Option<i64> = #none;
+                let pair: Pair<i64, i32> = { first: 40, second: 1i32 };
+                let swapped = swap(pair);
+                unwrap_or(some, 0i32)::as(i64)
+                    + unwrap_or(none, 500)
+                    + apply(widen, swapped.first)
+                    + swapped.second
+            }
+        "#;
+
+        assert_eq!(interpret("generics", source), Ok(RuntimeValue::Int(545)));
+    }
+
+    #[test]
+    fn test_builtin_static_functions() {
+        let source = r#"
+            fn main(): i64 {
+                let bytes: List<u8> = [104u8, 105u8];
+                let greeting = string::from_bytes(bytes);
+                let empty = i64[]::with_capacity(4usize);
+                if greeting == "hi" { 1 } else { 0 }
+            }
+        "#;
+
+        assert_eq!(interpret("static_fns", source), Ok(RuntimeValue::Int(1)));
+    }
+
+    #[test]
+    fn test_methods_mutate_their_receiver() {
+        let source = r#"
+            type Counter = { count: i32, step: i32 };
+            type Shape = #circle(i32) | #square(i32);
+
+            impl Counter {
+                fn new(step: i32): Counter {
+                    { count: 0i32, step: step }
+                }
+
+                fn bump(mut self): void {
+                    self.count = self.count + self.step;
+                }
+            }
+
+            impl Shape {
+                fn area(self): i32 {
+                    match self {
+                        circle(r) => 3i32 * r * r,
+                        square(s) => s * s,
+                    }
+                }
+            }
+
+            fn main(): i32 {
+                let mut counter = Counter::new(5i32);
+                counter.bump();
+                counter.bump();
+                let shape: Shape = #square(4i32);
+                counter.count + shape.area()
+            }
+        "#;
+
+        assert_eq!(interpret("methods", source), Ok(RuntimeValue::Int(26)));
+    }
+
+    #[test]
+    fn test_methods_replace_receiver_fields() {
+        let source = r#"
+            type Point = { x: i64, y: i64 };
+            type Bag = { n: i64, origin: Point };
+
+            impl Bag {
+                fn reset(mut self): void {
+                    self.n = 7;
+                    self.origin = { x: 2, y: 3 };
+                }
+            }
+
+            fn main(): i64 {
+                let mut bag: Bag = { n: 1, origin: { x: 0, y: 0 } };
+                bag.reset();
+                bag.n * 100 + bag.origin.x * 10 + bag.origin.y
+            }
+        "#;
+
+        assert_eq!(
+            interpret("replace_receiver_fields", source),
+            Ok(RuntimeValue::Int(723))
+        );
+    }
+
+    #[test]
+    fn test_for_loops() {
+        let source = r#"
+            fn main(): i64 {
+                let mut sum = 0;
+                for i in 0..10 {
+                    if i == 3 { continue }
+                    if i == 8 { break }
+                    sum = sum + i;
+                }
+                for x in [100, 200, 300] {
+                    sum = sum + x;
+                }
+                let n = 4i32;
+                for j in 1i32..n {
+                    sum = sum + j::as(i64);
+                }
+                sum
+            }
+        "#;
+
+        assert_eq!(interpret("for_loops", source), Ok(RuntimeValue::Int(631)));
+    }
+
+    #[test]
+    fn test_compound_assignment() {
+        let source = r#"
+            fn main(): i64 {
+                let mut calls = 0usize;
+                let mut xs = [1, 2, 3];
+                xs[{ calls += 1usize; calls }] += 10;
+
+                let mut point = { x: 5 };
+                point.x *= 3;
+                point.x -= 1;
+                let mut t = 100;
+                t /= 7;
+                t %= 5;
+
+                let mut sum = 0;
+                for x in xs { sum += x; }
+                if calls == 1usize { sum * 1000 + point.x * 10 + t } else { 0 }
+            }
+        "#;
+
+        assert_eq!(interpret("compound", source), Ok(RuntimeValue::Int(16144)));
+    }
+
+    #[test]
+    fn test_increment_and_decrement() {
+        let source = r#"
+            type Point = { x: i64, y: i64 };
+
+            fn main(): i64 {
+                let mut i = 0usize;
+                i++;
+                let mut small: i8 = 120i8;
+                small++;
+                small--;
+                small--;
+                let mut ratio = 1.5f64;
+                ratio++;
+
+                let mut counts: [i64; 3] = [0; 3];
+                counts[i]++;
+                counts[i]++;
+                let mut point: Point = { x: 7, y: 8 };
+                point.x--;
+
+                let above = if ratio > 2f64 { 1000 } else { 0 };
+                let counted = if small == 119i8 { counts[1usize] * 10 } else { 0 };
+                above + counted + point.x
+            }
+        "#;
+
+        assert_eq!(interpret("increment", source), Ok(RuntimeValue::Int(1026)));
+    }
+
+    #[test]
+    fn test_elements_as_match_subjects() {
+        let source = r#"
+            type Shape = #Circle(i64) | #Square(i64) | #Empty;
+            type Point = { x: i64, y: i64 };
+
+            fn origin(): Point {
+                { x: 0, y: 4 }
+            }
+
+            fn main(): i64 {
+                let shapes: [Shape; 2] = [#Square(3), #Empty];
+                let mut reads = 0usize;
+                let size = match shapes[{ reads += 1usize; 0usize }] {
+                    Circle(r) => r,
+                    Square(s) => s,
+                    Empty => 0,
+                };
+                let points: [Point; 2] = [origin(), origin()];
+                if reads == 1usize { size * 100 + points[1usize].y + origin().y } else { 0 }
+            }
+        "#;
+
+        assert_eq!(
+            interpret("element_subjects", source),
+            Ok(RuntimeValue::Int(308))
+        );
+    }
+
+    #[test]
+    fn test_bitwise_operators() {
+        let source = r#"
+            fn main(): i64 {
+                let flags = 12 & 10 | 1 ^ 3;
+                let arithmetic = (0 - 8) >> 1;
+                let wrapped = 1 << 65;
+                let b: u8 = 200u8;
+                let logical = if (b << 1u8) >> 1u8 == 72u8 { 1 } else { 0 };
+                let inverted = if ~b == 55u8 { 1 } else { 0 };
+                flags * 100 + arithmetic * 1000 + ~0 * 10 + wrapped
+                    + logical * 10000 + inverted * 100000
+            }
+        "#;
+
+        assert_eq!(interpret("bitwise", source), Ok(RuntimeValue::Int(106992)));
+    }
+
+    #[test]
+    fn test_constant_folding() {
+        let source = r#"
+            const BASE: u32 = 1u32 << 4u32;
+            const MASK = (BASE - 1u32) ^ 5u32;
+            const LIMIT = 10 * 1000 + 7 % 4;
+            const ENABLED = LIMIT > 10000 && MASK != 0u32;
+            const SAME = "hi" == "hi";
+
+            fn main(): i64 {
+                let folded = if ENABLED && SAME { LIMIT } else { 0 };
+                let mask = if MASK == 10u32 { 1 } else { 0 };
+                folded + mask * 100000 + (2 + 3) * 4
+            }
+        "#;
+
+        assert_eq!(
+            interpret("const_folding", source),
+            Ok(RuntimeValue::Int(110023))
+        );
+    }
+
+    #[test]
+    fn test_constants_refer_to_later_constants() {
+        let source = r#"
+            const SIZE = HALF * 2;
+
+            fn sum(values: [i64; SIZE]): i64 {
+                values[0usize] + values[3usize] + TOTAL
+            }
+
+            const TOTAL = SIZE + HALF;
+            const HALF = 2;
+
+            fn main(): i64 {
+                sum([10, 0, 0, 20])
+            }
+        "#;
+
+        assert_eq!(
+            interpret("later_constants", source),
+            Ok(RuntimeValue::Int(36))
+        );
+    }
+
+    #[test]
+    fn test_pointer_sized_integers() {
+        let source = r#"
+            fn nth(items: [i64; 3], index: usize): i64 {
+                items[index]
+            }
+
+            fn main(): i64 {
+                let last: usize = 2usize;
+                let offset: isize = -1isize;
+                if offset < 0isize { nth([10, 20, 30], last) - 1 } else { 0 }
+            }
+        "#;
+
+        assert_eq!(
+            interpret("pointer_sized", source),
+            Ok(RuntimeValue::Int(29))
+        );
+    }
+
+    #[test]
+    fn test_fixed_size_arrays() {
+        let source = r#"
+            const SIZE = 4;
+
+            fn sum(values: [i64; SIZE]): i64 {
+                let mut total = 0;
+                for value in values {
+                    total += value;
+                }
+                total
+            }
+
+            fn main(): i64 {
+                let mut a: [i64; SIZE] = [1; SIZE];
+                a[0usize] = 10;
+                a[3usize] += 5;
+                let copy = a;
+                a[1usize] = 100;
+                let mut list = i64[]::from_array(a);
+                list[1usize] = 0;
+                sum(a) * 1000 + sum(copy) + a[1usize] * 1000000
+            }
+        "#;
+
+        assert_eq!(
+            interpret("arrays", source),
+            Ok(RuntimeValue::Int(100117018))
+        );
+    }
+
+    #[test]
+    fn test_arrays_from_list_literals() {
+        let source = r#"
+            fn sum(values: [i64; 3]): i64 {
+                values[0usize] + values[1usize] + values[2usize]
+            }
+
+            fn main(): i64 {
+                let a: [i64; 3] = [1, 2, 3];
+                let grid: [[u8; 2]; 2] = [[1u8, 2u8], [3u8, 4u8]];
+                let corner = if grid[1usize][0usize] == 3u8 { 100000 } else { 0 };
+                sum(a) * 1000 + sum([10, 20, 30]) + corner
+            }
+        "#;
+
+        assert_eq!(
+            interpret("array_list_literals", source),
+            Ok(RuntimeValue::Int(106060))
+        );
+    }
+
+    #[test]
+    fn test_array_slices() {
+        let source = r#"
+            type Holder = { buffer: [i64; 4] };
+
+            fn total(items: [i64]): i64 {
+                let mut sum = 0;
+                for x in items {
+                    sum += x;
+                }
+                sum
+            }
+
+            fn make(): [i64; 3] {
+                let items: [i64; 3] = [7, 8, 9];
+                items
+            }
+
+            fn main(): i64 {
+                let mut buffer: [i64; 5] = [1, 2, 3, 4, 5];
+                let mut view = buffer[1usize..4usize];
+                view[0usize] = 20;
+                let mut holder: Holder = { buffer: [0; 4] };
+                let mut inner = holder.buffer[2usize..4usize];
+                inner[1usize] = 6;
+                let copied = total(make()[1usize..3usize]);
+                total(view) + buffer[1usize] + holder.buffer[3usize] + copied
+            }
+        "#;
+
+        assert_eq!(interpret("array_slices", source), Ok(RuntimeValue::Int(70)));
+    }
+
+    #[test]
+    fn test_slices() {
+        let source = r#"
+            fn sum(values: [i64]): i64 {
+                let mut total = 0;
+                for value in values {
+                    total += value;
+                }
+                total
+            }
+
+            fn fill(mut buffer: [u8], value: u8): void {
+                let mut i = 0usize;
+                while i < buffer.len {
+                    buffer[i] = value;
+                    i += 1usize;
+                }
+            }
+
+            fn count_spaces(text: [u8]): i64 {
+                let mut count = 0;
+                for byte in text {
+                    if byte == 32u8 {
+                        count += 1;
+                    };
+                }
+                count
+            }
+
+            fn main(): i64 {
+                let items = [1, 2, 3, 4, 5, 6];
+                let middle = items[1usize..5usize];
+                let inner = middle[1usize..3usize];
+                let first_item = inner[0usize];
+                let first = match first_item {
+                    some(v) => v,
+                    none => 0,
+                };
+                let missing_item = inner[2usize];
+                let missing = match missing_item {
+                    some(v) => v,
+                    none => 100,
+                };
+
+                let bytes = [0u8, 0u8, 0u8];
+                fill(bytes[1usize..3usize], 32u8);
+                let text = "a b c d";
+
+                sum(middle) * 100000
+                    + first * 10000
+                    + count_spaces(text[2usize..7usize]) * 1000
+                    + missing
+                    + count_spaces(bytes[0usize..3usize])
+                    - sum(items[3usize..3usize])
+            }
+        "#;
+
+        assert_eq!(interpret("slices", source), Ok(RuntimeValue::Int(1432102)));
+    }
+
+    #[test]
+    fn test_union_reassignment_and_tag_equality() {
+        let source = r#"
+            type Id = #U32(u32) | #UUID(string);
+
+            fn get(id: Id): u32 {
+                match id {
+                    U32(v) => v,
+                    UUID(s) => 99u32,
+                }
+            }
+
+            fn main(): u32 {
+                let mut id: Id = #U32(17u32);
+                let a: u32 = get(id);
+                id = #UUID("x");
+                let b: u32 = get(id);
+                let mut c: u32 = 0u32;
+                if id == #UUID("x") { c = 1u32; }
+                if id == #UUID("y") { c = c + 10u32; }
+                a + b + c
+            }
+        "#;
+
+        assert_eq!(interpret("union", source), Ok(RuntimeValue::Int(117)));
+    }
+
+    #[test]
+    fn test_returns_narrowed_union_payload() {
+        let source = r#"
+            fn main(): string {
+                let mut id: #U32(u32) | #UUID(string) = #U32(17u32);
+                id = #UUID("world");
+                id.value
+            }
+        "#;
+
+        assert_eq!(
+            interpret("payload", source),
+            Ok(RuntimeValue::String("world".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_returns_struct() {
+        let source = r#"
+            fn main(): { a: i32, ok: bool } {
+                let mut p: { a: i32, ok: bool } = { a: 1i32, ok: true };
+                p.a = p.a + 41i32;
+                p
+            }
+        "#;
+
+        assert_eq!(
+            interpret("struct", source),
+            Ok(RuntimeValue::Struct(vec![
+                ("a".to_string(), RuntimeValue::Int(42)),
+                ("ok".to_string(), RuntimeValue::Bool(true)),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_struct_fields_passed_as_narrowed_arguments() {
+        let source = r#"
+            type Shape = #Circle(i64) | #Square(i64);
+
+            fn area(shape: Shape): i64 {
+                match shape {
+                    Circle(r) => 3 * r * r,
+                    Square(s) => s * s,
+                }
+            }
+
+            fn main(): i64 {
+                let point: { x: i64, y: i64 } = { x: 2, y: 5 };
+                area(#Circle(point.x)) + area(#Square(point.y))
+            }
+        "#;
+
+        assert_eq!(
+            interpret("narrowed_args", source),
+            Ok(RuntimeValue::Int(37))
+        );
+    }
+
+    #[test]
+    fn test_integer_arithmetic_wraps() {
+        let source = r#"
+            fn main(): u8 {
+                let x: u8 = 250u8;
+                x + 10u8
+            }
+        "#;
+
+        assert_eq!(interpret("wrap", source), Ok(RuntimeValue::Int(4)));
+    }
+
+    #[test]
+    fn test_division_by_zero_is_reported() {
+        let source = r#"
+            fn main(): i32 {
+                let zero: i32 = 0i32;
+                10i32 / zero
+            }
+        "#;
+
+        assert_eq!(
+            interpret("div_zero", source),
+            Err(RuntimeError::DivisionByZero)
+        );
+    }
+
+    #[test]
+    fn test_huge_allocation_is_reported() {
+        let source = r#"
+            fn main(): i64 {
+                let items = i64[]::with_capacity(1152921504606846976usize);
+                0
+            }
+        "#;
+
+        assert_eq!(
+            interpret("huge_allocation", source),
+            Err(RuntimeError::OutOfMemory(1 << 63))
+        );
+    }
+}
@@ -0,0 +1,290 @@
+use std::fmt;
+
+use crate::{
+    ast::DeclarationId,
+    hir::{
+        types::{
+            checked_declaration::CheckedDeclaration,
+            checked_type::{StructKind, Type},
+        },
+        utils::{
+            layout::{get_field_offset, get_layout_of},
+            numeric::is_signed,
+        },
+        ProgramBuilder,
+    },
+    interpret::{
+        memory::{Memory, FUNCTION_POINTER_TAG},
+        RuntimeError,
+    },
+};
+
+/// A value produced by the interpreter, detached from interpreter memory so it can
+/// be inspected after the program finished
+#[derive(Debug, Clone, PartialEq)]
+pub enum RuntimeValue {
+    Void,
+    Bool(bool),
+    Int(i128),
+    Float(f64),
+    String(String),
+    Tag {
+        name: String,
+        value: Option<Box<RuntimeValue>>,
+    },
+    Struct(Vec<(String, RuntimeValue)>),
+    List(Vec<RuntimeValue>),
+    Function(String),
+    /// A pointer to something other than a struct, shown as its raw address
+    Pointer(u64),
+}
+
+impl fmt::Display for RuntimeValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuntimeValue::Void => write!(f, "void"),
+            RuntimeValue::Bool(b) => write!(f, "{}", b),
+            RuntimeValue::Int(n) => write!(f, "{}", n),
+            RuntimeValue::Float(n) => write!(f, "{:?}", n),
+            RuntimeValue::String(s) => write!(f, "{:?}", s),
+            RuntimeValue::Tag { name, value } => match value {
+                Some(value) => write!(f, "#{}({})", name, value),
+                None => write!(f, "#{}", name),
+            },
+            RuntimeValue::Struct(fields) => {
+                write!(f, "{{ ")?;
+                for (index, (name, value)) in fields.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}: {}", name, value)?;
+                }
+                write!(f, " }}")
+            }
+            RuntimeValue::List(items) => {
+                write!(f, "[")?;
+                for (index, item) in items.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "]")
+            }
+            RuntimeValue::Function(name) => write!(f, "fn {}", name),
+            RuntimeValue::Pointer(address) => write!(f, "ptr({:#x})", address),
+        }
+    }
+}
+
+/// Reads an integer of the given type from little-endian bytes, sign-extending
+/// signed types. Booleans, pointers and function pointers read as unsigned.
+pub fn read_int(bytes: &[u8], ty: &Type) -> i128 {
+    let mut buffer = [0u8; 16];
+    buffer[..bytes.len()].copy_from_slice(bytes);
+    let value = u128::from_le_bytes(buffer);
+
+    let bits = bytes.len() * 8;
+    if is_signed(ty) && bits > 0 && bits < 128 {
+        let shift = 128 - bits;
+        ((value << shift) as i128) >> shift
+    } else {
+        value as i128
+    }
+}
+
+/// Truncates the integer to the size of `ty`, the same wrapping behavior as the
+/// native backends
+pub fn write_int(value: i128, ty: &Type, program: &ProgramBuilder) -> Vec<u8> {
+    let size = get_layout_of(ty, program).size;
+    value.to_le_bytes()[..size].to_vec()
+}
+
+pub fn read_float(bytes: &[u8], ty: &Type) -> f64 {
+    match ty {
+        Type::F32 => f32::from_le_bytes(bytes.try_into().unwrap()) as f64,
+        _ => f64::from_le_bytes(bytes.try_into().unwrap()),
+    }
+}
+
+pub fn write_float(value: f64, ty: &Type) -> Vec<u8> {
+    match ty {
+        Type::F32 => (value as f32).to_le_bytes().to_vec(),
+        _ => value.to_le_bytes().to_vec(),
+    }
+}
+
+pub fn read_pointer(bytes: &[u8]) -> u64 {
+    read_int(bytes, &Type::U64) as u64
+}
+
+/// Reads the contents of a string through a pointer to its header
+pub fn read_string(
+    ptr: u64,
+    memory: &Memory,
+    program: &ProgramBuilder,
+) -> Result<Vec<u8>, RuntimeError> {
+    let header_size = get_layout_of(&Type::Struct(StructKind::String), program).size;
+    let header = memory.read(ptr, header_size)?;
+
+    let field = |name| {
+        let (index, ty) = StructKind::String
+            .get_field(program, name)
+            .expect("INTERNAL COMPILER ERROR: String header is missing a field");
+        let offset = get_field_offset(&StructKind::String, index, program);
+        let size = get_layout_of(&ty, program).size;
+        read_int(&header[offset..offset + size], &ty)
+    };
+
+    let len = field(program.common_identifiers.len) as usize;
+    let data = field(program.common_identifiers.ptr) as u64;
+
+    Ok(memory.read(data, len)?.to_vec())
+}
+
+/// Converts the in-memory representation of a value into a RuntimeValue, following
+/// pointers to strings, lists and user-defined structs
+pub fn decode_value(
+    bytes: &[u8],
+    ty: &Type,
+    memory: &Memory,
+    program: &ProgramBuilder,
+) -> Result<RuntimeValue, RuntimeError> {
+    let value = match ty {
+        Type::Void | Type::Unknown => RuntimeValue::Void,
+        Type::Bool => RuntimeValue::Bool(bytes[0] != 0),
+        Type::F32 | Type::F64 => RuntimeValue::Float(read_float(bytes, ty)),
+        Type::U8
+        | Type::U16
+        | Type::U32
+        | Type::U64
+        | Type::USize
+        | Type::I8
+        | Type::I16
+        | Type::I32
+        | Type::I64
+        | Type::ISize => RuntimeValue::Int(read_int(bytes, ty)),
+        Type::Fn(_) => {
+            let ptr = read_pointer(bytes);
+            let id = DeclarationId((ptr & !FUNCTION_POINTER_TAG) as usize);
+            match program.declarations.get(&id) {
+                Some(CheckedDeclaration::Function(f))
+                    if ptr & FUNCTION_POINTER_TAG != 0 =>
+                {
+                    RuntimeValue::Function(
+                        program.string_interner.resolve(f.identifier.name),
+                    )
+                }
+                _ => RuntimeValue::Pointer(ptr),
+            }
+        }
+        Type::Pointer { constraint, .. } => {
+            let ptr = read_pointer(bytes);
+            match &**constraint {
+                Type::Struct(StructKind::String) => {
+                    let contents = read_string(ptr, memory, program)?;
+                    RuntimeValue::String(String::from_utf8_lossy(&contents).into_owned())
+                }
+                pointee @ Type::Struct(
+                    StructKind::UserDefined(_) | StructKind::List(_),
+                ) => {
+                    let size = get_layout_of(pointee, program).size;
+                    let bytes = memory.read(ptr, size)?;
+                    decode_value(bytes, pointee, memory, program)?
+                }
+                _ => RuntimeValue::Pointer(ptr),
+            }
+        }
+        Type::Buffer { .. } => RuntimeValue::Void,
+        Type::Array { item_type, size } => {
+            let item_size = get_layout_of(item_type, program).size;
+            let mut items = Vec::with_capacity(*size);
+            for index in 0..*size {
+                let item = &bytes[index * item_size..(index + 1) * item_size];
+                items.push(decode_value(item, item_type, memory, program)?);
+            }
+
+            RuntimeValue::List(items)
+        }
+        Type::Struct(kind) => decode_struct(bytes, kind, memory, program)?,
+    };
+
+    Ok(value)
+}
+
+fn decode_struct(
+    bytes: &[u8],
+    kind: &StructKind,
+    memory: &Memory,
+    program: &ProgramBuilder,
+) -> Result<RuntimeValue, RuntimeError> {
+    let field_bytes = |index: usize, ty: &Type| {
+        let offset = get_field_offset(kind, index, program);
+        &bytes[offset..offset + get_layout_of(ty, program).size]
+    };
+
+    let value = match kind {
+        StructKind::Tag(_) | StructKind::Union { .. } => {
+            let id = read_int(field_bytes(0, &Type::U16), &Type::U16) as u16;
+            let variants = match kind {
+                StructKind::Tag(tag) => vec![tag.clone()],
+                StructKind::Union { variants } => variants.clone(),
+                _ => unreachable!(),
+            };
+            let variant = variants
+                .iter()
+                .find(|v| v.id.0 == id)
+                .ok_or(RuntimeError::InvalidTagId(id))?;
+
+            let name = program
+                .string_interner
+                .resolve(program.tag_interner.resolve(variant.id));
+            let value = match &variant.value_type {
+                Some(value_type) => {
+                    let payload = field_bytes(1, value_type);
+                    Some(Box::new(decode_value(
+                        payload, value_type, memory, program,
+                    )?))
+                }
+                None => None,
+            };
+
+            RuntimeValue::Tag { name, value }
+        }
+        StructKind::List(item_type) | StructKind::Slice(item_type) => {
+            // Lists and slices both have `len` and `ptr`, at different indices
+            let field = |name| {
+                let (index, ty) = kind
+                    .get_field(program, name)
+                    .expect("INTERNAL COMPILER ERROR: List is missing a field");
+                field_bytes(index, &ty)
+            };
+            let len =
+                read_int(field(program.common_identifiers.len), &Type::USize) as usize;
+            let ptr = read_pointer(field(program.common_identifiers.ptr));
+            let item_size = get_layout_of(item_type, program).size;
+            let data = memory.read(ptr, item_size * len)?;
+
+            let mut items = Vec::with_capacity(len);
+            for index in 0..len {
+                let item = &data[index * item_size..(index + 1) * item_size];
+                items.push(decode_value(item, item_type, memory, program)?);
+            }
+
+            RuntimeValue::List(items)
+        }
+        StructKind::UserDefined(_) | StructKind::String => {
+            let mut fields = Vec::new();
+            for (index, (name, ty)) in kind.fields(program).into_iter().enumerate() {
+                fields.push((
+                    program.string_interner.resolve(name),
+                    decode_value(field_bytes(index, &ty), &ty, memory, program)?,
+                ));
+            }
+
+            RuntimeValue::Struct(fields)
+        }
+    };
+
+    Ok(value)
+}
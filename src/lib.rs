@@ -3,8 +3,13 @@
 #![allow(clippy::redundant_pattern_matching)]
 
 pub mod ast;
+pub mod cli;
 pub mod codegen;
 pub mod compile;
+pub mod cst;
+pub mod format;
 pub mod hir;
+pub mod interpret;
+pub mod lsp;
 pub mod parse;
 pub mod tokenize;
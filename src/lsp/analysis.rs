@@ -0,0 +1,324 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::{atomic::AtomicUsize, Arc},
+};
+
+use crate::{
+    ast::{
+        expr::{Expr, ExprKind},
+        stmt::StmtKind,
+        Span,
+    },
+    compile::{
+        diagnostics::Diagnostic, interner::SharedStringInterner, resolve_path, Analysis,
+        Compiler,
+    },
+    hir::{
+        types::checked_declaration::CheckedDeclaration,
+        utils::type_to_string::type_to_string,
+    },
+    parse::Parser,
+    tokenize::Tokenizer,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolKind {
+    Function,
+    TypeAlias,
+}
+
+/// A top-level declaration of a document
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DocumentSymbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    /// The whole declaration
+    pub span: Span,
+    /// The declared identifier
+    pub selection_span: Span,
+}
+
+/// The checked program of a single open document, used to answer editor queries
+/// until the document changes
+pub struct DocumentAnalysis {
+    pub path: PathBuf,
+    compiler: Compiler,
+    analysis: Analysis,
+}
+
+impl DocumentAnalysis {
+    /// Checks the program with `path` as its entry module, using `text` instead of
+    /// the content on disk
+    pub fn new(path: &Path, text: &str) -> Self {
+        let path = resolve_path(path);
+
+        let mut compiler = Compiler::default();
+        compiler.set_source(path.clone(), text.to_string());
+        let analysis = compiler.analyze(&path);
+
+        Self {
+            path,
+            compiler,
+            analysis,
+        }
+    }
+
+    /// Diagnostics which belong to this document
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        self.compiler
+            .diagnostics(&self.analysis.errors)
+            .into_iter()
+            .filter(|d| d.path.as_ref() == Some(&self.path))
+            .collect()
+    }
+
+    /// Describes the declaration referenced by the identifier at `offset`
+    pub fn hover(&self, offset: usize) -> Option<(Span, String)> {
+        let (span, decl_id) = self.reference_at(offset)?;
+        let program = self.analysis.program.as_ref()?;
+        let interners = self.compiler.interners();
+        let name = |decl: &CheckedDeclaration| {
+            interners.string_interner.resolve(decl.identifier().name)
+        };
+
+        let decl = program.declarations.get(&decl_id)?;
+        let description = match decl {
+            CheckedDeclaration::Var(var) => {
+                format!(
+                    "{}: {}",
+                    name(decl),
+                    type_to_string(&var.constraint, interners)
+                )
+            }
+            CheckedDeclaration::Const(constant) => {
+                format!(
+                    "const {}: {}",
+                    name(decl),
+                    type_to_string(&constant.value.get_type(), interners)
+                )
+            }
+            CheckedDeclaration::UninitializedVar { .. } => name(decl),
+            CheckedDeclaration::Function(f) => {
+                let params: Vec<String> = f
+                    .params
+                    .iter()
+                    .map(|p| {
+                        format!(
+                            "{}: {}",
+                            interners.string_interner.resolve(p.identifier.name),
+                            type_to_string(&p.ty, interners)
+                        )
+                    })
+                    .collect();
+                format!(
+                    "fn {}({}): {}",
+                    name(decl),
+                    params.join(", "),
+                    type_to_string(&f.return_type, interners)
+                )
+            }
+            CheckedDeclaration::Module(module) => {
+                format!("{}: module \"{}\"", name(decl), module.path.display())
+            }
+            CheckedDeclaration::TypeAlias(alias) => {
+                format!(
+                    "type {} = {}",
+                    name(decl),
+                    type_to_string(&alias.value, interners)
+                )
+            }
+        };
+
+        Some((span, description))
+    }
+
+    /// The module and span of the declaring identifier referenced at `offset`
+    pub fn definition(&self, offset: usize) -> Option<(PathBuf, Span)> {
+        let (_, decl_id) = self.reference_at(offset)?;
+        let program = self.analysis.program.as_ref()?;
+
+        program.modules.iter().find_map(|(path, module)| {
+            module
+                .definitions
+                .get(&decl_id)
+                .map(|span| (path.clone(), *span))
+        })
+    }
+
+    fn reference_at(&self, offset: usize) -> Option<(Span, crate::ast::DeclarationId)> {
+        let program = self.analysis.program.as_ref()?;
+        let module = program.modules.get(&self.path)?;
+
+        module
+            .references
+            .iter()
+            .find(|r| {
+                r.span.start.byte_offset <= offset && offset <= r.span.end.byte_offset
+            })
+            .map(|r| (r.span, r.decl_id))
+    }
+}
+
+/// Lists the top-level functions and type aliases of a document. Only the document
+/// itself is parsed, so this works even when the program does not type-check.
+pub fn document_symbols(text: &str) -> Vec<DocumentSymbol> {
+    let interner = Arc::new(SharedStringInterner::default());
+    let (tokens, _) = Tokenizer::tokenize(text, interner.clone());
+    let (statements, _) =
+        Parser::parse(tokens, interner.clone(), Arc::new(AtomicUsize::new(0)));
+
+    statements
+        .iter()
+        .filter_map(|stmt| {
+            let (identifier, kind) = match &stmt.kind {
+                StmtKind::Expression(Expr {
+                    kind: ExprKind::Fn(decl),
+                    ..
+                }) => (decl.identifier, SymbolKind::Function),
+                StmtKind::TypeAliasDecl(decl) => (decl.identifier, SymbolKind::TypeAlias),
+                _ => return None,
+            };
+
+            Some(DocumentSymbol {
+                name: interner.resolve(identifier.name),
+                kind,
+                span: stmt.span,
+                selection_span: identifier.span,
+            })
+        })
+        .collect()
+}
+
+/// Converts a byte offset into a zero-based line and UTF-16 column, as used by LSP
+pub fn offset_to_position(text: &str, offset: usize) -> (usize, usize) {
+    let offset = offset.min(text.len());
+    let before = &text[..text.floor_char_boundary(offset)];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+
+    (
+        before.matches('\n').count(),
+        before[line_start..].encode_utf16().count(),
+    )
+}
+
+/// Converts a zero-based line and UTF-16 column into a byte offset, clamped to the
+/// end of the line
+pub fn position_to_offset(text: &str, line: usize, character: usize) -> usize {
+    let mut line_start = 0;
+    for _ in 0..line {
+        match text[line_start..].find('\n') {
+            Some(i) => line_start += i + 1,
+            None => return text.len(),
+        }
+    }
+
+    let mut units = 0;
+    for (i, c) in text[line_start..].char_indices() {
+        if c == '\n' || units >= character {
+            return line_start + i;
+        }
+        units += c.len_utf16();
+    }
+
+    text.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::{
+        compile::write_test_program,
+        lsp::analysis::{
+            document_symbols, offset_to_position, position_to_offset, DocumentAnalysis,
+            SymbolKind,
+        },
+    };
+    use pretty_assertions::assert_eq;
+
+    const SOURCE: &str = "type Id = i32;
+
+fn twice(value: Id): i32 {
+    value * 2i32
+}
+
+fn main(): i32 {
+    let result = twice(21i32);
+    result
+}
+";
+
+    #[test]
+    fn test_hover_and_definition() {
+        let main_path = write_test_program("lsp_hover", SOURCE);
+        let analysis = DocumentAnalysis::new(&main_path, SOURCE);
+        let _ = fs::remove_dir_all(main_path.parent().unwrap());
+
+        assert_eq!(analysis.diagnostics(), vec![]);
+
+        let call = SOURCE.find("twice(21i32)").unwrap();
+        let (_, hover) = analysis.hover(call + 1).unwrap();
+        assert_eq!(hover, "fn twice(value: i32): i32");
+
+        let (path, span) = analysis.definition(call).unwrap();
+        assert_eq!(path, analysis.path);
+        assert_eq!(span.start.byte_offset, SOURCE.find("twice").unwrap());
+
+        let use_of_result = SOURCE.rfind("result").unwrap();
+        assert_eq!(
+            analysis.hover(use_of_result).map(|(_, text)| text),
+            Some("result: i32".to_string())
+        );
+    }
+
+    #[test]
+    fn test_diagnostics_of_unsaved_text() {
+        let main_path = write_test_program("lsp_diagnostics", SOURCE);
+        let analysis = DocumentAnalysis::new(&main_path, "fn main(): i32 {\n    x\n}\n");
+        let _ = fs::remove_dir_all(main_path.parent().unwrap());
+
+        let diagnostics = analysis.diagnostics();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code.as_deref(), Some("S5"));
+    }
+
+    #[test]
+    fn test_diagnostics_of_new_document() {
+        let main_path = write_test_program("lsp_new_document", SOURCE)
+            .with_file_name("never_saved.wl");
+        let analysis = DocumentAnalysis::new(&main_path, "fn main(): i32 {\n    x\n}\n");
+        let _ = fs::remove_dir_all(main_path.parent().unwrap());
+
+        let diagnostics = analysis.diagnostics();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code.as_deref(), Some("S5"));
+    }
+
+    #[test]
+    fn test_document_symbols() {
+        let symbols: Vec<(String, SymbolKind)> = document_symbols(SOURCE)
+            .into_iter()
+            .map(|s| (s.name, s.kind))
+            .collect();
+
+        assert_eq!(
+            symbols,
+            vec![
+                ("Id".to_string(), SymbolKind::TypeAlias),
+                ("twice".to_string(), SymbolKind::Function),
+                ("main".to_string(), SymbolKind::Function),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_position_conversion() {
+        let text = "ab\n😀c\n";
+
+        assert_eq!(offset_to_position(text, 0), (0, 0));
+        assert_eq!(offset_to_position(text, 7), (1, 2));
+        assert_eq!(position_to_offset(text, 1, 2), 7);
+        assert_eq!(position_to_offset(text, 0, 10), 2);
+        assert_eq!(position_to_offset(text, 5, 0), text.len());
+    }
+}
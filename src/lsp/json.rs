@@ -0,0 +1,334 @@
+use std::fmt;
+
+use crate::compile::diagnostics::json_string;
+
+/// Minimal JSON document model for the messages of the language server protocol.
+/// Object members keep their insertion order.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonParseError {
+    pub offset: usize,
+    pub message: &'static str,
+}
+
+impl Json {
+    pub fn object<const N: usize>(members: [(&str, Json); N]) -> Json {
+        Json::Object(
+            members
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+        )
+    }
+
+    pub fn string(value: impl Into<String>) -> Json {
+        Json::String(value.into())
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(members) => {
+                members.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_usize(&self) -> Option<usize> {
+        match self {
+            Json::Number(n) if *n >= 0.0 && n.fract() == 0.0 => Some(*n as usize),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Json]> {
+        match self {
+            Json::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    pub fn parse(source: &str) -> Result<Json, JsonParseError> {
+        let mut parser = JsonParser {
+            bytes: source.as_bytes(),
+            source,
+            position: 0,
+        };
+
+        let value = parser.parse_value()?;
+        parser.skip_whitespace();
+        if parser.position != parser.bytes.len() {
+            return Err(parser.error("Unexpected trailing characters"));
+        }
+
+        Ok(value)
+    }
+}
+
+impl From<bool> for Json {
+    fn from(value: bool) -> Self {
+        Json::Bool(value)
+    }
+}
+
+impl From<usize> for Json {
+    fn from(value: usize) -> Self {
+        Json::Number(value as f64)
+    }
+}
+
+impl From<&str> for Json {
+    fn from(value: &str) -> Self {
+        Json::String(value.to_string())
+    }
+}
+
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(b) => write!(f, "{}", b),
+            Json::Number(n) if n.fract() == 0.0 && n.abs() < 1e15 => {
+                write!(f, "{}", *n as i64)
+            }
+            Json::Number(n) => write!(f, "{}", n),
+            Json::String(s) => write!(f, "{}", json_string(s)),
+            Json::Array(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "]")
+            }
+            Json::Object(members) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in members.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}:{}", json_string(key), value)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+struct JsonParser<'a> {
+    source: &'a str,
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> JsonParser<'a> {
+    fn error(&self, message: &'static str) -> JsonParseError {
+        JsonParseError {
+            offset: self.position,
+            message,
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self
+            .bytes
+            .get(self.position)
+            .is_some_and(|b| b.is_ascii_whitespace())
+        {
+            self.position += 1;
+        }
+    }
+
+    fn expect_literal(
+        &mut self,
+        literal: &str,
+        value: Json,
+    ) -> Result<Json, JsonParseError> {
+        if self.source[self.position..].starts_with(literal) {
+            self.position += literal.len();
+            Ok(value)
+        } else {
+            Err(self.error("Invalid literal"))
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<Json, JsonParseError> {
+        self.skip_whitespace();
+
+        match self.bytes.get(self.position) {
+            None => Err(self.error("Unexpected end of input")),
+            Some(b'n') => self.expect_literal("null", Json::Null),
+            Some(b't') => self.expect_literal("true", Json::Bool(true)),
+            Some(b'f') => self.expect_literal("false", Json::Bool(false)),
+            Some(b'"') => Ok(Json::String(self.parse_string()?)),
+            Some(b'[') => {
+                self.position += 1;
+                let mut items = Vec::new();
+                self.skip_whitespace();
+                if self.bytes.get(self.position) == Some(&b']') {
+                    self.position += 1;
+                    return Ok(Json::Array(items));
+                }
+                loop {
+                    items.push(self.parse_value()?);
+                    self.skip_whitespace();
+                    match self.bytes.get(self.position) {
+                        Some(b',') => self.position += 1,
+                        Some(b']') => {
+                            self.position += 1;
+                            return Ok(Json::Array(items));
+                        }
+                        _ => return Err(self.error("Expected \",\" or \"]\"")),
+                    }
+                }
+            }
+            Some(b'{') => {
+                self.position += 1;
+                let mut members = Vec::new();
+                self.skip_whitespace();
+                if self.bytes.get(self.position) == Some(&b'}') {
+                    self.position += 1;
+                    return Ok(Json::Object(members));
+                }
+                loop {
+                    self.skip_whitespace();
+                    if self.bytes.get(self.position) != Some(&b'"') {
+                        return Err(self.error("Expected an object key"));
+                    }
+                    let key = self.parse_string()?;
+                    self.skip_whitespace();
+                    if self.bytes.get(self.position) != Some(&b':') {
+                        return Err(self.error("Expected \":\""));
+                    }
+                    self.position += 1;
+                    members.push((key, self.parse_value()?));
+                    self.skip_whitespace();
+                    match self.bytes.get(self.position) {
+                        Some(b',') => self.position += 1,
+                        Some(b'}') => {
+                            self.position += 1;
+                            return Ok(Json::Object(members));
+                        }
+                        _ => return Err(self.error("Expected \",\" or \"}\"")),
+                    }
+                }
+            }
+            Some(b'-' | b'0'..=b'9') => {
+                let start = self.position;
+                while self
+                    .bytes
+                    .get(self.position)
+                    .is_some_and(|b| b"+-.eE0123456789".contains(b))
+                {
+                    self.position += 1;
+                }
+                self.source[start..self.position]
+                    .parse()
+                    .map(Json::Number)
+                    .map_err(|_| self.error("Invalid number"))
+            }
+            Some(_) => Err(self.error("Unexpected character")),
+        }
+    }
+
+    fn parse_string(&mut self) -> Result<String, JsonParseError> {
+        // Skip the opening quote
+        self.position += 1;
+        let mut out = String::new();
+
+        loop {
+            let Some(c) = self.source[self.position..].chars().next() else {
+                return Err(self.error("Unterminated string"));
+            };
+            self.position += c.len_utf8();
+
+            match c {
+                '"' => return Ok(out),
+                '\\' => {
+                    let escaped = self.bytes.get(self.position).copied();
+                    self.position += 1;
+                    match escaped {
+                        Some(b'"') => out.push('"'),
+                        Some(b'\\') => out.push('\\'),
+                        Some(b'/') => out.push('/'),
+                        Some(b'b') => out.push('\u{8}'),
+                        Some(b'f') => out.push('\u{c}'),
+                        Some(b'n') => out.push('\n'),
+                        Some(b'r') => out.push('\r'),
+                        Some(b't') => out.push('\t'),
+                        Some(b'u') => {
+                            let mut code = self.parse_hex_escape()?;
+                            // Characters outside the BMP are written as surrogate pairs
+                            if (0xD800..0xDC00).contains(&code)
+                                && self.source[self.position..].starts_with("\\u")
+                            {
+                                self.position += 2;
+                                let low = self.parse_hex_escape()?;
+                                code = 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00);
+                            }
+                            out.push(char::from_u32(code).unwrap_or('\u{FFFD}'));
+                        }
+                        _ => return Err(self.error("Invalid escape sequence")),
+                    }
+                }
+                c => out.push(c),
+            }
+        }
+    }
+
+    fn parse_hex_escape(&mut self) -> Result<u32, JsonParseError> {
+        let hex = self
+            .source
+            .get(self.position..self.position + 4)
+            .ok_or_else(|| self.error("Invalid unicode escape"))?;
+        let code = u32::from_str_radix(hex, 16)
+            .map_err(|_| self.error("Invalid unicode escape"))?;
+        self.position += 4;
+        Ok(code)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lsp::json::Json;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_parse_and_print() {
+        let source = r#" {"id": 3, "params": {"text": "a\n\"b\" 😀", "list": [true, null, -1.5]}} "#;
+        let json = Json::parse(source).unwrap();
+
+        assert_eq!(json.get("id").and_then(Json::as_usize), Some(3));
+        assert_eq!(
+            json.get("params")
+                .and_then(|p| p.get("text"))
+                .and_then(Json::as_str),
+            Some("a\n\"b\" 😀")
+        );
+        assert_eq!(
+            json.to_string(),
+            r#"{"id":3,"params":{"text":"a\n\"b\" 😀","list":[true,null,-1.5]}}"#
+        );
+
+        assert!(Json::parse("{\"a\": }").is_err());
+        assert!(Json::parse("[1, 2").is_err());
+    }
+}
@@ -0,0 +1,557 @@
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    ast::Span,
+    compile::diagnostics::{Diagnostic, Severity},
+    lsp::{
+        analysis::{
+            document_symbols, offset_to_position, position_to_offset, DocumentAnalysis,
+            SymbolKind,
+        },
+        json::Json,
+    },
+};
+
+pub mod analysis;
+pub mod json;
+
+/// JSON-RPC error code for requests the server does not implement
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+/// Runs the language server on stdin/stdout until the client sends `exit`
+pub fn run_stdio() -> io::Result<()> {
+    let stdin = io::stdin();
+    let mut reader = stdin.lock();
+    let mut server = LanguageServer::default();
+
+    while let Some(message) = read_message(&mut reader)? {
+        let message = match Json::parse(&message) {
+            Ok(message) => message,
+            Err(e) => {
+                eprintln!("willow-lsp: ignoring malformed message: {:?}", e);
+                continue;
+            }
+        };
+
+        for response in server.handle(&message) {
+            write_message(&mut io::stdout().lock(), &response.to_string())?;
+        }
+
+        if server.should_exit {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads one `Content-Length` framed message, None at the end of input
+fn read_message(reader: &mut impl BufRead) -> io::Result<Option<String>> {
+    let mut content_length = None;
+
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some(length) = header.strip_prefix("Content-Length:") {
+            content_length = length.trim().parse().ok();
+        }
+    }
+
+    let Some(length) = content_length else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Message without a Content-Length header",
+        ));
+    };
+
+    let mut content = vec![0; length];
+    reader.read_exact(&mut content)?;
+
+    String::from_utf8(content)
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+fn write_message(writer: &mut impl Write, content: &str) -> io::Result<()> {
+    write!(
+        writer,
+        "Content-Length: {}\r\n\r\n{}",
+        content.len(),
+        content
+    )?;
+    writer.flush()
+}
+
+struct OpenDocument {
+    text: String,
+    analysis: DocumentAnalysis,
+}
+
+#[derive(Default)]
+pub struct LanguageServer {
+    documents: HashMap<String, OpenDocument>,
+    should_exit: bool,
+}
+
+impl LanguageServer {
+    /// Handles one client message and returns the messages to send back: the
+    /// response of a request and any notifications it caused
+    pub fn handle(&mut self, message: &Json) -> Vec<Json> {
+        let method = message.get("method").and_then(Json::as_str).unwrap_or("");
+        let params = message.get("params").unwrap_or(&Json::Null);
+        let id = message.get("id");
+
+        let result = match method {
+            "initialize" => Ok(initialize_result()),
+            "shutdown" => Ok(Json::Null),
+            "exit" => {
+                self.should_exit = true;
+                return vec![];
+            }
+            "textDocument/didOpen" => {
+                let document = params.get("textDocument");
+                let uri = document.and_then(|d| d.get("uri")).and_then(Json::as_str);
+                let text = document.and_then(|d| d.get("text")).and_then(Json::as_str);
+                match (uri, text) {
+                    (Some(uri), Some(text)) => return self.update(uri, text.to_string()),
+                    _ => return vec![],
+                }
+            }
+            "textDocument/didChange" => {
+                let uri = text_document_uri(params);
+                // The server asks for full synchronization, the last change is the
+                // whole document
+                let text = params
+                    .get("contentChanges")
+                    .and_then(Json::as_array)
+                    .and_then(|changes| changes.last())
+                    .and_then(|change| change.get("text"))
+                    .and_then(Json::as_str);
+                match (uri, text) {
+                    (Some(uri), Some(text)) => return self.update(uri, text.to_string()),
+                    _ => return vec![],
+                }
+            }
+            "textDocument/didSave" => {
+                // Other modules may have changed on disk, so check again
+                let Some(uri) = text_document_uri(params) else {
+                    return vec![];
+                };
+                match self.documents.get(uri).map(|d| d.text.clone()) {
+                    Some(text) => return self.update(uri, text),
+                    None => return vec![],
+                }
+            }
+            "textDocument/didClose" => {
+                let Some(uri) = text_document_uri(params) else {
+                    return vec![];
+                };
+                self.documents.remove(uri);
+                return vec![publish_diagnostics(uri, Vec::new())];
+            }
+            "textDocument/hover" => self.hover(params),
+            "textDocument/definition" => self.definition(params),
+            "textDocument/documentSymbol" => self.document_symbols(params),
+            _ if id.is_none() => return vec![],
+            _ => Err((METHOD_NOT_FOUND, format!("Unknown method \"{}\"", method))),
+        };
+
+        // Notifications never get a response
+        let Some(id) = id else {
+            return vec![];
+        };
+
+        let response = match result {
+            Ok(result) => Json::object([
+                ("jsonrpc", "2.0".into()),
+                ("id", id.clone()),
+                ("result", result),
+            ]),
+            Err((code, message)) => Json::object([
+                ("jsonrpc", "2.0".into()),
+                ("id", id.clone()),
+                (
+                    "error",
+                    Json::object([
+                        ("code", Json::Number(code as f64)),
+                        ("message", Json::String(message)),
+                    ]),
+                ),
+            ]),
+        };
+
+        vec![response]
+    }
+
+    /// Re-checks a document and publishes its diagnostics
+    fn update(&mut self, uri: &str, text: String) -> Vec<Json> {
+        let Some(path) = uri_to_path(uri) else {
+            return vec![];
+        };
+
+        let analysis = DocumentAnalysis::new(&path, &text);
+        let diagnostics = analysis
+            .diagnostics()
+            .iter()
+            .map(|d| diagnostic_to_json(d, &text))
+            .collect();
+
+        self.documents
+            .insert(uri.to_string(), OpenDocument { text, analysis });
+
+        vec![publish_diagnostics(uri, diagnostics)]
+    }
+
+    /// Finds the open document and byte offset of a `TextDocumentPositionParams`
+    fn document_position(
+        &self,
+        params: &Json,
+    ) -> Result<(&OpenDocument, usize), (i64, String)> {
+        let uri = text_document_uri(params);
+        let document = uri
+            .and_then(|uri| self.documents.get(uri))
+            .ok_or((INVALID_PARAMS, "The document is not open".to_string()))?;
+
+        let position = params.get("position");
+        let line = position
+            .and_then(|p| p.get("line"))
+            .and_then(Json::as_usize);
+        let character = position
+            .and_then(|p| p.get("character"))
+            .and_then(Json::as_usize);
+
+        match (line, character) {
+            (Some(line), Some(character)) => Ok((
+                document,
+                position_to_offset(&document.text, line, character),
+            )),
+            _ => Err((INVALID_PARAMS, "Missing position".to_string())),
+        }
+    }
+
+    fn hover(&self, params: &Json) -> Result<Json, (i64, String)> {
+        let (document, offset) = self.document_position(params)?;
+
+        let Some((span, description)) = document.analysis.hover(offset) else {
+            return Ok(Json::Null);
+        };
+
+        Ok(Json::object([
+            (
+                "contents",
+                Json::object([
+                    ("kind", "markdown".into()),
+                    (
+                        "value",
+                        Json::String(format!("```willow\n{}\n```", description)),
+                    ),
+                ]),
+            ),
+            ("range", range_to_json(&document.text, span)),
+        ]))
+    }
+
+    fn definition(&self, params: &Json) -> Result<Json, (i64, String)> {
+        let (document, offset) = self.document_position(params)?;
+
+        let Some((path, span)) = document.analysis.definition(offset) else {
+            return Ok(Json::Null);
+        };
+
+        // The definition may live in another module which is not open
+        let text = if path == document.analysis.path {
+            document.text.clone()
+        } else {
+            let open = self
+                .documents
+                .values()
+                .find(|d| d.analysis.path == path)
+                .map(|d| d.text.clone());
+            match open.or_else(|| std::fs::read_to_string(&path).ok()) {
+                Some(text) => text,
+                None => return Ok(Json::Null),
+            }
+        };
+
+        Ok(Json::object([
+            ("uri", Json::String(path_to_uri(&path))),
+            ("range", range_to_json(&text, span)),
+        ]))
+    }
+
+    fn document_symbols(&self, params: &Json) -> Result<Json, (i64, String)> {
+        let document = text_document_uri(params)
+            .and_then(|uri| self.documents.get(uri))
+            .ok_or((INVALID_PARAMS, "The document is not open".to_string()))?;
+
+        let symbols = document_symbols(&document.text)
+            .into_iter()
+            .map(|symbol| {
+                // Values of the SymbolKind enumeration of the protocol
+                let kind = match symbol.kind {
+                    SymbolKind::Function => 12,
+                    SymbolKind::TypeAlias => 26,
+                };
+
+                Json::object([
+                    ("name", Json::String(symbol.name)),
+                    ("kind", kind.into()),
+                    ("range", range_to_json(&document.text, symbol.span)),
+                    (
+                        "selectionRange",
+                        range_to_json(&document.text, symbol.selection_span),
+                    ),
+                ])
+            })
+            .collect();
+
+        Ok(Json::Array(symbols))
+    }
+}
+
+fn initialize_result() -> Json {
+    Json::object([
+        (
+            "capabilities",
+            Json::object([
+                // Full document synchronization
+                ("textDocumentSync", 1.into()),
+                ("hoverProvider", true.into()),
+                ("definitionProvider", true.into()),
+                ("documentSymbolProvider", true.into()),
+            ]),
+        ),
+        (
+            "serverInfo",
+            Json::object([
+                ("name", "willow-lsp".into()),
+                ("version", env!("CARGO_PKG_VERSION").into()),
+            ]),
+        ),
+    ])
+}
+
+fn text_document_uri(params: &Json) -> Option<&str> {
+    params
+        .get("textDocument")
+        .and_then(|d| d.get("uri"))
+        .and_then(Json::as_str)
+}
+
+fn publish_diagnostics(uri: &str, diagnostics: Vec<Json>) -> Json {
+    Json::object([
+        ("jsonrpc", "2.0".into()),
+        ("method", "textDocument/publishDiagnostics".into()),
+        (
+            "params",
+            Json::object([
+                ("uri", uri.into()),
+                ("diagnostics", Json::Array(diagnostics)),
+            ]),
+        ),
+    ])
+}
+
+fn range_to_json(text: &str, span: Span) -> Json {
+    let position = |offset| {
+        let (line, character) = offset_to_position(text, offset);
+        Json::object([("line", line.into()), ("character", character.into())])
+    };
+
+    Json::object([
+        ("start", position(span.start.byte_offset)),
+        ("end", position(span.end.byte_offset)),
+    ])
+}
+
+fn diagnostic_to_json(diagnostic: &Diagnostic, text: &str) -> Json {
+    let primary = diagnostic.primary_label();
+    let span = primary.map(|l| l.span).unwrap_or_default();
+
+    // The label says what is wrong at this location, which is what editors show
+    let message = match primary {
+        Some(label) if !label.message.is_empty() => label.message.clone(),
+        _ => diagnostic.message.clone(),
+    };
+
+    let related: Vec<Json> = match &diagnostic.path {
+        Some(path) => diagnostic
+            .labels
+            .iter()
+            .filter(|l| !l.is_primary)
+            .map(|l| {
+                Json::object([
+                    (
+                        "location",
+                        Json::object([
+                            ("uri", Json::String(path_to_uri(path))),
+                            ("range", range_to_json(text, l.span)),
+                        ]),
+                    ),
+                    ("message", Json::String(l.message.clone())),
+                ])
+            })
+            .collect(),
+        None => Vec::new(),
+    };
+
+    let severity: usize = match diagnostic.severity {
+        Severity::Error => 1,
+        Severity::Warning => 2,
+    };
+
+    Json::object([
+        ("range", range_to_json(text, span)),
+        ("severity", severity.into()),
+        (
+            "code",
+            diagnostic.code.as_deref().map_or(Json::Null, Json::from),
+        ),
+        ("source", "willow".into()),
+        ("message", Json::String(message)),
+        ("relatedInformation", Json::Array(related)),
+    ])
+}
+
+/// Converts a `file://` URI into a path, decoding percent escapes
+fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    let encoded = uri.strip_prefix("file://")?;
+    let bytes = encoded.as_bytes();
+
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| encoded.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8(decoded).ok().map(PathBuf::from)
+}
+
+fn path_to_uri(path: &Path) -> String {
+    let mut uri = String::from("file://");
+    for byte in path.to_string_lossy().bytes() {
+        if byte.is_ascii_alphanumeric() || b"/-_.~".contains(&byte) {
+            uri.push(byte as char);
+        } else {
+            uri.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    uri
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, io::Cursor};
+
+    use crate::{
+        compile::write_test_program,
+        lsp::{json::Json, path_to_uri, read_message, uri_to_path, LanguageServer},
+    };
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_read_message() {
+        let mut input =
+            Cursor::new("Content-Length: 2\r\n\r\n{}Content-Length: 4\r\n\r\nnull");
+
+        assert_eq!(read_message(&mut input).unwrap(), Some("{}".to_string()));
+        assert_eq!(read_message(&mut input).unwrap(), Some("null".to_string()));
+        assert_eq!(read_message(&mut input).unwrap(), None);
+    }
+
+    #[test]
+    fn test_uri_round_trip() {
+        let path = std::path::PathBuf::from("/tmp/my project/main.wl");
+        let uri = path_to_uri(&path);
+
+        assert_eq!(uri, "file:///tmp/my%20project/main.wl");
+        assert_eq!(uri_to_path(&uri), Some(path));
+    }
+
+    #[test]
+    fn test_session() {
+        let source = "fn main(): i32 {\n    x\n}\n";
+        let main_path = write_test_program("lsp_session", source);
+        let uri = path_to_uri(&main_path.canonicalize().unwrap());
+
+        let mut server = LanguageServer::default();
+        let mut send = |message: String| server.handle(&Json::parse(&message).unwrap());
+
+        let responses = send(
+            r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{}}"#.to_string(),
+        );
+        assert_eq!(
+            responses[0]
+                .get("result")
+                .and_then(|r| r.get("capabilities"))
+                .and_then(|c| c.get("hoverProvider")),
+            Some(&Json::Bool(true))
+        );
+
+        let notifications = send(format!(
+            r#"{{"jsonrpc":"2.0","method":"textDocument/didOpen","params":{{"textDocument":{{"uri":"{}","languageId":"willow","version":1,"text":{}}}}}}}"#,
+            uri,
+            Json::from(source)
+        ));
+        let _ = fs::remove_dir_all(main_path.parent().unwrap());
+
+        let diagnostics = notifications[0]
+            .get("params")
+            .and_then(|p| p.get("diagnostics"))
+            .and_then(Json::as_array)
+            .unwrap();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].get("message").and_then(Json::as_str),
+            Some("Undeclared identifier \"x\"")
+        );
+        assert_eq!(
+            diagnostics[0].get("range").map(|r| r.to_string()),
+            Some(
+                r#"{"start":{"line":1,"character":4},"end":{"line":1,"character":5}}"#
+                    .to_string()
+            )
+        );
+
+        let responses = send(format!(
+            r#"{{"jsonrpc":"2.0","id":2,"method":"textDocument/documentSymbol","params":{{"textDocument":{{"uri":"{}"}}}}}}"#,
+            uri
+        ));
+        assert_eq!(
+            responses[0]
+                .get("result")
+                .and_then(Json::as_array)
+                .map(|symbols| symbols.len()),
+            Some(1)
+        );
+
+        let responses =
+            send(r#"{"jsonrpc":"2.0","id":3,"method":"unknown"}"#.to_string());
+        assert!(responses[0].get("error").is_some());
+    }
+}
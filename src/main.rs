@@ -1,11 +1,120 @@
-use std::path::PathBuf;
-use willow::compile::Compiler;
+use std::{fs, process::ExitCode};
+use willow::{
+    cli::{self, Command},
+    compile::{repl::Repl, Compiler},
+    interpret::RuntimeValue,
+};
 
-fn main() {
-    let file_path = std::env::args()
-        .nth(1)
-        .expect("\nExpected file path to the program entry\n");
+/// Exit code for invalid command-line arguments
+const USAGE_ERROR: u8 = 2;
+
+fn main() -> ExitCode {
+    let options = match cli::parse_args(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, cli::USAGE);
+            return ExitCode::from(USAGE_ERROR);
+        }
+    };
 
     let mut compiler = Compiler::default();
-    compiler.compile(PathBuf::from(file_path));
+    compiler.target = options.target;
+    compiler.output_path = options.output.clone();
+    compiler.error_format = options.error_format;
+    compiler.color = options.color.should_color();
+
+    let input = match options.command {
+        Command::Help => {
+            println!("{}", cli::USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Command::Version => {
+            println!("willow {}", env!("CARGO_PKG_VERSION"));
+            return ExitCode::SUCCESS;
+        }
+        Command::Repl => {
+            return match Repl::new(compiler).run_stdio() {
+                Ok(()) => ExitCode::SUCCESS,
+                Err(e) => {
+                    eprintln!("Could not read the input, error: {}", e);
+                    ExitCode::FAILURE
+                }
+            };
+        }
+        _ => options
+            .input
+            .expect("Input is required by every other command"),
+    };
+
+    let result = match options.command {
+        Command::Check => compiler.check(&input).map(|_| ExitCode::SUCCESS),
+        Command::Build => compiler.compile(input).map(|compiled| {
+            if let Some(output_path) = compiled.output_path {
+                println!(
+                    "Compilation successful, output written to \"{}\"",
+                    output_path.display()
+                );
+            }
+            ExitCode::SUCCESS
+        }),
+        Command::Run => compiler.run(input).map(|value| {
+            if value != RuntimeValue::Void {
+                println!("{}", value);
+            }
+            // Like a native executable, an integer result becomes the exit code
+            match value {
+                RuntimeValue::Int(code) => ExitCode::from(code as u8),
+                _ => ExitCode::SUCCESS,
+            }
+        }),
+        Command::Emit(kind) => compiler.emit(input, kind).map(|output| {
+            let Some(path) = &options.output else {
+                print!("{}", output);
+                return ExitCode::SUCCESS;
+            };
+
+            match fs::write(path, output) {
+                Ok(()) => ExitCode::SUCCESS,
+                Err(e) => {
+                    eprintln!(
+                        "Could not write the output to \"{}\", error: {}",
+                        path.display(),
+                        e
+                    );
+                    ExitCode::FAILURE
+                }
+            }
+        }),
+        Command::Fmt => compiler.format(&input).map(|formatted| {
+            let Some(formatted) = formatted else {
+                return ExitCode::SUCCESS;
+            };
+
+            if options.check {
+                eprintln!("\"{}\" is not formatted", input.display());
+                return ExitCode::FAILURE;
+            }
+
+            match fs::write(&input, formatted) {
+                Ok(()) => ExitCode::SUCCESS,
+                Err(e) => {
+                    eprintln!(
+                        "Could not write the output to \"{}\", error: {}",
+                        input.display(),
+                        e
+                    );
+                    ExitCode::FAILURE
+                }
+            }
+        }),
+        Command::Help | Command::Version | Command::Repl => unreachable!(),
+    };
+
+    match result {
+        Ok(exit_code) => exit_code,
+        Err(errors) => {
+            compiler.report_errors(&errors);
+            ExitCode::FAILURE
+        }
+    }
 }
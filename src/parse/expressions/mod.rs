@@ -24,7 +24,7 @@ fn prefix_bp(token_kind: &TokenKind) -> Option<((), u8)> {
     use TokenKind::*;
 
     let priority = match token_kind {
-        Punctuation(Minus) | Punctuation(Not) => ((), 13),
+        Punctuation(Minus) | Punctuation(Not) | Punctuation(Tilde) => ((), 21),
         _ => return None,
     };
 
@@ -36,27 +36,35 @@ fn infix_bp(token_kind: &TokenKind) -> Option<(u8, u8)> {
     use TokenKind::*;
 
     let priority = match token_kind {
+        Punctuation(DoubleDot) => (0, 1),
         Punctuation(DoubleOr) => (1, 2),
         Punctuation(DoubleAnd) => (3, 4),
         Punctuation(DoubleEq) | Punctuation(NotEq) => (5, 6),
         Punctuation(Lt) | Punctuation(Lte) | Punctuation(Gt) | Punctuation(Gte) => (7, 8),
-        Punctuation(Plus) | Punctuation(Minus) => (9, 10),
-        Punctuation(Star) | Punctuation(Slash) | Punctuation(Percent) => (11, 12),
+        Punctuation(Or) => (9, 10),
+        Punctuation(Caret) => (11, 12),
+        Punctuation(And) => (13, 14),
+        Punctuation(Plus) | Punctuation(Minus) => (17, 18),
+        Punctuation(Star) | Punctuation(Slash) | Punctuation(Percent) => (19, 20),
         _ => return None,
     };
 
     Some(priority)
 }
 
+/// The binding power of `<<` and `>>`, which are parsed from two adjacent `<` or `>`
+/// tokens so that `>>` can still close nested generic arguments
+const SHIFT_BP: (u8, u8) = (15, 16);
+
 fn suffix_bp(token_kind: &TokenKind) -> Option<(u8, ())> {
     use PunctuationKind::*;
     use TokenKind::*;
 
     let priority = match token_kind {
-        Punctuation(LParen) => (14, ()),    // fn call
-        Punctuation(Dot) => (14, ()),       // member access
-        Punctuation(DoubleCol) => (14, ()), // static member accesses
-        Punctuation(LBracket) => (14, ()),  // array index
+        Punctuation(LParen) => (22, ()),    // fn call
+        Punctuation(Dot) => (22, ()),       // member access
+        Punctuation(DoubleCol) => (22, ()), // static member accesses
+        Punctuation(LBracket) => (22, ()),  // array index
         _ => return None,
     };
 
@@ -73,29 +81,119 @@ pub fn is_start_of_expr(token_kind: &TokenKind) -> bool {
         | TokenKind::Keyword(KeywordKind::False)
         | TokenKind::Keyword(KeywordKind::If)
         | TokenKind::Keyword(KeywordKind::Match)
+        | TokenKind::Keyword(KeywordKind::Bool) // Static access on a type
+        | TokenKind::Keyword(KeywordKind::String)
+        | TokenKind::Keyword(KeywordKind::U8)
+        | TokenKind::Keyword(KeywordKind::U16)
+        | TokenKind::Keyword(KeywordKind::U32)
+        | TokenKind::Keyword(KeywordKind::U64)
+        | TokenKind::Keyword(KeywordKind::USize)
+        | TokenKind::Keyword(KeywordKind::ISize)
+        | TokenKind::Keyword(KeywordKind::I8)
+        | TokenKind::Keyword(KeywordKind::I16)
+        | TokenKind::Keyword(KeywordKind::I32)
+        | TokenKind::Keyword(KeywordKind::I64)
+        | TokenKind::Keyword(KeywordKind::F32)
+        | TokenKind::Keyword(KeywordKind::F64)
         | TokenKind::Punctuation(PunctuationKind::Hash)   // Tag expr
         | TokenKind::Punctuation(PunctuationKind::LParen)   // Parenthesized expr
         | TokenKind::Punctuation(PunctuationKind::LBrace)   // Codeblock or Struct expr
         | TokenKind::Punctuation(PunctuationKind::LBracket) // List literal
         | TokenKind::Punctuation(PunctuationKind::Minus)    // Negation
         | TokenKind::Punctuation(PunctuationKind::Not)      // Logical NOT
+        | TokenKind::Punctuation(PunctuationKind::Tilde)    // Bitwise NOT
           => true,
         _ => false,
     }
 }
 
 impl Parser {
+    /// The direction of a shift operator at the current token, `Lt` for `<<` and `Gt`
+    /// for `>>`
+    fn shift_operator(&self) -> Option<PunctuationKind> {
+        let first = self.tokens.get(self.offset)?;
+        let second = self.tokens.get(self.offset + 1)?;
+
+        match (&first.kind, &second.kind) {
+            (
+                TokenKind::Punctuation(
+                    first_kind @ (PunctuationKind::Lt | PunctuationKind::Gt),
+                ),
+                TokenKind::Punctuation(second_kind),
+            ) if first_kind == second_kind
+                && first.span.end.byte_offset == second.span.start.byte_offset =>
+            {
+                Some(*first_kind)
+            }
+            _ => None,
+        }
+    }
+
+    /// Parses a generic type followed by `::`, e.g. `List<i32>` in
+    /// `List<i32>::with_capacity(8usize)`. Otherwise the `<` is a comparison, the
+    /// offset is restored and nothing is returned.
+    fn try_parse_static_access_type(&mut self) -> Option<Expr> {
+        if !self.match_token(1, TokenKind::Punctuation(PunctuationKind::Lt)) {
+            return None;
+        }
+
+        let start_offset = self.offset;
+        match self.parse_type_annotation(0) {
+            Ok(ty)
+                if self.match_token(
+                    0,
+                    TokenKind::Punctuation(PunctuationKind::DoubleCol),
+                ) =>
+            {
+                Some(Expr {
+                    span: ty.span,
+                    kind: ExprKind::Type(ty),
+                })
+            }
+            _ => {
+                self.offset = start_offset;
+                None
+            }
+        }
+    }
+
     pub fn parse_expr(&mut self, min_prec: u8) -> Result<Expr, ParsingError> {
         let token = self.current().ok_or(self.unexpected_end_of_input())?;
 
         let token_span = token.span;
 
         let mut lhs = match token.kind {
-            TokenKind::Identifier(_) => {
-                let identifier = self.consume_identifier()?;
+            TokenKind::Identifier(_) => match self.try_parse_static_access_type() {
+                Some(expr) => expr,
+                None => {
+                    let identifier = self.consume_identifier()?;
+                    Expr {
+                        kind: ExprKind::Identifier(identifier),
+                        span: token_span,
+                    }
+                }
+            },
+            // The type of a static access, e.g. `string::from_bytes`
+            TokenKind::Keyword(
+                KeywordKind::Bool
+                | KeywordKind::String
+                | KeywordKind::U8
+                | KeywordKind::U16
+                | KeywordKind::U32
+                | KeywordKind::U64
+                | KeywordKind::USize
+                | KeywordKind::ISize
+                | KeywordKind::I8
+                | KeywordKind::I16
+                | KeywordKind::I32
+                | KeywordKind::I64
+                | KeywordKind::F32
+                | KeywordKind::F64,
+            ) => {
+                let ty = self.parse_type_annotation(0)?;
                 Expr {
-                    kind: ExprKind::Identifier(identifier),
-                    span: token_span,
+                    span: ty.span,
+                    kind: ExprKind::Type(ty),
                 }
             }
             TokenKind::Number(_) => {
@@ -179,6 +277,25 @@ impl Parser {
                     span: self.get_span(start_offset, self.offset - 1)?,
                 }
             }
+            TokenKind::Punctuation(PunctuationKind::Tilde) => {
+                let ((), r_bp) = prefix_bp(&TokenKind::Punctuation(
+                    PunctuationKind::Tilde,
+                ))
+                .expect(
+                    "INTERNAL COMPILER ERROR: expected the tilde \'~\' symbol to have \
+                         a corresponding prefix binding power",
+                );
+                let start_offset = self.offset;
+
+                self.consume_punctuation(PunctuationKind::Tilde)?;
+                let expr = self.parse_expr(r_bp)?;
+                Expr {
+                    kind: ExprKind::BitNot {
+                        right: Box::new(expr),
+                    },
+                    span: self.get_span(start_offset, self.offset - 1)?,
+                }
+            }
             TokenKind::Keyword(KeywordKind::If) => self.parse_if_expr()?,
             TokenKind::Keyword(
                 variant @ KeywordKind::True | variant @ KeywordKind::False,
@@ -315,6 +432,44 @@ impl Parser {
                 }
             }
 
+            if let Some(direction) = self.shift_operator() {
+                let (left_prec, right_prec) = SHIFT_BP;
+                if left_prec < min_prec {
+                    break;
+                }
+
+                let start_pos = lhs.span.start;
+
+                self.advance();
+                self.advance();
+
+                let rhs = self.parse_expr(right_prec)?;
+
+                let end_pos = rhs.span.end;
+
+                let expr_kind = if direction == PunctuationKind::Lt {
+                    ExprKind::ShiftLeft {
+                        left: Box::new(lhs),
+                        right: Box::new(rhs),
+                    }
+                } else {
+                    ExprKind::ShiftRight {
+                        left: Box::new(lhs),
+                        right: Box::new(rhs),
+                    }
+                };
+
+                lhs = Expr {
+                    kind: expr_kind,
+                    span: Span {
+                        start: start_pos,
+                        end: end_pos,
+                    },
+                };
+
+                continue;
+            }
+
             if let Some((left_prec, right_prec)) = infix_bp(&op.kind) {
                 if left_prec < min_prec {
                     break;
@@ -395,6 +550,24 @@ impl Parser {
                         left: Box::new(lhs),
                         right: Box::new(rhs),
                     },
+                    TokenKind::Punctuation(PunctuationKind::And) => ExprKind::BitAnd {
+                        left: Box::new(lhs),
+                        right: Box::new(rhs),
+                    },
+                    TokenKind::Punctuation(PunctuationKind::Or) => ExprKind::BitOr {
+                        left: Box::new(lhs),
+                        right: Box::new(rhs),
+                    },
+                    TokenKind::Punctuation(PunctuationKind::Caret) => ExprKind::BitXor {
+                        left: Box::new(lhs),
+                        right: Box::new(rhs),
+                    },
+                    TokenKind::Punctuation(PunctuationKind::DoubleDot) => {
+                        ExprKind::Range {
+                            start: Box::new(lhs),
+                            end: Box::new(rhs),
+                        }
+                    }
                     _ => break,
                 };
 
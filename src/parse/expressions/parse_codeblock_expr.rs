@@ -4,7 +4,13 @@ use crate::{
         stmt::{Stmt, StmtKind},
         Span,
     },
-    parse::{statements::is_start_of_stmt, Parser, ParsingError, ParsingErrorKind},
+    parse::{
+        statements::{
+            is_start_of_stmt,
+            parse_assignment_stmt::{compound_operator, increment_operator},
+        },
+        Parser, ParsingError, ParsingErrorKind,
+    },
     tokenize::{PunctuationKind, TokenKind},
 };
 
@@ -51,6 +57,16 @@ impl Parser {
                 {
                     let stmt = self.parse_assignment_stmt(expr)?;
                     statements.push(stmt);
+                } else if let Some(operator) =
+                    self.current().and_then(|t| compound_operator(&t.kind))
+                {
+                    let stmt = self.parse_compound_assignment_stmt(expr, operator)?;
+                    statements.push(stmt);
+                } else if let Some(operator) =
+                    self.current().and_then(|t| increment_operator(&t.kind))
+                {
+                    let stmt = self.parse_increment_stmt(expr, operator)?;
+                    statements.push(stmt);
                 } else if self
                     .match_token(0, TokenKind::Punctuation(PunctuationKind::SemiCol))
                 {
@@ -2,6 +2,8 @@ use crate::{
     ast::{
         decl::{FnDecl, Param},
         expr::{Expr, ExprKind},
+        type_annotation::{TypeAnnotation, TypeAnnotationKind},
+        IdentifierNode,
     },
     parse::{Parser, ParsingError},
     tokenize::{KeywordKind, PunctuationKind, TokenKind},
@@ -9,9 +11,23 @@ use crate::{
 
 impl Parser {
     pub fn parse_fn_expr(&mut self) -> Result<Expr, ParsingError> {
-        let documentation = self.consume_optional_doc();
-
         let start_offset = self.offset;
+        let decl = self.parse_fn_decl(None)?;
+
+        Ok(Expr {
+            kind: ExprKind::Fn(Box::new(decl)),
+            span: self.get_span(start_offset, self.offset - 1)?,
+        })
+    }
+
+    /// Parses a function declaration. Inside `impl` blocks the first parameter may be
+    /// a bare `self`, it is given the type of the block's target with the span of the
+    /// `self` token. Parameters, `self` included, may be declared `mut`.
+    pub fn parse_fn_decl(
+        &mut self,
+        impl_target: Option<IdentifierNode>,
+    ) -> Result<FnDecl, ParsingError> {
+        let documentation = self.consume_optional_doc();
 
         let is_exported = if self.match_token(0, TokenKind::Keyword(KeywordKind::Export))
         {
@@ -23,16 +39,46 @@ impl Parser {
 
         self.consume_keyword(KeywordKind::Fn)?;
         let identifier = self.consume_identifier()?;
+        let generic_params = self.parse_optional_generic_params()?;
         self.consume_punctuation(PunctuationKind::LParen)?;
+        let mut is_first_param = true;
         let params = self.comma_separated(
             |p| {
+                let is_mutable = p.match_token(0, TokenKind::Keyword(KeywordKind::Mut));
+                if is_mutable {
+                    p.advance();
+                }
+
                 let identifier = p.consume_identifier()?;
+
+                let receiver_target = impl_target.filter(|_| {
+                    is_first_param
+                        && p.interner.resolve(identifier.name) == "self"
+                        && !p.match_token(0, TokenKind::Punctuation(PunctuationKind::Col))
+                });
+                is_first_param = false;
+
+                if let Some(target) = receiver_target {
+                    return Ok(Param {
+                        identifier,
+                        constraint: TypeAnnotation {
+                            kind: TypeAnnotationKind::Identifier(IdentifierNode {
+                                name: target.name,
+                                span: identifier.span,
+                            }),
+                            span: identifier.span,
+                        },
+                        is_mutable,
+                    });
+                }
+
                 p.consume_punctuation(PunctuationKind::Col)?;
                 let constraint = p.parse_type_annotation(0)?;
 
                 Ok(Param {
                     constraint,
                     identifier,
+                    is_mutable,
                 })
             },
             |p| p.match_token(0, TokenKind::Punctuation(PunctuationKind::RParen)),
@@ -45,17 +91,15 @@ impl Parser {
 
         let id = self.new_declaration_id();
 
-        Ok(Expr {
-            kind: ExprKind::Fn(Box::new(FnDecl {
-                id,
-                identifier,
-                params,
-                return_type,
-                body,
-                documentation,
-                is_exported,
-            })),
-            span: self.get_span(start_offset, self.offset - 1)?,
+        Ok(FnDecl {
+            id,
+            identifier,
+            generic_params,
+            params,
+            return_type,
+            body,
+            documentation,
+            is_exported,
         })
     }
 }
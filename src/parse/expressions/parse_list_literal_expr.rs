@@ -5,13 +5,42 @@ use crate::{
 };
 
 impl Parser {
+    /// Parses `[a, b, c]` list literals and `[value; size]` array literals
     pub fn parse_list_literal_expr(&mut self) -> Result<Expr, ParsingError> {
         let start_offset = self.offset;
         self.consume_punctuation(PunctuationKind::LBracket)?;
-        let items: Vec<Expr> = self.comma_separated(
-            |p| p.parse_expr(0),
-            |p| p.match_token(0, TokenKind::Punctuation(PunctuationKind::RBracket)),
-        )?;
+
+        let is_end = |p: &Self| {
+            p.match_token(0, TokenKind::Punctuation(PunctuationKind::RBracket))
+        };
+        let mut items: Vec<Expr> = Vec::new();
+
+        if !is_end(self) {
+            let first_item = self.parse_expr(0)?;
+
+            if self.match_token(0, TokenKind::Punctuation(PunctuationKind::SemiCol)) {
+                self.consume_punctuation(PunctuationKind::SemiCol)?;
+                let size = self.parse_expr(0)?;
+                self.consume_punctuation(PunctuationKind::RBracket)?;
+
+                return Ok(Expr {
+                    kind: ExprKind::Array {
+                        value: Box::new(first_item),
+                        size: Box::new(size),
+                    },
+                    span: self.get_span(start_offset, self.offset - 1)?,
+                });
+            }
+
+            items.push(first_item);
+            while !is_end(self) {
+                self.consume_punctuation(PunctuationKind::Comma)?;
+                if is_end(self) {
+                    break;
+                }
+                items.push(self.parse_expr(0)?);
+            }
+        }
         self.consume_punctuation(PunctuationKind::RBracket)?;
 
         let span = self.get_span(start_offset, self.offset - 1)?;
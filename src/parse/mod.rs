@@ -85,8 +85,8 @@ pub struct ParsingError {
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct DocAnnotation {
-    message: String,
-    span: Span,
+    pub message: String,
+    pub span: Span,
 }
 
 impl Parser {
@@ -286,6 +286,25 @@ impl Parser {
         result
     }
 
+    /// Parses the `<T, U>` list of type parameters following the name of a generic
+    /// declaration, if there is one
+    pub fn parse_optional_generic_params(
+        &mut self,
+    ) -> Result<Vec<IdentifierNode>, ParsingError> {
+        if !self.match_token(0, TokenKind::Punctuation(PunctuationKind::Lt)) {
+            return Ok(vec![]);
+        }
+
+        self.consume_punctuation(PunctuationKind::Lt)?;
+        let params = self.comma_separated(
+            |p| p.consume_identifier(),
+            |p| p.match_token(0, TokenKind::Punctuation(PunctuationKind::Gt)),
+        )?;
+        self.consume_punctuation(PunctuationKind::Gt)?;
+
+        Ok(params)
+    }
+
     pub fn comma_separated<F, T, E>(
         &mut self,
         mut parser: F,
@@ -1,8 +1,11 @@
 pub mod parse_assignment_stmt;
 pub mod parse_break_stmt;
+pub mod parse_const_decl;
 pub mod parse_continue_stmt;
 pub mod parse_expr_stmt;
+pub mod parse_for_stmt;
 pub mod parse_from_stmt;
+pub mod parse_impl_decl;
 pub mod parse_return_stmt;
 pub mod parse_type_alias_decl;
 pub mod parse_var_decl;
@@ -18,6 +21,7 @@ use crate::{
 };
 
 use super::ParsingError;
+use parse_assignment_stmt::{compound_operator, increment_operator};
 
 pub fn is_start_of_stmt(token_kind: &TokenKind) -> bool {
     matches!(
@@ -25,11 +29,14 @@ pub fn is_start_of_stmt(token_kind: &TokenKind) -> bool {
         TokenKind::Keyword(
             KeywordKind::From
                 | KeywordKind::While
+                | KeywordKind::For
                 | KeywordKind::Return
                 | KeywordKind::Break
                 | KeywordKind::Continue
                 | KeywordKind::Type
+                | KeywordKind::Impl
                 | KeywordKind::Let
+                | KeywordKind::Const
                 | KeywordKind::Export
         ) | TokenKind::Doc(_)
     )
@@ -75,6 +82,14 @@ impl Parser {
             return self.parse_var_decl();
         }
 
+        if matches_token!(
+            self,
+            lookahead_index,
+            TokenKind::Keyword(KeywordKind::Const)
+        ) {
+            return self.parse_const_decl();
+        }
+
         if matches_token!(self, lookahead_index, TokenKind::Keyword(KeywordKind::Fn)) {
             let expr = self.parse_fn_expr()?;
             return Ok(Stmt {
@@ -104,12 +119,18 @@ impl Parser {
             });
         }
 
+        if matches_token!(self, 0, TokenKind::Keyword(KeywordKind::Impl)) {
+            return self.parse_impl_decl();
+        }
         if matches_token!(self, 0, TokenKind::Keyword(KeywordKind::From)) {
             return self.parse_from_stmt();
         }
         if matches_token!(self, 0, TokenKind::Keyword(KeywordKind::While)) {
             return self.parse_while_stmt();
         }
+        if matches_token!(self, 0, TokenKind::Keyword(KeywordKind::For)) {
+            return self.parse_for_stmt();
+        }
         if matches_token!(self, 0, TokenKind::Keyword(KeywordKind::Return)) {
             return self.parse_return_stmt();
         }
@@ -122,6 +143,14 @@ impl Parser {
 
         let lhs = self.parse_expr(0)?;
 
+        if let Some(operator) = self.current().and_then(|t| compound_operator(&t.kind)) {
+            return self.parse_compound_assignment_stmt(lhs, operator);
+        }
+
+        if let Some(operator) = self.current().and_then(|t| increment_operator(&t.kind)) {
+            return self.parse_increment_stmt(lhs, operator);
+        }
+
         if matches_token!(self, 0, TokenKind::Punctuation(PunctuationKind::Eq))
             && !matches_token!(self, 1, TokenKind::Punctuation(PunctuationKind::Eq))
         {
@@ -1,11 +1,11 @@
 use crate::{
     ast::{
         expr::Expr,
-        stmt::{Stmt, StmtKind},
+        stmt::{CompoundOperator, IncrementOperator, Stmt, StmtKind},
         Span,
     },
     parse::{Parser, ParsingError},
-    tokenize::PunctuationKind,
+    tokenize::{PunctuationKind, TokenKind},
 };
 
 impl Parser {
@@ -23,4 +23,81 @@ impl Parser {
             kind: StmtKind::Assignment { target: lhs, value },
         })
     }
+
+    pub fn parse_compound_assignment_stmt(
+        &mut self,
+        lhs: Expr,
+        operator: CompoundOperator,
+    ) -> Result<Stmt, ParsingError> {
+        let start_offset = self.offset;
+        self.advance();
+        let value = self.parse_expr(0)?;
+        self.consume_punctuation(PunctuationKind::SemiCol)?;
+        let span_end = self.get_span(start_offset, self.offset - 1)?;
+        Ok(Stmt {
+            span: Span {
+                start: lhs.span.start,
+                end: span_end.end,
+            },
+            kind: StmtKind::CompoundAssignment {
+                target: lhs,
+                operator,
+                value,
+            },
+        })
+    }
+
+    pub fn parse_increment_stmt(
+        &mut self,
+        lhs: Expr,
+        operator: IncrementOperator,
+    ) -> Result<Stmt, ParsingError> {
+        let start_offset = self.offset;
+        self.advance();
+        self.consume_punctuation(PunctuationKind::SemiCol)?;
+        let span_end = self.get_span(start_offset, self.offset - 1)?;
+        Ok(Stmt {
+            span: Span {
+                start: lhs.span.start,
+                end: span_end.end,
+            },
+            kind: StmtKind::Increment {
+                target: lhs,
+                operator,
+            },
+        })
+    }
+}
+
+/// The operator of a compound assignment token, e.g. `+=`
+pub fn compound_operator(token_kind: &TokenKind) -> Option<CompoundOperator> {
+    match token_kind {
+        TokenKind::Punctuation(PunctuationKind::PlusEq) => Some(CompoundOperator::Add),
+        TokenKind::Punctuation(PunctuationKind::MinusEq) => {
+            Some(CompoundOperator::Subtract)
+        }
+        TokenKind::Punctuation(PunctuationKind::StarEq) => {
+            Some(CompoundOperator::Multiply)
+        }
+        TokenKind::Punctuation(PunctuationKind::SlashEq) => {
+            Some(CompoundOperator::Divide)
+        }
+        TokenKind::Punctuation(PunctuationKind::PercentEq) => {
+            Some(CompoundOperator::Modulo)
+        }
+        _ => None,
+    }
+}
+
+/// The operator of an increment token, `++` or `--`
+pub fn increment_operator(token_kind: &TokenKind) -> Option<IncrementOperator> {
+    match token_kind {
+        TokenKind::Punctuation(PunctuationKind::DoublePlus) => {
+            Some(IncrementOperator::Increment)
+        }
+        TokenKind::Punctuation(PunctuationKind::DoubleMinus) => {
+            Some(IncrementOperator::Decrement)
+        }
+        _ => None,
+    }
 }
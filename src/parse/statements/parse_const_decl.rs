@@ -0,0 +1,58 @@
+use crate::{
+    ast::{
+        decl::ConstDecl,
+        stmt::{Stmt, StmtKind},
+    },
+    parse::{Parser, ParsingError},
+    tokenize::{KeywordKind, PunctuationKind, TokenKind},
+};
+
+impl Parser {
+    pub fn parse_const_decl(&mut self) -> Result<Stmt, ParsingError> {
+        let documentation = self.consume_optional_doc();
+
+        let start_offset = self.offset;
+
+        let is_exported = if self.match_token(0, TokenKind::Keyword(KeywordKind::Export))
+        {
+            self.consume_keyword(KeywordKind::Export)?;
+            true
+        } else {
+            false
+        };
+
+        self.consume_keyword(KeywordKind::Const)?;
+
+        let name = self.consume_identifier()?;
+
+        let constraint =
+            if self.match_token(0, TokenKind::Punctuation(PunctuationKind::Col)) {
+                self.advance();
+                Some(self.parse_type_annotation(0)?)
+            } else {
+                None
+            };
+
+        self.consume_punctuation(PunctuationKind::Eq)?;
+
+        let value = self.parse_expr(0)?;
+
+        self.consume_punctuation(PunctuationKind::SemiCol)?;
+
+        let span = self.get_span(start_offset, self.offset - 1)?;
+
+        let id = self.new_declaration_id();
+
+        Ok(Stmt {
+            kind: StmtKind::ConstDecl(ConstDecl {
+                id,
+                documentation,
+                identifier: name,
+                constraint,
+                value,
+                is_exported,
+            }),
+            span,
+        })
+    }
+}
@@ -0,0 +1,28 @@
+use crate::{
+    ast::stmt::{Stmt, StmtKind},
+    parse::{Parser, ParsingError},
+    tokenize::KeywordKind,
+};
+
+impl Parser {
+    pub fn parse_for_stmt(&mut self) -> Result<Stmt, ParsingError> {
+        let start_offset = self.offset;
+
+        self.consume_keyword(KeywordKind::For)?;
+        let item = self.consume_identifier()?;
+        self.consume_keyword(KeywordKind::In)?;
+        let iterable = Box::new(self.parse_expr(0)?);
+        let body = self.parse_codeblock_expr()?;
+
+        let span = self.get_span(start_offset, self.offset - 1)?;
+
+        Ok(Stmt {
+            kind: StmtKind::For {
+                item,
+                iterable,
+                body,
+            },
+            span,
+        })
+    }
+}
@@ -11,28 +11,43 @@ impl Parser {
         self.consume_keyword(KeywordKind::From)?;
         let path = self.consume_string()?;
 
-        self.consume_punctuation(PunctuationKind::LBrace)?;
-        let identifiers = self.comma_separated(
-            |p| {
-                let identifier = p.consume_identifier()?;
-                let alias =
-                    if p.match_token(0, TokenKind::Punctuation(PunctuationKind::Col)) {
+        // from "./module.wl" as name
+        let is_namespace = matches_token!(self, 0, TokenKind::Identifier(name)
+            if self.interner.resolve(name) == "as");
+        let (identifiers, namespace) = if is_namespace {
+            self.advance();
+            (vec![], Some(self.consume_identifier()?))
+        } else {
+            self.consume_punctuation(PunctuationKind::LBrace)?;
+            let identifiers = self.comma_separated(
+                |p| {
+                    let identifier = p.consume_identifier()?;
+                    let alias = if p
+                        .match_token(0, TokenKind::Punctuation(PunctuationKind::Col))
+                    {
                         p.advance();
                         Some(p.consume_identifier()?)
                     } else {
                         None
                     };
 
-                Ok((identifier, alias))
-            },
-            |p| p.match_token(0, TokenKind::Punctuation(PunctuationKind::RBrace)),
-        )?;
-        self.consume_punctuation(PunctuationKind::RBrace)?;
+                    Ok((identifier, alias))
+                },
+                |p| p.match_token(0, TokenKind::Punctuation(PunctuationKind::RBrace)),
+            )?;
+            self.consume_punctuation(PunctuationKind::RBrace)?;
+
+            (identifiers, None)
+        };
 
         let span = self.get_span(start_offset, self.offset - 1)?;
 
         Ok(Stmt {
-            kind: StmtKind::From { path, identifiers },
+            kind: StmtKind::From {
+                path,
+                identifiers,
+                namespace,
+            },
             span,
         })
     }
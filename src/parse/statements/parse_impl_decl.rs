@@ -0,0 +1,30 @@
+use crate::{
+    ast::{
+        decl::ImplDecl,
+        stmt::{Stmt, StmtKind},
+    },
+    parse::{Parser, ParsingError},
+    tokenize::{KeywordKind, PunctuationKind, TokenKind},
+};
+
+impl Parser {
+    pub fn parse_impl_decl(&mut self) -> Result<Stmt, ParsingError> {
+        let start_offset = self.offset;
+
+        self.consume_keyword(KeywordKind::Impl)?;
+        let target = self.consume_identifier()?;
+        self.consume_punctuation(PunctuationKind::LBrace)?;
+
+        let mut methods = vec![];
+        while !self.match_token(0, TokenKind::Punctuation(PunctuationKind::RBrace)) {
+            methods.push(self.parse_fn_decl(Some(target))?);
+        }
+
+        self.consume_punctuation(PunctuationKind::RBrace)?;
+
+        Ok(Stmt {
+            kind: StmtKind::ImplDecl(ImplDecl { target, methods }),
+            span: self.get_span(start_offset, self.offset - 1)?,
+        })
+    }
+}
@@ -24,6 +24,7 @@ impl Parser {
         self.consume_keyword(KeywordKind::Type)?;
 
         let name = self.consume_identifier()?;
+        let generic_params = self.parse_optional_generic_params()?;
 
         self.consume_punctuation(PunctuationKind::Eq)?;
 
@@ -39,6 +40,7 @@ impl Parser {
             kind: StmtKind::TypeAliasDecl(TypeAliasDecl {
                 id,
                 identifier: name,
+                generic_params,
                 documentation,
                 value: ty,
                 is_exported,
@@ -15,6 +15,11 @@ impl Parser {
 
         self.consume_keyword(KeywordKind::Let)?;
 
+        let is_mutable = self.match_token(0, TokenKind::Keyword(KeywordKind::Mut));
+        if is_mutable {
+            self.advance();
+        }
+
         let name = self.consume_identifier()?;
 
         let constraint =
@@ -40,6 +45,7 @@ impl Parser {
                 id,
                 documentation,
                 identifier: name,
+                is_mutable,
                 constraint,
                 value,
             }),
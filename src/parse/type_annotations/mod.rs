@@ -1,3 +1,4 @@
+pub mod parse_array_type_annotation;
 pub mod parse_fn_type_annotation;
 pub mod parse_parenthesized_type_annotation;
 pub mod parse_struct_type_annotation;
@@ -5,7 +6,10 @@ pub mod parse_tag_type_annotation;
 
 use super::{Parser, ParsingError, ParsingErrorKind};
 use crate::{
-    ast::type_annotation::{TypeAnnotation, TypeAnnotationKind},
+    ast::{
+        type_annotation::{TypeAnnotation, TypeAnnotationKind},
+        Span,
+    },
     tokenize::{KeywordKind, PunctuationKind, TokenKind},
 };
 
@@ -98,6 +102,26 @@ impl Parser {
                     span,
                 }
             }
+            TokenKind::Keyword(KeywordKind::USize) => {
+                let start_offset = self.offset;
+
+                self.consume_keyword(KeywordKind::USize)?;
+                let span = self.get_span(start_offset, self.offset - 1)?;
+                TypeAnnotation {
+                    kind: TypeAnnotationKind::USize,
+                    span,
+                }
+            }
+            TokenKind::Keyword(KeywordKind::ISize) => {
+                let start_offset = self.offset;
+
+                self.consume_keyword(KeywordKind::ISize)?;
+                let span = self.get_span(start_offset, self.offset - 1)?;
+                TypeAnnotation {
+                    kind: TypeAnnotationKind::ISize,
+                    span,
+                }
+            }
             TokenKind::Keyword(KeywordKind::I8) => {
                 let start_offset = self.offset;
 
@@ -167,12 +191,31 @@ impl Parser {
             TokenKind::Punctuation(PunctuationKind::LBrace) => {
                 self.parse_struct_type_annotation()?
             }
+            TokenKind::Punctuation(PunctuationKind::LBracket) => {
+                self.parse_array_type_annotation()?
+            }
             TokenKind::Keyword(KeywordKind::Fn) => self.parse_fn_type_annotation()?,
             TokenKind::Identifier(_) => {
+                let start_offset = self.offset;
                 let identifier = self.consume_identifier()?;
-                TypeAnnotation {
-                    span: identifier.span,
-                    kind: TypeAnnotationKind::Identifier(identifier),
+
+                if self.match_token(0, TokenKind::Punctuation(PunctuationKind::Lt)) {
+                    self.consume_punctuation(PunctuationKind::Lt)?;
+                    let args = self.comma_separated(
+                        |p| p.parse_type_annotation(0),
+                        |p| p.match_token(0, TokenKind::Punctuation(PunctuationKind::Gt)),
+                    )?;
+                    self.consume_punctuation(PunctuationKind::Gt)?;
+
+                    TypeAnnotation {
+                        kind: TypeAnnotationKind::GenericInstance { identifier, args },
+                        span: self.get_span(start_offset, self.offset - 1)?,
+                    }
+                } else {
+                    TypeAnnotation {
+                        span: identifier.span,
+                        kind: TypeAnnotationKind::Identifier(identifier),
+                    }
                 }
             }
             _ => {
@@ -194,8 +237,10 @@ impl Parser {
                         self.consume_punctuation(PunctuationKind::LBracket)?;
                         self.consume_punctuation(PunctuationKind::RBracket)?;
 
-                        let span =
-                            self.get_span(lhs.span.start.byte_offset, self.offset - 1)?;
+                        let span = Span {
+                            start: lhs.span.start,
+                            end: self.get_span(self.offset - 1, self.offset - 1)?.end,
+                        };
                         TypeAnnotation {
                             kind: TypeAnnotationKind::List(Box::new(lhs.clone())),
                             span,
@@ -419,6 +464,42 @@ mod tests {
                     },
                 },
             ),
+            (
+                "usize",
+                TypeAnnotation {
+                    kind: TypeAnnotationKind::USize,
+                    span: Span {
+                        start: Position {
+                            line: 1,
+                            col: 1,
+                            byte_offset: 0,
+                        },
+                        end: Position {
+                            line: 1,
+                            col: 6,
+                            byte_offset: 5,
+                        },
+                    },
+                },
+            ),
+            (
+                "isize",
+                TypeAnnotation {
+                    kind: TypeAnnotationKind::ISize,
+                    span: Span {
+                        start: Position {
+                            line: 1,
+                            col: 1,
+                            byte_offset: 0,
+                        },
+                        end: Position {
+                            line: 1,
+                            col: 6,
+                            byte_offset: 5,
+                        },
+                    },
+                },
+            ),
             (
                 "void",
                 TypeAnnotation {
@@ -491,4 +572,41 @@ mod tests {
             assert_eq!(result, Ok(expected))
         }
     }
+
+    #[test]
+    fn list_type_spans_its_brackets() {
+        use crate::ast::Position;
+        use crate::tokenize::Tokenizer;
+        use pretty_assertions::assert_eq;
+
+        // The annotation does not start at the first byte, so its token offset and its
+        // byte offset differ
+        let interner = Arc::new(SharedStringInterner::default());
+        let (tokens, _) = Tokenizer::tokenize("  u8[]", interner.clone());
+        let mut parser = Parser {
+            offset: 0,
+            checkpoint_offset: 0,
+            tokens,
+            interner,
+            decl_id_counter: Arc::new(AtomicUsize::new(0)),
+        };
+
+        let result = parser.parse_type_annotation(0).map(|ty| ty.span);
+
+        assert_eq!(
+            result,
+            Ok(Span {
+                start: Position {
+                    line: 1,
+                    col: 3,
+                    byte_offset: 2,
+                },
+                end: Position {
+                    line: 1,
+                    col: 7,
+                    byte_offset: 6,
+                },
+            })
+        );
+    }
 }
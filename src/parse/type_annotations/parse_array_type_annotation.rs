@@ -0,0 +1,40 @@
+use crate::{
+    ast::type_annotation::{TypeAnnotation, TypeAnnotationKind},
+    parse::ParsingError,
+    tokenize::{PunctuationKind, TokenKind},
+};
+
+use super::Parser;
+
+impl Parser {
+    /// `[T; N]` for arrays, `[T]` for slices
+    pub fn parse_array_type_annotation(
+        &mut self,
+    ) -> Result<TypeAnnotation, ParsingError> {
+        let start_offset = self.offset;
+
+        self.consume_punctuation(PunctuationKind::LBracket)?;
+        let item_type = self.parse_type_annotation(0)?;
+
+        if self.match_token(0, TokenKind::Punctuation(PunctuationKind::RBracket)) {
+            self.consume_punctuation(PunctuationKind::RBracket)?;
+
+            return Ok(TypeAnnotation {
+                kind: TypeAnnotationKind::Slice(Box::new(item_type)),
+                span: self.get_span(start_offset, self.offset - 1)?,
+            });
+        }
+
+        self.consume_punctuation(PunctuationKind::SemiCol)?;
+        let size = self.parse_expr(0)?;
+        self.consume_punctuation(PunctuationKind::RBracket)?;
+
+        Ok(TypeAnnotation {
+            kind: TypeAnnotationKind::Array {
+                item_type: Box::new(item_type),
+                size: Box::new(size),
+            },
+            span: self.get_span(start_offset, self.offset - 1)?,
+        })
+    }
+}
@@ -24,6 +24,7 @@ impl Parser {
                 Ok(Param {
                     constraint,
                     identifier,
+                    is_mutable: false,
                 })
             },
             |p| p.match_token(0, TokenKind::Punctuation(PunctuationKind::RParen)),
@@ -22,6 +22,7 @@ impl Parser {
                 Ok(Param {
                     identifier,
                     constraint,
+                    is_mutable: false,
                 })
             },
             |p| p.match_token(0, TokenKind::Punctuation(PunctuationKind::RBrace)),
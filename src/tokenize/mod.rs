@@ -7,6 +7,7 @@ pub mod tokenize_identifier;
 pub mod tokenize_number;
 pub mod tokenize_punctuation;
 pub mod tokenize_string;
+pub mod trivia;
 
 use crate::{
     ast::{Position, Span},
@@ -58,6 +59,7 @@ pub enum PunctuationKind {
     And,
     Not,
     Dot,
+    DoubleDot,
     LParen,
     RParen,
     LBracket,
@@ -71,10 +73,19 @@ pub enum PunctuationKind {
     Slash,
     Star,
     Percent,
+    PlusEq,
+    MinusEq,
+    DoublePlus,
+    DoubleMinus,
+    StarEq,
+    SlashEq,
+    PercentEq,
     Comma,
     Dollar,
     Question,
     Hash,
+    Caret,
+    Tilde,
 }
 
 impl PunctuationKind {
@@ -94,6 +105,7 @@ impl PunctuationKind {
             PunctuationKind::And => "&",
             PunctuationKind::Not => "!",
             PunctuationKind::Dot => ".",
+            PunctuationKind::DoubleDot => "..",
             PunctuationKind::LParen => "(",
             PunctuationKind::RParen => ")",
             PunctuationKind::LBracket => "[",
@@ -107,10 +119,19 @@ impl PunctuationKind {
             PunctuationKind::Slash => "/",
             PunctuationKind::Star => "*",
             PunctuationKind::Percent => "%",
+            PunctuationKind::PlusEq => "+=",
+            PunctuationKind::MinusEq => "-=",
+            PunctuationKind::DoublePlus => "++",
+            PunctuationKind::DoubleMinus => "--",
+            PunctuationKind::StarEq => "*=",
+            PunctuationKind::SlashEq => "/=",
+            PunctuationKind::PercentEq => "%=",
             PunctuationKind::Comma => ",",
             PunctuationKind::Dollar => "$",
             PunctuationKind::Question => "?",
             PunctuationKind::Hash => "#",
+            PunctuationKind::Caret => "^",
+            PunctuationKind::Tilde => "~",
         })
     }
 }
@@ -118,13 +139,18 @@ impl PunctuationKind {
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum KeywordKind {
     Let,
+    Mut,
+    Const,
     Return,
     If,
     Else,
     While,
+    For,
+    In,
     Break,
     Continue,
     Type,
+    Impl,
     From,
     Void,
     True,
@@ -139,6 +165,8 @@ pub enum KeywordKind {
     U16,
     U32,
     U64,
+    USize,
+    ISize,
     F32,
     F64,
     Fn,
@@ -150,13 +178,18 @@ impl KeywordKind {
     pub fn to_string(&self) -> String {
         String::from(match self {
             KeywordKind::Let => "let",
+            KeywordKind::Mut => "mut",
+            KeywordKind::Const => "const",
             KeywordKind::Return => "return",
             KeywordKind::If => "if",
             KeywordKind::Else => "else",
             KeywordKind::While => "while",
+            KeywordKind::For => "for",
+            KeywordKind::In => "in",
             KeywordKind::Break => "break",
             KeywordKind::Continue => "continue",
             KeywordKind::Type => "type",
+            KeywordKind::Impl => "impl",
             KeywordKind::From => "from",
             KeywordKind::Void => "void",
             KeywordKind::True => "true",
@@ -171,6 +204,8 @@ impl KeywordKind {
             KeywordKind::U16 => "u16",
             KeywordKind::U32 => "u32",
             KeywordKind::U64 => "u64",
+            KeywordKind::USize => "usize",
+            KeywordKind::ISize => "isize",
             KeywordKind::F32 => "f32",
             KeywordKind::F64 => "f64",
             KeywordKind::Fn => "fn",
@@ -247,7 +282,7 @@ impl<'a> Tokenizer<'a> {
 
     fn consume(&mut self) {
         if let Some(c) = self.current() {
-            if c == "\n" {
+            if c == "\n" || c == "\r\n" {
                 self.byte_offset += c.len();
                 self.line += 1;
                 self.col = 1;
@@ -317,7 +352,7 @@ impl<'a> Tokenizer<'a> {
     fn skip_comment(&mut self) {
         if self.peek(0) == Some("/") && self.peek(1) == Some("/") {
             while let Some(c) = self.current() {
-                if c == "\n" || c == "\r" {
+                if c == "\n" || c == "\r\n" || c == "\r" {
                     break;
                 }
                 self.consume();
@@ -522,13 +557,18 @@ fn is_keyword(identifier: &str) -> Option<KeywordKind> {
     match identifier {
         "fn" => Some(KeywordKind::Fn),
         "let" => Some(KeywordKind::Let),
+        "mut" => Some(KeywordKind::Mut),
+        "const" => Some(KeywordKind::Const),
         "return" => Some(KeywordKind::Return),
         "if" => Some(KeywordKind::If),
         "else" => Some(KeywordKind::Else),
         "while" => Some(KeywordKind::While),
+        "for" => Some(KeywordKind::For),
+        "in" => Some(KeywordKind::In),
         "break" => Some(KeywordKind::Break),
         "continue" => Some(KeywordKind::Continue),
         "type" => Some(KeywordKind::Type),
+        "impl" => Some(KeywordKind::Impl),
         "from" => Some(KeywordKind::From),
         "void" => Some(KeywordKind::Void),
         "true" => Some(KeywordKind::True),
@@ -543,6 +583,8 @@ fn is_keyword(identifier: &str) -> Option<KeywordKind> {
         "u16" => Some(KeywordKind::U16),
         "u32" => Some(KeywordKind::U32),
         "u64" => Some(KeywordKind::U64),
+        "usize" => Some(KeywordKind::USize),
+        "isize" => Some(KeywordKind::ISize),
         "f32" => Some(KeywordKind::F32),
         "f64" => Some(KeywordKind::F64),
         "match" => Some(KeywordKind::Match),
@@ -690,4 +732,32 @@ mod tests {
         let (tokens, _) = Tokenizer::tokenize(input, interner);
         assert_eq!(tokens.len(), 0);
     }
+
+    #[test]
+    fn test_double_minus() {
+        let interner = Arc::new(SharedStringInterner::default());
+        let a_id = interner.intern("a");
+        let kinds = |input| {
+            let (tokens, _) = Tokenizer::tokenize(input, interner.clone());
+            tokens.into_iter().map(|t| t.kind).collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            kinds("a--3"),
+            vec![
+                TokenKind::Identifier(a_id),
+                TokenKind::Punctuation(PunctuationKind::Minus),
+                TokenKind::Punctuation(PunctuationKind::Minus),
+                TokenKind::Number(NumberKind::I64(3)),
+            ]
+        );
+        assert_eq!(
+            kinds("a-- ;"),
+            vec![
+                TokenKind::Identifier(a_id),
+                TokenKind::Punctuation(PunctuationKind::DoubleMinus),
+                TokenKind::Punctuation(PunctuationKind::SemiCol),
+            ]
+        );
+    }
 }
@@ -8,6 +8,9 @@ impl<'a> Tokenizer<'a> {
         while let Some(c) = self.current() {
             if is_digit(c) {
                 self.consume();
+            } else if c == "." && self.peek(1) == Some(".") {
+                // The start of a range, e.g. "0..n"
+                break;
             } else if c == "." && !has_dot {
                 has_dot = true;
                 self.consume();
@@ -26,9 +29,11 @@ impl<'a> Tokenizer<'a> {
     }
 }
 
-const SUFFIX_INFOS: [(&str, bool); 10] = [
+const SUFFIX_INFOS: [(&str, bool); 12] = [
     ("f64", true),
     ("f32", true),
+    ("usize", false),
+    ("isize", false),
     ("u64", false),
     ("u32", false),
     ("u16", false),
@@ -60,6 +65,14 @@ fn parse_number(full_number_str: &str) -> Result<NumberKind, TokenizationErrorKi
                     .parse::<f32>()
                     .map(NumberKind::F32)
                     .or(Err(TokenizationErrorKind::InvalidFloatingNumber)),
+                "usize" => numeric_part
+                    .parse::<usize>()
+                    .map(NumberKind::USize)
+                    .or(Err(TokenizationErrorKind::InvalidIntegerNumber)),
+                "isize" => numeric_part
+                    .parse::<isize>()
+                    .map(NumberKind::ISize)
+                    .or(Err(TokenizationErrorKind::InvalidIntegerNumber)),
                 "u64" => numeric_part
                     .parse::<u64>()
                     .map(NumberKind::U64)
@@ -238,6 +251,22 @@ mod tests {
                     },
                 },
             ),
+            (
+                "8usize",
+                NumberKind::USize(8usize),
+                Span {
+                    start: Position {
+                        line: 1,
+                        col: 1,
+                        byte_offset: 0,
+                    },
+                    end: Position {
+                        line: 1,
+                        col: 7,
+                        byte_offset: 6,
+                    },
+                },
+            ),
             (
                 "1u8",
                 NumberKind::U8(1u8),
@@ -84,10 +84,17 @@ impl<'a> Tokenizer<'a> {
                 self.consume();
                 Some(PunctuationKind::SemiCol)
             }
-            "." => {
-                self.consume();
-                Some(PunctuationKind::Dot)
-            }
+            "." => match self.peek(1) {
+                Some(".") => {
+                    self.consume();
+                    self.consume();
+                    Some(PunctuationKind::DoubleDot)
+                }
+                _ => {
+                    self.consume();
+                    Some(PunctuationKind::Dot)
+                }
+            },
             "(" => {
                 self.consume();
                 Some(PunctuationKind::LParen)
@@ -112,26 +119,72 @@ impl<'a> Tokenizer<'a> {
                 self.consume();
                 Some(PunctuationKind::RBrace)
             }
-            "+" => {
-                self.consume();
-                Some(PunctuationKind::Plus)
-            }
-            "-" => {
-                self.consume();
-                Some(PunctuationKind::Minus)
-            }
-            "*" => {
-                self.consume();
-                Some(PunctuationKind::Star)
-            }
-            "/" => {
-                self.consume();
-                Some(PunctuationKind::Slash)
-            }
-            "%" => {
-                self.consume();
-                Some(PunctuationKind::Percent)
-            }
+            "+" => match self.peek(1) {
+                Some("=") => {
+                    self.consume();
+                    self.consume();
+                    Some(PunctuationKind::PlusEq)
+                }
+                Some("+") => {
+                    self.consume();
+                    self.consume();
+                    Some(PunctuationKind::DoublePlus)
+                }
+                _ => {
+                    self.consume();
+                    Some(PunctuationKind::Plus)
+                }
+            },
+            "-" => match self.peek(1) {
+                Some("=") => {
+                    self.consume();
+                    self.consume();
+                    Some(PunctuationKind::MinusEq)
+                }
+                // A decrement always ends its statement, so `a--3` stays `a - (-3)`
+                Some("-") if self.semicolon_follows(2) => {
+                    self.consume();
+                    self.consume();
+                    Some(PunctuationKind::DoubleMinus)
+                }
+                _ => {
+                    self.consume();
+                    Some(PunctuationKind::Minus)
+                }
+            },
+            "*" => match self.peek(1) {
+                Some("=") => {
+                    self.consume();
+                    self.consume();
+                    Some(PunctuationKind::StarEq)
+                }
+                _ => {
+                    self.consume();
+                    Some(PunctuationKind::Star)
+                }
+            },
+            "/" => match self.peek(1) {
+                Some("=") => {
+                    self.consume();
+                    self.consume();
+                    Some(PunctuationKind::SlashEq)
+                }
+                _ => {
+                    self.consume();
+                    Some(PunctuationKind::Slash)
+                }
+            },
+            "%" => match self.peek(1) {
+                Some("=") => {
+                    self.consume();
+                    self.consume();
+                    Some(PunctuationKind::PercentEq)
+                }
+                _ => {
+                    self.consume();
+                    Some(PunctuationKind::Percent)
+                }
+            },
             "," => {
                 self.consume();
                 Some(PunctuationKind::Comma)
@@ -148,7 +201,26 @@ impl<'a> Tokenizer<'a> {
                 self.consume();
                 Some(PunctuationKind::Hash)
             }
+            "^" => {
+                self.consume();
+                Some(PunctuationKind::Caret)
+            }
+            "~" => {
+                self.consume();
+                Some(PunctuationKind::Tilde)
+            }
             _ => None,
         }
     }
+
+    /// Whether the next grapheme from offset `i` that isn't whitespace is a `;`
+    fn semicolon_follows(&self, mut i: usize) -> bool {
+        while let Some(c) = self.peek(i) {
+            if !c.trim().is_empty() {
+                return c == ";";
+            }
+            i += 1;
+        }
+        false
+    }
 }
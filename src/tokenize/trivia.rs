@@ -0,0 +1,281 @@
+use std::sync::Arc;
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{
+    ast::{Position, Span},
+    compile::interner::SharedStringInterner,
+};
+
+use super::{Token, TokenizationError, Tokenizer};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TriviaKind {
+    /// Spaces and tabs
+    Whitespace,
+    /// A line break, `\r\n` is a single newline
+    Newline,
+    /// An ordinary `//` comment, without the line break which ends it
+    Comment,
+    /// Text which the tokenizer skipped after an error
+    Skipped,
+}
+
+/// Source text between tokens, which is not part of the token stream
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trivia {
+    pub kind: TriviaKind,
+    pub text: String,
+    pub span: Span,
+}
+
+/// A token together with the trivia around it. Trailing trivia extends up to, but
+/// not including, the next line break, everything after belongs to the leading
+/// trivia of the next token.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TriviaToken {
+    pub token: Token,
+    /// The source text of the token itself
+    pub text: String,
+    pub leading_trivia: Vec<Trivia>,
+    pub trailing_trivia: Vec<Trivia>,
+}
+
+impl TriviaToken {
+    /// Appends the token and its trivia as written in the source
+    pub fn write_full_text(&self, out: &mut String) {
+        for trivia in &self.leading_trivia {
+            out.push_str(&trivia.text);
+        }
+        out.push_str(&self.text);
+        for trivia in &self.trailing_trivia {
+            out.push_str(&trivia.text);
+        }
+    }
+}
+
+/// The output of `Tokenizer::tokenize_with_trivia`, which covers every byte of the
+/// input
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TriviaTokens {
+    pub tokens: Vec<TriviaToken>,
+    /// Trivia after the last token's trailing trivia, e.g. the final newline
+    pub end_trivia: Vec<Trivia>,
+}
+
+impl TriviaTokens {
+    /// The plain tokens, as expected by the parser
+    pub fn tokens(&self) -> Vec<Token> {
+        self.tokens.iter().map(|t| t.token.clone()).collect()
+    }
+
+    pub fn to_source(&self) -> String {
+        let mut out = String::new();
+        for token in &self.tokens {
+            token.write_full_text(&mut out);
+        }
+        for trivia in &self.end_trivia {
+            out.push_str(&trivia.text);
+        }
+        out
+    }
+
+    /// Every trivia in source order
+    pub fn trivia(&self) -> impl Iterator<Item = &Trivia> {
+        self.tokens
+            .iter()
+            .flat_map(|t| t.leading_trivia.iter().chain(&t.trailing_trivia))
+            .chain(&self.end_trivia)
+    }
+}
+
+impl<'a> Tokenizer<'a> {
+    /// Tokenizes like `Tokenizer::tokenize`, but keeps whitespace, comments and
+    /// skipped text as trivia of the surrounding tokens, so the input can be
+    /// reproduced byte for byte
+    pub fn tokenize_with_trivia(
+        input: &'a str,
+        interner: Arc<SharedStringInterner>,
+    ) -> (TriviaTokens, Vec<TokenizationError>) {
+        let (tokens, errors) = Tokenizer::tokenize(input, interner);
+
+        let mut result = TriviaTokens::default();
+        let mut position = Position {
+            line: 1,
+            col: 1,
+            byte_offset: 0,
+        };
+        let mut pending: Vec<Trivia> = Vec::new();
+
+        for token in tokens {
+            let gap = &input[position.byte_offset..token.span.start.byte_offset];
+            let mut trivia = split_trivia(gap, position);
+
+            // The part up to the first line break trails the previous token
+            if let Some(previous) = result.tokens.last_mut() {
+                let split = trivia
+                    .iter()
+                    .position(|t| t.kind == TriviaKind::Newline)
+                    .unwrap_or(trivia.len());
+                previous.trailing_trivia = trivia.drain(..split).collect();
+            }
+            pending.extend(trivia);
+
+            position = token.span.end;
+            result.tokens.push(TriviaToken {
+                text: input[token.span.start.byte_offset..token.span.end.byte_offset]
+                    .to_string(),
+                token,
+                leading_trivia: std::mem::take(&mut pending),
+                trailing_trivia: Vec::new(),
+            });
+        }
+
+        let mut trivia = split_trivia(&input[position.byte_offset..], position);
+        if let Some(previous) = result.tokens.last_mut() {
+            let split = trivia
+                .iter()
+                .position(|t| t.kind == TriviaKind::Newline)
+                .unwrap_or(trivia.len());
+            previous.trailing_trivia = trivia.drain(..split).collect();
+        }
+        result.end_trivia = trivia;
+
+        (result, errors)
+    }
+}
+
+/// Splits the text between two tokens, which starts at `start`, into trivia.
+/// Positions are counted like the tokenizer does, in graphemes.
+fn split_trivia(gap: &str, start: Position) -> Vec<Trivia> {
+    let mut trivia: Vec<Trivia> = Vec::new();
+    let mut position = start;
+    let mut graphemes = gap.graphemes(true).peekable();
+
+    while let Some(grapheme) = graphemes.next() {
+        let rest = &gap[position.byte_offset - start.byte_offset..];
+        let kind = if rest.starts_with("//") {
+            TriviaKind::Comment
+        } else if matches!(grapheme, "\n" | "\r\n" | "\r") {
+            TriviaKind::Newline
+        } else if grapheme.chars().all(char::is_whitespace) {
+            TriviaKind::Whitespace
+        } else {
+            TriviaKind::Skipped
+        };
+
+        let piece_start = position;
+        let mut text = String::from(grapheme);
+        advance(&mut position, grapheme);
+
+        // Newlines are separate pieces, other kinds extend over a run of graphemes
+        while let Some(&next) = graphemes.peek() {
+            let is_newline = matches!(next, "\n" | "\r\n" | "\r");
+            let continues = match kind {
+                TriviaKind::Newline => false,
+                TriviaKind::Comment => !is_newline,
+                TriviaKind::Whitespace => {
+                    !is_newline && next.chars().all(char::is_whitespace)
+                }
+                TriviaKind::Skipped => {
+                    let rest = &gap[position.byte_offset - start.byte_offset..];
+                    !rest.starts_with("//") && !next.chars().all(char::is_whitespace)
+                }
+            };
+            if !continues {
+                break;
+            }
+            text.push_str(next);
+            advance(&mut position, next);
+            graphemes.next();
+        }
+
+        trivia.push(Trivia {
+            kind,
+            text,
+            span: Span {
+                start: piece_start,
+                end: position,
+            },
+        });
+    }
+
+    trivia
+}
+
+fn advance(position: &mut Position, grapheme: &str) {
+    position.byte_offset += grapheme.len();
+    if grapheme == "\n" || grapheme == "\r\n" {
+        position.line += 1;
+        position.col = 1;
+    } else {
+        position.col += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{
+        compile::interner::SharedStringInterner,
+        tokenize::{trivia::TriviaKind, Tokenizer},
+    };
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_leading_and_trailing_trivia() {
+        let input = "// header\nlet x = 1; // one\n\n  x @ y\n";
+        let interner = Arc::new(SharedStringInterner::default());
+        let (tokens, errors) = Tokenizer::tokenize_with_trivia(input, interner);
+
+        assert_eq!(tokens.to_source(), input);
+        assert_eq!(errors.len(), 1);
+
+        let kinds = |trivia: &[crate::tokenize::trivia::Trivia]| {
+            trivia
+                .iter()
+                .map(|t| (t.kind, t.text.clone()))
+                .collect::<Vec<_>>()
+        };
+
+        let first = &tokens.tokens[0];
+        assert_eq!(first.text, "let");
+        assert_eq!(
+            kinds(&first.leading_trivia),
+            vec![
+                (TriviaKind::Comment, "// header".to_string()),
+                (TriviaKind::Newline, "\n".to_string()),
+            ]
+        );
+
+        let semicolon = &tokens.tokens[4];
+        assert_eq!(semicolon.text, ";");
+        assert_eq!(
+            kinds(&semicolon.trailing_trivia),
+            vec![
+                (TriviaKind::Whitespace, " ".to_string()),
+                (TriviaKind::Comment, "// one".to_string()),
+            ]
+        );
+
+        let x = &tokens.tokens[5];
+        assert_eq!(x.token.span.start.line, 4);
+        assert_eq!(x.leading_trivia[2].kind, TriviaKind::Whitespace);
+        assert_eq!(x.leading_trivia[2].span.start.line, 4);
+
+        // The unknown character is kept as skipped text
+        assert_eq!(
+            kinds(&x.trailing_trivia),
+            vec![
+                (TriviaKind::Whitespace, " ".to_string()),
+                (TriviaKind::Skipped, "@".to_string()),
+                (TriviaKind::Whitespace, " ".to_string()),
+            ]
+        );
+        assert_eq!(
+            kinds(&tokens.end_trivia),
+            vec![(TriviaKind::Newline, "\n".to_string())]
+        );
+    }
+}